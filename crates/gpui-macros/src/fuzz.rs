@@ -0,0 +1,91 @@
+use crate::test::{error_to_stream, error_with_message, parse_u64_array, parse_usize_from_expr};
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use std::mem;
+use syn::{
+    self, ItemFn, Meta, Token,
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+};
+
+struct Args {
+    seeds: Vec<u64>,
+    max_iterations: usize,
+    steps: usize,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> Result<Self, syn::Error> {
+        let mut seeds = Vec::<u64>::new();
+        let mut max_iterations = 10;
+        let mut steps = 100;
+
+        let metas = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+
+        for meta in metas {
+            let Some(ident) = meta.path().get_ident() else {
+                return Err(syn::Error::new(meta.path().span(), "unexpected path"));
+            };
+
+            match (&meta, ident.to_string().as_str()) {
+                (Meta::NameValue(meta), "iterations") => {
+                    max_iterations = parse_usize_from_expr(&meta.value)?
+                }
+                (Meta::NameValue(meta), "steps") => steps = parse_usize_from_expr(&meta.value)?,
+                (Meta::NameValue(meta), "seed") => {
+                    seeds = vec![parse_usize_from_expr(&meta.value)? as u64]
+                }
+                (Meta::List(list), "seeds") => seeds = parse_u64_array(list)?,
+                (Meta::Path(_), _) => {
+                    return Err(syn::Error::new(meta.span(), "invalid path argument"));
+                }
+                (_, _) => {
+                    return Err(syn::Error::new(meta.span(), "invalid argument name"));
+                }
+            }
+        }
+
+        Ok(Args {
+            seeds,
+            max_iterations,
+            steps,
+        })
+    }
+}
+
+pub fn fuzz(args: TokenStream, function: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(args as Args);
+    let mut inner_fn = match syn::parse::<ItemFn>(function) {
+        Ok(f) => f,
+        Err(err) => return error_to_stream(err),
+    };
+    if inner_fn.sig.asyncness.is_some() || inner_fn.sig.inputs.len() != 2 {
+        return error_with_message(
+            "fuzz functions take a `&mut Window` and a `&mut Context<V>` and return the view `V`",
+            &inner_fn.sig,
+        );
+    }
+
+    let inner_fn_attributes = mem::take(&mut inner_fn.attrs);
+    let inner_fn_name = format_ident!("__{}", inner_fn.sig.ident);
+    let outer_fn_name = mem::replace(&mut inner_fn.sig.ident, inner_fn_name.clone());
+
+    let seeds = &args.seeds;
+    let seeds = quote!( #(#seeds),* );
+    let num_iterations = args.max_iterations;
+    let steps = args.steps;
+
+    let mut outer_fn: ItemFn = parse_quote! {
+        #[test]
+        fn #outer_fn_name() {
+            #inner_fn
+
+            gpui::run_fuzz_test(#num_iterations, &[#seeds], #steps, &#inner_fn_name);
+        }
+    };
+    outer_fn.attrs.extend(inner_fn_attributes);
+
+    TokenStream::from(quote!(#outer_fn))
+}
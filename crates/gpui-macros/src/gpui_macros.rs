@@ -3,6 +3,7 @@ mod derive_app_context;
 mod derive_into_element;
 mod derive_render;
 mod derive_visual_context;
+mod fuzz;
 mod register_action;
 mod styles;
 mod test;
@@ -188,6 +189,33 @@ pub fn test(args: TokenStream, function: TokenStream) -> TokenStream {
     test::test(args, function)
 }
 
+/// `#[gpui::fuzz]` turns a function that builds a view into a test that throws random input at
+/// it: clicks, drags, scrolls, keystrokes from the keymap, resizes and focus changes.
+///
+/// ```ignore
+/// #[gpui::fuzz(steps = 200)]
+/// fn fuzz_editor(window: &mut Window, cx: &mut Context<Editor>) -> Editor {
+///     Editor::new(window, cx)
+/// }
+/// ```
+///
+/// After every step the view must not have panicked, the window's focus must be on a rendered
+/// element, and nothing may have been laid out with NaN bounds. Once the steps are done, the
+/// window is closed and the view must not have leaked. When any of this fails, the steps are
+/// shrunk to the shortest sequence that still fails, which is printed along with the seed.
+///
+/// # Arguments
+///
+/// - `iterations`, `seed` and `seeds(...)` pick the seeds to run, as for `#[gpui::test]`, except
+///   that 10 seeds are run by default.
+/// - `steps = 100` sets how many actions each seed performs.
+///
+/// The `SEED` and `ITERATIONS` environment variables work as for `#[gpui::test]`.
+#[proc_macro_attribute]
+pub fn fuzz(args: TokenStream, function: TokenStream) -> TokenStream {
+    fuzz::fuzz(args, function)
+}
+
 /// When added to a trait, `#[derive_inspector_reflection]` generates a module which provides
 /// enumeration and lookup by name of all methods that have the shape `fn method(self) -> Self`.
 /// This is used by the inspector so that it can use the builder methods in `Styled` and
@@ -288,7 +288,7 @@ fn generate_test_function(
     Ok(TokenStream::from(quote!(#outer_fn)))
 }
 
-fn parse_usize_from_expr(expr: &Expr) -> Result<usize, syn::Error> {
+pub(crate) fn parse_usize_from_expr(expr: &Expr) -> Result<usize, syn::Error> {
     let Expr::Lit(ExprLit {
         lit: Lit::Int(int), ..
     }) = expr
@@ -299,7 +299,7 @@ fn parse_usize_from_expr(expr: &Expr) -> Result<usize, syn::Error> {
         .map_err(|_| syn::Error::new(int.span(), "failed to parse integer"))
 }
 
-fn parse_u64_array(meta_list: &MetaList) -> Result<Vec<u64>, syn::Error> {
+pub(crate) fn parse_u64_array(meta_list: &MetaList) -> Result<Vec<u64>, syn::Error> {
     let mut result = Vec::new();
     let tokens = &meta_list.tokens;
     let parser = |input: ParseStream| {
@@ -321,10 +321,10 @@ fn parse_u64_array(meta_list: &MetaList) -> Result<Vec<u64>, syn::Error> {
     Ok(result)
 }
 
-fn error_with_message(message: &str, spanned: impl Spanned) -> TokenStream {
+pub(crate) fn error_with_message(message: &str, spanned: impl Spanned) -> TokenStream {
     error_to_stream(syn::Error::new(spanned.span(), message))
 }
 
-fn error_to_stream(err: syn::Error) -> TokenStream {
+pub(crate) fn error_to_stream(err: syn::Error) -> TokenStream {
     TokenStream::from(err.into_compile_error())
 }
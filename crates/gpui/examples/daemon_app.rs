@@ -1,4 +1,6 @@
-use gpui::single_instance::{send_activate_to_existing, SingleInstance};
+use gpui::single_instance::{
+    send_to_existing, SecondInstanceArgs, SecondInstanceReply, SingleInstance,
+};
 use gpui::{
     div, prelude::*, px, rgb, rgba, size, App, Application, Bounds, Context, Entity, Keystroke,
     Toast, ToastPosition, ToastStack, TrayIconEvent, TrayMenuItem, Window,
@@ -89,9 +91,19 @@ fn main() {
     let _instance = match SingleInstance::acquire(APP_ID) {
         Ok(instance) => instance,
         Err(_) => {
-            eprintln!("Another instance is already running. Sending activation signal.");
-            let _ = send_activate_to_existing(APP_ID);
-            std::process::exit(0);
+            eprintln!("Another instance is already running. Forwarding arguments.");
+            match send_to_existing(APP_ID, &SecondInstanceArgs::current(&[])) {
+                Ok(reply) => {
+                    if let Some(message) = reply.message {
+                        eprintln!("{message}");
+                    }
+                    std::process::exit(reply.exit_code);
+                }
+                Err(error) => {
+                    eprintln!("Failed to reach the running instance: {error}");
+                    std::process::exit(1);
+                }
+            }
         }
     };
 
@@ -101,6 +113,15 @@ fn main() {
         setup_tray(cx);
         setup_global_hotkey(cx);
 
+        cx.on_second_instance(APP_ID, |args, cx| {
+            eprintln!("Launched again with {:?} in {:?}", args.args, args.cwd);
+            cx.activate(true);
+            SecondInstanceReply {
+                exit_code: 0,
+                message: Some("Activated the running instance".into()),
+            }
+        });
+
         let _ = cx.show_notification("Daemon App", "Application started in background");
 
         cx.activate(true);
@@ -111,19 +132,10 @@ fn setup_tray(cx: &mut App) {
     cx.set_tray_tooltip("Daemon App");
 
     cx.set_tray_menu(vec![
-        TrayMenuItem::Action {
-            label: "Show Overlay".into(),
-            id: "show_overlay".into(),
-        },
-        TrayMenuItem::Action {
-            label: "Settings".into(),
-            id: "settings".into(),
-        },
-        TrayMenuItem::Separator,
-        TrayMenuItem::Action {
-            label: "Quit".into(),
-            id: "quit".into(),
-        },
+        TrayMenuItem::action("Show Overlay", "show_overlay"),
+        TrayMenuItem::action("Settings", "settings"),
+        TrayMenuItem::separator(),
+        TrayMenuItem::action("Quit", "quit"),
     ]);
 
     cx.on_tray_icon_event(|event, _cx| match event {
@@ -46,7 +46,10 @@ fn main() {
         }
 
         let bio = cx.biometric_status();
-        println!("Biometrics: {:?}", bio);
+        cx.spawn(async move |_cx| {
+            println!("Biometrics: {:?}", bio.await);
+        })
+        .detach();
 
         cx.set_dock_badge(Some("3"));
 
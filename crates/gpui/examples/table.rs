@@ -0,0 +1,101 @@
+use gpui::{
+    App, Application, Bounds, Context, SortDirection, TableColumn, TableSort, TableState, Window,
+    WindowBounds, WindowOptions, bind_table_keys, div, prelude::*, px, rgb, size, table,
+};
+
+const ROW_COUNT: usize = 10_000;
+const COLUMN_COUNT: usize = 30;
+
+struct TableExample {
+    state: TableState,
+    rows: Vec<usize>,
+}
+
+impl TableExample {
+    fn new(cx: &mut App) -> Self {
+        let columns = (0..COLUMN_COUNT)
+            .map(|ix| match ix {
+                0 => TableColumn::new("ID", px(64.)).sortable(true),
+                1 => TableColumn::new("Name", px(160.)).sortable(true),
+                _ => TableColumn::new(format!("Column {ix}"), px(96.)),
+            })
+            .collect();
+        Self {
+            state: TableState::new(columns, ROW_COUNT, cx).with_frozen_columns(2),
+            rows: (0..ROW_COUNT).collect(),
+        }
+    }
+
+    fn sort(&mut self, sort: &TableSort) {
+        match sort.column {
+            0 => self.rows.sort_unstable(),
+            _ => self
+                .rows
+                .sort_unstable_by_key(|row| format!("Item {}", row)),
+        }
+        if sort.direction == SortDirection::Descending {
+            self.rows.reverse();
+        }
+    }
+}
+
+impl Render for TableExample {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let rows = self.rows.clone();
+        let view = cx.entity();
+        div().size_full().bg(rgb(0xffffff)).p_4().child(
+            table("table", self.state.clone(), move |cell, _, _| {
+                let row = rows[cell.row];
+                let text = match cell.column {
+                    0 => row.to_string(),
+                    1 => format!("Item {row}"),
+                    column => format!("{row}.{column}"),
+                };
+                div()
+                    .size_full()
+                    .px_1()
+                    .border_b_1()
+                    .border_r_1()
+                    .border_color(rgb(0xe5e7eb))
+                    .bg(if cell.row % 2 == 0 {
+                        rgb(0xffffff)
+                    } else {
+                        rgb(0xf9fafb)
+                    })
+                    .child(text)
+                    .into_any_element()
+            })
+            .on_sort(move |sort, _, cx| {
+                view.update(cx, |this, cx| {
+                    this.sort(sort);
+                    cx.notify();
+                })
+            })
+            .size_full()
+            .border_1()
+            .border_color(rgb(0xd1d5db))
+            .when(self.state.focus_handle().is_focused(window), |this| {
+                this.border_color(rgb(0x3b82f6))
+            }),
+        )
+    }
+}
+
+fn main() {
+    Application::new().run(|cx: &mut App| {
+        bind_table_keys(cx);
+        let bounds = Bounds::centered(None, size(px(800.0), px(600.0)), cx);
+        cx.open_window(
+            WindowOptions {
+                window_bounds: Some(WindowBounds::Windowed(bounds)),
+                ..Default::default()
+            },
+            |window, cx| {
+                let view = cx.new(|cx| TableExample::new(cx));
+                window.focus(&view.read(cx).state.focus_handle());
+                view
+            },
+        )
+        .unwrap();
+    });
+}
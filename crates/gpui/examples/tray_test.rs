@@ -1,27 +1,79 @@
-use gpui::{App, Application, TrayMenuItem};
+use std::time::Duration;
+
+use gpui::{
+    App, AppContext as _, Application, Context, IntoElement, Keystroke, ParentElement as _, Render,
+    Styled as _, TrayIcon, TrayIconEvent, TrayMenuItem, TrayPopover, Window, div, px, rgb, size,
+};
+
+const SPINNER: [&str; 4] = [
+    "process-working-symbolic",
+    "content-loading-symbolic",
+    "emblem-synchronizing-symbolic",
+    "view-refresh-symbolic",
+];
+
+const DOT: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="16">
+  <circle cx="8" cy="8" r="6" fill="#e01b24"/>
+</svg>"##;
+
+struct Popover;
+
+impl Render for Popover {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .size_full()
+            .p_4()
+            .bg(rgb(0xfafafa))
+            .child("Opened from the tray icon")
+    }
+}
 
 fn main() {
     Application::new().run(|cx: &mut App| {
         cx.set_keep_alive_without_windows(true);
 
         cx.set_tray_tooltip("Test Tray App");
+        cx.set_tray_icon(Some(TrayIcon::Named("mail-unread-symbolic".into())));
 
         cx.set_tray_menu(vec![
-            TrayMenuItem::Action {
-                label: "Hello".into(),
-                id: "hello".into(),
-            },
-            TrayMenuItem::Separator,
-            TrayMenuItem::Action {
-                label: "Quit".into(),
-                id: "quit".into(),
-            },
+            TrayMenuItem::action("Hello", "hello")
+                .icon(TrayIcon::Named("face-smile-symbolic".into())),
+            TrayMenuItem::action("Busy", "busy"),
+            TrayMenuItem::action("Attention", "attention")
+                .icon(TrayIcon::Svg(DOT.as_bytes().into())),
+            TrayMenuItem::action("Unavailable", "unavailable").enabled(false),
+            TrayMenuItem::separator(),
+            TrayMenuItem::radio("Small", "size", false, "small"),
+            TrayMenuItem::radio("Large", "size", true, "large"),
+            TrayMenuItem::separator(),
+            TrayMenuItem::action("Quit", "quit").accelerator(Keystroke::parse("ctrl-q").unwrap()),
         ]);
 
         cx.on_tray_menu_action(|id, cx| {
             eprintln!("Menu action: {}", id);
-            if id.as_ref() == "quit" {
-                cx.quit();
+            match id.as_ref() {
+                "busy" => cx.set_tray_icon(Some(TrayIcon::Animated {
+                    frames: SPINNER
+                        .iter()
+                        .map(|name| TrayIcon::Named((*name).into()))
+                        .collect(),
+                    interval: Duration::from_millis(250),
+                })),
+                "attention" => cx.set_tray_attention(Some(TrayIcon::Svg(DOT.as_bytes().into()))),
+                "hello" => {
+                    cx.set_tray_icon(Some(TrayIcon::Named("mail-unread-symbolic".into())));
+                    cx.set_tray_attention(None);
+                }
+                "quit" => cx.quit(),
+                _ => {}
+            }
+        });
+
+        cx.on_tray_icon_event(|event, cx| {
+            if event == TrayIconEvent::LeftClick {
+                TrayPopover::new(size(px(240.), px(120.)))
+                    .open(cx, |_, cx| cx.new(|_| Popover))
+                    .ok();
             }
         });
 
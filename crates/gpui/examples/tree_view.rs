@@ -0,0 +1,88 @@
+//! A lazily loaded tree with a very large directory. Children take a moment to "load", so the
+//! loading row is visible when a directory is expanded for the first time.
+use std::time::Duration;
+
+use gpui::{
+    App, Application, Bounds, Context, Task, TreeItem, TreeItemId, TreeViewState, Window,
+    WindowBounds, WindowOptions, bind_tree_view_keys, div, prelude::*, px, rgb, size, tree_view,
+};
+
+const CHILDREN_PER_DIRECTORY: u64 = 8;
+const LARGE_DIRECTORY: u64 = 1;
+const LARGE_DIRECTORY_SIZE: u64 = 200_000;
+
+struct TreeViewExample {
+    state: TreeViewState,
+}
+
+impl TreeViewExample {
+    fn new(cx: &mut App) -> Self {
+        let roots = (1..=4u64).map(TreeItem::branch).collect();
+        let state = TreeViewState::new(roots, load_children, cx);
+        Self { state }
+    }
+}
+
+/// Item ids encode their path: the children of `id` are numbered from `id * 1_000_000 + 1`.
+fn load_children(id: TreeItemId, cx: &mut App) -> Task<Vec<TreeItem>> {
+    let count = if id.0 == LARGE_DIRECTORY {
+        LARGE_DIRECTORY_SIZE
+    } else {
+        CHILDREN_PER_DIRECTORY
+    };
+    let timer = cx.background_executor().timer(Duration::from_millis(500));
+    cx.background_executor().spawn(async move {
+        timer.await;
+        (1..=count)
+            .map(|ix| {
+                let child = id.0 * 1_000_000 + ix;
+                if ix % 3 == 0 && id.0 < 1_000_000_000 {
+                    TreeItem::branch(child)
+                } else {
+                    TreeItem::leaf(child)
+                }
+            })
+            .collect()
+    })
+}
+
+impl Render for TreeViewExample {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        div().size_full().bg(rgb(0xffffff)).p_2().child(
+            tree_view("tree", self.state.clone(), |id, details, _, _| {
+                let name = if details.has_children {
+                    format!("Directory {}", id.0)
+                } else {
+                    format!("File {}", id.0)
+                };
+                div().h(px(24.)).child(name).into_any_element()
+            })
+            .on_move(|tree_move, _, _| {
+                println!(
+                    "moved {:?} into {:?} at {}",
+                    tree_move.item, tree_move.parent, tree_move.index
+                );
+            })
+            .size_full(),
+        )
+    }
+}
+
+fn main() {
+    Application::new().run(|cx: &mut App| {
+        bind_tree_view_keys(cx);
+        let bounds = Bounds::centered(None, size(px(480.0), px(640.0)), cx);
+        cx.open_window(
+            WindowOptions {
+                window_bounds: Some(WindowBounds::Windowed(bounds)),
+                ..Default::default()
+            },
+            |window, cx| {
+                let view = cx.new(|cx| TreeViewExample::new(cx));
+                window.focus(&view.read(cx).state.focus_handle());
+                view
+            },
+        )
+        .unwrap();
+    });
+}
@@ -0,0 +1,84 @@
+use gpui::{
+    App, Application, Bounds, Context, GridCellSize, ScrollStrategy, VirtualGridState, Window,
+    WindowBounds, WindowOptions, div, prelude::*, px, rgb, size, virtual_grid,
+};
+
+const SECTION_SIZES: [usize; 4] = [12, 250, 40, 5000];
+
+struct VirtualGridExample {
+    state: VirtualGridState,
+}
+
+impl VirtualGridExample {
+    fn new() -> Self {
+        let state = VirtualGridState::new(0, GridCellSize::Fixed(size(px(96.), px(96.))), px(200.));
+        state.set_sections(SECTION_SIZES);
+        Self { state }
+    }
+}
+
+impl Render for VirtualGridExample {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let state = self.state.clone();
+        div()
+            .flex()
+            .flex_col()
+            .size_full()
+            .bg(rgb(0xffffff))
+            .child(
+                div()
+                    .id("jump")
+                    .p_2()
+                    .cursor_pointer()
+                    .child("Jump to item 1000")
+                    .on_click(cx.listener(move |_, _, _, cx| {
+                        state.scroll_to_item(1000, ScrollStrategy::Center);
+                        cx.notify();
+                    })),
+            )
+            .child(
+                virtual_grid(self.state.clone(), |ix, _, _| {
+                    div()
+                        .id(ix)
+                        .size_full()
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .bg(rgb(0xe0e7ff))
+                        .border_1()
+                        .border_color(rgb(0xffffff))
+                        .child(format!("{ix}"))
+                        .on_click(move |_, _, _| println!("clicked item {ix}"))
+                        .into_any_element()
+                })
+                .section_header(|section_ix, _, _| {
+                    div()
+                        .px_2()
+                        .py_1()
+                        .bg(rgb(0xf3f4f6))
+                        .child(format!(
+                            "Section {} ({} items)",
+                            section_ix + 1,
+                            SECTION_SIZES[section_ix]
+                        ))
+                        .into_any_element()
+                })
+                .flex_1()
+                .gap_1(),
+            )
+    }
+}
+
+fn main() {
+    Application::new().run(|cx: &mut App| {
+        let bounds = Bounds::centered(None, size(px(640.0), px(480.0)), cx);
+        cx.open_window(
+            WindowOptions {
+                window_bounds: Some(WindowBounds::Windowed(bounds)),
+                ..Default::default()
+            },
+            |_, cx| cx.new(|_| VirtualGridExample::new()),
+        )
+        .unwrap();
+    });
+}
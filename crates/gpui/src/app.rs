@@ -13,7 +13,7 @@ use std::{
 use anyhow::{Context as _, Result, anyhow};
 use derive_more::{Deref, DerefMut};
 use futures::{
-    Future, FutureExt,
+    Future, FutureExt, StreamExt as _,
     channel::oneshot,
     future::{LocalBoxFuture, Shared},
 };
@@ -26,6 +26,8 @@ use collections::{FxHashMap, FxHashSet, HashMap, VecDeque};
 pub use context::*;
 pub use entity_map::*;
 use http_client::{HttpClient, Url};
+pub use recording::{InputRecording, InputRecordingOptions, RecordedEntry, RecordedEvent};
+use recording::{Recorder, SharedRecorder};
 use smallvec::SmallVec;
 #[cfg(any(test, feature = "test-support"))]
 pub use test_context::*;
@@ -38,20 +40,23 @@ use crate::{
     AssetSource, AttentionType, BackgroundExecutor, BiometricStatus, Bounds, ClipboardItem,
     CrashReport, CursorStyle, DialogOptions, DispatchPhase, DisplayId, EventEmitter, FocusHandle,
     FocusMap, FocusedWindowInfo, ForegroundExecutor, Global, KeyBinding, KeyContext, Keymap,
-    Keystroke, LayoutId, MediaKeyEvent, Menu, MenuItem, NetworkStatus, OsInfo, OwnedMenu,
-    PathPromptOptions, PermissionStatus, Pixels, Platform, PlatformDisplay,
-    PlatformKeyboardLayout, PlatformKeyboardMapper, Point, PowerSaveBlockerKind, PromptBuilder,
-    PromptButton, PromptHandle, PromptLevel, Render, RenderImage, RenderablePromptHandle,
-    Reservation, ScreenCaptureSource, SharedString, Size, SubscriberSet, Subscription, SvgRenderer,
-    SystemPowerEvent, Task, TextSystem, TrayIconEvent, TrayMenuItem, Window, WindowAppearance,
+    Keystroke, LayoutId, MediaKeyEvent, Menu, MenuItem, NetworkStatus, NotificationEvent,
+    NotificationId, NotificationOptions, NowPlaying, OsInfo, OwnedMenu, PathPromptOptions,
+    PermissionStatus, Pixels, Platform, PlatformDisplay, PlatformKeyboardLayout,
+    PlatformKeyboardMapper, Point, PowerSaveBlockerKind, PromptBuilder, PromptButton, PromptHandle,
+    PromptLevel, Render, RenderImage, RenderablePromptHandle, Reservation, ScreenCaptureSource,
+    SharedString, Size, SubscriberSet, Subscription, SvgRenderer, SystemPowerEvent, Task,
+    TextSystem, TrayIcon, TrayIconEvent, TrayIconFrame, TrayMenuItem, Window, WindowAppearance,
     WindowHandle, WindowId, WindowInvalidator, WindowPosition,
     colors::{Colors, GlobalColors},
     current_platform, hash, init_app_menus,
+    single_instance::{SecondInstanceArgs, SecondInstanceReply},
 };
 
 mod async_context;
 mod context;
 mod entity_map;
+pub(crate) mod recording;
 #[cfg(any(test, feature = "test-support"))]
 mod test_context;
 
@@ -143,9 +148,15 @@ impl Application {
         ))
     }
 
-    /// Build an app in headless mode. This prevents opening windows,
-    /// but makes it possible to run an application in an context like
-    /// SSH, where GUI applications are not allowed.
+    /// Build an app in headless mode. This makes it possible to run an application in an context
+    /// like SSH, where GUI applications are not allowed.
+    ///
+    /// On Linux, windows open on the virtual displays configured by `GPUI_HEADLESS_DISPLAYS`, as in
+    /// `1920x1080@2,1280x720`, and are drawn in memory by a software renderer. Without it, there
+    /// are no displays to open windows on. `GPUI_HEADLESS_FRAMES` names a directory to write the
+    /// frames into as PNG, which happens in the background and skips frames while it falls behind.
+    /// Frames can also be read with [`Window::capture_frame`] or saved with
+    /// [`Window::save_frame`]. Elsewhere, headless apps can't open windows.
     pub fn headless() -> Self {
         Self(App::new_app(
             current_platform(true),
@@ -238,6 +249,7 @@ pub(crate) type KeystrokeObserver =
     Box<dyn FnMut(&KeystrokeEvent, &mut Window, &mut App) -> bool + 'static>;
 type QuitHandler = Box<dyn FnOnce(&mut App) -> LocalBoxFuture<'static, ()> + 'static>;
 type WindowClosedHandler = Box<dyn FnMut(&mut App)>;
+type FocusedWindowHandler = Box<dyn FnMut(&Option<FocusedWindowInfo>, &mut App) -> bool + 'static>;
 type ReleaseListener = Box<dyn FnOnce(&mut dyn Any, &mut App) + 'static>;
 type NewEntityListener = Box<dyn FnMut(AnyEntity, &mut Option<&mut Window>, &mut App) + 'static>;
 
@@ -576,6 +588,7 @@ pub struct App {
     pub(crate) keystroke_observers: SubscriberSet<(), KeystrokeObserver>,
     pub(crate) keystroke_interceptors: SubscriberSet<(), KeystrokeObserver>,
     pub(crate) keyboard_layout_observers: SubscriberSet<(), Handler>,
+    pub(crate) focused_window_observers: SubscriberSet<(), FocusedWindowHandler>,
     pub(crate) release_listeners: SubscriberSet<EntityId, ReleaseListener>,
     pub(crate) global_observers: SubscriberSet<TypeId, Handler>,
     pub(crate) quit_observers: SubscriberSet<(), QuitHandler>,
@@ -585,6 +598,10 @@ pub struct App {
     pub(crate) layout_id_buffer: Vec<LayoutId>, // We recycle this memory across layout requests.
     pub(crate) propagate_event: bool,
     pub(crate) prompt_builder: Option<PromptBuilder>,
+    tray_icon_animation: Option<Task<()>>,
+    tray_attention_animation: Option<Task<()>>,
+    tray_overlay_animation: Option<Task<()>>,
+    pub(crate) input_recorder: SharedRecorder,
     pub(crate) window_invalidators_by_entity:
         FxHashMap<EntityId, FxHashMap<WindowId, WindowInvalidator>>,
     pub(crate) tracked_entities: FxHashMap<WindowId, FxHashSet<EntityId>>,
@@ -625,7 +642,7 @@ impl App {
                 flushing_effects: false,
                 pending_updates: 0,
                 active_drag: None,
-                background_executor: executor,
+                background_executor: executor.clone(),
                 foreground_executor,
                 svg_renderer: SvgRenderer::new(asset_source.clone()),
                 loading_assets: Default::default(),
@@ -653,6 +670,7 @@ impl App {
                 keystroke_observers: SubscriberSet::new(),
                 keystroke_interceptors: SubscriberSet::new(),
                 keyboard_layout_observers: SubscriberSet::new(),
+                focused_window_observers: SubscriberSet::new(),
                 global_observers: SubscriberSet::new(),
                 quit_observers: SubscriberSet::new(),
                 restart_observers: SubscriberSet::new(),
@@ -661,6 +679,10 @@ impl App {
                 layout_id_buffer: Default::default(),
                 propagate_event: true,
                 prompt_builder: Some(PromptBuilder::Default),
+                tray_icon_animation: None,
+                tray_attention_animation: None,
+                tray_overlay_animation: None,
+                input_recorder: executor.input_recorder.clone(),
                 #[cfg(any(feature = "inspector", debug_assertions))]
                 inspector_renderer: None,
                 #[cfg(any(feature = "inspector", debug_assertions))]
@@ -689,6 +711,22 @@ impl App {
             }
         }));
 
+        platform.on_focused_window_change(Box::new({
+            let app = Rc::downgrade(&app);
+            move |info| {
+                if let Some(app) = app.upgrade() {
+                    let cx = &mut app.borrow_mut();
+                    cx.focused_window_observers
+                        .clone()
+                        .retain(&(), |callback| (callback)(&info, cx));
+                    // The last subscription may have been dropped by one of the callbacks.
+                    if cx.focused_window_observers.is_empty() {
+                        cx.platform.watch_focused_window(false);
+                    }
+                }
+            }
+        }));
+
         platform.on_quit(Box::new({
             let cx = app.clone();
             move || {
@@ -696,6 +734,18 @@ impl App {
             }
         }));
 
+        #[cfg(feature = "input-recording")]
+        if let Some(path) = std::env::var_os(recording::RECORD_INPUT_ENV) {
+            let options = InputRecordingOptions {
+                frames: false,
+                timers: true,
+            };
+            let recorder = Recorder::to_file(options, Path::new(&path)).log_err();
+            let app = app.borrow();
+            *app.input_recorder.lock() = recorder;
+            recording::flush_on_panic(&app.input_recorder);
+        }
+
         app
     }
 
@@ -722,6 +772,9 @@ impl App {
             log::error!("timed out waiting on app_will_quit");
         }
 
+        if let Some(recorder) = self.input_recorder.lock().as_mut() {
+            recorder.flush();
+        }
         self.quitting = false;
     }
 
@@ -969,8 +1022,19 @@ impl App {
                     let clear = window.draw(cx);
                     clear.clear();
 
+                    let modal_parent = window.modal_parent;
                     cx.window_handles.insert(id, window.handle);
                     cx.windows.get_mut(id).unwrap().replace(window);
+                    // The parent may be updating, so record the dialog once it's back in place.
+                    if let Some(parent) = modal_parent {
+                        cx.defer(move |cx| {
+                            parent
+                                .update(cx, |_, parent, _| {
+                                    parent.modal_dialog = Some(handle.into());
+                                })
+                                .ok();
+                        });
+                    }
                     Ok(handle)
                 }
                 Err(e) => {
@@ -1001,13 +1065,55 @@ impl App {
         self.platform.unhide_other_apps();
     }
 
-    /// Set the system tray icon.
-    pub fn set_tray_icon(&self, icon: Option<&[u8]>) {
-        self.platform.set_tray_icon(icon);
+    /// Set the system tray icon. An animated icon keeps animating until the icon is replaced.
+    pub fn set_tray_icon(&mut self, icon: Option<TrayIcon>) {
+        self.tray_icon_animation =
+            self.show_tray_icon(icon, |platform, frame| platform.set_tray_icon(frame));
+    }
+
+    /// Ask for the user's attention with `icon`, or stop asking with `None`. Tray hosts on Linux
+    /// highlight the icon or show `icon` in place of the normal one.
+    pub fn set_tray_attention(&mut self, icon: Option<TrayIcon>) {
+        self.tray_attention_animation =
+            self.show_tray_icon(icon, |platform, frame| platform.set_tray_attention(frame));
+    }
+
+    /// Show `icon` as a badge over the tray icon, such as to mark unread messages, or remove the
+    /// badge with `None`. Only tray hosts on Linux show overlay icons.
+    pub fn set_tray_overlay(&mut self, icon: Option<TrayIcon>) {
+        self.tray_overlay_animation =
+            self.show_tray_icon(icon, |platform, frame| platform.set_tray_overlay(frame));
+    }
+
+    /// Show the frames of `icon` with `show`, returning the task that animates them.
+    fn show_tray_icon(
+        &self,
+        icon: Option<TrayIcon>,
+        show: fn(&dyn Platform, Option<TrayIconFrame>),
+    ) -> Option<Task<()>> {
+        let (frames, interval) = match icon {
+            Some(icon) => icon.frames(&self.svg_renderer),
+            None => (Vec::new(), Duration::ZERO),
+        };
+        if frames.len() < 2 || interval.is_zero() {
+            show(self.platform.as_ref(), frames.into_iter().next());
+            return None;
+        }
+        let platform = self.platform.clone();
+        let executor = self.background_executor.clone();
+        Some(self.foreground_executor.spawn(async move {
+            for frame in frames.iter().cycle() {
+                show(platform.as_ref(), Some(frame.clone()));
+                executor.timer(interval).await;
+            }
+        }))
     }
 
     /// Set the system tray menu items.
-    pub fn set_tray_menu(&self, menu: Vec<TrayMenuItem>) {
+    pub fn set_tray_menu(&self, mut menu: Vec<TrayMenuItem>) {
+        for item in &mut menu {
+            item.resolve_icons(&self.svg_renderer);
+        }
         self.platform.set_tray_menu(menu);
     }
 
@@ -1065,11 +1171,82 @@ impl App {
         self.platform.on_global_hotkey(Box::new(callback));
     }
 
+    /// Register a callback for launches of the application while it is already running, once
+    /// [`SingleInstance::acquire`](crate::single_instance::SingleInstance::acquire) succeeded. The
+    /// returned reply is sent back to the launch, which receives it from
+    /// [`send_to_existing`](crate::single_instance::send_to_existing). Launches that happen before
+    /// the callback is registered are delivered to it afterwards, but get the default reply right
+    /// away. Only one callback can be registered per application id.
+    pub fn on_second_instance(
+        &self,
+        app_id: &str,
+        mut callback: impl FnMut(SecondInstanceArgs, &mut App) -> SecondInstanceReply + 'static,
+    ) {
+        let Some(mut requests) = crate::single_instance::take_second_instance_requests(app_id)
+        else {
+            log::error!("a second instance callback was already registered for {app_id}");
+            return;
+        };
+        self.spawn(async move |cx| {
+            while let Some(request) = requests.next().await {
+                let Ok(reply) = cx.update(|cx| callback(request.args, cx)) else {
+                    break;
+                };
+                request.reply.send(reply).ok();
+            }
+        })
+        .detach();
+    }
+
     /// Get information about the currently focused window from any application.
+    ///
+    /// On Wayland, the compositor only reports other applications' windows once this or
+    /// [`Self::on_focused_window_change`] was first called, so the first call returns `None`.
     pub fn focused_window_info(&self) -> Option<FocusedWindowInfo> {
         self.platform.focused_window_info()
     }
 
+    /// Register a callback for when the focused window of any application changes, or the title
+    /// of the focused window does. The platform only watches the focused window while there are
+    /// subscriptions.
+    pub fn on_focused_window_change(
+        &self,
+        mut callback: impl FnMut(Option<FocusedWindowInfo>, &mut App) + 'static,
+    ) -> Subscription {
+        if self.focused_window_observers.is_empty() {
+            self.platform.watch_focused_window(true);
+        }
+        let (subscription, activate) = self.focused_window_observers.insert(
+            (),
+            Box::new(move |info, cx| {
+                callback(info.clone(), cx);
+                true
+            }),
+        );
+        activate();
+        let observers = self.focused_window_observers.clone();
+        let platform = self.platform.clone();
+        Subscription::join(
+            subscription,
+            Subscription::new(move || {
+                if observers.is_empty() {
+                    platform.watch_focused_window(false);
+                }
+            }),
+        )
+    }
+
+    /// Register a callback for when a display is connected, disconnected or rearranged, or the
+    /// panels on it change its [`PlatformDisplay::visible_bounds`].
+    pub fn on_displays_changed(&self, mut callback: impl FnMut(&mut App) + 'static) {
+        let this = self.this.clone();
+        self.platform.on_displays_changed(Box::new(move || {
+            if let Some(app) = this.upgrade() {
+                callback(&mut app.borrow_mut());
+            }
+        }));
+    }
+
     /// Check accessibility permission status.
     pub fn accessibility_status(&self) -> PermissionStatus {
         self.platform.accessibility_status()
@@ -1102,10 +1279,45 @@ impl App {
     }
 
     /// Show an OS notification.
+    ///
+    /// On Linux the notification is sent without waiting for the notification server, so this
+    /// only fails if notifications aren't supported, and later failures are logged. Use
+    /// [`App::show_notification_with_options`] to find out whether the notification was shown.
     pub fn show_notification(&self, title: &str, body: &str) -> Result<()> {
         self.platform.show_notification(title, body)
     }
 
+    /// Show an OS notification with actions, an icon and other options. The task resolves to
+    /// an id that can be passed to [`NotificationOptions::replaces`] or
+    /// [`App::close_notification`].
+    ///
+    /// Only Linux supports the options so far. On macOS and Windows, only the title and body are
+    /// shown, and the notification can't be replaced or closed.
+    pub fn show_notification_with_options(
+        &self,
+        options: NotificationOptions,
+    ) -> Task<Result<NotificationId>> {
+        self.platform.show_notification_with_options(options)
+    }
+
+    /// Close a notification shown with [`App::show_notification_with_options`].
+    pub fn close_notification(&self, id: NotificationId) {
+        self.platform.close_notification(id);
+    }
+
+    /// Register a callback for actions invoked on, replies to, and closing of notifications.
+    pub fn on_notification_event(
+        &self,
+        mut callback: impl FnMut(NotificationEvent, &mut App) + 'static,
+    ) {
+        let this = self.this.clone();
+        self.platform.on_notification_event(Box::new(move |event| {
+            if let Some(app) = this.upgrade() {
+                callback(event, &mut app.borrow_mut());
+            }
+        }));
+    }
+
     /// Set whether the application should stay alive when all windows are closed.
     pub fn set_keep_alive_without_windows(&self, keep_alive: bool) {
         self.platform.set_keep_alive_without_windows(keep_alive);
@@ -1125,8 +1337,19 @@ impl App {
             }));
     }
 
+    /// Ask the system to wait before sleeping or shutting down until the
+    /// [`SystemPowerEvent::Suspend`] or [`SystemPowerEvent::Shutdown`] callback has returned,
+    /// so that it can finish saving state. The delay is bounded by the system. Pass `None` to
+    /// stop delaying sleep.
+    ///
+    /// This is only supported on Linux, where it takes a logind delay inhibitor.
+    pub fn delay_system_sleep(&self, reason: Option<&str>) {
+        self.platform.delay_system_sleep(reason);
+    }
+
     /// Start a power save blocker to prevent the system from sleeping or the display from dimming.
-    pub fn start_power_save_blocker(&self, kind: PowerSaveBlockerKind) -> Option<u32> {
+    /// The task resolves to an id that can be passed to [`App::stop_power_save_blocker`].
+    pub fn start_power_save_blocker(&self, kind: PowerSaveBlockerKind) -> Task<Result<u32>> {
         self.platform.start_power_save_blocker(kind)
     }
 
@@ -1173,6 +1396,14 @@ impl App {
             }));
     }
 
+    /// Show the media being played in the OS media controls, or clear it with `None`. Controls
+    /// used on the media are reported through [`App::on_media_key_event`].
+    ///
+    /// This is only supported on Linux, where it publishes an MPRIS player.
+    pub fn set_now_playing(&self, now_playing: Option<NowPlaying>) {
+        self.platform.set_now_playing(now_playing);
+    }
+
     /// Request the user's attention by bouncing the dock icon or flashing the taskbar.
     pub fn request_user_attention(&self, attention_type: AttentionType) {
         self.platform.request_user_attention(attention_type);
@@ -1189,27 +1420,49 @@ impl App {
     }
 
     /// Show a context menu at the given screen position with the specified menu items.
+    ///
+    /// Where the platform has no native context menus, GPUI renders the menu itself, in a popup
+    /// attached to the active window.
     pub fn show_context_menu(
-        &self,
+        &mut self,
         position: Point<Pixels>,
         items: Vec<TrayMenuItem>,
-        mut callback: impl FnMut(SharedString, &mut App) + 'static,
+        callback: impl FnMut(SharedString, &mut App) + 'static,
     ) {
         let this = self.this.clone();
-        self.platform.show_context_menu(
+        let callback = Rc::new(RefCell::new(callback));
+        let shown = self.platform.show_context_menu(
             position,
             items,
-            Box::new(move |id| {
-                if let Some(app) = this.upgrade() {
-                    callback(id, &mut app.borrow_mut());
+            Box::new({
+                let callback = callback.clone();
+                move |id| {
+                    if let Some(app) = this.upgrade() {
+                        (callback.borrow_mut())(id, &mut app.borrow_mut());
+                    }
                 }
             }),
         );
+        if let Err(items) = shown {
+            crate::open_context_menu(position, items, callback, self);
+        }
     }
 
     /// Show a native dialog with the given options, returning the index of the clicked button.
+    /// On Linux, GPUI renders the dialog in a modal window of its own, transient for the active
+    /// window.
     pub fn show_dialog(&self, options: DialogOptions) -> oneshot::Receiver<usize> {
-        self.platform.show_dialog(options)
+        if self.platform.has_native_dialogs() {
+            return self.platform.show_dialog(options);
+        }
+        let (sender, receiver) = oneshot::channel();
+        // Dialogs are usually shown from an event handler of the parent window, which can only be
+        // read once that handler returns.
+        self.spawn(async move |cx| {
+            cx.update(|cx| crate::open_dialog(options, sender, cx)).ok();
+        })
+        .detach();
+        receiver
     }
 
     /// Get operating system information (name, version, architecture).
@@ -1217,8 +1470,9 @@ impl App {
         self.platform.os_info()
     }
 
-    /// Check whether biometric authentication (Touch ID, Windows Hello) is available.
-    pub fn biometric_status(&self) -> BiometricStatus {
+    /// Check whether biometric authentication (Touch ID, Windows Hello, fprintd) is available.
+    /// On Linux this asks fprintd over D-Bus, which may have to be started first.
+    pub fn biometric_status(&self) -> Task<BiometricStatus> {
         self.platform.biometric_status()
     }
 
@@ -1232,6 +1486,22 @@ impl App {
             .authenticate_biometric(reason, Box::new(callback));
     }
 
+    /// Ask the system to authorize a privileged action, identified by a polkit action id on Linux.
+    /// The callback receives whether the user is allowed to perform it, after authenticating as an
+    /// administrator if the system requires it. Always `false` on other platforms.
+    ///
+    /// polkit doesn't show `reason`, but the `<message>` of the action in its `.policy` file, as
+    /// only root and the action's `org.freedesktop.policykit.owner` may pass one of their own.
+    pub fn authenticate_admin(
+        &self,
+        action_id: &str,
+        reason: &str,
+        callback: impl FnOnce(bool) + Send + 'static,
+    ) {
+        self.platform
+            .authenticate_admin(action_id, reason, Box::new(callback));
+    }
+
     /// Install a panic hook that captures crash reports with backtraces and OS info.
     pub fn set_crash_handler(
         &self,
@@ -1271,6 +1541,10 @@ impl App {
 
             handler(report);
         }));
+
+        // Keep flushing the `GPUI_RECORD_INPUT` recording, whose panic hook this replaced.
+        #[cfg(feature = "input-recording")]
+        recording::flush_on_panic(&self.input_recorder);
     }
 
     /// Returns the list of currently active displays.
@@ -1345,7 +1619,35 @@ impl App {
 
     /// Reads data from the platform clipboard.
     pub fn read_from_clipboard(&self) -> Option<ClipboardItem> {
-        self.platform.read_from_clipboard()
+        let item = self.platform.read_from_clipboard();
+        self.record_input(|| RecordedEvent::ClipboardRead {
+            text: item.as_ref().and_then(ClipboardItem::text),
+        });
+        item
+    }
+
+    /// Start recording the input windows receive, along with window resizes and clipboard reads,
+    /// so a bug can be reproduced with `VisualTestContext::replay`. The options add frames and
+    /// timer firings. Input to elements whose focus handle takes
+    /// [secure input](FocusHandle::secure_input) is left out. Replaces any recording in progress.
+    ///
+    /// Apps built with the `input-recording` feature and launched with `GPUI_RECORD_INPUT` set to
+    /// a path are recorded from the start, with timers, into that file. Stopping that recording
+    /// returns `None`.
+    pub fn start_input_recording(&self, options: InputRecordingOptions) {
+        *self.input_recorder.lock() = Some(Recorder::new(options));
+    }
+
+    /// Stop recording and return what was recorded since [`App::start_input_recording`].
+    pub fn stop_input_recording(&self) -> Option<InputRecording> {
+        self.input_recorder.lock().take()?.finish()
+    }
+
+    /// Add an event to the input recording in progress, if there is one.
+    pub(crate) fn record_input(&self, event: impl FnOnce() -> RecordedEvent) {
+        if let Some(recorder) = self.input_recorder.lock().as_mut() {
+            recorder.record(event());
+        }
     }
 
     /// Writes credentials to the platform keychain.
@@ -1668,6 +1970,13 @@ impl App {
             if window.removed {
                 cx.window_handles.remove(&id);
                 cx.windows.remove(id);
+                if let Some(Some(parent)) = window
+                    .modal_parent
+                    .and_then(|parent| cx.windows.get_mut(parent.window_id()))
+                    && parent.modal_dialog == Some(window.handle)
+                {
+                    parent.modal_dialog = None;
+                }
 
                 cx.window_closed_observers.clone().retain(&(), |callback| {
                     callback(cx);
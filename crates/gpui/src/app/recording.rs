@@ -0,0 +1,379 @@
+//! Recordings of what an app went through, for reproducing bugs in tests.
+
+use std::{
+    fs::File,
+    io::{BufRead as _, BufReader, BufWriter, Write},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context as _, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use util::ResultExt as _;
+
+use crate::{Pixels, PlatformInput, Size};
+
+/// Records the app from launch into this file. Entries are written as they happen, and flushed
+/// when the app quits or panics. Only read when gpui is built with the `input-recording` feature.
+#[cfg(feature = "input-recording")]
+pub(crate) const RECORD_INPUT_ENV: &str = "GPUI_RECORD_INPUT";
+
+/// The recording in progress. The app shares it with its background executor, which records
+/// timers firing on other threads.
+pub(crate) type SharedRecorder = Arc<Mutex<Option<Recorder>>>;
+
+/// What to record besides input, window resizes and clipboard reads.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InputRecordingOptions {
+    /// Record when windows draw a frame. There are many, so this is off by default.
+    pub frames: bool,
+    /// Record when timers started with [`crate::BackgroundExecutor::timer`] fire.
+    pub timers: bool,
+}
+
+/// The recording an app is making, started with [`crate::App::start_input_recording`].
+pub(crate) struct Recorder {
+    started: Instant,
+    sink: Sink,
+    options: InputRecordingOptions,
+    /// Whether the last input went to an element that takes secure input. That input, and the
+    /// clipboard reads it causes, are left out of the recording.
+    secure_input: bool,
+}
+
+/// Where a recording's entries go.
+enum Sink {
+    Memory(Vec<RecordedEntry>),
+    /// The `GPUI_RECORD_INPUT` file, written as the app goes so that a crash keeps what came
+    /// before it.
+    #[cfg_attr(not(feature = "input-recording"), allow(dead_code))]
+    File(BufWriter<File>),
+}
+
+/// Something that happened to the app while it was recorded, and when.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedEntry {
+    /// The time since the recording started.
+    #[serde(rename = "t", with = "micros")]
+    pub at: Duration,
+    /// What happened.
+    #[serde(flatten)]
+    pub event: RecordedEvent,
+}
+
+/// The events a recording captures. Windows are identified by [`crate::WindowId::as_u64`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    /// The platform delivered input to a window.
+    Input {
+        /// The window the input was for.
+        window: u64,
+        /// The input.
+        input: PlatformInput,
+    },
+    /// A window was resized.
+    Resize {
+        /// The window that was resized.
+        window: u64,
+        /// Its new content size.
+        size: Size<Pixels>,
+        /// Its scale factor.
+        scale_factor: f32,
+    },
+    /// A window drew a frame. Only recorded when asked for, as there are many.
+    Frame {
+        /// The window that drew.
+        window: u64,
+    },
+    /// The app read the clipboard.
+    ClipboardRead {
+        /// The text on the clipboard, if any.
+        text: Option<String>,
+    },
+    /// A timer started with [`crate::BackgroundExecutor::timer`] fired. Only recorded when asked
+    /// for, and only for timers started while recording.
+    TimerFired {
+        /// How long the timer was set for.
+        #[serde(with = "micros")]
+        duration: Duration,
+    },
+}
+
+impl RecordedEvent {
+    /// The window this event happened to, if it was window specific.
+    pub fn window(&self) -> Option<u64> {
+        match self {
+            RecordedEvent::Input { window, .. }
+            | RecordedEvent::Resize { window, .. }
+            | RecordedEvent::Frame { window } => Some(*window),
+            RecordedEvent::ClipboardRead { .. } | RecordedEvent::TimerFired { .. } => None,
+        }
+    }
+}
+
+/// A recording made with [`crate::App::start_input_recording`], or by launching an app built with
+/// the `input-recording` feature with `GPUI_RECORD_INPUT` set to the path to save it to. Replay
+/// it in a test with `VisualTestContext::replay`.
+#[derive(Clone, Debug, Default)]
+pub struct InputRecording {
+    /// Everything that happened, in order.
+    pub entries: Vec<RecordedEntry>,
+}
+
+impl InputRecording {
+    /// Read a recording saved with [`InputRecording::save`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).with_context(|| format!("opening input recording {path:?}"))?;
+        let entries = BufReader::new(file)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+            .map(|(index, line)| {
+                serde_json::from_str(&line?)
+                    .with_context(|| format!("reading line {} of {path:?}", index + 1))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { entries })
+    }
+
+    /// Write the recording to a file, one JSON entry per line.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut writer = BufWriter::new(
+            File::create(path).with_context(|| format!("creating input recording {path:?}"))?,
+        );
+        for entry in &self.entries {
+            write_entry(&mut writer, entry)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// The windows the recording has events for, in the order they first appear.
+    pub fn windows(&self) -> Vec<u64> {
+        let mut windows = Vec::new();
+        for window in self.entries.iter().filter_map(|entry| entry.event.window()) {
+            if !windows.contains(&window) {
+                windows.push(window);
+            }
+        }
+        windows
+    }
+}
+
+impl Recorder {
+    pub(crate) fn new(options: InputRecordingOptions) -> Self {
+        Self::with_sink(options, Sink::Memory(Vec::new()))
+    }
+
+    /// Record into a file instead of memory, for `GPUI_RECORD_INPUT`.
+    #[cfg(feature = "input-recording")]
+    pub(crate) fn to_file(options: InputRecordingOptions, path: &Path) -> Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("creating input recording {path:?}"))?;
+        Ok(Self::with_sink(options, Sink::File(BufWriter::new(file))))
+    }
+
+    fn with_sink(options: InputRecordingOptions, sink: Sink) -> Self {
+        Self {
+            started: Instant::now(),
+            sink,
+            options,
+            secure_input: false,
+        }
+    }
+
+    /// Add input a window received, unless it went to an element that takes secure input.
+    pub(crate) fn record_input(&mut self, window: u64, input: &PlatformInput, secure: bool) {
+        self.secure_input = secure;
+        if !secure {
+            self.push(RecordedEvent::Input {
+                window,
+                input: input.clone(),
+            });
+        }
+    }
+
+    /// Add an event to the recording, unless it's one the recording leaves out.
+    pub(crate) fn record(&mut self, event: RecordedEvent) {
+        let skip = match event {
+            RecordedEvent::Frame { .. } => !self.options.frames,
+            RecordedEvent::TimerFired { .. } => !self.options.timers,
+            RecordedEvent::ClipboardRead { .. } => self.secure_input,
+            RecordedEvent::Input { .. } | RecordedEvent::Resize { .. } => false,
+        };
+        if !skip {
+            self.push(event);
+        }
+    }
+
+    pub(crate) fn records_timers(&self) -> bool {
+        self.options.timers
+    }
+
+    fn push(&mut self, event: RecordedEvent) {
+        let entry = RecordedEntry {
+            at: self.started.elapsed(),
+            event,
+        };
+        match &mut self.sink {
+            Sink::Memory(entries) => entries.push(entry),
+            Sink::File(writer) => {
+                write_entry(writer, &entry)
+                    .context("writing input recording")
+                    .log_err();
+            }
+        }
+    }
+
+    /// Write out the entries a file recording has buffered.
+    pub(crate) fn flush(&mut self) {
+        if let Sink::File(writer) = &mut self.sink {
+            writer.flush().context("writing input recording").log_err();
+        }
+    }
+
+    /// The recording, unless it went to a file.
+    pub(crate) fn finish(mut self) -> Option<InputRecording> {
+        self.flush();
+        match self.sink {
+            Sink::Memory(entries) => Some(InputRecording { entries }),
+            Sink::File(_) => None,
+        }
+    }
+}
+
+fn write_entry(writer: &mut impl Write, entry: &RecordedEntry) -> Result<()> {
+    serde_json::to_writer(&mut *writer, entry)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Flush the recording when the app panics, before the panic hook that was installed.
+#[cfg(feature = "input-recording")]
+pub(crate) fn flush_on_panic(recorder: &SharedRecorder) {
+    let recorder = Arc::downgrade(recorder);
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // The panic may have happened while recording, with the lock held.
+        if let Some(recorder) = recorder.upgrade()
+            && let Some(mut recorder) = recorder.try_lock()
+            && let Some(recorder) = recorder.as_mut()
+        {
+            recorder.flush();
+        }
+        previous_hook(info);
+    }));
+}
+
+/// Durations as whole microseconds, to keep recordings small.
+mod micros {
+    use serde::{Deserialize as _, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_micros() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Ok(Duration::from_micros(u64::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Context, FocusHandle, InteractiveElement as _, IntoElement, Modifiers, MouseButton,
+        ParentElement as _, Render, Styled as _, TestAppContext, Window, div, point, px,
+    };
+
+    struct ClickCounter {
+        clicks: usize,
+        focus_handle: FocusHandle,
+    }
+
+    impl ClickCounter {
+        fn new(cx: &mut Context<Self>) -> Self {
+            Self {
+                clicks: 0,
+                focus_handle: cx.focus_handle(),
+            }
+        }
+    }
+
+    impl Render for ClickCounter {
+        fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+            div()
+                .size_full()
+                .track_focus(&self.focus_handle)
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(|this, _, _, _| this.clicks += 1),
+                )
+                .child(format!("{}", self.clicks))
+        }
+    }
+
+    fn inputs(recording: &InputRecording) -> usize {
+        recording
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry.event, RecordedEvent::Input { .. }))
+            .count()
+    }
+
+    #[gpui::test]
+    fn test_record_and_replay(cx: &mut TestAppContext) {
+        let (view, cx) = cx.add_window_view(|_, cx| ClickCounter::new(cx));
+        let window = cx.update(|window, _| window.window_handle().window_id().as_u64());
+
+        cx.update(|_, cx| {
+            cx.start_input_recording(InputRecordingOptions {
+                frames: false,
+                timers: true,
+            })
+        });
+        cx.simulate_click(point(px(10.), px(10.)), Modifiers::none());
+        let _timer = cx.executor().timer(Duration::from_millis(100));
+        cx.executor().advance_clock(Duration::from_millis(100));
+        cx.simulate_click(point(px(20.), px(20.)), Modifiers::none());
+        let recording = cx.update(|_, cx| cx.stop_input_recording()).unwrap();
+        assert_eq!(view.read_with(cx, |view, _| view.clicks), 2);
+        assert_eq!(inputs(&recording), 4);
+        assert_eq!(recording.windows(), [window]);
+        assert!(recording.entries.iter().any(|entry| matches!(
+            entry.event,
+            RecordedEvent::TimerFired { duration } if duration == Duration::from_millis(100)
+        )));
+
+        let path =
+            std::env::temp_dir().join(format!("gpui-recording-{}.jsonl", std::process::id()));
+        recording.save(&path).unwrap();
+        let loaded = InputRecording::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.entries.len(), recording.entries.len());
+
+        cx.replay(&loaded);
+        assert_eq!(view.read_with(cx, |view, _| view.clicks), 4);
+    }
+
+    #[gpui::test]
+    fn test_secure_input_is_not_recorded(cx: &mut TestAppContext) {
+        let (view, cx) = cx.add_window_view(|_, cx| ClickCounter::new(cx));
+        cx.update(|window, cx| {
+            let focus_handle = view.read(cx).focus_handle.clone().secure_input(true);
+            window.focus(&focus_handle);
+        });
+
+        cx.update(|_, cx| cx.start_input_recording(InputRecordingOptions::default()));
+        cx.simulate_click(point(px(10.), px(10.)), Modifiers::none());
+        let recording = cx.update(|_, cx| cx.stop_input_recording()).unwrap();
+        assert_eq!(view.read_with(cx, |view, _| view.clicks), 1);
+        assert_eq!(inputs(&recording), 0);
+    }
+}
@@ -1,11 +1,11 @@
 use crate::{
     Action, AnyView, AnyWindowHandle, App, AppCell, AppContext, AsyncApp, AvailableSpace,
     BackgroundExecutor, BorrowAppContext, Bounds, Capslock, ClipboardItem, DrawPhase, Drawable,
-    Element, Empty, EventEmitter, ForegroundExecutor, Global, InputEvent, Keystroke, Modifiers,
-    ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels,
-    Platform, Point, Render, Result, Size, Task, TestDispatcher, TestPlatform,
-    TestScreenCaptureSource, TestWindow, TextSystem, VisualContext, Window, WindowBounds,
-    WindowHandle, WindowOptions,
+    Element, Empty, EventEmitter, ForegroundExecutor, Global, InputEvent, InputRecording,
+    Keystroke, Modifiers, ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent,
+    MouseUpEvent, Pixels, Platform, Point, RecordedEvent, Render, Result, Size, Task,
+    TestDispatcher, TestPlatform, TestScreenCaptureSource, TestWindow, TextSystem, VisualContext,
+    Window, WindowBounds, WindowHandle, WindowOptions,
 };
 use anyhow::{anyhow, bail};
 use futures::{Stream, StreamExt, channel::oneshot};
@@ -844,6 +844,55 @@ impl VisualTestContext {
         self.background_executor.run_until_parked();
     }
 
+    /// Replay a recording made with [`App::start_input_recording`] into this window, as if the
+    /// user did it again. The recording's first window is replayed; see
+    /// [`VisualTestContext::replay_window`] to pick another one.
+    pub fn replay(&mut self, recording: &InputRecording) {
+        if let Some(window) = recording.windows().first() {
+            self.replay_window(recording, *window);
+        }
+    }
+
+    /// Replay the input and resizes of one window of a recording into this window. The clock is
+    /// advanced between events as much as it was when recording, so timers fire in between just
+    /// like they did, and clipboard reads see the text they saw.
+    pub fn replay_window(&mut self, recording: &InputRecording, window: u64) {
+        let mut last_at = recording
+            .entries
+            .first()
+            .map_or(Duration::ZERO, |entry| entry.at);
+        for (ix, entry) in recording.entries.iter().enumerate() {
+            if entry.event.window().is_some_and(|id| id != window) {
+                continue;
+            }
+            if entry.at > last_at {
+                self.background_executor.advance_clock(entry.at - last_at);
+                last_at = entry.at;
+            }
+            match &entry.event {
+                RecordedEvent::Input { input, .. } => {
+                    let clipboard_text = recording.entries[ix + 1..]
+                        .iter()
+                        .take_while(|entry| !matches!(entry.event, RecordedEvent::Input { .. }))
+                        .find_map(|entry| match &entry.event {
+                            RecordedEvent::ClipboardRead { text } => Some(text.clone()),
+                            _ => None,
+                        })
+                        .flatten();
+                    if let Some(text) = clipboard_text {
+                        self.write_to_clipboard(ClipboardItem::new_string(text));
+                    }
+                    self.test_window(self.window).simulate_input(input.clone());
+                }
+                RecordedEvent::Resize { size, .. } => self.simulate_resize(*size),
+                RecordedEvent::Frame { .. }
+                | RecordedEvent::ClipboardRead { .. }
+                | RecordedEvent::TimerFired { .. } => {}
+            }
+            self.background_executor.run_until_parked();
+        }
+    }
+
     /// Simulates the user blurring the window.
     pub fn deactivate_window(&mut self) {
         if Some(self.window) == self.test_platform.active_window() {
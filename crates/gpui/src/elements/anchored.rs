@@ -141,7 +141,7 @@ impl Element for Anchored {
         }
         let size: Size<Pixels> = (child_max - child_min).into();
 
-        let (origin, mut desired) = self.position_mode.get_position_and_bounds(
+        let (origin, desired) = self.position_mode.get_position_and_bounds(
             self.anchor_position,
             self.anchor_corner,
             size,
@@ -154,57 +154,14 @@ impl Element for Anchored {
             size: window.viewport_size(),
         };
 
-        if self.fit_mode == AnchoredFitMode::SwitchAnchor {
-            let mut anchor_corner = self.anchor_corner;
-
-            if desired.left() < limits.left() || desired.right() > limits.right() {
-                let switched = Bounds::from_corner_and_size(
-                    anchor_corner.other_side_corner_along(Axis::Horizontal),
-                    origin,
-                    size,
-                );
-                if !(switched.left() < limits.left() || switched.right() > limits.right()) {
-                    anchor_corner = anchor_corner.other_side_corner_along(Axis::Horizontal);
-                    desired = switched
-                }
-            }
-
-            if desired.top() < limits.top() || desired.bottom() > limits.bottom() {
-                let switched = Bounds::from_corner_and_size(
-                    anchor_corner.other_side_corner_along(Axis::Vertical),
-                    origin,
-                    size,
-                );
-                if !(switched.top() < limits.top() || switched.bottom() > limits.bottom()) {
-                    desired = switched;
-                }
-            }
-        }
-
-        let client_inset = window.client_inset.unwrap_or(px(0.));
-        let edges = match self.fit_mode {
-            AnchoredFitMode::SnapToWindowWithMargin(edges) => edges,
-            _ => Edges::default(),
-        }
-        .map(|edge| *edge + client_inset);
-
-        // Snap the horizontal edges of the anchored element to the horizontal edges of the window if
-        // its horizontal bounds overflow, aligning to the left if it is wider than the limits.
-        if desired.right() > limits.right() {
-            desired.origin.x -= desired.right() - limits.right() + edges.right;
-        }
-        if desired.left() < limits.left() {
-            desired.origin.x = limits.origin.x + edges.left;
-        }
-
-        // Snap the vertical edges of the anchored element to the vertical edges of the window if
-        // its vertical bounds overflow, aligning to the top if it is taller than the limits.
-        if desired.bottom() > limits.bottom() {
-            desired.origin.y -= desired.bottom() - limits.bottom() + edges.bottom;
-        }
-        if desired.top() < limits.top() {
-            desired.origin.y = limits.origin.y + edges.top;
-        }
+        let desired = fit_anchored_bounds(
+            desired,
+            Bounds::new(origin, Size::default()),
+            self.anchor_corner,
+            limits,
+            self.fit_mode,
+            window.client_inset.unwrap_or(px(0.)),
+        );
 
         let offset = desired.origin - bounds.origin;
         let offset = point(offset.x.round(), offset.y.round());
@@ -240,8 +197,72 @@ impl IntoElement for Anchored {
     }
 }
 
+/// Fit `desired`, the bounds of an element whose `corner` is attached to the opposite corner of
+/// `anchor`, inside `limits` using `fit_mode`, keeping `inset` clear of every edge it snaps to.
+pub(crate) fn fit_anchored_bounds(
+    mut desired: Bounds<Pixels>,
+    anchor: Bounds<Pixels>,
+    corner: Corner,
+    limits: Bounds<Pixels>,
+    fit_mode: AnchoredFitMode,
+    inset: Pixels,
+) -> Bounds<Pixels> {
+    let size = desired.size;
+    let switch = |corner: Corner, axis| {
+        let corner = corner.other_side_corner_along(axis);
+        let switched =
+            Bounds::from_corner_and_size(corner, anchor.corner(corner.opposite_corner()), size);
+        (corner, switched)
+    };
+
+    if fit_mode == AnchoredFitMode::SwitchAnchor {
+        let mut corner = corner;
+
+        if desired.left() < limits.left() || desired.right() > limits.right() {
+            let (switched_corner, switched) = switch(corner, Axis::Horizontal);
+            if !(switched.left() < limits.left() || switched.right() > limits.right()) {
+                corner = switched_corner;
+                desired = switched
+            }
+        }
+
+        if desired.top() < limits.top() || desired.bottom() > limits.bottom() {
+            let (_, switched) = switch(corner, Axis::Vertical);
+            if !(switched.top() < limits.top() || switched.bottom() > limits.bottom()) {
+                desired = switched;
+            }
+        }
+    }
+
+    let edges = match fit_mode {
+        AnchoredFitMode::SnapToWindowWithMargin(edges) => edges,
+        _ => Edges::default(),
+    }
+    .map(|edge| *edge + inset);
+
+    // Snap the horizontal edges of the anchored element to the horizontal edges of the window if
+    // its horizontal bounds overflow, aligning to the left if it is wider than the limits.
+    if desired.right() > limits.right() {
+        desired.origin.x -= desired.right() - limits.right() + edges.right;
+    }
+    if desired.left() < limits.left() {
+        desired.origin.x = limits.origin.x + edges.left;
+    }
+
+    // Snap the vertical edges of the anchored element to the vertical edges of the window if
+    // its vertical bounds overflow, aligning to the top if it is taller than the limits.
+    if desired.bottom() > limits.bottom() {
+        desired.origin.y -= desired.bottom() - limits.bottom() + edges.bottom;
+    }
+    if desired.top() < limits.top() {
+        desired.origin.y = limits.origin.y + edges.top;
+    }
+
+    desired
+}
+
 /// Which algorithm to use when fitting the anchored element to be inside the window.
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum AnchoredFitMode {
     /// Snap the anchored element to the window edge.
     SnapToWindow,
@@ -5,13 +5,17 @@
 //! In order to minimize re-renders, this element's state is stored intrusively
 //! on your own views, so that your code can coordinate directly with the list element's cached state.
 //!
+//! Items can be grouped into sections with [`ListState::set_sections`]. The first item of each
+//! section acts as its header, and while the rest of the section scrolls past, the header stays
+//! pinned to the top of the list.
+//!
 //! If all of your elements are the same height, see [`crate::UniformList`] for a simpler API
 
 use crate::{
     AnyElement, App, AvailableSpace, Bounds, ContentMask, DispatchPhase, Edges, Element, EntityId,
-    FocusHandle, GlobalElementId, Hitbox, HitboxBehavior, InspectorElementId, IntoElement,
-    Overflow, Pixels, Point, ScrollDelta, ScrollWheelEvent, Size, Style, StyleRefinement, Styled,
-    Window, point, px, size,
+    FocusHandle, GlobalElementId, Hitbox, HitboxBehavior, InspectorElementId, InteractiveElement,
+    IntoElement, Overflow, ParentElement, Pixels, Point, ScrollDelta, ScrollStrategy,
+    ScrollWheelEvent, Size, Style, StyleRefinement, Styled, Window, div, point, px, size,
 };
 use collections::VecDeque;
 use refineable::Refineable as _;
@@ -19,6 +23,7 @@ use std::{cell::RefCell, ops::Range, rc::Rc};
 use sum_tree::{Bias, Dimensions, SumTree};
 
 type RenderItemFn = dyn FnMut(usize, &mut Window, &mut App) -> AnyElement + 'static;
+type EdgeHandlerFn = dyn FnMut(&mut Window, &mut App) + 'static;
 
 /// Construct a new list element
 pub fn list(
@@ -30,6 +35,7 @@ pub fn list(
         render_item: Box::new(render_item),
         style: StyleRefinement::default(),
         sizing_behavior: ListSizingBehavior::default(),
+        sticky_headers: true,
     }
 }
 
@@ -39,6 +45,7 @@ pub struct List {
     render_item: Box<RenderItemFn>,
     style: StyleRefinement,
     sizing_behavior: ListSizingBehavior,
+    sticky_headers: bool,
 }
 
 impl List {
@@ -47,6 +54,13 @@ impl List {
         self.sizing_behavior = behavior;
         self
     }
+
+    /// Set whether the header of the section at the top of the list stays pinned while its
+    /// section scrolls. Defaults to `true`. See [`ListState::set_sections`].
+    pub fn sticky_headers(mut self, sticky: bool) -> Self {
+        self.sticky_headers = sticky;
+        self
+    }
 }
 
 /// The list state that views must hold on behalf of the list element.
@@ -70,6 +84,40 @@ struct StateInner {
     #[allow(clippy::type_complexity)]
     scroll_handler: Option<Box<dyn FnMut(&ListScrollEvent, &mut Window, &mut App)>>,
     scrollbar_drag_start_height: Option<Pixels>,
+    deferred_scroll_to_item: Option<(usize, ScrollStrategy)>,
+    sections: Vec<usize>,
+    reached_start: Option<EdgeHandler>,
+    reached_end: Option<EdgeHandler>,
+}
+
+/// A handler called when the viewport comes within `threshold` of one end of the list.
+struct EdgeHandler {
+    threshold: Pixels,
+    handler: Rc<RefCell<Box<EdgeHandlerFn>>>,
+    /// The item count when the handler was last called, while the viewport remains within the
+    /// threshold. The handler is called again if the item count changes, so that it can keep
+    /// loading pages until the viewport is filled.
+    fired_at_item_count: Option<usize>,
+}
+
+impl EdgeHandler {
+    fn update(
+        &mut self,
+        distance: Option<Pixels>,
+        item_count: usize,
+        window: &Window,
+        cx: &mut App,
+    ) {
+        if distance.is_some_and(|distance| distance <= self.threshold) {
+            if self.fired_at_item_count != Some(item_count) {
+                self.fired_at_item_count = Some(item_count);
+                let handler = self.handler.clone();
+                window.defer(cx, move |window, cx| (handler.borrow_mut())(window, cx));
+            }
+        } else {
+            self.fired_at_item_count = None;
+        }
+    }
 }
 
 /// Whether the list is scrolling from top to bottom or bottom to top.
@@ -91,6 +139,9 @@ pub struct ListScrollEvent {
 
     /// Whether the list has been scrolled.
     pub is_scrolled: bool,
+
+    /// The section containing the first visible item, if the list has sections.
+    pub visible_section: Option<usize>,
 }
 
 /// The sizing behavior to apply during layout.
@@ -129,6 +180,7 @@ struct ItemLayout {
 pub struct ListPrepaintState {
     hitbox: Hitbox,
     layout: LayoutItemsResponse,
+    sticky_header: Option<AnyElement>,
 }
 
 #[derive(Clone)]
@@ -203,6 +255,10 @@ impl ListState {
             scroll_handler: None,
             reset: false,
             scrollbar_drag_start_height: None,
+            deferred_scroll_to_item: None,
+            sections: Vec::new(),
+            reached_start: None,
+            reached_end: None,
         })));
         this.splice(0..0, item_count);
         this
@@ -217,6 +273,7 @@ impl ListState {
             state.reset = true;
             state.logical_scroll_top = None;
             state.scrollbar_drag_start_height = None;
+            state.deferred_scroll_to_item = None;
             state.items.summary().count
         };
 
@@ -261,6 +318,15 @@ impl ListState {
         drop(old_items);
         state.items = new_items;
 
+        state
+            .sections
+            .retain(|header_ix| !old_range.contains(header_ix));
+        for header_ix in &mut state.sections {
+            if *header_ix >= old_range.end {
+                *header_ix = *header_ix - (old_range.end - old_range.start) + spliced_count;
+            }
+        }
+
         if let Some(ListOffset {
             item_ix,
             offset_in_item,
@@ -275,6 +341,57 @@ impl ListState {
         }
     }
 
+    /// Insert `count` items at the start of the list, keeping the items currently in view at the
+    /// same position on screen. Use this to page in older items above the ones being read, as in
+    /// a chat or log view.
+    ///
+    /// A list with [`ListAlignment::Bottom`] that is scrolled to the bottom stays there.
+    pub fn prepend(&self, count: usize) {
+        {
+            let state = &mut *self.0.borrow_mut();
+            if state.logical_scroll_top.is_none() && state.alignment == ListAlignment::Top {
+                state.logical_scroll_top = Some(ListOffset::default());
+            }
+        }
+        self.splice(0..0, count);
+    }
+
+    /// Invalidate the cached sizes of all items while keeping the current scroll position.
+    ///
+    /// Call this when the contents of items may have changed size without the items
+    /// themselves being replaced.
+    pub fn remeasure(&self) {
+        self.0.borrow_mut().invalidate_measurements();
+    }
+
+    /// Group the list's items into sections. Each entry is the index of the item that starts a
+    /// section and serves as its header. Items before the first entry don't belong to any
+    /// section.
+    ///
+    /// Section boundaries follow the items through [`Self::splice`]. A section whose header
+    /// item is removed merges into the preceding section.
+    pub fn set_sections(&self, header_items: impl IntoIterator<Item = usize>) {
+        let state = &mut *self.0.borrow_mut();
+        state.sections = header_items.into_iter().collect();
+        state.sections.sort_unstable();
+        state.sections.dedup();
+    }
+
+    /// The number of sections in the list.
+    pub fn section_count(&self) -> usize {
+        self.0.borrow().sections.len()
+    }
+
+    /// The index of the item that heads the given section.
+    pub fn section_header_item(&self, section_ix: usize) -> Option<usize> {
+        self.0.borrow().sections.get(section_ix).copied()
+    }
+
+    /// The index of the section containing the given item, if any.
+    pub fn section_for_item(&self, ix: usize) -> Option<usize> {
+        self.0.borrow().section_for_item(ix)
+    }
+
     /// Set a handler that will be called when the list is scrolled.
     pub fn set_scroll_handler(
         &self,
@@ -283,6 +400,40 @@ impl ListState {
         self.0.borrow_mut().scroll_handler = Some(Box::new(handler))
     }
 
+    /// Set a handler that will be called when the top of the viewport comes within `threshold`
+    /// of the start of the list, to page in earlier items with [`Self::prepend`].
+    ///
+    /// The handler is called once when the threshold is reached, and again after a change in
+    /// the number of items if the viewport is still within the threshold. Items that haven't
+    /// been measured yet are considered to be beyond the threshold.
+    pub fn on_reached_start(
+        &self,
+        threshold: Pixels,
+        handler: impl FnMut(&mut Window, &mut App) + 'static,
+    ) {
+        self.0.borrow_mut().reached_start = Some(EdgeHandler {
+            threshold,
+            handler: Rc::new(RefCell::new(Box::new(handler))),
+            fired_at_item_count: None,
+        });
+    }
+
+    /// Set a handler that will be called when the bottom of the viewport comes within
+    /// `threshold` of the end of the list, to page in later items with [`Self::splice`].
+    ///
+    /// See [`Self::on_reached_start`] for when the handler is called.
+    pub fn on_reached_end(
+        &self,
+        threshold: Pixels,
+        handler: impl FnMut(&mut Window, &mut App) + 'static,
+    ) {
+        self.0.borrow_mut().reached_end = Some(EdgeHandler {
+            threshold,
+            handler: Rc::new(RefCell::new(Box::new(handler))),
+            fired_at_item_count: None,
+        });
+    }
+
     /// Get the current scroll offset, in terms of the list's items.
     pub fn logical_scroll_top(&self) -> ListOffset {
         self.0.borrow().logical_scroll_top()
@@ -357,6 +508,14 @@ impl ListState {
         state.logical_scroll_top = Some(scroll_top);
     }
 
+    /// Scroll the list so that the given item is placed according to the given strategy.
+    ///
+    /// The scroll position is resolved during the next layout, so that items above the
+    /// target that haven't been measured yet can be measured first.
+    pub fn scroll_to_item(&self, ix: usize, strategy: ScrollStrategy) {
+        self.0.borrow_mut().deferred_scroll_to_item = Some((ix, strategy));
+    }
+
     /// Get the bounds for the given item in window coordinates, if it's
     /// been rendered.
     pub fn bounds_for_item(&self, ix: usize) -> Option<Bounds<Pixels>> {
@@ -445,6 +604,12 @@ impl ListState {
 }
 
 impl StateInner {
+    fn section_for_item(&self, ix: usize) -> Option<usize> {
+        self.sections
+            .partition_point(|header_ix| *header_ix <= ix)
+            .checked_sub(1)
+    }
+
     fn visible_range(&self, height: Pixels, scroll_top: &ListOffset) -> Range<usize> {
         let mut cursor = self.items.cursor::<ListItemSummary>(());
         cursor.seek(&Count(scroll_top.item_ix), Bias::Right);
@@ -490,15 +655,15 @@ impl StateInner {
 
         if self.scroll_handler.is_some() {
             let visible_range = self.visible_range(height, scroll_top);
-            self.scroll_handler.as_mut().unwrap()(
-                &ListScrollEvent {
-                    visible_range,
-                    count: self.items.summary().count,
-                    is_scrolled: self.logical_scroll_top.is_some(),
-                },
-                window,
-                cx,
-            );
+            let event = ListScrollEvent {
+                visible_section: self.section_for_item(visible_range.start),
+                visible_range,
+                count: self.items.summary().count,
+                is_scrolled: self.logical_scroll_top.is_some(),
+            };
+            if let Some(scroll_handler) = self.scroll_handler.as_mut() {
+                scroll_handler(&event, window, cx);
+            }
         }
 
         cx.notify(current_view);
@@ -518,6 +683,70 @@ impl StateInner {
             })
     }
 
+    fn invalidate_measurements(&mut self) {
+        self.items = SumTree::from_iter(
+            self.items.iter().map(|item| ListItem::Unmeasured {
+                focus_handle: item.focus_handle(),
+            }),
+            (),
+        );
+    }
+
+    /// Compute the scroll top that places the item at `ix` according to `strategy`,
+    /// measuring the target and the items above it as needed.
+    fn scroll_top_for_item(
+        &self,
+        ix: usize,
+        strategy: ScrollStrategy,
+        available_width: Pixels,
+        viewport_height: Pixels,
+        render_item: &mut RenderItemFn,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> ListOffset {
+        let item_count = self.items.summary().count;
+        if item_count == 0 {
+            return ListOffset::default();
+        }
+
+        let available_item_space = size(
+            AvailableSpace::Definite(available_width),
+            AvailableSpace::MinContent,
+        );
+        let mut measure = |ix: usize, item: &ListItem, window: &mut Window, cx: &mut App| {
+            item.size()
+                .unwrap_or_else(|| {
+                    let mut element = render_item(ix, window, cx);
+                    element.layout_as_root(available_item_space, window, cx)
+                })
+                .height
+        };
+
+        let ix = ix.min(item_count - 1);
+        let mut cursor = self.items.cursor::<Count>(());
+        cursor.seek(&Count(ix), Bias::Right);
+        let Some(item) = cursor.item() else {
+            return ListOffset::default();
+        };
+        let item_height = measure(ix, item, window, cx);
+
+        let mut space_above = match strategy {
+            ScrollStrategy::Top => px(0.),
+            ScrollStrategy::Center => (viewport_height - item_height) / 2.,
+            ScrollStrategy::Bottom => viewport_height - item_height,
+        };
+        while space_above > px(0.) {
+            cursor.prev();
+            let Some(item) = cursor.item() else { break };
+            space_above -= measure(cursor.start().0, item, window, cx);
+        }
+
+        ListOffset {
+            item_ix: cursor.start().0,
+            offset_in_item: (-space_above).max(px(0.)),
+        }
+    }
+
     fn scroll_top(&self, logical_scroll_top: &ListOffset) -> Pixels {
         let mut cursor = self.items.cursor::<ListItemSummary>(());
         cursor.seek(&Count(logical_scroll_top.item_ix), Bias::Right);
@@ -783,6 +1012,98 @@ impl StateInner {
         })
     }
 
+    /// Call the reached start and end handlers if the viewport is within their thresholds, based
+    /// on the measured item heights after layout.
+    fn update_edge_handlers(
+        &mut self,
+        scroll_top: &ListOffset,
+        viewport_height: Pixels,
+        window: &Window,
+        cx: &mut App,
+    ) {
+        if self.reached_start.is_none() && self.reached_end.is_none() {
+            return;
+        }
+
+        let total = self.items.summary().clone();
+        let mut cursor = self.items.cursor::<ListItemSummary>(());
+        let above: ListItemSummary = cursor.summary(&Count(scroll_top.item_ix), Bias::Right);
+        let distance_to_start =
+            (above.unrendered_count == 0).then(|| above.height + scroll_top.offset_in_item);
+        let distance_to_end = (total.unrendered_count == above.unrendered_count)
+            .then(|| total.height - above.height - scroll_top.offset_in_item - viewport_height);
+
+        let item_count = total.count;
+        if let Some(reached_start) = self.reached_start.as_mut() {
+            reached_start.update(distance_to_start, item_count, window, cx);
+        }
+        if let Some(reached_end) = self.reached_end.as_mut() {
+            reached_end.update(distance_to_end, item_count, window, cx);
+        }
+    }
+
+    /// Lay out the header of the section at the top of the list, pinned to the top and pushed
+    /// upward by the header of the following section as it scrolls into view.
+    fn prepaint_sticky_header(
+        &self,
+        bounds: Bounds<Pixels>,
+        padding: &Edges<Pixels>,
+        layout: &LayoutItemsResponse,
+        render_item: &mut RenderItemFn,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<AnyElement> {
+        let scroll_top = layout.scroll_top;
+        let section_ix = self.section_for_item(scroll_top.item_ix)?;
+        let header_ix = self.sections[section_ix];
+        if header_ix == scroll_top.item_ix && scroll_top.offset_in_item <= px(0.) {
+            return None;
+        }
+
+        let next_header_top = self
+            .sections
+            .get(section_ix + 1)
+            .and_then(|next_header_ix| {
+                let mut top = bounds.top() + padding.top - scroll_top.offset_in_item;
+                for (ix, item) in (scroll_top.item_ix..).zip(&layout.item_layouts) {
+                    if item.index != ix {
+                        break;
+                    }
+                    if item.index == *next_header_ix {
+                        return Some(top);
+                    }
+                    top += item.size.height;
+                }
+                None
+            });
+
+        // The header is also rendered in place as an item, so give the pinned copy its own
+        // namespace for element state.
+        let mut header = div()
+            .id("sticky-header")
+            .w(bounds.size.width)
+            .block_mouse_except_scroll()
+            .child(render_item(header_ix, window, cx))
+            .into_any_element();
+        let header_size = header.layout_as_root(
+            size(
+                AvailableSpace::Definite(bounds.size.width),
+                AvailableSpace::MinContent,
+            ),
+            window,
+            cx,
+        );
+
+        let mut origin = point(bounds.left(), bounds.top() + padding.top);
+        if let Some(next_header_top) = next_header_top {
+            origin.y = origin.y.min(next_header_top - header_size.height);
+        }
+        window.with_content_mask(Some(ContentMask { bounds }), |window| {
+            header.prepaint_at(origin, window, cx);
+        });
+        Some(header)
+    }
+
     // Scrollbar support
 
     fn set_offset_from_scrollbar(&mut self, point: Point<Pixels>) {
@@ -942,19 +1263,25 @@ impl Element for List {
             .last_layout_bounds
             .is_none_or(|last_bounds| last_bounds.size.width != bounds.size.width)
         {
-            let new_items = SumTree::from_iter(
-                state.items.iter().map(|item| ListItem::Unmeasured {
-                    focus_handle: item.focus_handle(),
-                }),
-                (),
-            );
-
-            state.items = new_items;
+            state.invalidate_measurements();
         }
 
         let padding = style
             .padding
             .to_pixels(bounds.size.into(), window.rem_size());
+
+        if let Some((ix, strategy)) = state.deferred_scroll_to_item.take() {
+            let scroll_top = state.scroll_top_for_item(
+                ix,
+                strategy,
+                bounds.size.width,
+                bounds.size.height - padding.top - padding.bottom,
+                &mut self.render_item,
+                window,
+                cx,
+            );
+            state.logical_scroll_top = Some(scroll_top);
+        }
         let layout =
             match state.prepaint_items(bounds, padding, true, &mut self.render_item, window, cx) {
                 Ok(layout) => layout,
@@ -966,9 +1293,33 @@ impl Element for List {
                 }
             };
 
+        state.update_edge_handlers(
+            &layout.scroll_top,
+            bounds.size.height - padding.top - padding.bottom,
+            window,
+            cx,
+        );
+
+        let sticky_header = if self.sticky_headers {
+            state.prepaint_sticky_header(
+                bounds,
+                &padding,
+                &layout,
+                &mut self.render_item,
+                window,
+                cx,
+            )
+        } else {
+            None
+        };
+
         state.last_layout_bounds = Some(bounds);
         state.last_padding = Some(padding);
-        ListPrepaintState { hitbox, layout }
+        ListPrepaintState {
+            hitbox,
+            layout,
+            sticky_header,
+        }
     }
 
     fn paint(
@@ -986,6 +1337,9 @@ impl Element for List {
             for item in &mut prepaint.layout.item_layouts {
                 item.element.paint(window, cx);
             }
+            if let Some(sticky_header) = prepaint.sticky_header.as_mut() {
+                sticky_header.paint(window, cx);
+            }
         });
 
         let list_state = self.state.clone();
@@ -1199,4 +1553,210 @@ mod test {
         assert_eq!(offset.item_ix, 0);
         assert_eq!(offset.offset_in_item, px(0.));
     }
+
+    #[gpui::test]
+    fn test_sticky_section_headers(cx: &mut TestAppContext) {
+        use crate::{
+            Context, Element, InteractiveElement, IntoElement, ListOffset, ListState, Modifiers,
+            Render, StatefulInteractiveElement, Styled, Window, div, list, point, px,
+        };
+        use std::{cell::Cell, rc::Rc};
+
+        let state = ListState::new(20, crate::ListAlignment::Top, px(0.));
+        state.set_sections([0, 5, 10]);
+        let clicked = Rc::new(Cell::new(None));
+
+        struct TestView(ListState, Rc<Cell<Option<usize>>>);
+        impl Render for TestView {
+            fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+                let clicked = self.1.clone();
+                list(self.0.clone(), move |ix, _, _| {
+                    let clicked = clicked.clone();
+                    div()
+                        .id(ix)
+                        .h(px(20.))
+                        .w_full()
+                        .on_click(move |_, _, _| clicked.set(Some(ix)))
+                        .into_any()
+                })
+                .w(px(100.))
+                .h(px(100.))
+            }
+        }
+
+        let (_, cx) = cx.add_window_view(|_, _| TestView(state.clone(), clicked.clone()));
+        let draw = |cx: &mut gpui::VisualTestContext| {
+            cx.update(|window, _| window.refresh());
+            cx.run_until_parked();
+        };
+
+        // While item 7 is at the top, the header of its section covers it.
+        state.scroll_to(ListOffset {
+            item_ix: 7,
+            offset_in_item: px(0.),
+        });
+        draw(cx);
+        cx.simulate_click(point(px(1.), px(1.)), Modifiers::none());
+        assert_eq!(clicked.get(), Some(5));
+
+        // The next section's header pushes the pinned header upward.
+        state.scroll_to(ListOffset {
+            item_ix: 9,
+            offset_in_item: px(10.),
+        });
+        draw(cx);
+        cx.simulate_click(point(px(1.), px(5.)), Modifiers::none());
+        assert_eq!(clicked.get(), Some(5));
+        cx.simulate_click(point(px(1.), px(15.)), Modifiers::none());
+        assert_eq!(clicked.get(), Some(10));
+
+        let visible_section = Rc::new(Cell::new(None));
+        state.set_scroll_handler({
+            let visible_section = visible_section.clone();
+            move |event, _, _| visible_section.set(event.visible_section)
+        });
+        draw(cx);
+        cx.simulate_event(ScrollWheelEvent {
+            position: point(px(1.), px(50.)),
+            delta: ScrollDelta::Pixels(point(px(0.), px(-20.))),
+            ..Default::default()
+        });
+        assert_eq!(visible_section.get(), Some(1));
+
+        // Section boundaries follow their items when items are spliced.
+        state.splice(0..2, 0);
+        assert_eq!(state.section_count(), 2);
+        assert_eq!(state.section_header_item(0), Some(3));
+        assert_eq!(state.section_for_item(2), None);
+        assert_eq!(state.section_for_item(8), Some(1));
+    }
+
+    #[gpui::test]
+    fn test_prepend_and_reached_edges(cx: &mut TestAppContext) {
+        use crate::{
+            Context, Element, IntoElement, ListAlignment, ListOffset, ListState, Render, Styled,
+            Window, div, list, px,
+        };
+        use std::{cell::Cell, rc::Rc};
+
+        // Prepending keeps the item at the top of the viewport in place.
+        let state = ListState::new(10, ListAlignment::Top, px(0.));
+        state.prepend(3);
+        assert_eq!(state.logical_scroll_top().item_ix, 3);
+        state.scroll_to(ListOffset {
+            item_ix: 4,
+            offset_in_item: px(5.),
+        });
+        state.prepend(5);
+        let scroll_top = state.logical_scroll_top();
+        assert_eq!((scroll_top.item_ix, scroll_top.offset_in_item), (9, px(5.)));
+
+        // A bottom-aligned list keeps following the bottom.
+        let state = ListState::new(10, ListAlignment::Bottom, px(0.));
+        state.prepend(5);
+        assert_eq!(state.item_count(), 15);
+        assert_eq!(state.logical_scroll_top().item_ix, 15);
+
+        struct TestView(ListState);
+        impl Render for TestView {
+            fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+                list(self.0.clone(), |_, _, _| div().h(px(20.)).into_any())
+                    .w(px(100.))
+                    .h(px(100.))
+            }
+        }
+
+        let state = ListState::new(50, ListAlignment::Top, px(1000.));
+        let reached_start = Rc::new(Cell::new(0));
+        let reached_end = Rc::new(Cell::new(0));
+        state.on_reached_start(px(40.), {
+            let reached_start = reached_start.clone();
+            move |_, _| reached_start.set(reached_start.get() + 1)
+        });
+        state.on_reached_end(px(40.), {
+            let reached_end = reached_end.clone();
+            move |_, _| reached_end.set(reached_end.get() + 1)
+        });
+
+        let (_, cx) = cx.add_window_view(|_, _| TestView(state.clone()));
+        let draw = |cx: &mut gpui::VisualTestContext| {
+            cx.update(|window, _| window.refresh());
+            cx.run_until_parked();
+        };
+
+        draw(cx);
+        draw(cx);
+        assert_eq!((reached_start.get(), reached_end.get()), (1, 0));
+
+        state.scroll_to(ListOffset {
+            item_ix: 44,
+            offset_in_item: px(0.),
+        });
+        draw(cx);
+        assert_eq!((reached_start.get(), reached_end.get()), (1, 1));
+
+        // Loading more items calls the handler again while the viewport is still in range.
+        state.splice(50..50, 1);
+        draw(cx);
+        assert_eq!((reached_start.get(), reached_end.get()), (1, 2));
+
+        // Scrolling back to the start calls the start handler again.
+        state.scroll_to(ListOffset::default());
+        draw(cx);
+        assert_eq!((reached_start.get(), reached_end.get()), (2, 2));
+    }
+
+    #[gpui::test]
+    fn test_scroll_to_item_with_strategy(cx: &mut TestAppContext) {
+        use crate::{
+            AppContext, Context, Element, IntoElement, ListState, Render, ScrollStrategy, Styled,
+            Window, div, list, point, px, size,
+        };
+
+        let cx = cx.add_empty_window();
+
+        let state = ListState::new(20, crate::ListAlignment::Top, px(0.));
+
+        struct TestView(ListState);
+        impl Render for TestView {
+            fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+                list(self.0.clone(), |_, _, _| {
+                    div().h(px(20.)).w_full().into_any()
+                })
+                .w_full()
+                .h_full()
+            }
+        }
+
+        let draw = |cx: &mut gpui::VisualTestContext| {
+            cx.draw(point(px(0.), px(0.)), size(px(100.), px(100.)), |_, cx| {
+                cx.new(|_| TestView(state.clone()))
+            });
+        };
+
+        // Items that haven't been measured yet are measured while resolving the scroll position.
+        state.scroll_to_item(10, ScrollStrategy::Bottom);
+        draw(cx);
+        let offset = state.logical_scroll_top();
+        assert_eq!(offset.item_ix, 6);
+        assert_eq!(offset.offset_in_item, px(0.));
+
+        state.scroll_to_item(10, ScrollStrategy::Center);
+        draw(cx);
+        let offset = state.logical_scroll_top();
+        assert_eq!(offset.item_ix, 8);
+        assert_eq!(offset.offset_in_item, px(0.));
+
+        state.scroll_to_item(1, ScrollStrategy::Center);
+        draw(cx);
+        let offset = state.logical_scroll_top();
+        assert_eq!(offset.item_ix, 0);
+        assert_eq!(offset.offset_in_item, px(0.));
+
+        state.scroll_to_item(15, ScrollStrategy::Top);
+        draw(cx);
+        let offset = state.logical_scroll_top();
+        assert_eq!(offset.item_ix, 15);
+        assert_eq!(offset.offset_in_item, px(0.));
+    }
 }
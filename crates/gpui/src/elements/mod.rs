@@ -8,9 +8,12 @@ mod img;
 mod list;
 mod surface;
 mod svg;
+mod table;
 mod text;
 mod toast;
+mod tree_view;
 mod uniform_list;
+mod virtual_grid;
 
 pub use anchored::*;
 pub use animation::*;
@@ -22,6 +25,9 @@ pub use img::*;
 pub use list::*;
 pub use surface::*;
 pub use svg::*;
+pub use table::*;
 pub use text::*;
 pub use toast::*;
+pub use tree_view::*;
 pub use uniform_list::*;
+pub use virtual_grid::*;
@@ -0,0 +1,1260 @@
+//! A virtualized data table with resizable, reorderable, sortable and frozen columns.
+//!
+//! Rows have a uniform height, so like [`crate::UniformList`] the table only renders the rows
+//! intersecting its viewport. Columns are virtualized as well: only the scrollable columns that
+//! overlap the viewport horizontally are rendered, while the leading frozen columns stay pinned
+//! to the left edge. The scroll position is tracked by a [`UniformListScrollHandle`], so
+//! `scroll_to_item` on the handle scrolls to a row.
+//!
+//! The table owns a [`FocusHandle`] that participates in tab navigation. While it is focused,
+//! the actions in [`table_actions`] move the focused cell. They are dispatched in the `Table` key
+//! context, and [`bind_table_keys`] binds them to the arrow keys, Page Up/Down, Home/End and Tab.
+
+use crate::{
+    Action, AnyElement, App, AppContext, AvailableSpace, BorderStyle, Bounds, ClickEvent,
+    ContentMask, Context, CursorStyle, DispatchPhase, Element, ElementId, EntityId, FocusHandle,
+    GlobalElementId, Hitbox, Hsla, InspectorElementId, InteractiveElement, Interactivity,
+    IntoElement, KeyBinding, KeyContext, LayoutId, MouseButton, MouseDownEvent, MouseMoveEvent,
+    MouseUpEvent, Overflow, ParentElement, Pixels, Render, ScrollStrategy, SharedString,
+    StatefulInteractiveElement, StyleRefinement, Styled, UniformListScrollHandle, Window, div,
+    hsla, outline, point, px, size,
+};
+use smallvec::SmallVec;
+use std::{cell::RefCell, ops::Range, rc::Rc};
+
+type RenderCellFn = dyn Fn(TableCell, &mut Window, &mut App) -> AnyElement + 'static;
+type RenderHeaderFn = dyn Fn(usize, Option<SortDirection>, &mut Window, &mut App) -> AnyElement;
+type SortListener = dyn Fn(&TableSort, &mut Window, &mut App) + 'static;
+
+const RESIZE_HANDLE_WIDTH: Pixels = px(6.);
+const KEY_CONTEXT: &str = "Table";
+
+/// The actions that move the focused cell of a [`Table`].
+pub mod table_actions {
+    crate::actions!(
+        table,
+        [
+            /// Move the focused cell up one row.
+            SelectUp,
+            /// Move the focused cell down one row.
+            SelectDown,
+            /// Move the focused cell left one column.
+            SelectLeft,
+            /// Move the focused cell right one column.
+            SelectRight,
+            /// Move the focused cell up one page.
+            SelectPageUp,
+            /// Move the focused cell down one page.
+            SelectPageDown,
+            /// Move the focused cell to the first column of its row.
+            SelectFirstColumn,
+            /// Move the focused cell to the last column of its row.
+            SelectLastColumn,
+            /// Move the focused cell to the first row of its column.
+            SelectFirstRow,
+            /// Move the focused cell to the last row of its column.
+            SelectLastRow,
+            /// Move the focused cell to the next cell, wrapping to the next row, or move focus
+            /// out of the table after the last cell.
+            SelectNextCell,
+            /// Move the focused cell to the previous cell, wrapping to the previous row, or move
+            /// focus out of the table before the first cell.
+            SelectPreviousCell,
+        ]
+    );
+}
+
+/// Bind the default keys for the [`table_actions`] in the `Table` key context.
+pub fn bind_table_keys(cx: &mut App) {
+    use table_actions::*;
+
+    let context = Some(KEY_CONTEXT);
+    cx.bind_keys([
+        KeyBinding::new("up", SelectUp, context),
+        KeyBinding::new("down", SelectDown, context),
+        KeyBinding::new("left", SelectLeft, context),
+        KeyBinding::new("right", SelectRight, context),
+        KeyBinding::new("pageup", SelectPageUp, context),
+        KeyBinding::new("pagedown", SelectPageDown, context),
+        KeyBinding::new("home", SelectFirstColumn, context),
+        KeyBinding::new("end", SelectLastColumn, context),
+        KeyBinding::new("secondary-home", SelectFirstRow, context),
+        KeyBinding::new("secondary-end", SelectLastRow, context),
+        KeyBinding::new("tab", SelectNextCell, context),
+        KeyBinding::new("shift-tab", SelectPreviousCell, context),
+    ]);
+}
+
+/// Construct a new table element.
+///
+/// `render_cell` is called for every visible cell, with the row index and the index of the
+/// column in the table's column list (not its current display position).
+#[track_caller]
+pub fn table(
+    id: impl Into<ElementId>,
+    state: TableState,
+    render_cell: impl Fn(TableCell, &mut Window, &mut App) -> AnyElement + 'static,
+) -> Table {
+    let mut base_style = StyleRefinement::default();
+    base_style.overflow.x = Some(Overflow::Scroll);
+    base_style.overflow.y = Some(Overflow::Scroll);
+
+    let (focus_handle, scroll_handle) = {
+        let inner = state.0.borrow();
+        (
+            inner.focus_handle.clone(),
+            inner.scroll_handle.0.borrow().base_handle.clone(),
+        )
+    };
+
+    let mut interactivity = Interactivity {
+        element_id: Some(id.into()),
+        base_style: Box::new(base_style),
+        key_context: KeyContext::parse(KEY_CONTEXT).ok(),
+        tracked_focus_handle: Some(focus_handle),
+        tracked_scroll_handle: Some(scroll_handle),
+        ..Interactivity::new()
+    };
+    {
+        use table_actions::*;
+
+        let interactivity = &mut interactivity;
+        on_movement::<SelectUp>(interactivity, &state, Movement::Up);
+        on_movement::<SelectDown>(interactivity, &state, Movement::Down);
+        on_movement::<SelectLeft>(interactivity, &state, Movement::Left);
+        on_movement::<SelectRight>(interactivity, &state, Movement::Right);
+        on_movement::<SelectPageUp>(interactivity, &state, Movement::PageUp);
+        on_movement::<SelectPageDown>(interactivity, &state, Movement::PageDown);
+        on_movement::<SelectFirstColumn>(interactivity, &state, Movement::FirstColumn);
+        on_movement::<SelectLastColumn>(interactivity, &state, Movement::LastColumn);
+        on_movement::<SelectFirstRow>(interactivity, &state, Movement::FirstRow);
+        on_movement::<SelectLastRow>(interactivity, &state, Movement::LastRow);
+        on_movement::<SelectNextCell>(interactivity, &state, Movement::NextCell);
+        on_movement::<SelectPreviousCell>(interactivity, &state, Movement::PreviousCell);
+    }
+
+    Table {
+        state,
+        render_cell: Rc::new(render_cell),
+        render_header: None,
+        on_sort: None,
+        row_height: px(28.),
+        header_height: px(32.),
+        focus_color: hsla(0.6, 0.8, 0.55, 1.),
+        interactivity,
+    }
+}
+
+fn on_movement<A: Action>(
+    interactivity: &mut Interactivity,
+    state: &TableState,
+    movement: Movement,
+) {
+    let state = state.clone();
+    interactivity.on_action(move |_: &A, window, cx| {
+        if state.move_focused_cell(movement, window) {
+            state.notify(cx);
+        } else {
+            cx.propagate();
+        }
+    });
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Movement {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    FirstColumn,
+    LastColumn,
+    FirstRow,
+    LastRow,
+    NextCell,
+    PreviousCell,
+}
+
+/// A virtualized data table element. See [`table`].
+pub struct Table {
+    state: TableState,
+    render_cell: Rc<RenderCellFn>,
+    render_header: Option<Rc<RenderHeaderFn>>,
+    on_sort: Option<Rc<SortListener>>,
+    row_height: Pixels,
+    header_height: Pixels,
+    focus_color: Hsla,
+    interactivity: Interactivity,
+}
+
+impl Table {
+    /// Set the height of every row. Defaults to 28px.
+    pub fn row_height(mut self, height: Pixels) -> Self {
+        self.row_height = height;
+        self
+    }
+
+    /// Set the height of the header row. Defaults to 32px.
+    pub fn header_height(mut self, height: Pixels) -> Self {
+        self.header_height = height;
+        self
+    }
+
+    /// Set the color of the outline drawn around the focused cell.
+    pub fn focus_color(mut self, color: impl Into<Hsla>) -> Self {
+        self.focus_color = color.into();
+        self
+    }
+
+    /// Render the contents of each column header. The callback receives the column index and
+    /// the column's current sort direction, if the table is sorted by it.
+    ///
+    /// By default the header shows the column title followed by a sort indicator.
+    pub fn render_header(
+        mut self,
+        render_header: impl Fn(usize, Option<SortDirection>, &mut Window, &mut App) -> AnyElement
+        + 'static,
+    ) -> Self {
+        self.render_header = Some(Rc::new(render_header));
+        self
+    }
+
+    /// Called when the user clicks a sortable column header. The table records the new sort
+    /// order in its [`TableState`]; sorting the rows is up to the listener.
+    pub fn on_sort(
+        mut self,
+        listener: impl Fn(&TableSort, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_sort = Some(Rc::new(listener));
+        self
+    }
+}
+
+/// A cell in a [`Table`], identified by its row and its index in the table's column list.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TableCell {
+    /// The row index.
+    pub row: usize,
+    /// The index of the column in the table's column list.
+    pub column: usize,
+}
+
+/// The direction a [`Table`] is sorted in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SortDirection {
+    /// Smallest values first.
+    Ascending,
+    /// Largest values first.
+    Descending,
+}
+
+impl SortDirection {
+    /// The opposite direction.
+    pub fn toggled(self) -> Self {
+        match self {
+            SortDirection::Ascending => SortDirection::Descending,
+            SortDirection::Descending => SortDirection::Ascending,
+        }
+    }
+}
+
+/// The column and direction a [`Table`] is sorted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TableSort {
+    /// The index of the column in the table's column list.
+    pub column: usize,
+    /// The sort direction.
+    pub direction: SortDirection,
+}
+
+/// The definition of a column in a [`Table`].
+#[derive(Clone, Debug)]
+pub struct TableColumn {
+    title: SharedString,
+    width: Pixels,
+    min_width: Pixels,
+    resizable: bool,
+    sortable: bool,
+}
+
+impl TableColumn {
+    /// Create a resizable, unsortable column with the given title and initial width.
+    pub fn new(title: impl Into<SharedString>, width: Pixels) -> Self {
+        Self {
+            title: title.into(),
+            width,
+            min_width: px(24.),
+            resizable: true,
+            sortable: false,
+        }
+    }
+
+    /// Set the minimum width the column can be resized to.
+    pub fn min_width(mut self, min_width: Pixels) -> Self {
+        self.min_width = min_width;
+        self.width = self.width.max(min_width);
+        self
+    }
+
+    /// Set whether the column can be resized by dragging the edge of its header.
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Set whether clicking the column header sorts by this column.
+    pub fn sortable(mut self, sortable: bool) -> Self {
+        self.sortable = sortable;
+        self
+    }
+
+    /// The column title.
+    pub fn title(&self) -> &SharedString {
+        &self.title
+    }
+
+    /// The current width of the column.
+    pub fn width(&self) -> Pixels {
+        self.width
+    }
+}
+
+/// The table state that views must hold on behalf of the table element.
+///
+/// It records the column layout, sort order and focused cell, so they survive re-renders and
+/// can be persisted by the application.
+#[derive(Clone)]
+pub struct TableState(Rc<RefCell<TableStateInner>>);
+
+impl std::fmt::Debug for TableState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TableState")
+    }
+}
+
+struct TableStateInner {
+    columns: Vec<TableColumn>,
+    order: Vec<usize>,
+    frozen_columns: usize,
+    row_count: usize,
+    sort: Option<TableSort>,
+    focused_cell: Option<TableCell>,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    resize: Option<ColumnResize>,
+    reveal_focused_cell: bool,
+    page_rows: usize,
+    view: Option<EntityId>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ColumnResize {
+    column: usize,
+    start_x: Pixels,
+    start_width: Pixels,
+}
+
+impl TableState {
+    /// Create a new table state with the given columns and number of rows.
+    pub fn new(columns: Vec<TableColumn>, row_count: usize, cx: &mut App) -> Self {
+        let order = (0..columns.len()).collect();
+        Self(Rc::new(RefCell::new(TableStateInner {
+            columns,
+            order,
+            frozen_columns: 0,
+            row_count,
+            sort: None,
+            focused_cell: None,
+            focus_handle: cx.focus_handle().tab_stop(true),
+            scroll_handle: UniformListScrollHandle::new(),
+            resize: None,
+            reveal_focused_cell: false,
+            page_rows: 1,
+            view: None,
+        })))
+    }
+
+    /// Set the number of leading columns, in display order, that stay pinned while the table
+    /// scrolls horizontally.
+    pub fn with_frozen_columns(self, count: usize) -> Self {
+        self.set_frozen_columns(count);
+        self
+    }
+
+    /// The number of rows in the table.
+    pub fn row_count(&self) -> usize {
+        self.0.borrow().row_count
+    }
+
+    /// Update the number of rows in the table.
+    pub fn set_row_count(&self, row_count: usize) {
+        let state = &mut *self.0.borrow_mut();
+        state.row_count = row_count;
+        if let Some(cell) = state.focused_cell.as_mut() {
+            if row_count == 0 {
+                state.focused_cell = None;
+            } else {
+                cell.row = cell.row.min(row_count - 1);
+            }
+        }
+    }
+
+    /// The table's columns, in the order they were defined.
+    pub fn columns(&self) -> Vec<TableColumn> {
+        self.0.borrow().columns.clone()
+    }
+
+    /// The column indices in the order they are displayed.
+    pub fn column_order(&self) -> Vec<usize> {
+        self.0.borrow().order.clone()
+    }
+
+    /// Set the order in which the columns are displayed. Returns an error, leaving the order as
+    /// it was, unless `order` is a permutation of the column indices.
+    pub fn set_column_order(&self, order: Vec<usize>) -> anyhow::Result<()> {
+        let state = &mut *self.0.borrow_mut();
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        anyhow::ensure!(
+            sorted.iter().copied().eq(0..state.columns.len()),
+            "column order {order:?} is not a permutation of the column indices"
+        );
+        state.order = order;
+        Ok(())
+    }
+
+    /// Move the column at display position `from` to display position `to`.
+    pub fn move_column(&self, from: usize, to: usize) {
+        let state = &mut *self.0.borrow_mut();
+        if from < state.order.len() && to < state.order.len() && from != to {
+            let column = state.order.remove(from);
+            state.order.insert(to, column);
+        }
+    }
+
+    /// The number of leading columns that stay pinned while the table scrolls horizontally.
+    pub fn frozen_columns(&self) -> usize {
+        self.0.borrow().frozen_columns
+    }
+
+    /// Set the number of leading columns, in display order, that stay pinned while the table
+    /// scrolls horizontally.
+    pub fn set_frozen_columns(&self, count: usize) {
+        let state = &mut *self.0.borrow_mut();
+        state.frozen_columns = count.min(state.columns.len());
+    }
+
+    /// The width of the given column, or `None` if there is no such column.
+    pub fn column_width(&self, column: usize) -> Option<Pixels> {
+        Some(self.0.borrow().columns.get(column)?.width)
+    }
+
+    /// Set the width of the given column, respecting its minimum width. Does nothing if there is
+    /// no such column.
+    pub fn set_column_width(&self, column: usize, width: Pixels) {
+        let state = &mut *self.0.borrow_mut();
+        if let Some(column) = state.columns.get_mut(column) {
+            column.width = width.max(column.min_width);
+        }
+    }
+
+    /// The column and direction the table is sorted by, if any.
+    pub fn sort(&self) -> Option<TableSort> {
+        self.0.borrow().sort
+    }
+
+    /// Set the column and direction the table is sorted by.
+    pub fn set_sort(&self, sort: Option<TableSort>) {
+        self.0.borrow_mut().sort = sort;
+    }
+
+    /// The focused cell, if any.
+    pub fn focused_cell(&self) -> Option<TableCell> {
+        self.0.borrow().focused_cell
+    }
+
+    /// Focus the given cell and scroll it into view during the next layout.
+    pub fn set_focused_cell(&self, cell: Option<TableCell>) {
+        let state = &mut *self.0.borrow_mut();
+        state.focused_cell = cell;
+        state.reveal_focused_cell = cell.is_some();
+    }
+
+    /// The focus handle of the table, which is a tab stop.
+    pub fn focus_handle(&self) -> FocusHandle {
+        self.0.borrow().focus_handle.clone()
+    }
+
+    /// The scroll handle tracking the table's scroll position. Use
+    /// [`UniformListScrollHandle::scroll_to_item`] to scroll to a row.
+    pub fn scroll_handle(&self) -> UniformListScrollHandle {
+        self.0.borrow().scroll_handle.clone()
+    }
+
+    fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+
+    fn notify(&self, cx: &mut App) {
+        if let Some(view) = self.0.borrow().view {
+            cx.notify(view);
+        }
+    }
+
+    fn cycle_sort(&self, column: usize) -> TableSort {
+        let state = &mut *self.0.borrow_mut();
+        let direction = match state.sort {
+            Some(sort) if sort.column == column => sort.direction.toggled(),
+            _ => SortDirection::Ascending,
+        };
+        let sort = TableSort { column, direction };
+        state.sort = Some(sort);
+        sort
+    }
+
+    /// Move the focused cell, returning whether the movement was handled.
+    fn move_focused_cell(&self, movement: Movement, window: &mut Window) -> bool {
+        let state = &mut *self.0.borrow_mut();
+        if state.row_count == 0 || state.order.is_empty() {
+            return false;
+        }
+
+        let last_row = state.row_count - 1;
+        let last_position = state.order.len() - 1;
+        let (row, position) = match state.focused_cell {
+            Some(cell) => (
+                cell.row.min(last_row),
+                state
+                    .order
+                    .iter()
+                    .position(|column| *column == cell.column)
+                    .unwrap_or(0),
+            ),
+            None => (0, 0),
+        };
+
+        let (row, position) = match movement {
+            Movement::Up => (row.saturating_sub(1), position),
+            Movement::Down => ((row + 1).min(last_row), position),
+            Movement::Left => (row, position.saturating_sub(1)),
+            Movement::Right => (row, (position + 1).min(last_position)),
+            Movement::PageUp => (row.saturating_sub(state.page_rows), position),
+            Movement::PageDown => ((row + state.page_rows).min(last_row), position),
+            Movement::FirstRow => (0, position),
+            Movement::LastRow => (last_row, position),
+            Movement::FirstColumn => (row, 0),
+            Movement::LastColumn => (row, last_position),
+            Movement::PreviousCell => {
+                if position > 0 {
+                    (row, position - 1)
+                } else if row > 0 {
+                    (row - 1, last_position)
+                } else {
+                    window.focus_prev();
+                    return true;
+                }
+            }
+            Movement::NextCell => {
+                if position < last_position {
+                    (row, position + 1)
+                } else if row < last_row {
+                    (row + 1, 0)
+                } else {
+                    window.focus_next();
+                    return true;
+                }
+            }
+        };
+
+        state.focused_cell = Some(TableCell {
+            row,
+            column: state.order[position],
+        });
+        state.reveal_focused_cell = true;
+        true
+    }
+}
+
+/// The value dragged while reordering the columns of a [`Table`].
+#[derive(Clone, Debug)]
+pub struct DraggedTableColumn {
+    table: usize,
+    position: usize,
+    title: SharedString,
+}
+
+impl Render for DraggedTableColumn {
+    fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .px_2()
+            .py_1()
+            .rounded_sm()
+            .bg(hsla(0., 0., 0.5, 0.2))
+            .child(self.title.clone())
+    }
+}
+
+/// Frame state used by the [`Table`] element.
+pub struct TableFrameState {
+    body_cells: SmallVec<[AnyElement; 64]>,
+    frozen_body_cells: SmallVec<[AnyElement; 16]>,
+    header_cells: SmallVec<[AnyElement; 16]>,
+    frozen_header_cells: SmallVec<[AnyElement; 4]>,
+    focused_cell_bounds: Option<(Bounds<Pixels>, ContentMask<Pixels>)>,
+}
+
+/// The horizontal layout of the table's columns for a single frame.
+struct ColumnLayout {
+    /// Column indices in display order, paired with their offset from the left edge of the
+    /// table's content.
+    columns: Vec<(usize, Pixels, Pixels)>,
+    frozen: usize,
+    frozen_width: Pixels,
+    total_width: Pixels,
+}
+
+impl ColumnLayout {
+    fn new(state: &TableStateInner) -> Self {
+        let mut left = px(0.);
+        let columns = state
+            .order
+            .iter()
+            .map(|&column| {
+                let width = state.columns[column].width;
+                let entry = (column, left, width);
+                left += width;
+                entry
+            })
+            .collect::<Vec<_>>();
+        let frozen = state.frozen_columns.min(columns.len());
+        let frozen_width = columns[..frozen]
+            .iter()
+            .fold(px(0.), |total, (_, _, width)| total + *width);
+        Self {
+            columns,
+            frozen,
+            frozen_width,
+            total_width: left,
+        }
+    }
+
+    /// The display positions of the scrollable columns overlapping the viewport.
+    fn visible_scrollable_columns(&self, scroll_x: Pixels, viewport_width: Pixels) -> Range<usize> {
+        let visible_left = scroll_x + self.frozen_width;
+        let visible_right = scroll_x + viewport_width;
+        let scrollable = &self.columns[self.frozen..];
+        let start = scrollable.partition_point(|(_, left, width)| *left + *width <= visible_left);
+        let end = scrollable.partition_point(|(_, left, _)| *left < visible_right);
+        self.frozen + start..self.frozen + end.max(start)
+    }
+}
+
+impl Element for Table {
+    type RequestLayoutState = TableFrameState;
+    type PrepaintState = Option<Hitbox>;
+
+    fn id(&self) -> Option<ElementId> {
+        self.interactivity.element_id.clone()
+    }
+
+    fn source_location(&self) -> Option<&'static core::panic::Location<'static>> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        global_id: Option<&GlobalElementId>,
+        inspector_id: Option<&InspectorElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let layout_id = self.interactivity.request_layout(
+            global_id,
+            inspector_id,
+            window,
+            cx,
+            |style, window, cx| {
+                window.with_text_style(style.text_style().cloned(), |window| {
+                    window.request_layout(style, None, cx)
+                })
+            },
+        );
+
+        (
+            layout_id,
+            TableFrameState {
+                body_cells: SmallVec::new(),
+                frozen_body_cells: SmallVec::new(),
+                header_cells: SmallVec::new(),
+                frozen_header_cells: SmallVec::new(),
+                focused_cell_bounds: None,
+            },
+        )
+    }
+
+    fn prepaint(
+        &mut self,
+        global_id: Option<&GlobalElementId>,
+        inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        frame_state: &mut Self::RequestLayoutState,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Hitbox> {
+        let row_height = self.row_height.max(px(1.));
+        let header_height = self.header_height;
+        let (layout, row_count) = {
+            let state = &mut *self.state.0.borrow_mut();
+            state.view = Some(window.current_view());
+            (ColumnLayout::new(state), state.row_count)
+        };
+        let content_size = size(
+            layout.total_width,
+            header_height + row_height * row_count as f32,
+        );
+        let body_height = (bounds.size.height - header_height).max(px(0.));
+        let shared_scroll_offset = self.interactivity.scroll_offset.clone().unwrap();
+        let render_cell = self.render_cell.clone();
+        let header = TableHeader {
+            state: self.state.clone(),
+            render_header: self.render_header.clone(),
+            on_sort: self.on_sort.clone(),
+        };
+
+        // Resolve pending scroll requests before clamping, so they take effect this frame.
+        {
+            let state = &mut *self.state.0.borrow_mut();
+            state.page_rows = ((body_height / row_height).floor() as usize).max(1);
+
+            let mut scroll_handle = state.scroll_handle.0.borrow_mut();
+            scroll_handle.last_item_size = Some(crate::ItemSize {
+                item: bounds.size,
+                contents: content_size,
+            });
+            let deferred_scroll = scroll_handle.deferred_scroll_to_item.take();
+            drop(scroll_handle);
+
+            let mut offset = shared_scroll_offset.borrow_mut();
+            let max_scroll_y = (content_size.height - bounds.size.height).max(px(0.));
+            if let Some(deferred_scroll) = deferred_scroll {
+                let row_top =
+                    row_height * deferred_scroll.item_index.min(row_count.saturating_sub(1)) as f32;
+                let is_visible =
+                    row_top >= -offset.y && row_top + row_height <= -offset.y + body_height;
+                if deferred_scroll.scroll_strict || !is_visible {
+                    let scroll_top = match deferred_scroll.strategy {
+                        ScrollStrategy::Top => row_top,
+                        ScrollStrategy::Center => row_top - (body_height - row_height) / 2.,
+                        ScrollStrategy::Bottom => row_top + row_height - body_height,
+                    };
+                    offset.y = -scroll_top.clamp(px(0.), max_scroll_y);
+                }
+            }
+
+            if std::mem::take(&mut state.reveal_focused_cell)
+                && let Some(cell) = state.focused_cell
+            {
+                let row_top = row_height * cell.row as f32;
+                if row_top < -offset.y {
+                    offset.y = -row_top;
+                } else if row_top + row_height > -offset.y + body_height {
+                    offset.y = -(row_top + row_height - body_height).clamp(px(0.), max_scroll_y);
+                }
+
+                if let Some(position) = layout
+                    .columns
+                    .iter()
+                    .position(|(column, _, _)| *column == cell.column)
+                    && position >= layout.frozen
+                {
+                    let (_, left, width) = layout.columns[position];
+                    let visible_left = -offset.x + layout.frozen_width;
+                    let visible_right = -offset.x + bounds.size.width;
+                    if left < visible_left {
+                        offset.x = -(left - layout.frozen_width).max(px(0.));
+                    } else if left + width > visible_right {
+                        offset.x = -(left + width - bounds.size.width).max(px(0.));
+                    }
+                }
+            }
+        }
+
+        self.interactivity.prepaint(
+            global_id,
+            inspector_id,
+            bounds,
+            content_size,
+            window,
+            cx,
+            |_style, scroll_offset, hitbox, window, cx| {
+                let scroll_x = -scroll_offset.x;
+                let scroll_y = -scroll_offset.y;
+                let frozen_right = bounds.left() + layout.frozen_width;
+                let body_top = bounds.top() + header_height;
+
+                let header_mask = ContentMask {
+                    bounds: Bounds::from_corners(
+                        point(frozen_right, bounds.top()),
+                        point(bounds.right(), body_top),
+                    ),
+                };
+                let frozen_header_mask = ContentMask {
+                    bounds: Bounds::from_corners(bounds.origin, point(frozen_right, body_top)),
+                };
+                let body_mask = ContentMask {
+                    bounds: Bounds::from_corners(
+                        point(frozen_right, body_top),
+                        bounds.bottom_right(),
+                    ),
+                };
+                let frozen_body_mask = ContentMask {
+                    bounds: Bounds::from_corners(
+                        point(bounds.left(), body_top),
+                        point(frozen_right, bounds.bottom()),
+                    ),
+                };
+
+                let scrollable_columns =
+                    layout.visible_scrollable_columns(scroll_x, bounds.size.width);
+                let visible_rows = if row_count == 0 {
+                    0..0
+                } else {
+                    let first = (scroll_y / row_height).floor() as usize;
+                    let last = ((scroll_y + body_height) / row_height).ceil() as usize;
+                    first.min(row_count)..last.min(row_count)
+                };
+                let column_left = |position: usize| {
+                    let (_, left, _) = layout.columns[position];
+                    if position < layout.frozen {
+                        bounds.left() + left
+                    } else {
+                        bounds.left() + left - scroll_x
+                    }
+                };
+                let focused_cell = header.state.focused_cell();
+
+                for (positions, mask, is_frozen) in [
+                    (scrollable_columns.clone(), body_mask, false),
+                    (0..layout.frozen, frozen_body_mask, true),
+                ] {
+                    window.with_content_mask(Some(mask.clone()), |window| {
+                        for row in visible_rows.clone() {
+                            let top = body_top + row_height * row as f32 - scroll_y;
+                            for position in positions.clone() {
+                                let (column, _, width) = layout.columns[position];
+                                let cell = TableCell { row, column };
+                                let origin = point(column_left(position), top);
+                                let mut element = render_cell(cell, window, cx);
+                                element.layout_as_root(
+                                    size(
+                                        AvailableSpace::Definite(width),
+                                        AvailableSpace::Definite(row_height),
+                                    ),
+                                    window,
+                                    cx,
+                                );
+                                element.prepaint_at(origin, window, cx);
+                                if is_frozen {
+                                    frame_state.frozen_body_cells.push(element);
+                                } else {
+                                    frame_state.body_cells.push(element);
+                                }
+
+                                if focused_cell == Some(cell) {
+                                    frame_state.focused_cell_bounds = Some((
+                                        Bounds::new(origin, size(width, row_height)),
+                                        mask.clone(),
+                                    ));
+                                }
+                            }
+                        }
+                    });
+                }
+
+                for (positions, mask, is_frozen) in [
+                    (scrollable_columns, header_mask, false),
+                    (0..layout.frozen, frozen_header_mask, true),
+                ] {
+                    window.with_content_mask(Some(mask), |window| {
+                        for position in positions {
+                            let (column, _, width) = layout.columns[position];
+                            let mut element = header.render_cell(position, column, window, cx);
+                            element.layout_as_root(
+                                size(
+                                    AvailableSpace::Definite(width),
+                                    AvailableSpace::Definite(header_height),
+                                ),
+                                window,
+                                cx,
+                            );
+                            element.prepaint_at(
+                                point(column_left(position), bounds.top()),
+                                window,
+                                cx,
+                            );
+                            if is_frozen {
+                                frame_state.frozen_header_cells.push(element);
+                            } else {
+                                frame_state.header_cells.push(element);
+                            }
+                        }
+                    });
+                }
+
+                hitbox
+            },
+        )
+    }
+
+    fn paint(
+        &mut self,
+        global_id: Option<&GlobalElementId>,
+        inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        frame_state: &mut Self::RequestLayoutState,
+        hitbox: &mut Option<Hitbox>,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let focus_color = self.focus_color;
+        let header_height = self.header_height;
+        let row_height = self.row_height.max(px(1.));
+        let state = self.state.clone();
+        self.interactivity.paint(
+            global_id,
+            inspector_id,
+            bounds,
+            hitbox.as_ref(),
+            window,
+            cx,
+            |_, window, cx| {
+                for cell in frame_state
+                    .body_cells
+                    .iter_mut()
+                    .chain(frame_state.frozen_body_cells.iter_mut())
+                    .chain(frame_state.header_cells.iter_mut())
+                    .chain(frame_state.frozen_header_cells.iter_mut())
+                {
+                    cell.paint(window, cx);
+                }
+
+                let focus_handle = state.focus_handle();
+                if focus_handle.contains_focused(window, cx)
+                    && let Some((cell_bounds, mask)) = frame_state.focused_cell_bounds.take()
+                {
+                    window.with_content_mask(Some(mask), |window| {
+                        window.paint_quad(outline(cell_bounds, focus_color, BorderStyle::Solid));
+                    });
+                }
+
+                let Some(hitbox) = hitbox.as_ref() else {
+                    return;
+                };
+
+                if state.0.borrow().resize.is_some() {
+                    window.set_cursor_style(CursorStyle::ResizeLeftRight, hitbox);
+                }
+
+                window.on_mouse_event({
+                    let state = state.clone();
+                    let hitbox = hitbox.clone();
+                    move |event: &MouseDownEvent, phase, window, cx| {
+                        if phase != DispatchPhase::Bubble
+                            || event.button != MouseButton::Left
+                            || !hitbox.is_hovered(window)
+                            || event.position.y < bounds.top() + header_height
+                        {
+                            return;
+                        }
+
+                        let scroll_offset = state.scroll_handle().0.borrow().base_handle.offset();
+                        let layout = ColumnLayout::new(&state.0.borrow());
+                        let x = event.position.x - bounds.left();
+                        let content_x = if x < layout.frozen_width {
+                            x
+                        } else {
+                            x - scroll_offset.x
+                        };
+                        let row =
+                            ((event.position.y - bounds.top() - header_height - scroll_offset.y)
+                                / row_height)
+                                .floor() as usize;
+                        let column = layout
+                            .columns
+                            .iter()
+                            .find(|(_, left, width)| {
+                                content_x >= *left && content_x < *left + *width
+                            })
+                            .map(|(column, _, _)| *column);
+
+                        if let Some(column) = column
+                            && row < state.row_count()
+                        {
+                            state.set_focused_cell(Some(TableCell { row, column }));
+                            window.focus(&state.focus_handle());
+                            state.notify(cx);
+                        }
+                    }
+                });
+
+                window.on_mouse_event({
+                    let state = state.clone();
+                    move |event: &MouseMoveEvent, phase, _window, cx| {
+                        if phase != DispatchPhase::Bubble {
+                            return;
+                        }
+                        let resize = state.0.borrow().resize;
+                        if let Some(resize) = resize {
+                            state.set_column_width(
+                                resize.column,
+                                resize.start_width + event.position.x - resize.start_x,
+                            );
+                            state.notify(cx);
+                        }
+                    }
+                });
+
+                window.on_mouse_event({
+                    let state = state.clone();
+                    move |_: &MouseUpEvent, phase, _window, cx| {
+                        if phase == DispatchPhase::Bubble
+                            && state.0.borrow_mut().resize.take().is_some()
+                        {
+                            state.notify(cx);
+                        }
+                    }
+                });
+            },
+        )
+    }
+}
+
+/// The parts of a [`Table`] needed to render its header cells, split out so they can be used
+/// while the table's interactivity is borrowed.
+struct TableHeader {
+    state: TableState,
+    render_header: Option<Rc<RenderHeaderFn>>,
+    on_sort: Option<Rc<SortListener>>,
+}
+
+impl TableHeader {
+    fn render_cell(
+        &self,
+        position: usize,
+        column: usize,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> AnyElement {
+        let (title, resizable, sortable, sort) = {
+            let state = self.state.0.borrow();
+            let definition = &state.columns[column];
+            (
+                definition.title.clone(),
+                definition.resizable,
+                definition.sortable,
+                state
+                    .sort
+                    .filter(|sort| sort.column == column)
+                    .map(|sort| sort.direction),
+            )
+        };
+
+        let contents = match self.render_header.as_ref() {
+            Some(render_header) => render_header(column, sort, window, cx),
+            None => div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap_1()
+                .size_full()
+                .px_1()
+                .overflow_hidden()
+                .whitespace_nowrap()
+                .child(title.clone())
+                .children(sort.map(|direction| match direction {
+                    SortDirection::Ascending => "▲",
+                    SortDirection::Descending => "▼",
+                }))
+                .into_any_element(),
+        };
+
+        let table_id = self.state.id();
+        let mut header = div()
+            .id(("table-column", column))
+            .relative()
+            .size_full()
+            .child(contents)
+            .on_drag(
+                DraggedTableColumn {
+                    table: table_id,
+                    position,
+                    title,
+                },
+                |dragged, _, _, cx| cx.new(|_| dragged.clone()),
+            )
+            .on_drop({
+                let state = self.state.clone();
+                move |dragged: &DraggedTableColumn, _window, cx| {
+                    if dragged.table == table_id {
+                        state.move_column(dragged.position, position);
+                        state.notify(cx);
+                    }
+                }
+            });
+
+        if sortable {
+            let state = self.state.clone();
+            let on_sort = self.on_sort.clone();
+            header = header.on_click(move |_: &ClickEvent, window, cx| {
+                let sort = state.cycle_sort(column);
+                if let Some(on_sort) = on_sort.as_ref() {
+                    on_sort(&sort, window, cx);
+                }
+                state.notify(cx);
+            });
+        }
+
+        if resizable {
+            let state = self.state.clone();
+            header = header.child(
+                div()
+                    .id(("table-column-resize", column))
+                    .absolute()
+                    .top_0()
+                    .right_0()
+                    .h_full()
+                    .w(RESIZE_HANDLE_WIDTH)
+                    .cursor(CursorStyle::ResizeLeftRight)
+                    .on_mouse_down(MouseButton::Left, move |event, _window, cx| {
+                        let Some(start_width) = state.column_width(column) else {
+                            return;
+                        };
+                        state.0.borrow_mut().resize = Some(ColumnResize {
+                            column,
+                            start_x: event.position.x,
+                            start_width,
+                        });
+                        cx.stop_propagation();
+                    }),
+            );
+        }
+
+        header.into_any_element()
+    }
+}
+
+impl IntoElement for Table {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl Styled for Table {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.interactivity.base_style
+    }
+}
+
+impl InteractiveElement for Table {
+    fn interactivity(&mut self) -> &mut Interactivity {
+        &mut self.interactivity
+    }
+}
+
+impl StatefulInteractiveElement for Table {}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        self as gpui, Context, IntoElement, ParentElement, Render, ScrollStrategy, Styled,
+        TableCell, TableColumn, TableState, TestAppContext, Window, bind_table_keys, div, px,
+        table,
+    };
+
+    struct TestView(TableState);
+
+    impl Render for TestView {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            table("table", self.0.clone(), |cell, _, _| {
+                div()
+                    .child(format!("{}:{}", cell.row, cell.column))
+                    .into_any_element()
+            })
+            .row_height(px(20.))
+            .header_height(px(20.))
+            .w(px(120.))
+            .h(px(100.))
+        }
+    }
+
+    fn columns() -> Vec<TableColumn> {
+        (0..6)
+            .map(|ix| TableColumn::new(format!("Column {ix}"), px(50.)))
+            .collect()
+    }
+
+    #[gpui::test]
+    fn test_column_order_and_resize(cx: &mut TestAppContext) {
+        let state = cx.update(|cx| TableState::new(columns(), 100, cx).with_frozen_columns(1));
+
+        state.move_column(4, 1);
+        assert_eq!(state.column_order(), vec![0, 4, 1, 2, 3, 5]);
+        state.move_column(1, 5);
+        assert_eq!(state.column_order(), vec![0, 1, 2, 3, 5, 4]);
+        assert!(state.set_column_order(vec![0, 1, 1, 2, 3, 4]).is_err());
+        assert_eq!(state.column_order(), vec![0, 1, 2, 3, 5, 4]);
+        state.set_column_order(vec![5, 4, 3, 2, 1, 0]).unwrap();
+        assert_eq!(state.column_order(), vec![5, 4, 3, 2, 1, 0]);
+
+        state.set_column_width(2, px(5.));
+        assert_eq!(state.column_width(2), Some(px(24.)));
+        state.set_column_width(2, px(80.));
+        assert_eq!(state.column_width(2), Some(px(80.)));
+
+        state.set_column_width(6, px(80.));
+        assert_eq!(state.column_width(6), None);
+    }
+
+    #[gpui::test]
+    fn test_keyboard_navigation_reveals_cell(cx: &mut TestAppContext) {
+        let state = cx.update(|cx| {
+            bind_table_keys(cx);
+            TableState::new(columns(), 100, cx).with_frozen_columns(1)
+        });
+        let (_, cx) = cx.add_window_view(|_, _| TestView(state.clone()));
+        cx.run_until_parked();
+
+        cx.update(|window, _| window.focus(&state.focus_handle()));
+        state.set_focused_cell(Some(TableCell { row: 0, column: 0 }));
+
+        for key in ["right", "right", "right", "down", "pagedown"] {
+            cx.simulate_keystrokes(key);
+        }
+        // Four body rows fit in the viewport below the header, so page down moves by four.
+        assert_eq!(state.focused_cell(), Some(TableCell { row: 5, column: 3 }));
+
+        let offset = state.scroll_handle().0.borrow().base_handle.offset();
+        // Row 5 ends at 120px, and the body is 80px tall.
+        assert_eq!(offset.y, px(-40.));
+        // Column 3 ends at 200px, and the viewport is 120px wide.
+        assert_eq!(offset.x, px(-80.));
+
+        cx.simulate_keystrokes("home");
+        assert_eq!(state.focused_cell(), Some(TableCell { row: 5, column: 0 }));
+        cx.simulate_keystrokes("secondary-end");
+        assert_eq!(state.focused_cell(), Some(TableCell { row: 99, column: 0 }));
+        cx.simulate_keystrokes("shift-tab");
+        assert_eq!(state.focused_cell(), Some(TableCell { row: 98, column: 5 }));
+
+        state
+            .scroll_handle()
+            .scroll_to_item(50, ScrollStrategy::Top);
+        cx.update(|window, _| window.refresh());
+        cx.run_until_parked();
+        let offset = state.scroll_handle().0.borrow().base_handle.offset();
+        assert_eq!(offset.y, px(-1000.));
+    }
+}
@@ -0,0 +1,1432 @@
+//! A virtualized tree view with lazily loaded children.
+//!
+//! The visible rows of the tree are stored flattened in a [`SumTree`], so expanding or collapsing
+//! a node only splices in or out the rows being shown or hidden, even when the tree has hundreds
+//! of thousands of visible items. The index from items to their rows is updated for those rows,
+//! and renumbered after them when the count of rows changes. Rows are rendered with a [`crate::UniformList`], so only the rows
+//! intersecting the viewport are laid out.
+//!
+//! Children are requested from the application the first time a node is expanded. While the
+//! returned [`Task`] is pending, the node shows a loading row in place of its children.
+//!
+//! Keyboard navigation follows the WAI-ARIA tree view pattern. While the tree view is focused,
+//! the actions in [`tree_view_actions`] are dispatched in the `TreeView` key context, and
+//! [`bind_tree_view_keys`] binds them: Up and Down move between rows, Right expands a node or
+//! moves to its first child, Left collapses a node or moves to its parent, and Home and End move
+//! to the first and last rows. Holding shift extends the selection, while holding the platform's
+//! secondary modifier moves without selecting.
+
+use crate::util::FluentBuilder as _;
+use crate::{
+    Action, Animation, AnimationExt as _, AnyElement, App, AppContext as _, ClickEvent, Context,
+    ElementId, EntityId, FocusHandle, InteractiveElement, IntoElement, KeyBinding, Modifiers,
+    ParentElement, Pixels, Render, RenderOnce, ScrollStrategy, Stateful,
+    StatefulInteractiveElement, StyleRefinement, Styled, Task, UniformListScrollHandle, Window,
+    div, hsla, pulsating_between, px, uniform_list,
+};
+use collections::{HashMap, HashSet};
+use std::{cell::RefCell, ops::Range, rc::Rc, time::Duration};
+use sum_tree::{Bias, SumTree};
+
+type RenderItemFn = dyn Fn(TreeItemId, TreeItemDetails, &mut Window, &mut App) -> AnyElement;
+type RenderLoadingFn = dyn Fn(usize, &mut Window, &mut App) -> AnyElement;
+type LoadChildrenFn = dyn Fn(TreeItemId, &mut App) -> Task<Vec<TreeItem>>;
+type MoveListener = dyn Fn(&TreeMove, &mut Window, &mut App);
+
+const KEY_CONTEXT: &str = "TreeView";
+
+/// The actions that move keyboard focus and change the selection in a [`TreeView`].
+pub mod tree_view_actions {
+    crate::actions!(
+        tree_view,
+        [
+            /// Focus and select the previous item.
+            SelectPrevious,
+            /// Focus and select the next item.
+            SelectNext,
+            /// Focus and select the first item.
+            SelectFirst,
+            /// Focus and select the last item.
+            SelectLast,
+            /// Focus the previous item, extending the selection to it from the anchor.
+            SelectToPrevious,
+            /// Focus the next item, extending the selection to it from the anchor.
+            SelectToNext,
+            /// Focus the first item, extending the selection to it from the anchor.
+            SelectToFirst,
+            /// Focus the last item, extending the selection to it from the anchor.
+            SelectToLast,
+            /// Focus the previous item without changing the selection.
+            FocusPrevious,
+            /// Focus the next item without changing the selection.
+            FocusNext,
+            /// Focus the first item without changing the selection.
+            FocusFirst,
+            /// Focus the last item without changing the selection.
+            FocusLast,
+            /// Expand the focused item, or focus its first child if it is already expanded.
+            ExpandOrSelectChild,
+            /// Collapse the focused item, or focus its parent if it is already collapsed.
+            CollapseOrSelectParent,
+            /// Select only the focused item.
+            SelectFocused,
+            /// Toggle whether the focused item is selected.
+            ToggleSelected,
+            /// Select every visible item.
+            SelectAll,
+        ]
+    );
+}
+
+/// Bind the default keys for the [`tree_view_actions`] in the `TreeView` key context.
+pub fn bind_tree_view_keys(cx: &mut App) {
+    use tree_view_actions::*;
+
+    let context = Some(KEY_CONTEXT);
+    cx.bind_keys([
+        KeyBinding::new("up", SelectPrevious, context),
+        KeyBinding::new("down", SelectNext, context),
+        KeyBinding::new("home", SelectFirst, context),
+        KeyBinding::new("end", SelectLast, context),
+        KeyBinding::new("shift-up", SelectToPrevious, context),
+        KeyBinding::new("shift-down", SelectToNext, context),
+        KeyBinding::new("shift-home", SelectToFirst, context),
+        KeyBinding::new("shift-end", SelectToLast, context),
+        KeyBinding::new("secondary-up", FocusPrevious, context),
+        KeyBinding::new("secondary-down", FocusNext, context),
+        KeyBinding::new("secondary-home", FocusFirst, context),
+        KeyBinding::new("secondary-end", FocusLast, context),
+        KeyBinding::new("right", ExpandOrSelectChild, context),
+        KeyBinding::new("left", CollapseOrSelectParent, context),
+        KeyBinding::new("space", SelectFocused, context),
+        KeyBinding::new("secondary-space", ToggleSelected, context),
+        KeyBinding::new("secondary-a", SelectAll, context),
+    ]);
+}
+
+/// Construct a new tree view element.
+///
+/// `render_item` is called for every visible item and should render its contents. The tree view
+/// takes care of indentation, the disclosure indicator, selection, focus and drag and drop.
+#[track_caller]
+pub fn tree_view(
+    id: impl Into<ElementId>,
+    state: TreeViewState,
+    render_item: impl Fn(TreeItemId, TreeItemDetails, &mut Window, &mut App) -> AnyElement + 'static,
+) -> TreeView {
+    TreeView {
+        base: div().id(id),
+        state,
+        render_item: Rc::new(render_item),
+        render_loading: None,
+        on_move: None,
+        indent: px(16.),
+        selected_color: hsla(0.6, 0.8, 0.55, 0.25),
+        focused_color: hsla(0.6, 0.8, 0.55, 1.),
+    }
+}
+
+/// The identifier of an item in a [`TreeView`]. Identifiers must be unique within a tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TreeItemId(pub u64);
+
+impl From<u64> for TreeItemId {
+    fn from(id: u64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<usize> for TreeItemId {
+    fn from(id: usize) -> Self {
+        Self(id as u64)
+    }
+}
+
+/// An item in a [`TreeView`], as provided by the application.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TreeItem {
+    /// The item's identifier.
+    pub id: TreeItemId,
+    /// Whether the item can be expanded to load its children.
+    pub has_children: bool,
+}
+
+impl TreeItem {
+    /// An item without children.
+    pub fn leaf(id: impl Into<TreeItemId>) -> Self {
+        Self {
+            id: id.into(),
+            has_children: false,
+        }
+    }
+
+    /// An item whose children are loaded when it is first expanded.
+    pub fn branch(id: impl Into<TreeItemId>) -> Self {
+        Self {
+            id: id.into(),
+            has_children: true,
+        }
+    }
+}
+
+/// The presentation state of an item, passed to the tree view's render callback.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TreeItemDetails {
+    /// How deeply the item is nested. Root items have a depth of zero.
+    pub depth: usize,
+    /// Whether the item can be expanded.
+    pub has_children: bool,
+    /// Whether the item is expanded.
+    pub expanded: bool,
+    /// Whether the item is selected.
+    pub selected: bool,
+    /// Whether the item has keyboard focus within the tree.
+    pub focused: bool,
+}
+
+/// Describes an item moved by dragging it onto another item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeMove {
+    /// The item that was moved.
+    pub item: TreeItemId,
+    /// The item's new parent, or `None` if it is now a root item.
+    pub parent: Option<TreeItemId>,
+    /// The item's new index among its parent's children.
+    pub index: usize,
+}
+
+/// The tree view state that views must hold on behalf of the tree view element.
+///
+/// It caches the children loaded for each item and records which items are expanded and
+/// selected.
+#[derive(Clone)]
+pub struct TreeViewState(Rc<RefCell<TreeViewStateInner>>);
+
+impl std::fmt::Debug for TreeViewState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TreeViewState")
+    }
+}
+
+struct TreeViewStateInner {
+    rows: SumTree<TreeRow>,
+    /// The row of every visible item, updated by `splice`.
+    item_rows: HashMap<TreeItemId, usize>,
+    children: HashMap<Option<TreeItemId>, Vec<TreeItem>>,
+    expanded: HashSet<TreeItemId>,
+    loading: HashMap<TreeItemId, Task<()>>,
+    load_children: Rc<LoadChildrenFn>,
+    selection: HashSet<TreeItemId>,
+    cursor: Option<usize>,
+    anchor: Option<usize>,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    view: Option<EntityId>,
+}
+
+#[derive(Clone, Debug)]
+struct TreeRow {
+    kind: TreeRowKind,
+    parent: Option<TreeItemId>,
+    depth: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum TreeRowKind {
+    Item(TreeItem),
+    Loading,
+}
+
+impl TreeRow {
+    fn item(&self) -> Option<TreeItem> {
+        match self.kind {
+            TreeRowKind::Item(item) => Some(item),
+            TreeRowKind::Loading => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TreeRowSummary {
+    count: usize,
+    min_depth: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct RowCount(usize);
+
+impl TreeViewState {
+    /// Create a new tree view state with the given root items.
+    ///
+    /// `load_children` is called the first time an item is expanded, and should resolve to the
+    /// item's children. Use [`Task::ready`] when the children are available synchronously.
+    pub fn new(
+        roots: Vec<TreeItem>,
+        load_children: impl Fn(TreeItemId, &mut App) -> Task<Vec<TreeItem>> + 'static,
+        cx: &mut App,
+    ) -> Self {
+        let rows = SumTree::from_iter(
+            roots.iter().map(|item| TreeRow {
+                kind: TreeRowKind::Item(*item),
+                parent: None,
+                depth: 0,
+            }),
+            (),
+        );
+        let mut children = HashMap::default();
+        children.insert(None, roots);
+        let mut state = TreeViewStateInner {
+            rows,
+            item_rows: HashMap::default(),
+            children,
+            expanded: HashSet::default(),
+            loading: HashMap::default(),
+            load_children: Rc::new(load_children),
+            selection: HashSet::default(),
+            cursor: None,
+            anchor: None,
+            focus_handle: cx.focus_handle().tab_stop(true),
+            scroll_handle: UniformListScrollHandle::new(),
+            view: None,
+        };
+        let row_count = state.rows.summary().count;
+        state.index_item_rows(0, row_count);
+        Self(Rc::new(RefCell::new(state)))
+    }
+
+    /// The number of visible rows, including loading rows.
+    pub fn row_count(&self) -> usize {
+        self.0.borrow().rows.summary().count
+    }
+
+    /// The item displayed at the given row, or `None` if the row shows a loading indicator.
+    pub fn item_at(&self, ix: usize) -> Option<TreeItemId> {
+        self.0
+            .borrow()
+            .row(ix)
+            .and_then(|row| row.item())
+            .map(|item| item.id)
+    }
+
+    /// The row at which the given item is displayed, if it is visible.
+    pub fn row_for_item(&self, id: TreeItemId) -> Option<usize> {
+        self.0.borrow().row_for_item(id)
+    }
+
+    /// Whether the given item is expanded.
+    pub fn is_expanded(&self, id: TreeItemId) -> bool {
+        self.0.borrow().expanded.contains(&id)
+    }
+
+    /// Whether the children of the given item are being loaded.
+    pub fn is_loading(&self, id: TreeItemId) -> bool {
+        self.0.borrow().loading.contains_key(&id)
+    }
+
+    /// Expand the given item, loading its children if they haven't been loaded yet.
+    pub fn expand(&self, id: TreeItemId, cx: &mut App) {
+        let ix = self.0.borrow().row_for_item(id);
+        if let Some(ix) = ix {
+            self.expand_row(ix, cx);
+        } else {
+            self.0.borrow_mut().expanded.insert(id);
+        }
+    }
+
+    /// Collapse the given item, hiding its descendants.
+    pub fn collapse(&self, id: TreeItemId) {
+        let ix = self.0.borrow().row_for_item(id);
+        if let Some(ix) = ix {
+            self.collapse_row(ix);
+        } else {
+            self.0.borrow_mut().expanded.remove(&id);
+        }
+    }
+
+    /// Expand the given item if it is collapsed, and collapse it otherwise.
+    pub fn toggle(&self, id: TreeItemId, cx: &mut App) {
+        if self.is_expanded(id) {
+            self.collapse(id);
+        } else {
+            self.expand(id, cx);
+        }
+    }
+
+    /// Replace the children of the given item, or the root items if `parent` is `None`.
+    ///
+    /// Call this when the application's data changes. Items that remain in the tree keep their
+    /// expanded and selected state.
+    pub fn set_children(&self, parent: Option<TreeItemId>, children: Vec<TreeItem>, cx: &mut App) {
+        let pending = {
+            let state = &mut *self.0.borrow_mut();
+            state.children.insert(parent, children);
+            let (range, depth) = match parent {
+                None => (0..state.rows.summary().count, 0),
+                Some(parent) => {
+                    let Some(ix) = state.row_for_item(parent) else {
+                        return;
+                    };
+                    if !state.expanded.contains(&parent) {
+                        return;
+                    }
+                    let depth = state.row(ix).map_or(0, |row| row.depth);
+                    (ix + 1..state.subtree_end(ix), depth + 1)
+                }
+            };
+            let mut rows = Vec::new();
+            let mut pending = Vec::new();
+            state.build_rows(parent, depth, &mut rows, &mut pending);
+            state.splice(range, rows);
+            pending
+        };
+        for id in pending {
+            self.load(id, cx);
+        }
+    }
+
+    /// The selected items, in ascending order of their identifiers.
+    pub fn selected_items(&self) -> Vec<TreeItemId> {
+        let mut selection = self
+            .0
+            .borrow()
+            .selection
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        selection.sort_unstable();
+        selection
+    }
+
+    /// Whether the given item is selected.
+    pub fn is_selected(&self, id: TreeItemId) -> bool {
+        self.0.borrow().selection.contains(&id)
+    }
+
+    /// Replace the selection.
+    pub fn set_selection(&self, items: impl IntoIterator<Item = TreeItemId>) {
+        self.0.borrow_mut().selection = items.into_iter().collect();
+    }
+
+    /// The item with keyboard focus within the tree, if any.
+    pub fn focused_item(&self) -> Option<TreeItemId> {
+        let state = self.0.borrow();
+        state
+            .cursor
+            .and_then(|ix| state.row(ix))
+            .and_then(|row| row.item())
+            .map(|item| item.id)
+    }
+
+    /// Give keyboard focus within the tree to the given item and scroll it into view. The item
+    /// must be visible.
+    pub fn set_focused_item(&self, id: TreeItemId) {
+        let state = &mut *self.0.borrow_mut();
+        if let Some(ix) = state.row_for_item(id) {
+            state.cursor = Some(ix);
+            state.anchor = Some(ix);
+            state.scroll_handle.scroll_to_item(ix, ScrollStrategy::Top);
+        }
+    }
+
+    /// Move an item, along with its descendants, to just before the `before` item, making it a
+    /// sibling of `before`. Both items must be visible, and `before` cannot be a descendant of
+    /// `item`.
+    pub fn move_item(&self, item: TreeItemId, before: TreeItemId) -> Option<TreeMove> {
+        let state = &mut *self.0.borrow_mut();
+        let from = state.row_for_item(item)?;
+        let to = state.row_for_item(before)?;
+        let end = state.subtree_end(from);
+        if (from..end).contains(&to) {
+            return None;
+        }
+
+        let source = state.row(from)?.clone();
+        let target = state.row(to)?.clone();
+        if let Some(siblings) = state.children.get_mut(&source.parent) {
+            siblings.retain(|sibling| sibling.id != item);
+        }
+        let siblings = state.children.entry(target.parent).or_default();
+        let index = siblings
+            .iter()
+            .position(|sibling| sibling.id == before)
+            .unwrap_or(siblings.len());
+        siblings.insert(index, source.item()?);
+
+        let mut rows = state.rows_in(from..end);
+        for row in &mut rows {
+            row.depth = row.depth - source.depth + target.depth;
+        }
+        rows[0].parent = target.parent;
+        let moved_rows = rows.len();
+        state.splice(from..end, []);
+        let to = if to > from { to - moved_rows } else { to };
+        state.splice(to..to, rows);
+        state.cursor = Some(to);
+        state.anchor = Some(to);
+
+        Some(TreeMove {
+            item,
+            parent: target.parent,
+            index,
+        })
+    }
+
+    /// The focus handle of the tree view, which is a tab stop.
+    pub fn focus_handle(&self) -> FocusHandle {
+        self.0.borrow().focus_handle.clone()
+    }
+
+    /// The scroll handle tracking the tree view's scroll position.
+    pub fn scroll_handle(&self) -> UniformListScrollHandle {
+        self.0.borrow().scroll_handle.clone()
+    }
+
+    fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+
+    fn notify(&self, cx: &mut App) {
+        if let Some(view) = self.0.borrow().view {
+            cx.notify(view);
+        }
+    }
+
+    fn details(&self, ix: usize, window: &Window) -> Option<(TreeItemId, TreeItemDetails)> {
+        let state = self.0.borrow();
+        let row = state.row(ix)?;
+        let item = row.item()?;
+        Some((
+            item.id,
+            TreeItemDetails {
+                depth: row.depth,
+                has_children: item.has_children,
+                expanded: state.expanded.contains(&item.id),
+                selected: state.selection.contains(&item.id),
+                focused: state.cursor == Some(ix) && state.focus_handle.is_focused(window),
+            },
+        ))
+    }
+
+    fn expand_row(&self, ix: usize, cx: &mut App) {
+        let pending = {
+            let state = &mut *self.0.borrow_mut();
+            let Some(row) = state.row(ix).cloned() else {
+                return;
+            };
+            let Some(item) = row.item().filter(|item| item.has_children) else {
+                return;
+            };
+            if !state.expanded.insert(item.id) {
+                return;
+            }
+            let mut rows = Vec::new();
+            let mut pending = Vec::new();
+            if state.children.contains_key(&Some(item.id)) {
+                state.build_rows(Some(item.id), row.depth + 1, &mut rows, &mut pending);
+            } else {
+                rows.push(TreeRow {
+                    kind: TreeRowKind::Loading,
+                    parent: Some(item.id),
+                    depth: row.depth + 1,
+                });
+                if !state.loading.contains_key(&item.id) {
+                    pending.push(item.id);
+                }
+            }
+            state.splice(ix + 1..ix + 1, rows);
+            pending
+        };
+        for id in pending {
+            self.load(id, cx);
+        }
+    }
+
+    fn collapse_row(&self, ix: usize) {
+        let state = &mut *self.0.borrow_mut();
+        let Some(item) = state.row(ix).and_then(|row| row.item()) else {
+            return;
+        };
+        if state.expanded.remove(&item.id) {
+            let end = state.subtree_end(ix);
+            state.splice(ix + 1..end, []);
+        }
+    }
+
+    fn load(&self, id: TreeItemId, cx: &mut App) {
+        let load_children = self.0.borrow().load_children.clone();
+        let children = load_children(id, cx);
+        let this = Rc::downgrade(&self.0);
+        let task = cx.spawn(async move |cx| {
+            let children = children.await;
+            cx.update(|cx| {
+                if let Some(this) = this.upgrade() {
+                    TreeViewState(this).finish_loading(id, children, cx);
+                }
+            })
+            .ok();
+        });
+        let mut state = self.0.borrow_mut();
+        if state.children.contains_key(&Some(id)) {
+            // The children were set while the loader was being called.
+            return;
+        }
+        state.loading.insert(id, task);
+    }
+
+    fn finish_loading(&self, id: TreeItemId, children: Vec<TreeItem>, cx: &mut App) {
+        self.0.borrow_mut().loading.remove(&id);
+        self.set_children(Some(id), children, cx);
+        self.notify(cx);
+    }
+
+    /// Select items in response to a click on the given row.
+    fn click_row(&self, ix: usize, modifiers: Modifiers) {
+        let state = &mut *self.0.borrow_mut();
+        let Some(item) = state.row(ix).and_then(|row| row.item()) else {
+            return;
+        };
+        if modifiers.shift {
+            let anchor = state.anchor.unwrap_or(ix);
+            state.select_range(anchor.min(ix)..anchor.max(ix) + 1);
+        } else if modifiers.secondary() {
+            if !state.selection.remove(&item.id) {
+                state.selection.insert(item.id);
+            }
+            state.anchor = Some(ix);
+        } else {
+            state.selection.clear();
+            state.selection.insert(item.id);
+            state.anchor = Some(ix);
+        }
+        state.cursor = Some(ix);
+    }
+
+    /// Move keyboard focus to another row, returning whether there was a row to move to. Without
+    /// a focused row, every movement focuses the first row.
+    fn move_focus(&self, movement: Movement, mode: SelectionMode) -> bool {
+        let target = {
+            let state = self.0.borrow();
+            let row_count = state.rows.summary().count;
+            match (movement, state.cursor) {
+                _ if row_count == 0 => None,
+                (Movement::First, _) | (_, None) => Some(0),
+                (Movement::Previous, Some(cursor)) => state.item_row_before(cursor),
+                (Movement::Next, Some(cursor)) => state.item_row_after(cursor),
+                (Movement::Last, Some(_)) => {
+                    if state
+                        .row(row_count - 1)
+                        .is_some_and(|row| row.item().is_some())
+                    {
+                        Some(row_count - 1)
+                    } else {
+                        Some(state.item_row_before(row_count - 1).unwrap_or(0))
+                    }
+                }
+            }
+        };
+        let Some(target) = target else {
+            return false;
+        };
+        self.move_cursor(target, mode);
+        true
+    }
+
+    /// Expand the focused item, or move to its first child if it is already expanded.
+    fn expand_or_select_child(&self, cx: &mut App) -> bool {
+        let Some(cursor) = self.0.borrow().cursor else {
+            return self.move_focus(Movement::First, SelectionMode::Replace);
+        };
+        let (expandable, expanded, first_child) = {
+            let state = self.0.borrow();
+            let item = state.row(cursor).and_then(|row| row.item());
+            let first_child = state
+                .row(cursor + 1)
+                .filter(|row| row.item().is_some() && row.parent == item.map(|i| i.id))
+                .map(|_| cursor + 1);
+            (
+                item.is_some_and(|item| item.has_children),
+                item.is_some_and(|item| state.expanded.contains(&item.id)),
+                first_child,
+            )
+        };
+        if expandable && !expanded {
+            self.expand_row(cursor, cx);
+        } else if let Some(first_child) = first_child {
+            self.move_cursor(first_child, SelectionMode::Replace);
+        }
+        true
+    }
+
+    /// Collapse the focused item, or move to its parent if it is already collapsed.
+    fn collapse_or_select_parent(&self) -> bool {
+        let Some(cursor) = self.0.borrow().cursor else {
+            return self.move_focus(Movement::First, SelectionMode::Replace);
+        };
+        let (expanded, parent) = {
+            let state = self.0.borrow();
+            let item = state.row(cursor).and_then(|row| row.item());
+            (
+                item.is_some_and(|item| state.expanded.contains(&item.id)),
+                state.parent_row(cursor),
+            )
+        };
+        if expanded {
+            self.collapse_row(cursor);
+        } else if let Some(parent) = parent {
+            self.move_cursor(parent, SelectionMode::Replace);
+        }
+        true
+    }
+
+    /// Select only the focused item, or toggle whether it is selected.
+    fn select_focused(&self, toggle: bool) -> bool {
+        let state = &mut *self.0.borrow_mut();
+        let Some(cursor) = state.cursor else {
+            return false;
+        };
+        if let Some(item) = state.row(cursor).and_then(|row| row.item()) {
+            if !toggle {
+                state.selection.clear();
+                state.selection.insert(item.id);
+            } else if !state.selection.remove(&item.id) {
+                state.selection.insert(item.id);
+            }
+            state.anchor = Some(cursor);
+        }
+        true
+    }
+
+    fn select_all(&self) -> bool {
+        let state = &mut *self.0.borrow_mut();
+        let row_count = state.rows.summary().count;
+        if row_count == 0 {
+            return false;
+        }
+        state.select_range(0..row_count);
+        true
+    }
+
+    /// Move keyboard focus to the given row, updating the selection according to `mode`.
+    fn move_cursor(&self, ix: usize, mode: SelectionMode) {
+        let state = &mut *self.0.borrow_mut();
+        let Some(item) = state.row(ix).and_then(|row| row.item()) else {
+            return;
+        };
+        match mode {
+            SelectionMode::Replace => {
+                state.selection.clear();
+                state.selection.insert(item.id);
+                state.anchor = Some(ix);
+            }
+            SelectionMode::Extend => {
+                let anchor = state.anchor.unwrap_or(ix);
+                state.select_range(anchor.min(ix)..anchor.max(ix) + 1);
+            }
+            SelectionMode::Keep => {}
+        }
+        state.cursor = Some(ix);
+        state.scroll_handle.scroll_to_item(ix, ScrollStrategy::Top);
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Movement {
+    Previous,
+    Next,
+    First,
+    Last,
+}
+
+/// How moving keyboard focus changes the selection.
+#[derive(Clone, Copy, Debug)]
+enum SelectionMode {
+    /// Select only the newly focused item.
+    Replace,
+    /// Select the items between the anchor and the newly focused item.
+    Extend,
+    /// Leave the selection untouched.
+    Keep,
+}
+
+impl TreeViewStateInner {
+    fn row(&self, ix: usize) -> Option<&TreeRow> {
+        let mut cursor = self.rows.cursor::<RowCount>(());
+        cursor.seek(&RowCount(ix), Bias::Right);
+        cursor.item()
+    }
+
+    fn rows_in(&self, range: Range<usize>) -> Vec<TreeRow> {
+        let mut cursor = self.rows.cursor::<RowCount>(());
+        cursor.seek(&RowCount(range.start), Bias::Right);
+        let mut rows = Vec::with_capacity(range.len());
+        while let Some(row) = cursor.item() {
+            if cursor.start().0 >= range.end {
+                break;
+            }
+            rows.push(row.clone());
+            cursor.next();
+        }
+        rows
+    }
+
+    fn row_for_item(&self, id: TreeItemId) -> Option<usize> {
+        self.item_rows.get(&id).copied()
+    }
+
+    /// Record the rows of the items from row `start` up to row `end` in the item index.
+    fn index_item_rows(&mut self, start: usize, end: usize) {
+        let mut cursor = self.rows.cursor::<RowCount>(());
+        cursor.seek(&RowCount(start), Bias::Right);
+        while let Some(row) = cursor.item() {
+            let ix = cursor.start().0;
+            if ix >= end {
+                break;
+            }
+            if let Some(item) = row.item() {
+                self.item_rows.insert(item.id, ix);
+            }
+            cursor.next();
+        }
+    }
+
+    /// The end of the range of rows descending from the row at `ix`.
+    fn subtree_end(&self, ix: usize) -> usize {
+        let Some(depth) = self.row(ix).map(|row| row.depth) else {
+            return ix;
+        };
+        let mut cursor = self.rows.cursor::<RowCount>(());
+        cursor.seek(&RowCount(ix), Bias::Right);
+        cursor.search_forward(|summary| summary.min_depth <= depth);
+        cursor.start().0
+    }
+
+    /// The row of the parent of the item at `ix`.
+    fn parent_row(&self, ix: usize) -> Option<usize> {
+        let depth = self.row(ix)?.depth;
+        if depth == 0 {
+            return None;
+        }
+        let mut cursor = self.rows.cursor::<RowCount>(());
+        cursor.seek(&RowCount(ix), Bias::Right);
+        cursor.search_backward(|summary| summary.min_depth < depth);
+        cursor.item().map(|_| cursor.start().0)
+    }
+
+    fn item_row_before(&self, ix: usize) -> Option<usize> {
+        (0..ix)
+            .rev()
+            .find(|ix| self.row(*ix).is_some_and(|row| row.item().is_some()))
+    }
+
+    fn item_row_after(&self, ix: usize) -> Option<usize> {
+        (ix + 1..self.rows.summary().count)
+            .find(|ix| self.row(*ix).is_some_and(|row| row.item().is_some()))
+    }
+
+    fn select_range(&mut self, range: Range<usize>) {
+        self.selection = self
+            .rows_in(range)
+            .into_iter()
+            .filter_map(|row| row.item())
+            .map(|item| item.id)
+            .collect();
+    }
+
+    /// Append the visible rows for the children of `parent`, recursing into expanded children.
+    /// Expanded items whose children haven't been loaded are pushed onto `pending`.
+    fn build_rows(
+        &self,
+        parent: Option<TreeItemId>,
+        depth: usize,
+        rows: &mut Vec<TreeRow>,
+        pending: &mut Vec<TreeItemId>,
+    ) {
+        let Some(children) = self.children.get(&parent) else {
+            return;
+        };
+        for child in children {
+            rows.push(TreeRow {
+                kind: TreeRowKind::Item(*child),
+                parent,
+                depth,
+            });
+            if child.has_children && self.expanded.contains(&child.id) {
+                if self.children.contains_key(&Some(child.id)) {
+                    self.build_rows(Some(child.id), depth + 1, rows, pending);
+                } else {
+                    rows.push(TreeRow {
+                        kind: TreeRowKind::Loading,
+                        parent: Some(child.id),
+                        depth: depth + 1,
+                    });
+                    if !self.loading.contains_key(&child.id) {
+                        pending.push(child.id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replace the rows in `range`, keeping the focused row and selection anchor on the same
+    /// items. If they were among the removed rows, they move to the row before the range.
+    fn splice(&mut self, range: Range<usize>, rows: impl IntoIterator<Item = TreeRow>) {
+        for row in self.rows_in(range.clone()) {
+            if let Some(item) = row.item() {
+                self.item_rows.remove(&item.id);
+            }
+        }
+
+        let mut cursor = self.rows.cursor::<RowCount>(());
+        let mut new_rows = cursor.slice(&RowCount(range.start), Bias::Right);
+        new_rows.extend(rows, ());
+        let inserted = new_rows.summary().count - range.start;
+        cursor.seek_forward(&RowCount(range.end), Bias::Right);
+        new_rows.append(cursor.suffix(), ());
+        drop(cursor);
+        self.rows = new_rows;
+
+        // The rows after the range only need renumbering when the range changed length.
+        let row_count = self.rows.summary().count;
+        let index_end = if inserted == range.len() {
+            range.start + inserted
+        } else {
+            row_count
+        };
+        self.index_item_rows(range.start, index_end);
+
+        let adjust = |ix: usize| {
+            let ix = if ix < range.start {
+                ix
+            } else if ix < range.end {
+                range.start.saturating_sub(1)
+            } else {
+                ix - range.len() + inserted
+            };
+            (ix < row_count).then_some(ix)
+        };
+        self.cursor = self.cursor.and_then(adjust);
+        self.anchor = self.anchor.and_then(adjust);
+    }
+}
+
+/// A virtualized tree view element. See [`tree_view`].
+#[derive(IntoElement)]
+pub struct TreeView {
+    base: Stateful<crate::Div>,
+    state: TreeViewState,
+    render_item: Rc<RenderItemFn>,
+    render_loading: Option<Rc<RenderLoadingFn>>,
+    on_move: Option<Rc<MoveListener>>,
+    indent: Pixels,
+    selected_color: crate::Hsla,
+    focused_color: crate::Hsla,
+}
+
+impl TreeView {
+    /// Set how far each level of the tree is indented. Defaults to 16px.
+    pub fn indent(mut self, indent: Pixels) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Set the background color of selected items.
+    pub fn selected_color(mut self, color: impl Into<crate::Hsla>) -> Self {
+        self.selected_color = color.into();
+        self
+    }
+
+    /// Set the color of the outline drawn around the item with keyboard focus.
+    pub fn focused_color(mut self, color: impl Into<crate::Hsla>) -> Self {
+        self.focused_color = color.into();
+        self
+    }
+
+    /// Render the row shown while an item's children are loading. The callback receives the
+    /// depth of the row. By default a pulsing "Loading…" label is shown.
+    pub fn render_loading(
+        mut self,
+        render_loading: impl Fn(usize, &mut Window, &mut App) -> AnyElement + 'static,
+    ) -> Self {
+        self.render_loading = Some(Rc::new(render_loading));
+        self
+    }
+
+    /// Enable reordering items by dragging them onto other items. The dragged item is moved in
+    /// the [`TreeViewState`] before the listener is called, which should update the application's
+    /// data to match.
+    pub fn on_move(
+        mut self,
+        listener: impl Fn(&TreeMove, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_move = Some(Rc::new(listener));
+        self
+    }
+}
+
+impl Styled for TreeView {
+    fn style(&mut self) -> &mut StyleRefinement {
+        self.base.style()
+    }
+}
+
+impl RenderOnce for TreeView {
+    fn render(self, window: &mut Window, _: &mut App) -> impl IntoElement {
+        use SelectionMode::*;
+        use tree_view_actions::*;
+
+        let state = self.state;
+        state.0.borrow_mut().view = Some(window.current_view());
+        let focus_handle = state.focus_handle();
+        let row_count = state.row_count();
+        let scroll_handle = state.scroll_handle();
+
+        let rows = TreeRows {
+            state: state.clone(),
+            render_item: self.render_item,
+            render_loading: self.render_loading,
+            on_move: self.on_move,
+            indent: self.indent,
+            selected_color: self.selected_color,
+            focused_color: self.focused_color,
+        };
+
+        self.base
+            .track_focus(&focus_handle)
+            .key_context(KEY_CONTEXT)
+            .on_action(move_focus::<SelectPrevious>(
+                &state,
+                Movement::Previous,
+                Replace,
+            ))
+            .on_action(move_focus::<SelectNext>(&state, Movement::Next, Replace))
+            .on_action(move_focus::<SelectFirst>(&state, Movement::First, Replace))
+            .on_action(move_focus::<SelectLast>(&state, Movement::Last, Replace))
+            .on_action(move_focus::<SelectToPrevious>(
+                &state,
+                Movement::Previous,
+                Extend,
+            ))
+            .on_action(move_focus::<SelectToNext>(&state, Movement::Next, Extend))
+            .on_action(move_focus::<SelectToFirst>(&state, Movement::First, Extend))
+            .on_action(move_focus::<SelectToLast>(&state, Movement::Last, Extend))
+            .on_action(move_focus::<FocusPrevious>(
+                &state,
+                Movement::Previous,
+                Keep,
+            ))
+            .on_action(move_focus::<FocusNext>(&state, Movement::Next, Keep))
+            .on_action(move_focus::<FocusFirst>(&state, Movement::First, Keep))
+            .on_action(move_focus::<FocusLast>(&state, Movement::Last, Keep))
+            .on_action(handle::<ExpandOrSelectChild>(&state, |state, cx| {
+                state.expand_or_select_child(cx)
+            }))
+            .on_action(handle::<CollapseOrSelectParent>(&state, |state, _| {
+                state.collapse_or_select_parent()
+            }))
+            .on_action(handle::<SelectFocused>(&state, |state, _| {
+                state.select_focused(false)
+            }))
+            .on_action(handle::<ToggleSelected>(&state, |state, _| {
+                state.select_focused(true)
+            }))
+            .on_action(handle::<SelectAll>(&state, |state, _| state.select_all()))
+            .child(
+                uniform_list("tree-view-rows", row_count, move |range, window, cx| {
+                    range
+                        .map(|ix| rows.render_row(ix, window, cx))
+                        .collect::<Vec<_>>()
+                })
+                .track_scroll(scroll_handle)
+                .size_full(),
+            )
+    }
+}
+
+/// An action listener that runs `handler` and re-renders the tree view if it handled the action,
+/// or lets the action propagate otherwise.
+fn handle<A: Action>(
+    state: &TreeViewState,
+    handler: impl Fn(&TreeViewState, &mut App) -> bool + 'static,
+) -> impl Fn(&A, &mut Window, &mut App) + 'static {
+    let state = state.clone();
+    move |_, _, cx| {
+        if handler(&state, cx) {
+            state.notify(cx);
+        } else {
+            cx.propagate();
+        }
+    }
+}
+
+fn move_focus<A: Action>(
+    state: &TreeViewState,
+    movement: Movement,
+    mode: SelectionMode,
+) -> impl Fn(&A, &mut Window, &mut App) + 'static {
+    handle(state, move |state, _| state.move_focus(movement, mode))
+}
+
+/// The parts of a [`TreeView`] needed to render its rows.
+struct TreeRows {
+    state: TreeViewState,
+    render_item: Rc<RenderItemFn>,
+    render_loading: Option<Rc<RenderLoadingFn>>,
+    on_move: Option<Rc<MoveListener>>,
+    indent: Pixels,
+    selected_color: crate::Hsla,
+    focused_color: crate::Hsla,
+}
+
+impl TreeRows {
+    fn render_row(&self, ix: usize, window: &mut Window, cx: &mut App) -> AnyElement {
+        let Some((id, details)) = self.state.details(ix, window) else {
+            let depth = self.state.0.borrow().row(ix).map_or(0, |row| row.depth);
+            let contents = match self.render_loading.as_ref() {
+                Some(render_loading) => render_loading(depth, window, cx),
+                None => div()
+                    .child("Loading…")
+                    .with_animation(
+                        ("tree-view-loading", ix),
+                        Animation::new(Duration::from_secs(1))
+                            .repeat()
+                            .with_easing(pulsating_between(0.3, 1.)),
+                        |label, delta| label.opacity(delta),
+                    )
+                    .into_any_element(),
+            };
+            return div()
+                .id(("tree-view-loading-row", ix))
+                .pl(self.indent * (depth + 1) as f32)
+                .child(contents)
+                .into_any_element();
+        };
+
+        let state = self.state.clone();
+        let disclosure = div()
+            .id(("tree-view-disclosure", id.0))
+            .flex_none()
+            .w(self.indent)
+            .when(details.has_children, |this| {
+                this.child(if details.expanded { "▾" } else { "▸" })
+                    .on_click({
+                        let state = state.clone();
+                        move |_, window, cx| {
+                            let ix = state.row_for_item(id);
+                            if let Some(ix) = ix {
+                                if state.is_expanded(id) {
+                                    state.collapse_row(ix);
+                                } else {
+                                    state.expand_row(ix, cx);
+                                }
+                            }
+                            window.focus(&state.focus_handle());
+                            state.notify(cx);
+                            cx.stop_propagation();
+                        }
+                    })
+            });
+
+        let mut row = div()
+            .id(("tree-view-item", id.0))
+            .flex()
+            .flex_row()
+            .items_center()
+            .pl(self.indent * details.depth as f32)
+            .when(details.selected, |this| this.bg(self.selected_color))
+            .when(details.focused, |this| {
+                this.border_1().border_color(self.focused_color)
+            })
+            .child(disclosure)
+            .child((self.render_item)(id, details, window, cx))
+            .on_click({
+                let state = state.clone();
+                move |event: &ClickEvent, window, cx| {
+                    if let Some(ix) = state.row_for_item(id) {
+                        state.click_row(ix, event.modifiers());
+                        if event.click_count() == 2 {
+                            if state.is_expanded(id) {
+                                state.collapse_row(ix);
+                            } else {
+                                state.expand_row(ix, cx);
+                            }
+                        }
+                    }
+                    window.focus(&state.focus_handle());
+                    state.notify(cx);
+                }
+            });
+
+        if let Some(on_move) = self.on_move.clone() {
+            let tree = state.id();
+            let focused_color = self.focused_color;
+            row = row
+                .on_drag(
+                    DraggedTreeItem {
+                        tree,
+                        item: id,
+                        details,
+                        render_item: self.render_item.clone(),
+                    },
+                    |dragged, _, _, cx| cx.new(|_| dragged.clone()),
+                )
+                .drag_over::<DraggedTreeItem>(move |style, dragged, _, _| {
+                    if dragged.tree == tree {
+                        style.border_t_2().border_color(focused_color)
+                    } else {
+                        style
+                    }
+                })
+                .on_drop(move |dragged: &DraggedTreeItem, window, cx| {
+                    if dragged.tree != tree {
+                        return;
+                    }
+                    if let Some(tree_move) = state.move_item(dragged.item, id) {
+                        on_move(&tree_move, window, cx);
+                        state.notify(cx);
+                    }
+                });
+        }
+
+        row.into_any_element()
+    }
+}
+
+/// The value dragged while reordering the items of a [`TreeView`].
+#[derive(Clone)]
+pub struct DraggedTreeItem {
+    tree: usize,
+    item: TreeItemId,
+    details: TreeItemDetails,
+    render_item: Rc<RenderItemFn>,
+}
+
+impl DraggedTreeItem {
+    /// The item being dragged.
+    pub fn item(&self) -> TreeItemId {
+        self.item
+    }
+}
+
+impl Render for DraggedTreeItem {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .opacity(0.8)
+            .child((self.render_item)(self.item, self.details, window, cx))
+    }
+}
+
+impl sum_tree::Item for TreeRow {
+    type Summary = TreeRowSummary;
+
+    fn summary(&self, _: ()) -> Self::Summary {
+        TreeRowSummary {
+            count: 1,
+            min_depth: self.depth,
+        }
+    }
+}
+
+impl sum_tree::ContextLessSummary for TreeRowSummary {
+    fn zero() -> Self {
+        TreeRowSummary {
+            count: 0,
+            min_depth: usize::MAX,
+        }
+    }
+
+    fn add_summary(&mut self, summary: &Self) {
+        self.count += summary.count;
+        self.min_depth = self.min_depth.min(summary.min_depth);
+    }
+}
+
+impl<'a> sum_tree::Dimension<'a, TreeRowSummary> for RowCount {
+    fn zero(_cx: ()) -> Self {
+        Default::default()
+    }
+
+    fn add_summary(&mut self, summary: &'a TreeRowSummary, _: ()) {
+        self.0 += summary.count;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        self as gpui, Context, IntoElement, ParentElement, Render, Styled, Task, TestAppContext,
+        TreeItem, TreeItemId, TreeMove, TreeViewState, Window, bind_tree_view_keys, div, px,
+        tree_view,
+    };
+    use std::time::Duration;
+
+    fn state(cx: &mut TestAppContext) -> TreeViewState {
+        cx.update(|cx| {
+            TreeViewState::new(
+                vec![
+                    TreeItem::branch(1u64),
+                    TreeItem::branch(2u64),
+                    TreeItem::leaf(3u64),
+                ],
+                |id, cx| match id.0 {
+                    1 => Task::ready(vec![TreeItem::branch(10u64), TreeItem::leaf(11u64)]),
+                    10 => {
+                        let timer = cx.background_executor().timer(Duration::from_millis(10));
+                        cx.background_executor().spawn(async move {
+                            timer.await;
+                            vec![TreeItem::leaf(100u64), TreeItem::leaf(101u64)]
+                        })
+                    }
+                    _ => Task::ready(Vec::new()),
+                },
+                cx,
+            )
+        })
+    }
+
+    fn visible_items(state: &TreeViewState) -> Vec<Option<u64>> {
+        (0..state.row_count())
+            .map(|ix| state.item_at(ix).map(|id| id.0))
+            .collect()
+    }
+
+    #[gpui::test]
+    fn test_expand_collapse_and_move(cx: &mut TestAppContext) {
+        let state = state(cx);
+
+        cx.update(|cx| state.expand(TreeItemId(1), cx));
+        assert_eq!(visible_items(&state), [Some(1), None, Some(2), Some(3)]);
+        assert!(state.is_loading(TreeItemId(1)));
+        cx.run_until_parked();
+        assert_eq!(
+            visible_items(&state),
+            [Some(1), Some(10), Some(11), Some(2), Some(3)]
+        );
+
+        cx.update(|cx| state.expand(TreeItemId(10), cx));
+        cx.run_until_parked();
+        assert_eq!(
+            visible_items(&state),
+            [Some(1), Some(10), None, Some(11), Some(2), Some(3)]
+        );
+        cx.executor().advance_clock(Duration::from_millis(10));
+        cx.run_until_parked();
+        assert_eq!(
+            visible_items(&state),
+            [
+                Some(1),
+                Some(10),
+                Some(100),
+                Some(101),
+                Some(11),
+                Some(2),
+                Some(3)
+            ]
+        );
+
+        // Re-expanding restores expanded descendants from the cached children.
+        state.collapse(TreeItemId(1));
+        assert_eq!(visible_items(&state), [Some(1), Some(2), Some(3)]);
+        cx.update(|cx| state.expand(TreeItemId(1), cx));
+        assert_eq!(
+            visible_items(&state),
+            [
+                Some(1),
+                Some(10),
+                Some(100),
+                Some(101),
+                Some(11),
+                Some(2),
+                Some(3)
+            ]
+        );
+
+        assert_eq!(
+            state.move_item(TreeItemId(100), TreeItemId(11)),
+            Some(TreeMove {
+                item: TreeItemId(100),
+                parent: Some(TreeItemId(1)),
+                index: 1,
+            })
+        );
+        assert_eq!(
+            visible_items(&state),
+            [
+                Some(1),
+                Some(10),
+                Some(101),
+                Some(100),
+                Some(11),
+                Some(2),
+                Some(3)
+            ]
+        );
+        assert_eq!(state.move_item(TreeItemId(1), TreeItemId(101)), None);
+
+        // Moving a subtree to the root level carries its descendants along.
+        state.move_item(TreeItemId(10), TreeItemId(3));
+        assert_eq!(
+            visible_items(&state),
+            [
+                Some(1),
+                Some(100),
+                Some(11),
+                Some(2),
+                Some(10),
+                Some(101),
+                Some(3)
+            ]
+        );
+        state.collapse(TreeItemId(10));
+        assert_eq!(
+            visible_items(&state),
+            [Some(1), Some(100), Some(11), Some(2), Some(10), Some(3)]
+        );
+        assert_eq!(state.row_for_item(TreeItemId(10)), Some(4));
+        assert_eq!(state.row_for_item(TreeItemId(3)), Some(5));
+        assert_eq!(state.row_for_item(TreeItemId(101)), None);
+    }
+
+    struct TestView(TreeViewState);
+
+    impl Render for TestView {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            tree_view("tree", self.0.clone(), |id, _, _, _| {
+                div().child(format!("{}", id.0)).into_any_element()
+            })
+            .w(px(200.))
+            .h(px(200.))
+        }
+    }
+
+    #[gpui::test]
+    fn test_keyboard_navigation(cx: &mut TestAppContext) {
+        cx.update(bind_tree_view_keys);
+        let state = state(cx);
+        let (_, cx) = cx.add_window_view(|_, _| TestView(state.clone()));
+        cx.update(|window, _| window.focus(&state.focus_handle()));
+        cx.run_until_parked();
+
+        cx.simulate_keystrokes("down right");
+        cx.run_until_parked();
+        assert_eq!(state.focused_item(), Some(TreeItemId(1)));
+        assert!(state.is_expanded(TreeItemId(1)));
+
+        cx.simulate_keystrokes("right shift-down");
+        assert_eq!(state.focused_item(), Some(TreeItemId(11)));
+        assert_eq!(state.selected_items(), [TreeItemId(10), TreeItemId(11)]);
+
+        cx.simulate_keystrokes("left");
+        assert_eq!(state.focused_item(), Some(TreeItemId(1)));
+        assert_eq!(state.selected_items(), [TreeItemId(1)]);
+
+        cx.simulate_keystrokes("left end");
+        assert_eq!(visible_items(&state), [Some(1), Some(2), Some(3)]);
+        assert_eq!(state.focused_item(), Some(TreeItemId(3)));
+
+        cx.simulate_keystrokes("secondary-home");
+        assert_eq!(state.focused_item(), Some(TreeItemId(1)));
+        assert_eq!(state.selected_items(), [TreeItemId(3)]);
+        cx.simulate_keystrokes("secondary-space");
+        assert_eq!(state.selected_items(), [TreeItemId(1), TreeItemId(3)]);
+    }
+}
@@ -0,0 +1,578 @@
+//! A virtualized two-dimensional grid, for photo galleries, icon views and similar layouts.
+//!
+//! The grid arranges its items into rows, deriving the number of columns from the available
+//! width, and optionally groups them into sections with a header row per section. Rows are
+//! stored in a [`ListState`], so only the rows intersecting the viewport are rendered and the
+//! measured height of every row is cached until the width of the grid changes.
+//!
+//! If every item in your grid is laid out as a single column, see [`crate::List`].
+
+use crate::{
+    AnyElement, App, Bounds, Element, GlobalElementId, InspectorElementId, IntoElement, LayoutId,
+    List, ListAlignment, ListOffset, ListPrepaintState, ListState, ParentElement, Pixels,
+    ScrollStrategy, Size, Style, StyleRefinement, Styled, Window, div, list, point, px, size,
+};
+use refineable::Refineable as _;
+use std::{cell::RefCell, ops::Range, rc::Rc};
+
+type RenderCellFn = dyn FnMut(usize, &mut Window, &mut App) -> AnyElement + 'static;
+type RenderHeaderFn = dyn FnMut(usize, &mut Window, &mut App) -> AnyElement + 'static;
+
+/// Construct a new virtualized grid element.
+///
+/// `render_item` is called with the index of each visible item, counted across all sections.
+pub fn virtual_grid(
+    state: VirtualGridState,
+    render_item: impl FnMut(usize, &mut Window, &mut App) -> AnyElement + 'static,
+) -> VirtualGrid {
+    let render_item: Rc<RefCell<Box<RenderCellFn>>> = Rc::new(RefCell::new(Box::new(render_item)));
+    let render_header: Rc<RefCell<Option<Box<RenderHeaderFn>>>> = Rc::default();
+    let list_state = state.0.borrow().list.clone();
+    let list = list(list_state, {
+        let state = state.clone();
+        let render_item = render_item.clone();
+        let render_header = render_header.clone();
+        move |row_ix, window, cx| state.render_row(row_ix, &render_item, &render_header, window, cx)
+    });
+
+    VirtualGrid {
+        state,
+        list,
+        render_header,
+    }
+}
+
+/// A virtualized grid element. See [`virtual_grid`].
+pub struct VirtualGrid {
+    state: VirtualGridState,
+    list: List,
+    render_header: Rc<RefCell<Option<Box<RenderHeaderFn>>>>,
+}
+
+impl VirtualGrid {
+    /// Render the header of each section declared with [`VirtualGridState::set_sections`].
+    pub fn section_header(
+        self,
+        render_header: impl FnMut(usize, &mut Window, &mut App) -> AnyElement + 'static,
+    ) -> Self {
+        *self.render_header.borrow_mut() = Some(Box::new(render_header));
+        self
+    }
+
+    /// Set whether the header of the section at the top of the viewport stays pinned while
+    /// its section scrolls. Defaults to `true`.
+    pub fn sticky_headers(mut self, sticky: bool) -> Self {
+        self.list = self.list.sticky_headers(sticky);
+        self
+    }
+}
+
+/// How the cells of a [`VirtualGrid`] are sized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GridCellSize {
+    /// Every cell has exactly this size, and as many columns as fit the width are shown.
+    Fixed(Size<Pixels>),
+    /// Cells are at least `min_width` wide and stretch to fill the row. Each row is as tall as
+    /// its tallest cell, which is measured when the row is first rendered.
+    Measured {
+        /// The minimum width of a cell, used to derive the number of columns.
+        min_width: Pixels,
+    },
+}
+
+impl GridCellSize {
+    fn min_width(&self) -> Pixels {
+        match self {
+            GridCellSize::Fixed(size) => size.width,
+            GridCellSize::Measured { min_width } => *min_width,
+        }
+    }
+}
+
+/// The grid state that views must hold on behalf of the grid element.
+#[derive(Clone)]
+pub struct VirtualGridState(Rc<RefCell<GridStateInner>>);
+
+impl std::fmt::Debug for VirtualGridState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("VirtualGridState")
+    }
+}
+
+struct GridStateInner {
+    list: ListState,
+    cell_size: GridCellSize,
+    sections: Vec<GridSection>,
+    has_headers: bool,
+    columns: usize,
+    cell_width: Pixels,
+    column_gap: Pixels,
+    row_gap: Pixels,
+    deferred_scroll_to_item: Option<(usize, ScrollStrategy)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct GridSection {
+    item_start: usize,
+    item_count: usize,
+    row_start: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum GridRow {
+    Header(usize),
+    Cells(Range<usize>),
+}
+
+impl VirtualGridState {
+    /// Construct a new grid state with a single section of `item_count` items, for storage on
+    /// a view.
+    ///
+    /// The overdraw parameter controls how much extra space is measured above and below the
+    /// visible area, as with [`ListState::new`].
+    pub fn new(item_count: usize, cell_size: GridCellSize, overdraw: Pixels) -> Self {
+        let this = Self(Rc::new(RefCell::new(GridStateInner {
+            list: ListState::new(0, ListAlignment::Top, overdraw),
+            cell_size,
+            sections: Vec::new(),
+            has_headers: false,
+            columns: 1,
+            cell_width: cell_size.min_width(),
+            column_gap: px(0.),
+            row_gap: px(0.),
+            deferred_scroll_to_item: None,
+        })));
+        this.reset(item_count);
+        this
+    }
+
+    /// Replace all items with a single section of `item_count` items, without a header.
+    pub fn reset(&self, item_count: usize) {
+        let state = &mut *self.0.borrow_mut();
+        state.has_headers = false;
+        state.set_section_counts([item_count]);
+    }
+
+    /// Replace all items with sections of the given sizes. Every section gets a header row,
+    /// rendered with [`VirtualGrid::section_header`].
+    pub fn set_sections(&self, item_counts: impl IntoIterator<Item = usize>) {
+        let state = &mut *self.0.borrow_mut();
+        state.has_headers = true;
+        state.set_section_counts(item_counts);
+    }
+
+    /// Change how cells are sized. Takes effect on the next layout.
+    pub fn set_cell_size(&self, cell_size: GridCellSize) {
+        let state = &mut *self.0.borrow_mut();
+        if state.cell_size != cell_size {
+            state.cell_size = cell_size;
+            state.list.remeasure();
+        }
+    }
+
+    /// Invalidate the cached row heights, keeping the current scroll position.
+    ///
+    /// Call this when the contents of items may have changed height.
+    pub fn remeasure(&self) {
+        self.0.borrow().list.remeasure();
+    }
+
+    /// The total number of items in the grid, across all sections.
+    pub fn item_count(&self) -> usize {
+        self.0
+            .borrow()
+            .sections
+            .last()
+            .map_or(0, |section| section.item_start + section.item_count)
+    }
+
+    /// The number of sections in the grid.
+    pub fn section_count(&self) -> usize {
+        self.0.borrow().sections.len()
+    }
+
+    /// The number of columns computed during the last layout.
+    pub fn column_count(&self) -> usize {
+        self.0.borrow().columns
+    }
+
+    /// The index of the section containing the given item.
+    pub fn section_for_item(&self, ix: usize) -> Option<usize> {
+        self.0.borrow().section_containing_item(ix)
+    }
+
+    /// Scroll the grid so that the row containing the given item is placed according to
+    /// `strategy`. The position is resolved during the next layout.
+    pub fn scroll_to_item(&self, ix: usize, strategy: ScrollStrategy) {
+        self.0.borrow_mut().deferred_scroll_to_item = Some((ix, strategy));
+    }
+
+    /// Get the bounds of the given item in window coordinates, if its row has been rendered.
+    pub fn bounds_for_item(&self, ix: usize) -> Option<Bounds<Pixels>> {
+        let state = self.0.borrow();
+        let row_ix = state.row_for_item(ix)?;
+        let section = state.sections[state.section_containing_item(ix)?];
+        let column = (ix - section.item_start) % state.columns;
+        let row_bounds = state.list.bounds_for_item(row_ix)?;
+        let cell_height = match state.cell_size {
+            GridCellSize::Fixed(size) => size.height,
+            GridCellSize::Measured { .. } => row_bounds.size.height - state.row_gap,
+        };
+        Some(Bounds::new(
+            point(
+                row_bounds.left() + (state.cell_width + state.column_gap) * column as f32,
+                row_bounds.top(),
+            ),
+            size(state.cell_width, cell_height),
+        ))
+    }
+
+    /// Get the current scroll offset, in terms of the grid's rows.
+    pub fn logical_scroll_top(&self) -> ListOffset {
+        self.0.borrow().list.logical_scroll_top()
+    }
+
+    /// Return the bounds of the viewport in pixels.
+    pub fn viewport_bounds(&self) -> Bounds<Pixels> {
+        self.0.borrow().list.viewport_bounds()
+    }
+
+    fn render_row(
+        &self,
+        row_ix: usize,
+        render_item: &RefCell<Box<RenderCellFn>>,
+        render_header: &RefCell<Option<Box<RenderHeaderFn>>>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> AnyElement {
+        let (row, cell_size, cell_width, column_gap, row_gap) = {
+            let state = self.0.borrow();
+            (
+                state.row(row_ix),
+                state.cell_size,
+                state.cell_width,
+                state.column_gap,
+                state.row_gap,
+            )
+        };
+
+        match row {
+            Some(GridRow::Header(section_ix)) => match render_header.borrow_mut().as_mut() {
+                Some(render_header) => render_header(section_ix, window, cx),
+                None => div().into_any_element(),
+            },
+            Some(GridRow::Cells(items)) => {
+                let mut render_item = render_item.borrow_mut();
+                div()
+                    .flex()
+                    .flex_row()
+                    .w_full()
+                    .gap_x(column_gap)
+                    .pb(row_gap)
+                    .children(items.map(|ix| {
+                        let cell = div().flex_none().w(cell_width);
+                        let cell = match cell_size {
+                            GridCellSize::Fixed(size) => cell.h(size.height),
+                            GridCellSize::Measured { .. } => cell,
+                        };
+                        cell.child(render_item(ix, window, cx))
+                    }))
+                    .into_any_element()
+            }
+            None => div().into_any_element(),
+        }
+    }
+}
+
+impl GridStateInner {
+    fn set_section_counts(&mut self, item_counts: impl IntoIterator<Item = usize>) {
+        let mut item_start = 0;
+        self.sections = item_counts
+            .into_iter()
+            .map(|item_count| {
+                let section = GridSection {
+                    item_start,
+                    item_count,
+                    row_start: 0,
+                };
+                item_start += item_count;
+                section
+            })
+            .collect();
+        self.reset_rows();
+    }
+
+    /// Assign the first row of each section for the current column count and reset the list
+    /// to the new rows. The header rows become the list's sections, so they stick to the top.
+    fn reset_rows(&mut self) {
+        let mut row_start = 0;
+        for section in &mut self.sections {
+            section.row_start = row_start;
+            row_start += self.has_headers as usize + section.item_count.div_ceil(self.columns);
+        }
+        self.list.reset(row_start);
+        if self.has_headers {
+            self.list
+                .set_sections(self.sections.iter().map(|section| section.row_start));
+        }
+    }
+
+    fn section_containing_item(&self, ix: usize) -> Option<usize> {
+        let section_ix = self
+            .sections
+            .partition_point(|section| section.item_start + section.item_count <= ix);
+        (section_ix < self.sections.len()).then_some(section_ix)
+    }
+
+    fn section_containing_row(&self, row_ix: usize) -> Option<usize> {
+        self.sections
+            .partition_point(|section| section.row_start <= row_ix)
+            .checked_sub(1)
+    }
+
+    fn header_row(&self, section_ix: usize) -> Option<usize> {
+        let section = self.sections.get(section_ix)?;
+        self.has_headers.then_some(section.row_start)
+    }
+
+    fn row_for_item(&self, ix: usize) -> Option<usize> {
+        let section = self.sections[self.section_containing_item(ix)?];
+        Some(
+            section.row_start
+                + self.has_headers as usize
+                + (ix - section.item_start) / self.columns,
+        )
+    }
+
+    fn row(&self, row_ix: usize) -> Option<GridRow> {
+        let section = self.sections[self.section_containing_row(row_ix)?];
+        let mut row_in_section = row_ix - section.row_start;
+        if self.has_headers {
+            if row_in_section == 0 {
+                return Some(GridRow::Header(self.section_containing_row(row_ix)?));
+            }
+            row_in_section -= 1;
+        }
+
+        let start = section.item_start + row_in_section * self.columns;
+        let end = (start + self.columns).min(section.item_start + section.item_count);
+        (start < end).then_some(GridRow::Cells(start..end))
+    }
+
+    /// Update the column layout for the given width. If the number of columns changes, the
+    /// rows are rebuilt and the item at the top of the viewport stays at the top.
+    fn layout_columns(&mut self, width: Pixels, column_gap: Pixels, row_gap: Pixels) {
+        let min_width = self.cell_size.min_width().max(px(1.));
+        let columns = (((width + column_gap) / (min_width + column_gap)).floor() as usize).max(1);
+        self.cell_width = match self.cell_size {
+            GridCellSize::Fixed(size) => size.width,
+            GridCellSize::Measured { .. } => {
+                ((width - column_gap * (columns - 1) as f32) / columns as f32).max(px(0.))
+            }
+        };
+
+        if self.column_gap != column_gap || self.row_gap != row_gap {
+            self.column_gap = column_gap;
+            self.row_gap = row_gap;
+            self.list.remeasure();
+        }
+
+        if columns != self.columns {
+            let anchor = self.row(self.list.logical_scroll_top().item_ix);
+
+            self.columns = columns;
+            self.reset_rows();
+
+            let anchor_row = match anchor {
+                Some(GridRow::Header(section_ix)) => self.header_row(section_ix),
+                Some(GridRow::Cells(items)) => self.row_for_item(items.start),
+                None => None,
+            };
+            if let Some(item_ix) = anchor_row {
+                self.list.scroll_to(ListOffset {
+                    item_ix,
+                    offset_in_item: px(0.),
+                });
+            }
+        }
+    }
+}
+
+impl Element for VirtualGrid {
+    type RequestLayoutState = ();
+    type PrepaintState = ListPrepaintState;
+
+    fn id(&self) -> Option<crate::ElementId> {
+        None
+    }
+
+    fn source_location(&self) -> Option<&'static core::panic::Location<'static>> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        self.list.request_layout(None, None, window, cx)
+    }
+
+    fn prepaint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        request_layout: &mut Self::RequestLayoutState,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> ListPrepaintState {
+        let mut style = Style::default();
+        style.refine(self.list.style());
+        let rem_size = window.rem_size();
+        let column_gap = style
+            .gap
+            .width
+            .to_pixels(bounds.size.width.into(), rem_size);
+        let row_gap = style
+            .gap
+            .height
+            .to_pixels(bounds.size.height.into(), rem_size);
+
+        {
+            let state = &mut *self.state.0.borrow_mut();
+            state.layout_columns(bounds.size.width, column_gap, row_gap);
+            if let Some((ix, strategy)) = state.deferred_scroll_to_item.take()
+                && let Some(row_ix) = state.row_for_item(ix)
+            {
+                state.list.scroll_to_item(row_ix, strategy);
+            }
+        }
+
+        self.list
+            .prepaint(None, None, bounds, request_layout, window, cx)
+    }
+
+    fn paint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        request_layout: &mut Self::RequestLayoutState,
+        prepaint: &mut Self::PrepaintState,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        self.list
+            .paint(None, None, bounds, request_layout, prepaint, window, cx);
+    }
+}
+
+impl IntoElement for VirtualGrid {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl Styled for VirtualGrid {
+    fn style(&mut self) -> &mut StyleRefinement {
+        self.list.style()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        self as gpui, AppContext, Context, GridCellSize, IntoElement, ParentElement, Render,
+        ScrollStrategy, Styled, TestAppContext, VirtualGridState, Window, div, point, px, size,
+        virtual_grid,
+    };
+
+    struct TestView(VirtualGridState);
+
+    impl Render for TestView {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            virtual_grid(self.0.clone(), |ix, _, _| {
+                div().h(px(10. + (ix % 3) as f32)).into_any_element()
+            })
+            .section_header(|section_ix, _, _| {
+                div()
+                    .h(px(5.))
+                    .child(format!("Section {section_ix}"))
+                    .into_any_element()
+            })
+            .w_full()
+            .h_full()
+        }
+    }
+
+    #[gpui::test]
+    fn test_columns_follow_available_width(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = VirtualGridState::new(10, GridCellSize::Fixed(size(px(30.), px(20.))), px(0.));
+
+        cx.draw(point(px(0.), px(0.)), size(px(100.), px(100.)), |_, cx| {
+            cx.new(|_| TestView(state.clone()))
+        });
+        assert_eq!(state.column_count(), 3);
+        assert_eq!(
+            state.bounds_for_item(4),
+            Some(gpui::Bounds::new(
+                point(px(30.), px(20.)),
+                size(px(30.), px(20.))
+            ))
+        );
+
+        cx.draw(point(px(0.), px(0.)), size(px(61.), px(100.)), |_, cx| {
+            cx.new(|_| TestView(state.clone()))
+        });
+        assert_eq!(state.column_count(), 2);
+        assert_eq!(
+            state.bounds_for_item(4),
+            Some(gpui::Bounds::new(
+                point(px(0.), px(40.)),
+                size(px(30.), px(20.))
+            ))
+        );
+    }
+
+    #[gpui::test]
+    fn test_sections_and_scroll_to_item(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = VirtualGridState::new(0, GridCellSize::Measured { min_width: px(25.) }, px(0.));
+        state.set_sections([5, 100, 7]);
+        assert_eq!(state.item_count(), 112);
+        assert_eq!(state.section_count(), 3);
+        assert_eq!(state.section_for_item(4), Some(0));
+        assert_eq!(state.section_for_item(5), Some(1));
+        assert_eq!(state.section_for_item(111), Some(2));
+        assert_eq!(state.section_for_item(112), None);
+
+        cx.draw(point(px(0.), px(0.)), size(px(100.), px(50.)), |_, cx| {
+            cx.new(|_| TestView(state.clone()))
+        });
+        assert_eq!(state.column_count(), 4);
+
+        // Section 0 has a header row and two rows of cells, so item 45 (the 40th item of
+        // section 1) lives in row 3 + 1 + 10 = 14.
+        state.scroll_to_item(45, ScrollStrategy::Top);
+        cx.draw(point(px(0.), px(0.)), size(px(100.), px(50.)), |_, cx| {
+            cx.new(|_| TestView(state.clone()))
+        });
+        assert_eq!(state.logical_scroll_top().item_ix, 14);
+        let item_bounds = state.bounds_for_item(45).unwrap();
+        assert_eq!(item_bounds.top(), px(0.));
+        assert_eq!(item_bounds.left(), px(0.));
+
+        state.scroll_to_item(45, ScrollStrategy::Bottom);
+        cx.draw(point(px(0.), px(0.)), size(px(100.), px(50.)), |_, cx| {
+            cx.new(|_| TestView(state.clone()))
+        });
+        let item_bounds = state.bounds_for_item(45).unwrap();
+        assert_eq!(item_bounds.bottom(), px(50.));
+    }
+}
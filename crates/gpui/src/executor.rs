@@ -1,4 +1,4 @@
-use crate::{App, PlatformDispatcher};
+use crate::{App, PlatformDispatcher, RecordedEvent, app::recording::SharedRecorder};
 use async_task::Runnable;
 use futures::channel::mpsc;
 use smol::prelude::*;
@@ -31,6 +31,7 @@ use rand::rngs::StdRng;
 pub struct BackgroundExecutor {
     #[doc(hidden)]
     pub dispatcher: Arc<dyn PlatformDispatcher>,
+    pub(crate) input_recorder: SharedRecorder,
 }
 
 /// A pointer to the executor that is currently running,
@@ -138,7 +139,10 @@ type AnyFuture<R> = Pin<Box<dyn 'static + Send + Future<Output = R>>>;
 impl BackgroundExecutor {
     #[doc(hidden)]
     pub fn new(dispatcher: Arc<dyn PlatformDispatcher>) -> Self {
-        Self { dispatcher }
+        Self {
+            dispatcher,
+            input_recorder: SharedRecorder::default(),
+        }
     }
 
     /// Enqueues the given future to be run to completion on a background thread.
@@ -346,10 +350,25 @@ impl BackgroundExecutor {
         if duration.is_zero() {
             return Task::ready(());
         }
-        let (runnable, task) = async_task::spawn(async move {}, {
-            let dispatcher = self.dispatcher.clone();
-            move |runnable| dispatcher.dispatch_after(duration, runnable)
-        });
+        let recorder = self
+            .input_recorder
+            .lock()
+            .as_ref()
+            .is_some_and(|recorder| recorder.records_timers())
+            .then(|| self.input_recorder.clone());
+        let (runnable, task) = async_task::spawn(
+            async move {
+                if let Some(recorder) = recorder
+                    && let Some(recorder) = recorder.lock().as_mut()
+                {
+                    recorder.record(RecordedEvent::TimerFired { duration });
+                }
+            },
+            {
+                let dispatcher = self.dispatcher.clone();
+                move |runnable| dispatcher.dispatch_after(duration, runnable)
+            },
+        );
         runnable.schedule();
         Task(TaskState::Spawned(task))
     }
@@ -78,7 +78,9 @@ pub use elements::*;
 pub use executor::*;
 pub use geometry::*;
 pub use global::*;
-pub use gpui_macros::{AppContext, IntoElement, Render, VisualContext, register_action, test};
+pub use gpui_macros::{
+    AppContext, IntoElement, Render, VisualContext, fuzz, register_action, test,
+};
 pub use http_client;
 pub use input::*;
 pub use inspector::*;
@@ -2,6 +2,7 @@ use crate::{
     point, seal::Sealed, Bounds, Capslock, Context, Empty, IntoElement, Keystroke, Modifiers,
     Pixels, Point, Render, Window,
 };
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::{any::Any, fmt::Debug, ops::Deref, path::PathBuf};
 
@@ -18,7 +19,7 @@ pub trait KeyEvent: InputEvent {}
 pub trait MouseEvent: InputEvent {}
 
 /// The key down event equivalent for the platform.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct KeyDownEvent {
     /// The keystroke that was generated.
     pub keystroke: Keystroke,
@@ -36,7 +37,7 @@ impl InputEvent for KeyDownEvent {
 impl KeyEvent for KeyDownEvent {}
 
 /// The key up event equivalent for the platform.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyUpEvent {
     /// The keystroke that was released.
     pub keystroke: Keystroke,
@@ -51,7 +52,7 @@ impl InputEvent for KeyUpEvent {
 impl KeyEvent for KeyUpEvent {}
 
 /// The modifiers changed event equivalent for the platform.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ModifiersChangedEvent {
     /// The new state of the modifier keys
     pub modifiers: Modifiers,
@@ -77,7 +78,7 @@ impl Deref for ModifiersChangedEvent {
 
 /// The phase of a touch motion event.
 /// Based on the winit enum of the same name.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub enum TouchPhase {
     /// The touch started.
     Started,
@@ -89,7 +90,7 @@ pub enum TouchPhase {
 }
 
 /// A mouse down event from the platform
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MouseDownEvent {
     /// Which mouse button was pressed.
     pub button: MouseButton,
@@ -116,7 +117,7 @@ impl InputEvent for MouseDownEvent {
 impl MouseEvent for MouseDownEvent {}
 
 /// A mouse up event from the platform
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MouseUpEvent {
     /// Which mouse button was released.
     pub button: MouseButton,
@@ -280,7 +281,7 @@ pub enum KeyboardButton {
 }
 
 /// An enum representing the mouse button that was pressed.
-#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug, Default)]
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug, Default, Serialize, Deserialize)]
 pub enum MouseButton {
     /// The left mouse button.
     #[default]
@@ -310,7 +311,7 @@ impl MouseButton {
 }
 
 /// A navigation direction, such as back or forward.
-#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug, Default)]
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug, Default, Serialize, Deserialize)]
 pub enum NavigationDirection {
     /// The back button.
     #[default]
@@ -321,7 +322,7 @@ pub enum NavigationDirection {
 }
 
 /// A mouse move event from the platform
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MouseMoveEvent {
     /// The position of the mouse on the window.
     pub position: Point<Pixels>,
@@ -349,7 +350,7 @@ impl MouseMoveEvent {
 }
 
 /// A mouse wheel event from the platform
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ScrollWheelEvent {
     /// The position of the mouse on the window.
     pub position: Point<Pixels>,
@@ -381,7 +382,7 @@ impl Deref for ScrollWheelEvent {
 }
 
 /// The scroll delta for a scroll wheel event.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum ScrollDelta {
     /// An exact scroll delta in pixels.
     Pixels(Point<Pixels>),
@@ -456,7 +457,7 @@ impl ScrollDelta {
 }
 
 /// A mouse exit event from the platform, generated when the mouse leaves the window.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MouseExitEvent {
     /// The position of the mouse relative to the window.
     pub position: Point<Pixels>,
@@ -483,7 +484,7 @@ impl Deref for MouseExitEvent {
 }
 
 /// A collection of paths from the platform, such as from a file drop.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ExternalPaths(pub(crate) SmallVec<[PathBuf; 2]>);
 
 impl ExternalPaths {
@@ -501,7 +502,7 @@ impl Render for ExternalPaths {
 }
 
 /// A file drop event from the platform, generated when files are dragged and dropped onto the window.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FileDropEvent {
     /// The files have entered the window.
     Entered {
@@ -533,7 +534,7 @@ impl InputEvent for FileDropEvent {
 impl MouseEvent for FileDropEvent {}
 
 /// An enum corresponding to all kinds of platform input events.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum PlatformInput {
     /// A key was pressed.
     KeyDown(KeyDownEvent),
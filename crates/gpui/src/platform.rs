@@ -40,12 +40,12 @@ mod windows;
 pub(crate) mod scap_screen_capture;
 
 use crate::{
-    Action, AnyWindowHandle, App, AsyncWindowContext, BackgroundExecutor, Bounds,
-    DEFAULT_WINDOW_SIZE, DevicePixels, DispatchEventResult, Font, FontId, FontMetrics, FontRun,
-    ForegroundExecutor, GlyphId, GpuSpecs, ImageSource, Keymap, LineLayout, Pixels, PlatformInput,
-    Point, RenderGlyphParams, RenderImage, RenderImageParams, RenderSvgParams, Scene, ShapedGlyph,
-    ShapedRun, SharedString, Size, SvgRenderer, SvgSize, SystemWindowTab, Task, TaskLabel, Window,
-    WindowControlArea, hash, point, px, size,
+    Action, AnchoredFitMode, AnyWindowHandle, App, AsyncWindowContext, BackgroundExecutor, Bounds,
+    Corner, DEFAULT_WINDOW_SIZE, DevicePixels, DispatchEventResult, Font, FontId, FontMetrics,
+    FontRun, ForegroundExecutor, GlyphId, GpuSpecs, ImageSource, Keymap, LineLayout, Pixels,
+    PlatformInput, Point, RenderGlyphParams, RenderImage, RenderImageParams, RenderSvgParams,
+    Scene, ShapedGlyph, ShapedRun, SharedString, Size, SvgRenderer, SvgSize, SystemWindowTab, Task,
+    TaskLabel, Window, WindowControlArea, hash, point, px, size,
 };
 use anyhow::Result;
 use async_task::Runnable;
@@ -68,7 +68,10 @@ use std::{
     ops::Range,
     path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{self, AtomicBool, AtomicU32},
+    },
 };
 use strum::EnumIter;
 use uuid::Uuid;
@@ -279,7 +282,14 @@ pub(crate) trait Platform: 'static {
     fn keyboard_mapper(&self) -> Rc<dyn PlatformKeyboardMapper>;
     fn on_keyboard_layout_change(&self, callback: Box<dyn FnMut()>);
 
-    fn set_tray_icon(&self, _icon: Option<&[u8]>) {}
+    /// Show `icon`, a single frame as returned by [`TrayIcon::frames`], in the system tray.
+    fn set_tray_icon(&self, _icon: Option<TrayIconFrame>) {}
+    /// Ask for the user's attention with `icon`, a single frame as returned by
+    /// [`TrayIcon::frames`], or go back to the normal icon.
+    fn set_tray_attention(&self, _icon: Option<TrayIconFrame>) {}
+    /// Show `icon`, a single frame as returned by [`TrayIcon::frames`], as a badge over the tray
+    /// icon, or remove the badge.
+    fn set_tray_overlay(&self, _icon: Option<TrayIconFrame>) {}
     fn set_tray_menu(&self, _menu: Vec<TrayMenuItem>) {}
     fn set_tray_tooltip(&self, _tooltip: &str) {}
     fn set_tray_panel_mode(&self, _enabled: bool) {}
@@ -300,6 +310,10 @@ pub(crate) trait Platform: 'static {
     fn focused_window_info(&self) -> Option<FocusedWindowInfo> {
         None
     }
+    fn on_focused_window_change(&self, _callback: Box<dyn FnMut(Option<FocusedWindowInfo>)>) {}
+    fn watch_focused_window(&self, _watch: bool) {}
+
+    fn on_displays_changed(&self, _callback: Box<dyn FnMut()>) {}
 
     fn accessibility_status(&self) -> PermissionStatus {
         PermissionStatus::Granted
@@ -327,25 +341,57 @@ pub(crate) trait Platform: 'static {
             "Notifications not supported on this platform"
         ))
     }
+    fn show_notification_with_options(
+        &self,
+        options: NotificationOptions,
+    ) -> Task<Result<NotificationId>> {
+        // Only the title and body are shown, but every notification still gets its own id.
+        static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+        Task::ready(
+            self.show_notification(&options.title, &options.body)
+                .map(|_| NotificationId(NEXT_ID.fetch_add(1, atomic::Ordering::Relaxed))),
+        )
+    }
+    fn close_notification(&self, _id: NotificationId) {}
+    fn on_notification_event(&self, _callback: Box<dyn FnMut(NotificationEvent)>) {}
 
     fn set_keep_alive_without_windows(&self, _keep_alive: bool) {}
 
     fn on_system_power_event(&self, _callback: Box<dyn FnMut(SystemPowerEvent)>) {}
-    fn start_power_save_blocker(&self, _kind: PowerSaveBlockerKind) -> Option<u32> { None }
+    fn delay_system_sleep(&self, _reason: Option<&str>) {}
+    fn start_power_save_blocker(&self, _kind: PowerSaveBlockerKind) -> Task<Result<u32>> {
+        Task::ready(Err(anyhow::anyhow!(
+            "Power save blockers not supported on this platform"
+        )))
+    }
     fn stop_power_save_blocker(&self, _id: u32) {}
-    fn system_idle_time(&self) -> Option<Duration> { None }
-    fn network_status(&self) -> NetworkStatus { NetworkStatus::Online }
+    fn system_idle_time(&self) -> Option<Duration> {
+        None
+    }
+    fn network_status(&self) -> NetworkStatus {
+        NetworkStatus::ONLINE
+    }
     fn on_network_status_change(&self, _callback: Box<dyn FnMut(NetworkStatus)>) {}
     fn on_media_key_event(&self, _callback: Box<dyn FnMut(MediaKeyEvent)>) {}
+    fn set_now_playing(&self, _now_playing: Option<NowPlaying>) {}
     fn request_user_attention(&self, _attention_type: AttentionType) {}
     fn cancel_user_attention(&self) {}
     fn set_dock_badge(&self, _label: Option<&str>) {}
+    /// Show a native context menu, handing the items back if the platform has none and GPUI
+    /// should render the menu itself.
     fn show_context_menu(
         &self,
         _position: Point<Pixels>,
-        _items: Vec<TrayMenuItem>,
+        items: Vec<TrayMenuItem>,
         _callback: Box<dyn FnMut(SharedString)>,
-    ) {}
+    ) -> Result<(), Vec<TrayMenuItem>> {
+        Err(items)
+    }
+    /// Whether [`Platform::show_dialog`] shows a native dialog. Where it doesn't, GPUI renders
+    /// dialogs in a window of its own.
+    fn has_native_dialogs(&self) -> bool {
+        true
+    }
     fn show_dialog(&self, _options: DialogOptions) -> oneshot::Receiver<usize> {
         let (tx, rx) = oneshot::channel();
         tx.send(0).ok();
@@ -360,7 +406,9 @@ pub(crate) trait Platform: 'static {
             hostname: String::new().into(),
         }
     }
-    fn biometric_status(&self) -> BiometricStatus { BiometricStatus::Unavailable }
+    fn biometric_status(&self) -> Task<BiometricStatus> {
+        Task::ready(BiometricStatus::Unavailable)
+    }
     fn authenticate_biometric(
         &self,
         _reason: &str,
@@ -368,6 +416,14 @@ pub(crate) trait Platform: 'static {
     ) {
         callback(false);
     }
+    fn authenticate_admin(
+        &self,
+        _action_id: &str,
+        _reason: &str,
+        callback: Box<dyn FnOnce(bool) + Send>,
+    ) {
+        callback(false);
+    }
 }
 
 /// A handle to a platform's display, e.g. a monitor or laptop screen.
@@ -382,9 +438,56 @@ pub trait PlatformDisplay: Send + Sync + Debug {
     /// Get the bounds for this display
     fn bounds(&self) -> Bounds<Pixels>;
 
+    /// Get the part of this display that windows can use, which excludes panels, docks and
+    /// taskbars. Defaults to the whole display where the platform doesn't report it.
+    fn visible_bounds(&self) -> Bounds<Pixels> {
+        self.bounds()
+    }
+
+    /// Get the refresh rate of this display in hertz, if the platform reports it.
+    fn refresh_rate(&self) -> Option<f32> {
+        None
+    }
+
+    /// Get the physical size of this display in millimeters, in its natural orientation, if the
+    /// platform reports it.
+    fn physical_size(&self) -> Option<Size<u32>> {
+        None
+    }
+
+    /// Get the number of logical pixels per inch on this display, computed from its bounds and
+    /// physical size.
+    fn dpi(&self) -> Option<f32> {
+        let physical_size = self.physical_size()?;
+        let width_mm = match self.rotation() {
+            DisplayRotation::Normal | DisplayRotation::Rotate180 => physical_size.width,
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => physical_size.height,
+        };
+        (width_mm > 0).then(|| f32::from(self.bounds().size.width) * 25.4 / width_mm as f32)
+    }
+
+    /// Get how this display is rotated from its natural orientation.
+    fn rotation(&self) -> DisplayRotation {
+        DisplayRotation::Normal
+    }
+
+    /// Whether this display can show high dynamic range content.
+    ///
+    /// On X11 this comes from the HDR static metadata in the monitor's EDID, and on Wayland from
+    /// the transfer function `color-management-v1` reports for the output, which compositors
+    /// without that protocol don't. It's always `false` on Windows, where HDR isn't detected.
+    fn is_hdr(&self) -> bool {
+        false
+    }
+
+    /// Get the ICC color profile assigned to this display, if it has one.
+    fn icc_profile(&self) -> Option<Vec<u8>> {
+        None
+    }
+
     /// Get the default bounds for this display to place a window
     fn default_bounds(&self) -> Bounds<Pixels> {
-        let center = self.bounds().center();
+        let center = self.visible_bounds().center();
         let offset = DEFAULT_WINDOW_SIZE / 2.0;
         let origin = point(center.x - offset.width, center.y - offset.height);
         Bounds::new(origin, DEFAULT_WINDOW_SIZE)
@@ -445,6 +548,20 @@ impl Debug for DisplayId {
 
 unsafe impl Send for DisplayId {}
 
+/// How a display is rotated, counter-clockwise from its natural orientation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DisplayRotation {
+    /// The display is in its natural orientation.
+    #[default]
+    Normal,
+    /// The display is rotated by 90 degrees.
+    Rotate90,
+    /// The display is rotated by 180 degrees.
+    Rotate180,
+    /// The display is rotated by 270 degrees.
+    Rotate270,
+}
+
 /// Which part of the window to resize
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ResizeEdge {
@@ -549,6 +666,8 @@ pub(crate) struct RequestFrameOptions {
     pub(crate) require_presentation: bool,
     /// Force refresh of all rendering states when true
     pub(crate) force_render: bool,
+    /// The display whose vertical blank drove this frame, if the platform knows it
+    pub(crate) display_id: Option<DisplayId>,
 }
 
 pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
@@ -636,6 +755,9 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     fn map_window(&mut self) -> anyhow::Result<()> {
         Ok(())
     }
+    /// Route all pointer and keyboard input to the window once it's mapped, so that it gets focus
+    /// and clicks outside of it make it inactive. The grab ends when the window is closed.
+    fn grab_input(&self) {}
     fn window_controls(&self) -> WindowControls {
         WindowControls::default()
     }
@@ -651,6 +773,9 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     }
     fn set_mouse_passthrough(&self, _passthrough: bool) {}
     fn set_progress_bar(&self, _state: ProgressBarState) {}
+    fn capture_frame(&self) -> Option<image::RgbaImage> {
+        None
+    }
 
     #[cfg(any(test, feature = "test-support"))]
     fn as_test(&mut self) -> Option<&mut TestWindow> {
@@ -695,15 +820,20 @@ pub(crate) trait PlatformTextSystem: Send + Sync {
     fn layout_line(&self, text: &str, font_size: Pixels, runs: &[FontRun]) -> LineLayout;
 }
 
+#[cfg_attr(
+    not(any(
+        test,
+        feature = "test-support",
+        all(target_os = "macos", not(feature = "font-kit")),
+        all(
+            any(target_os = "linux", target_os = "freebsd"),
+            not(any(feature = "x11", feature = "wayland"))
+        )
+    )),
+    allow(dead_code)
+)]
 pub(crate) struct NoopTextSystem;
 
-impl NoopTextSystem {
-    #[allow(dead_code)]
-    pub fn new() -> Self {
-        Self
-    }
-}
-
 impl PlatformTextSystem for NoopTextSystem {
     fn add_fonts(&self, _fonts: Vec<Cow<'static, [u8]>>) -> Result<()> {
         Ok(())
@@ -901,7 +1031,13 @@ impl<T> AtlasTextureList<T> {
         self.textures.drain(..)
     }
 
-    #[allow(dead_code)]
+    #[cfg_attr(
+        all(
+            any(target_os = "linux", target_os = "freebsd"),
+            not(any(feature = "x11", feature = "wayland"))
+        ),
+        allow(dead_code)
+    )]
     fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut T> {
         self.textures.iter_mut().flatten()
     }
@@ -1049,7 +1185,7 @@ impl PlatformInputHandler {
             .flatten()
     }
 
-    #[allow(dead_code)]
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
     fn apple_press_and_hold_enabled(&mut self) -> bool {
         self.handler.apple_press_and_hold_enabled()
     }
@@ -1182,7 +1318,6 @@ pub trait InputHandler: 'static {
     /// sending these to the platform.
     /// TODO: Ideally we should be able to set ApplePressAndHoldEnabled in NSUserDefaults
     /// (which is how iTerm does it) but it doesn't seem to work for me.
-    #[allow(dead_code)]
     fn apple_press_and_hold_enabled(&mut self) -> bool {
         true
     }
@@ -1239,6 +1374,10 @@ pub struct WindowOptions {
 
     /// Whether the window should allow mouse events to pass through to windows behind it
     pub mouse_passthrough: bool,
+
+    /// The window a [`WindowKind::Dialog`] is modal for, which gets no input until the dialog
+    /// closes. Without one, the dialog doesn't block any window. Ignored for other kinds.
+    pub modal_parent: Option<AnyWindowHandle>,
 }
 
 /// The variables that can be configured when creating a new window
@@ -1291,6 +1430,23 @@ pub(crate) struct WindowParams {
 
     #[allow(dead_code)]
     pub mouse_passthrough: bool,
+
+    /// Whether a [`WindowKind::Dialog`] is modal for the window it's opened over.
+    #[cfg_attr(not(any(target_os = "linux", target_os = "freebsd")), allow(dead_code))]
+    pub modal: bool,
+
+    /// Opens the window as a popup of another window, from [`WindowKind::AnchoredPopup`].
+    #[cfg_attr(not(any(target_os = "linux", target_os = "freebsd")), allow(dead_code))]
+    pub popup: Option<PopupParams>,
+}
+
+/// Where a [`WindowKind::AnchoredPopup`] is placed, relative to its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PopupParams {
+    pub parent: AnyWindowHandle,
+    pub anchor_rect: Bounds<Pixels>,
+    pub gravity: Corner,
+    pub constraint_adjustment: AnchoredFitMode,
 }
 
 /// Represents the status of how a window should be opened.
@@ -1350,6 +1506,7 @@ impl Default for WindowOptions {
             window_decorations: None,
             tabbing_identifier: None,
             mouse_passthrough: false,
+            modal_parent: None,
         }
     }
 }
@@ -1381,8 +1538,33 @@ pub enum WindowKind {
     /// A floating window that appears on top of its parent window
     Floating,
 
+    /// A dialog on top of the active window. It is modal when [`WindowOptions::modal_parent`] is
+    /// set, so the parent gets no input until the dialog closes.
+    Dialog,
+
     /// An overlay window that appears above all other windows, including fullscreen apps
     Overlay,
+
+    /// A window attached to an area of its parent window, such as a menu, dropdown or tooltip,
+    /// that may extend past the parent's bounds.
+    ///
+    /// The popup is placed at the `gravity` corner of `anchor_rect` and extends away from it, so a
+    /// dropdown that lines up with the left edge of a button uses a zero-width rect along that edge
+    /// with [`Corner::BottomRight`]. Its size comes from the window bounds in [`WindowOptions`];
+    /// their origin is ignored.
+    AnchoredPopup {
+        /// The window the popup belongs to.
+        parent: AnyWindowHandle,
+        /// The area of the parent window, in window coordinates, that the popup is attached to.
+        anchor_rect: Bounds<Pixels>,
+        /// The corner of `anchor_rect` the popup is attached to, and the direction it extends in.
+        gravity: Corner,
+        /// How to keep the popup on screen when it would overflow the display.
+        /// [`AnchoredFitMode::SwitchAnchor`] flips it to the other side of `anchor_rect` where it
+        /// fits, and then slides it onto the screen like [`AnchoredFitMode::SnapToWindow`]. Margins
+        /// are not supported on Wayland, where the compositor positions popups.
+        constraint_adjustment: AnchoredFitMode,
+    },
 }
 
 /// The appearance of the window, as defined by the operating system.
@@ -1445,7 +1627,138 @@ pub enum TrayIconEvent {
     DoubleClick,
 }
 
+/// The sizes, in pixels, that SVG tray icons are rendered at. Tray hosts pick the one closest to
+/// the size they show the icon at.
+const TRAY_ICON_SIZES: [u32; 6] = [16, 22, 24, 32, 48, 64];
+
+/// An icon shown in the system tray or next to a tray menu item.
+#[derive(Debug, Clone)]
+pub enum TrayIcon {
+    /// Encoded images (PNG, ICO, ...) of the same icon at different sizes, such as one for each
+    /// scale factor. The platform shows the one closest to the size it needs.
+    Images(Vec<Arc<[u8]>>),
+    /// An SVG document, rendered at each size the tray may show it at.
+    Svg(Arc<[u8]>),
+    /// The name of an icon in the freedesktop icon theme, such as `mail-unread-symbolic`. Only
+    /// tray hosts on Linux look these up.
+    Named(SharedString),
+    /// Icons shown one after another, starting over after the last one.
+    Animated {
+        /// The frames of the animation. Animated frames show their first frame.
+        frames: Vec<TrayIcon>,
+        /// How long each frame is shown.
+        interval: Duration,
+    },
+}
+
+impl TrayIcon {
+    /// An icon from a single encoded image.
+    pub fn image(bytes: impl Into<Arc<[u8]>>) -> Self {
+        Self::Images(vec![bytes.into()])
+    }
+
+    /// The frames of the icon and how long each is shown, with SVGs rendered and images decoded
+    /// once, so that animations don't decode them again for every frame.
+    pub(crate) fn frames(&self, renderer: &SvgRenderer) -> (Vec<TrayIconFrame>, Duration) {
+        let (frames, interval) = match self {
+            TrayIcon::Animated { frames, interval } => (
+                frames
+                    .iter()
+                    .filter_map(|frame| frame.still(renderer))
+                    .collect::<Vec<_>>(),
+                *interval,
+            ),
+            icon => (icon.still(renderer).into_iter().collect(), Duration::ZERO),
+        };
+        (frames.iter().map(TrayIconFrame::new).collect(), interval)
+    }
+
+    /// The icon as [`TrayIcon::Images`] or [`TrayIcon::Named`], with SVGs rendered to PNG images
+    /// and animations replaced by their first frame.
+    pub(crate) fn still(&self, renderer: &SvgRenderer) -> Option<TrayIcon> {
+        match self {
+            TrayIcon::Animated { frames, .. } => frames.first()?.still(renderer),
+            TrayIcon::Svg(svg) => {
+                let images = TRAY_ICON_SIZES
+                    .iter()
+                    .filter_map(|&size| {
+                        let size = Size::new(DevicePixels(size as i32), DevicePixels(size as i32));
+                        let pixmap = renderer
+                            .render_pixmap(svg, SvgSize::Size(size))
+                            .map_err(|error| log::error!("failed to render a tray icon: {error}"))
+                            .ok()?;
+                        pixmap.encode_png().ok().map(Arc::from)
+                    })
+                    .collect();
+                Some(TrayIcon::Images(images))
+            }
+            TrayIcon::Images(_) | TrayIcon::Named(_) => Some(self.clone()),
+        }
+    }
+}
+
+/// A single frame of a [`TrayIcon`], as the platform shows it.
+#[derive(Debug, Clone)]
+pub(crate) enum TrayIconFrame {
+    /// The decoded images of the frame at different sizes.
+    Images(Arc<[TrayIconImage]>),
+    /// The name of an icon in the freedesktop icon theme.
+    Named(SharedString),
+}
+
+/// An image of a [`TrayIconFrame`], along with its decoded pixels.
+#[derive(Debug)]
+pub(crate) struct TrayIconImage {
+    /// The encoded image.
+    pub bytes: Arc<[u8]>,
+    /// The pixels of the image.
+    pub rgba: image::RgbaImage,
+}
+
+impl TrayIconFrame {
+    /// The frame of `icon`, which is [`TrayIcon::Images`] or [`TrayIcon::Named`] as returned by
+    /// [`TrayIcon::still`].
+    pub(crate) fn new(icon: &TrayIcon) -> Self {
+        match icon {
+            TrayIcon::Named(name) => Self::Named(name.clone()),
+            TrayIcon::Images(images) => Self::images(images),
+            TrayIcon::Svg(_) | TrayIcon::Animated { .. } => Self::Images(Arc::from([])),
+        }
+    }
+
+    /// Decode `images`, leaving out the ones that fail to decode.
+    pub(crate) fn images(images: &[Arc<[u8]>]) -> Self {
+        Self::Images(
+            images
+                .iter()
+                .filter_map(|bytes| match image::load_from_memory(bytes) {
+                    Ok(image) => Some(TrayIconImage {
+                        bytes: bytes.clone(),
+                        rgba: image.into_rgba8(),
+                    }),
+                    Err(error) => {
+                        log::error!("failed to decode a tray icon: {error}");
+                        None
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// The image closest to `size` pixels wide, for platforms that show a single image.
+    pub(crate) fn image_closest_to(&self, size: u32) -> Option<&TrayIconImage> {
+        let TrayIconFrame::Images(images) = self else {
+            return None;
+        };
+        images
+            .iter()
+            .min_by_key(|image| image.rgba.width().abs_diff(size))
+    }
+}
+
 /// A menu item for a system tray context menu.
+///
+/// Icons are shown by tray hosts on Linux. Disabled items are shown but can't be chosen.
 #[derive(Debug, Clone)]
 pub enum TrayMenuItem {
     /// A clickable action item.
@@ -1454,6 +1767,12 @@ pub enum TrayMenuItem {
         label: SharedString,
         /// A unique identifier for this action.
         id: SharedString,
+        /// An icon shown next to the label.
+        icon: Option<TrayIcon>,
+        /// Whether the item can be chosen.
+        enabled: bool,
+        /// The keystroke shown next to the label.
+        accelerator: Option<Keystroke>,
     },
     /// A visual separator between menu items.
     Separator,
@@ -1463,6 +1782,10 @@ pub enum TrayMenuItem {
         label: SharedString,
         /// The nested menu items.
         items: Vec<TrayMenuItem>,
+        /// An icon shown next to the label.
+        icon: Option<TrayIcon>,
+        /// Whether the submenu can be opened.
+        enabled: bool,
     },
     /// A toggleable menu item with a checkmark.
     Toggle {
@@ -1472,11 +1795,172 @@ pub enum TrayMenuItem {
         checked: bool,
         /// A unique identifier for this toggle.
         id: SharedString,
+        /// An icon shown next to the label.
+        icon: Option<TrayIcon>,
+        /// Whether the item can be chosen.
+        enabled: bool,
+        /// The keystroke shown next to the label.
+        accelerator: Option<Keystroke>,
     },
+    /// One of a group of items of which only one is checked. Adjacent radio items with the same
+    /// group form one radio group.
+    Radio {
+        /// The display label.
+        label: SharedString,
+        /// The group this item belongs to.
+        group: SharedString,
+        /// Whether this is the checked item of its group.
+        checked: bool,
+        /// A unique identifier for this item.
+        id: SharedString,
+        /// An icon shown next to the label.
+        icon: Option<TrayIcon>,
+        /// Whether the item can be chosen.
+        enabled: bool,
+        /// The keystroke shown next to the label.
+        accelerator: Option<Keystroke>,
+    },
+}
+
+impl TrayMenuItem {
+    /// Create a clickable action item.
+    pub fn action(label: impl Into<SharedString>, id: impl Into<SharedString>) -> Self {
+        Self::Action {
+            label: label.into(),
+            id: id.into(),
+            icon: None,
+            enabled: true,
+            accelerator: None,
+        }
+    }
+
+    /// Create a separator.
+    pub fn separator() -> Self {
+        Self::Separator
+    }
+
+    /// Create a submenu.
+    pub fn submenu(label: impl Into<SharedString>, items: Vec<TrayMenuItem>) -> Self {
+        Self::Submenu {
+            label: label.into(),
+            items,
+            icon: None,
+            enabled: true,
+        }
+    }
+
+    /// Create an item with a checkmark.
+    pub fn toggle(
+        label: impl Into<SharedString>,
+        checked: bool,
+        id: impl Into<SharedString>,
+    ) -> Self {
+        Self::Toggle {
+            label: label.into(),
+            checked,
+            id: id.into(),
+            icon: None,
+            enabled: true,
+            accelerator: None,
+        }
+    }
+
+    /// Create an item of the radio group `group`.
+    pub fn radio(
+        label: impl Into<SharedString>,
+        group: impl Into<SharedString>,
+        checked: bool,
+        id: impl Into<SharedString>,
+    ) -> Self {
+        Self::Radio {
+            label: label.into(),
+            group: group.into(),
+            checked,
+            id: id.into(),
+            icon: None,
+            enabled: true,
+            accelerator: None,
+        }
+    }
+
+    /// Show `icon` next to the label of the item.
+    pub fn icon(mut self, icon: TrayIcon) -> Self {
+        if let Self::Action { icon: slot, .. }
+        | Self::Submenu { icon: slot, .. }
+        | Self::Toggle { icon: slot, .. }
+        | Self::Radio { icon: slot, .. } = &mut self
+        {
+            *slot = Some(icon);
+        }
+        self
+    }
+
+    /// Set whether the item can be chosen.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        if let Self::Action { enabled: slot, .. }
+        | Self::Submenu { enabled: slot, .. }
+        | Self::Toggle { enabled: slot, .. }
+        | Self::Radio { enabled: slot, .. } = &mut self
+        {
+            *slot = enabled;
+        }
+        self
+    }
+
+    /// Show `keystroke` next to the label of the item. The application handles the keystroke
+    /// itself, as tray menus don't receive key events while closed.
+    pub fn accelerator(mut self, keystroke: Keystroke) -> Self {
+        if let Self::Action { accelerator, .. }
+        | Self::Toggle { accelerator, .. }
+        | Self::Radio { accelerator, .. } = &mut self
+        {
+            *accelerator = Some(keystroke);
+        }
+        self
+    }
+
+    /// The label of the item, unless it is a separator.
+    pub fn label(&self) -> Option<&SharedString> {
+        match self {
+            Self::Action { label, .. }
+            | Self::Submenu { label, .. }
+            | Self::Toggle { label, .. }
+            | Self::Radio { label, .. } => Some(label),
+            Self::Separator => None,
+        }
+    }
+
+    /// Whether the item can be chosen. Separators can't.
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Self::Action { enabled, .. }
+            | Self::Submenu { enabled, .. }
+            | Self::Toggle { enabled, .. }
+            | Self::Radio { enabled, .. } => *enabled,
+            Self::Separator => false,
+        }
+    }
+
+    /// Render the SVG icons of this item and its submenu to images, and replace animated icons
+    /// with their first frame.
+    pub(crate) fn resolve_icons(&mut self, renderer: &SvgRenderer) {
+        if let Self::Submenu { items, .. } = self {
+            for item in items {
+                item.resolve_icons(renderer);
+            }
+        }
+        if let Self::Action { icon, .. }
+        | Self::Submenu { icon, .. }
+        | Self::Toggle { icon, .. }
+        | Self::Radio { icon, .. } = self
+        {
+            *icon = icon.take().and_then(|icon| icon.still(renderer));
+        }
+    }
 }
 
 /// Information about the currently focused window from any application.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FocusedWindowInfo {
     /// The name of the application that owns the focused window.
     pub app_name: String,
@@ -1525,13 +2009,65 @@ pub enum PowerSaveBlockerKind {
 
 /// The current network connectivity status.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum NetworkStatus {
-    /// The system has network connectivity.
+pub struct NetworkStatus {
+    /// How far the network reaches.
+    pub connectivity: NetworkConnectivity,
+    /// Whether the connection is metered, so large transfers should be avoided.
+    pub metered: bool,
+    /// The kind of interface carrying the primary connection, if known.
+    pub interface: Option<NetworkInterfaceKind>,
+}
+
+impl NetworkStatus {
+    /// Full connectivity over an unmetered connection of unknown kind.
+    pub const ONLINE: Self = Self {
+        connectivity: NetworkConnectivity::Online,
+        metered: false,
+        interface: None,
+    };
+
+    /// No network connectivity.
+    pub const OFFLINE: Self = Self {
+        connectivity: NetworkConnectivity::Offline,
+        metered: false,
+        interface: None,
+    };
+
+    /// Whether the system can reach the internet.
+    pub fn is_online(&self) -> bool {
+        self.connectivity == NetworkConnectivity::Online
+    }
+}
+
+/// How far the system's network connection reaches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkConnectivity {
+    /// The system can reach the internet.
     Online,
+    /// The system is behind a captive portal that must be signed in to before reaching the
+    /// internet.
+    Portal,
+    /// The system is connected to a network that doesn't reach the internet.
+    Limited,
     /// The system has no network connectivity.
     Offline,
 }
 
+/// The kind of network interface carrying a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkInterfaceKind {
+    /// A wired connection.
+    Ethernet,
+    /// A wireless connection.
+    Wifi,
+    /// A virtual private network.
+    Vpn,
+    /// A mobile broadband connection.
+    Cellular,
+    /// Any other kind of interface.
+    Other,
+}
+
 /// Media key events from hardware media keys or OS media controls.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MediaKeyEvent {
@@ -1547,6 +2083,56 @@ pub enum MediaKeyEvent {
     NextTrack,
     /// Skip to the previous track.
     PreviousTrack,
+    /// Seek by `offset` from the current position.
+    Seek {
+        /// Whether to seek forward or backward.
+        direction: SeekDirection,
+        /// How far to seek.
+        offset: Duration,
+    },
+    /// Seek to the given position in the current track.
+    SetPosition(Duration),
+}
+
+/// The direction of a [`MediaKeyEvent::Seek`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekDirection {
+    /// Seek toward the end of the track.
+    Forward,
+    /// Seek toward the start of the track.
+    Backward,
+}
+
+/// Metadata about the media being played, shown by the OS media controls.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NowPlaying {
+    /// The title of the track.
+    pub title: Option<SharedString>,
+    /// The artist of the track.
+    pub artist: Option<SharedString>,
+    /// The album the track is from.
+    pub album: Option<SharedString>,
+    /// A URL of the cover art, such as a `file://` or `https://` URL.
+    pub art_url: Option<SharedString>,
+    /// The length of the track, if known.
+    pub duration: Option<Duration>,
+    /// The playback position when this metadata was set. While playing, the OS advances the
+    /// position on its own, so it only needs to be updated after seeking.
+    pub position: Duration,
+    /// Whether the media is playing.
+    pub state: PlaybackState,
+}
+
+/// The playback state of the media being played.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaybackState {
+    /// The media is playing.
+    Playing,
+    /// The media is paused.
+    Paused,
+    /// Nothing is playing.
+    #[default]
+    Stopped,
 }
 
 /// The type of user attention to request from the OS.
@@ -1574,9 +2160,10 @@ pub enum ProgressBarState {
 }
 
 /// The kind of a native dialog.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum DialogKind {
     /// An informational dialog.
+    #[default]
     Info,
     /// A warning dialog.
     Warning,
@@ -1584,8 +2171,170 @@ pub enum DialogKind {
     Error,
 }
 
-/// Options for displaying a native dialog.
+/// Identifies a notification shown with [`App::show_notification_with_options`], to replace or
+/// close it later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NotificationId(pub u32);
+
+/// Options for an OS notification.
+#[derive(Debug, Clone, Default)]
+pub struct NotificationOptions {
+    /// The title of the notification.
+    pub title: SharedString,
+    /// The body text of the notification.
+    pub body: SharedString,
+    /// The icon or image shown with the notification.
+    pub icon: Option<NotificationIcon>,
+    /// How urgent the notification is.
+    pub urgency: NotificationUrgency,
+    /// The kind of event the notification is about, such as `"im.received"` or
+    /// `"transfer.complete"`, which the OS may use to group or style it.
+    pub category: Option<SharedString>,
+    /// How long the notification is shown. `None` uses the OS default, and [`Duration::ZERO`]
+    /// keeps it until it is dismissed.
+    pub timeout: Option<Duration>,
+    /// Buttons shown on the notification.
+    pub actions: Vec<NotificationAction>,
+    /// If set, the notification offers an inline reply field with this placeholder text.
+    pub reply_placeholder: Option<SharedString>,
+    /// A notification to replace with this one, such as an earlier progress update.
+    pub replaces: Option<NotificationId>,
+}
+
+impl NotificationOptions {
+    /// Create options for a notification with the given title.
+    pub fn new(title: impl Into<SharedString>) -> Self {
+        Self {
+            title: title.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the body text of the notification.
+    pub fn body(mut self, body: impl Into<SharedString>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Set the icon or image shown with the notification.
+    pub fn icon(mut self, icon: NotificationIcon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Set how urgent the notification is.
+    pub fn urgency(mut self, urgency: NotificationUrgency) -> Self {
+        self.urgency = urgency;
+        self
+    }
+
+    /// Set the kind of event the notification is about.
+    pub fn category(mut self, category: impl Into<SharedString>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    /// Set how long the notification is shown.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Add a button, reported as [`NotificationEvent::ActionInvoked`] with the given id.
+    pub fn action(mut self, id: impl Into<SharedString>, label: impl Into<SharedString>) -> Self {
+        self.actions.push(NotificationAction {
+            id: id.into(),
+            label: label.into(),
+        });
+        self
+    }
+
+    /// Offer an inline reply field, reported as [`NotificationEvent::Replied`].
+    pub fn reply(mut self, placeholder: impl Into<SharedString>) -> Self {
+        self.reply_placeholder = Some(placeholder.into());
+        self
+    }
+
+    /// Replace an earlier notification instead of showing a new one.
+    pub fn replaces(mut self, id: NotificationId) -> Self {
+        self.replaces = Some(id);
+        self
+    }
+}
+
+/// A button on a notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationAction {
+    /// The id reported when the button is clicked.
+    pub id: SharedString,
+    /// The label of the button.
+    pub label: SharedString,
+}
+
+/// The icon or image shown with a notification.
 #[derive(Debug, Clone)]
+pub enum NotificationIcon {
+    /// An icon from the icon theme, such as `"mail-unread"`.
+    Named(SharedString),
+    /// An image file.
+    Path(PathBuf),
+    /// Encoded image data.
+    Image(Arc<Image>),
+}
+
+/// How urgent a notification is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NotificationUrgency {
+    /// Informational, and may be shown unobtrusively.
+    Low,
+    /// The default urgency.
+    #[default]
+    Normal,
+    /// Needs attention, and usually stays until dismissed.
+    Critical,
+}
+
+/// An interaction with, or change to, a notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotificationEvent {
+    /// A button was clicked. Clicking the notification itself reports the `"default"` action.
+    ActionInvoked {
+        /// The notification.
+        id: NotificationId,
+        /// The id of the action.
+        action: SharedString,
+    },
+    /// Text was entered in the inline reply field.
+    Replied {
+        /// The notification.
+        id: NotificationId,
+        /// The reply.
+        text: String,
+    },
+    /// The notification was closed.
+    Closed {
+        /// The notification.
+        id: NotificationId,
+        /// Why it was closed.
+        reason: NotificationCloseReason,
+    },
+}
+
+/// Why a notification was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationCloseReason {
+    /// The notification timed out.
+    Expired,
+    /// The user dismissed the notification.
+    Dismissed,
+    /// The application closed the notification.
+    Closed,
+    /// The reason wasn't given.
+    Unknown,
+}
+
+/// Options for displaying a native dialog.
+#[derive(Debug, Clone, Default)]
 pub struct DialogOptions {
     /// The kind of dialog to display.
     pub kind: DialogKind,
@@ -1595,8 +2344,20 @@ pub struct DialogOptions {
     pub message: SharedString,
     /// Optional detail text shown below the message.
     pub detail: Option<SharedString>,
-    /// The button labels for the dialog.
+    /// The button labels for the dialog. The first one is the default, chosen with Enter.
     pub buttons: Vec<SharedString>,
+    /// A "don't ask again" checkbox shown below the message.
+    pub suppression: Option<DialogSuppression>,
+}
+
+/// A "don't ask again" checkbox in a dialog.
+#[derive(Debug, Clone, Default)]
+pub struct DialogSuppression {
+    /// The label of the checkbox, such as "Don't ask again".
+    pub label: SharedString,
+    /// Whether the checkbox was checked when the user chose a button. Set before the dialog's
+    /// receiver gets the button.
+    pub checked: Arc<AtomicBool>,
 }
 
 /// Information about the operating system.
@@ -1643,35 +2404,38 @@ pub struct WindowState {
     pub display_id: Option<DisplayId>,
     /// Whether the window is fullscreen.
     pub fullscreen: bool,
+    /// Which edges of the window are tiled, such as by snapping it to half of the display.
+    pub tiling: Tiling,
 }
 
 /// A semantic window position for positioning windows relative to the screen.
 #[derive(Debug, Clone, PartialEq)]
 pub enum WindowPosition {
-    /// Center the window on the primary display.
+    /// Center the window in the visible bounds of the primary display.
     Center,
-    /// Center the window on the given display.
+    /// Center the window in the visible bounds of the given display.
     CenterOnDisplay(DisplayId),
-    /// Center the window above the tray icon area.
+    /// Place the window next to the tray icon at these bounds, on the side of its panel that faces
+    /// the rest of the display.
     TrayCenter(Bounds<Pixels>),
-    /// Position the window in the top-right corner.
+    /// Position the window in the top-right corner of the primary display's visible bounds.
     TopRight {
-        /// The margin from the screen edge.
+        /// The margin from the edge of the visible bounds.
         margin: Pixels,
     },
-    /// Position the window in the bottom-right corner.
+    /// Position the window in the bottom-right corner of the primary display's visible bounds.
     BottomRight {
-        /// The margin from the screen edge.
+        /// The margin from the edge of the visible bounds.
         margin: Pixels,
     },
-    /// Position the window in the top-left corner.
+    /// Position the window in the top-left corner of the primary display's visible bounds.
     TopLeft {
-        /// The margin from the screen edge.
+        /// The margin from the edge of the visible bounds.
         margin: Pixels,
     },
-    /// Position the window in the bottom-left corner.
+    /// Position the window in the bottom-left corner of the primary display's visible bounds.
     BottomLeft {
-        /// The margin from the screen edge.
+        /// The margin from the edge of the visible bounds.
         margin: Pixels,
     },
 }
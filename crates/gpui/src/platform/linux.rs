@@ -1,13 +1,19 @@
 mod auto_launch;
-mod dialog;
 mod dispatcher;
+mod fprintd;
 mod global_hotkey;
 mod headless;
 mod keyboard;
+mod logind;
+mod mpris;
+mod network;
 mod notifications;
 mod os_info;
 mod platform;
+mod polkit;
 mod power;
+#[cfg(test)]
+mod test_bus;
 #[cfg(any(feature = "wayland", feature = "x11"))]
 mod text_system;
 mod tray;
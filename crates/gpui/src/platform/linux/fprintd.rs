@@ -0,0 +1,388 @@
+//! Fingerprint authentication through [fprintd].
+//!
+//! The default reader is claimed for the current user and a verification of any enrolled finger
+//! is started. fprintd reports progress with `VerifyStatus` signals until one of them is final,
+//! after which the reader is released again. fprintd has no UI of its own, so it is up to the
+//! application to ask the user to touch the reader.
+//!
+//! [fprintd]: https://fprint.freedesktop.org/fprintd-dev/
+
+use std::time::Duration;
+
+use anyhow::{Context as _, Result, anyhow};
+use futures::FutureExt as _;
+use util::ResultExt as _;
+use zbus::{Connection, Proxy, zvariant::OwnedObjectPath};
+
+use crate::{BiometricKind, BiometricStatus, ForegroundExecutor};
+
+const DESTINATION: &str = "net.reactivated.Fprint";
+const MANAGER_PATH: &str = "/net/reactivated/Fprint/Manager";
+const MANAGER_INTERFACE: &str = "net.reactivated.Fprint.Manager";
+const DEVICE_INTERFACE: &str = "net.reactivated.Fprint.Device";
+
+/// The final `VerifyStatus` result of a successful verification.
+const VERIFY_MATCH: &str = "verify-match";
+
+/// How long the user has to touch the reader before authentication fails.
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Whether the default reader on the system bus has fingerprints enrolled for the current user.
+pub(crate) async fn system_biometric_status() -> BiometricStatus {
+    match Connection::system().await {
+        Ok(connection) => biometric_status(&connection).await,
+        Err(error) => {
+            log::info!("can't connect to the system bus, fingerprints are unavailable: {error}");
+            BiometricStatus::Unavailable
+        }
+    }
+}
+
+/// Verify a fingerprint of the current user on the default reader of the system bus.
+pub(crate) fn authenticate(executor: &ForegroundExecutor, callback: Box<dyn FnOnce(bool) + Send>) {
+    executor
+        .spawn(async move {
+            let result = async { verify(&Connection::system().await?, VERIFY_TIMEOUT).await };
+            let verified = result
+                .await
+                .context("fingerprint authentication failed")
+                .log_err();
+            callback(verified.unwrap_or(false));
+        })
+        .detach();
+}
+
+async fn biometric_status(connection: &Connection) -> BiometricStatus {
+    let enrolled_fingers = async {
+        default_device(connection)
+            .await?
+            .call::<_, _, Vec<String>>("ListEnrolledFingers", &("",))
+            .await
+            .map_err(anyhow::Error::from)
+    };
+    match enrolled_fingers.await {
+        Ok(fingers) if !fingers.is_empty() => {
+            BiometricStatus::Available(BiometricKind::Fingerprint)
+        }
+        Ok(_) => BiometricStatus::Unavailable,
+        Err(error) => {
+            log::debug!("fingerprint authentication is unavailable: {error}");
+            BiometricStatus::Unavailable
+        }
+    }
+}
+
+async fn default_device(connection: &Connection) -> Result<Proxy<'static>> {
+    let manager = Proxy::new(connection, DESTINATION, MANAGER_PATH, MANAGER_INTERFACE).await?;
+    let path = manager
+        .call::<_, _, OwnedObjectPath>("GetDefaultDevice", &())
+        .await
+        .context("no fingerprint reader")?;
+    Ok(Proxy::new(connection, DESTINATION, path, DEVICE_INTERFACE).await?)
+}
+
+async fn verify(connection: &Connection, timeout: Duration) -> Result<bool> {
+    let device = default_device(connection).await?;
+    let mut reader = ClaimedReader::claim(device).await?;
+    let result = futures::select_biased! {
+        result = Box::pin(reader.verify()).fuse() => result,
+        _ = smol::Timer::after(timeout).fuse() => {
+            Err(anyhow!("no finger was verified within {timeout:?}"))
+        }
+    };
+    reader.release().await;
+    result
+}
+
+/// A reader claimed for the current user. It is released when dropped, after stopping the
+/// verification in progress, so that it's released however the verification ends.
+struct ClaimedReader {
+    device: Proxy<'static>,
+    verifying: bool,
+    released: bool,
+}
+
+impl ClaimedReader {
+    async fn claim(device: Proxy<'static>) -> Result<Self> {
+        // An empty user name claims the reader for the user that owns the connection.
+        device
+            .call::<_, _, ()>("Claim", &("",))
+            .await
+            .context("failed to claim the fingerprint reader")?;
+        Ok(Self {
+            device,
+            verifying: false,
+            released: false,
+        })
+    }
+
+    async fn verify(&mut self) -> Result<bool> {
+        use futures::StreamExt as _;
+
+        let mut statuses = self.device.receive_signal("VerifyStatus").await?;
+        // Set before the call returns, so that cancelling the call still stops the verification.
+        self.verifying = true;
+        let started = self.device.call::<_, _, ()>("VerifyStart", &("any",)).await;
+        self.verifying = started.is_ok();
+        started?;
+        let result = loop {
+            let status = statuses
+                .next()
+                .await
+                .context("fprintd stopped during verification")?;
+            let (result, done) = status.body().deserialize::<(String, bool)>()?;
+            if done {
+                break result;
+            }
+            log::debug!("fingerprint verification: {result}");
+        };
+        Ok(result == VERIFY_MATCH)
+    }
+
+    async fn release(mut self) {
+        self.released = true;
+        stop_and_release(&self.device, self.verifying).await;
+    }
+}
+
+impl Drop for ClaimedReader {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        // The verification was cancelled. Drop can't wait for fprintd, so release the reader from
+        // a thread of its own.
+        let device = self.device.clone();
+        let verifying = self.verifying;
+        std::thread::spawn(move || smol::block_on(stop_and_release(&device, verifying)));
+    }
+}
+
+async fn stop_and_release(device: &Proxy<'_>, verifying: bool) {
+    if verifying {
+        device.call::<_, _, ()>("VerifyStop", &()).await.log_err();
+    }
+    device.call::<_, _, ()>("Release", &()).await.log_err();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use zbus::object_server::SignalEmitter;
+
+    use super::*;
+    use crate::platform::linux::test_bus::{TestBus, wait_until};
+
+    const DEVICE_PATH: &str = "/net/reactivated/Fprint/Device/0";
+
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    struct FakeManager;
+
+    #[zbus::interface(name = "net.reactivated.Fprint.Manager")]
+    impl FakeManager {
+        fn get_default_device(&self) -> OwnedObjectPath {
+            OwnedObjectPath::try_from(DEVICE_PATH).unwrap()
+        }
+    }
+
+    struct FakeDevice {
+        calls: Calls,
+        /// The `VerifyStatus` signals sent after `VerifyStart`.
+        statuses: Vec<(&'static str, bool)>,
+    }
+
+    #[zbus::interface(name = "net.reactivated.Fprint.Device")]
+    impl FakeDevice {
+        fn list_enrolled_fingers(&self, username: &str) -> Vec<String> {
+            assert_eq!(username, "");
+            vec!["right-index-finger".into()]
+        }
+
+        fn claim(&self, username: &str) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("Claim {username:?}"));
+        }
+
+        fn release(&self) {
+            self.calls.lock().unwrap().push("Release".into());
+        }
+
+        async fn verify_start(
+            &self,
+            finger: &str,
+            #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        ) -> zbus::fdo::Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("VerifyStart {finger}"));
+            for (result, done) in &self.statuses {
+                Self::verify_status(&emitter, result, *done).await?;
+            }
+            Ok(())
+        }
+
+        fn verify_stop(&self) {
+            self.calls.lock().unwrap().push("VerifyStop".into());
+        }
+
+        #[zbus(signal)]
+        async fn verify_status(
+            emitter: &SignalEmitter<'_>,
+            result: &str,
+            done: bool,
+        ) -> zbus::Result<()>;
+    }
+
+    async fn serve(bus: &TestBus, device: FakeDevice) -> Connection {
+        bus.builder()
+            .name(DESTINATION)
+            .unwrap()
+            .serve_at(MANAGER_PATH, FakeManager)
+            .unwrap()
+            .serve_at(DEVICE_PATH, device)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fingerprint_verification() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let calls = Calls::default();
+            let _service = serve(
+                &bus,
+                FakeDevice {
+                    calls: calls.clone(),
+                    statuses: vec![("verify-retry-scan", false), ("verify-match", true)],
+                },
+            )
+            .await;
+            let client = bus.connect().await;
+
+            assert_eq!(
+                biometric_status(&client).await,
+                BiometricStatus::Available(BiometricKind::Fingerprint)
+            );
+            assert!(verify(&client, VERIFY_TIMEOUT).await.unwrap());
+            assert_eq!(
+                *calls.lock().unwrap(),
+                ["Claim \"\"", "VerifyStart any", "VerifyStop", "Release"]
+            );
+        });
+    }
+
+    #[test]
+    fn test_fingerprint_mismatch() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let calls = Calls::default();
+            let _service = serve(
+                &bus,
+                FakeDevice {
+                    calls: calls.clone(),
+                    statuses: vec![("verify-no-match", true)],
+                },
+            )
+            .await;
+            let client = bus.connect().await;
+
+            assert!(!verify(&client, VERIFY_TIMEOUT).await.unwrap());
+            // The reader is released even though the finger didn't match.
+            assert_eq!(calls.lock().unwrap().last().unwrap(), "Release");
+        });
+    }
+
+    #[test]
+    fn test_fingerprint_timeout() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let calls = Calls::default();
+            let _service = serve(
+                &bus,
+                FakeDevice {
+                    calls: calls.clone(),
+                    statuses: vec![("verify-retry-scan", false)],
+                },
+            )
+            .await;
+            let client = bus.connect().await;
+
+            assert!(verify(&client, Duration::from_millis(50)).await.is_err());
+            assert_eq!(
+                *calls.lock().unwrap(),
+                ["Claim \"\"", "VerifyStart any", "VerifyStop", "Release"]
+            );
+        });
+    }
+
+    #[test]
+    fn test_fingerprint_verification_cancelled() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let calls = Calls::default();
+            let _service = serve(
+                &bus,
+                FakeDevice {
+                    calls: calls.clone(),
+                    statuses: Vec::new(),
+                },
+            )
+            .await;
+            let client = bus.connect().await;
+
+            let started = || {
+                calls
+                    .lock()
+                    .unwrap()
+                    .contains(&"VerifyStart any".to_string())
+            };
+            futures::select_biased! {
+                _ = Box::pin(verify(&client, VERIFY_TIMEOUT)).fuse() => {
+                    panic!("verification finished without a finger")
+                }
+                _ = Box::pin(wait_until(started)).fuse() => {}
+            }
+            // Dropping the verification stops it and releases the reader.
+            wait_until(|| calls.lock().unwrap().last().unwrap() == "Release").await;
+            assert_eq!(
+                *calls.lock().unwrap(),
+                ["Claim \"\"", "VerifyStart any", "VerifyStop", "Release"]
+            );
+        });
+    }
+
+    #[test]
+    fn test_no_fingerprint_reader() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let client = bus.connect().await;
+            assert_eq!(
+                biometric_status(&client).await,
+                BiometricStatus::Unavailable
+            );
+            assert!(verify(&client, VERIFY_TIMEOUT).await.is_err());
+        });
+    }
+}
@@ -1,3 +1,9 @@
 mod client;
+mod display;
+mod renderer;
+mod window;
 
 pub(crate) use client::*;
+pub(crate) use display::*;
+pub(crate) use renderer::*;
+pub(crate) use window::*;
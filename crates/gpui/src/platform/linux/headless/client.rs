@@ -1,6 +1,8 @@
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
+use anyhow::Context as _;
 use calloop::{EventLoop, LoopHandle};
 use util::ResultExt;
 
@@ -11,10 +13,76 @@ use crate::{
     PlatformKeyboardLayout, WindowParams,
 };
 
+use super::{HEADLESS_DISPLAYS_ENV, HeadlessDisplay, HeadlessWindow, HeadlessWindowStatePtr};
+
 pub struct HeadlessClientState {
-    pub(crate) _loop_handle: LoopHandle<'static, HeadlessClient>,
+    pub(crate) loop_handle: LoopHandle<'static, HeadlessClient>,
     pub(crate) event_loop: Option<calloop::EventLoop<'static, HeadlessClient>>,
     pub(crate) common: LinuxCommon,
+    pub(crate) displays: Vec<Rc<HeadlessDisplay>>,
+    /// Open windows from back to front. The frontmost one is active.
+    pub(crate) windows: Vec<HeadlessWindowStatePtr>,
+    /// Whether the timer that draws the windows is running. It stops once the last window closes.
+    refresh_timer_armed: bool,
+}
+
+impl HeadlessClientState {
+    pub(crate) fn activate_window(&mut self, handle: AnyWindowHandle) {
+        let Some(index) = self
+            .windows
+            .iter()
+            .position(|window| window.handle == handle)
+        else {
+            return;
+        };
+        let window = self.windows.remove(index);
+        self.windows.push(window);
+        self.update_active_window();
+    }
+
+    pub(crate) fn drop_window(&mut self, handle: AnyWindowHandle) {
+        self.windows.retain(|window| window.handle != handle);
+        self.update_active_window();
+    }
+
+    /// Nothing presents frames, so draw them at a steady 60Hz like a display would ask for, while
+    /// there are windows to draw.
+    fn arm_refresh_timer(&mut self) {
+        if self.refresh_timer_armed {
+            return;
+        }
+        let refresh_rate = Duration::from_micros(1_000_000 / 60);
+        self.loop_handle
+            .insert_source(
+                calloop::timer::Timer::immediate(),
+                move |mut instant, (), client: &mut HeadlessClient| {
+                    let windows = client.0.borrow().windows.clone();
+                    if windows.is_empty() {
+                        client.0.borrow_mut().refresh_timer_armed = false;
+                        return calloop::timer::TimeoutAction::Drop;
+                    }
+                    for window in windows {
+                        window.refresh();
+                    }
+
+                    // Take into account that some frames have been skipped
+                    let now = Instant::now();
+                    while instant < now {
+                        instant += refresh_rate;
+                    }
+                    calloop::timer::TimeoutAction::ToInstant(instant)
+                },
+            )
+            .expect("Failed to initialize window refresh timer");
+        self.refresh_timer_armed = true;
+    }
+
+    fn update_active_window(&self) {
+        let frontmost = self.windows.len().saturating_sub(1);
+        for (index, window) in self.windows.iter().enumerate() {
+            window.set_active(index == frontmost);
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -38,8 +106,14 @@ impl HeadlessClient {
 
         HeadlessClient(Rc::new(RefCell::new(HeadlessClientState {
             event_loop: Some(event_loop),
-            _loop_handle: handle,
+            loop_handle: handle,
             common,
+            displays: HeadlessDisplay::from_env()
+                .into_iter()
+                .map(Rc::new)
+                .collect(),
+            windows: Vec::new(),
+            refresh_timer_armed: false,
         })))
     }
 }
@@ -54,15 +128,23 @@ impl LinuxClient for HeadlessClient {
     }
 
     fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
-        vec![]
+        self.0
+            .borrow()
+            .displays
+            .iter()
+            .map(|display| display.clone() as Rc<dyn PlatformDisplay>)
+            .collect()
     }
 
     fn primary_display(&self) -> Option<Rc<dyn PlatformDisplay>> {
-        None
+        let display = self.0.borrow().displays.first()?.clone();
+        Some(display)
     }
 
-    fn display(&self, _id: DisplayId) -> Option<Rc<dyn PlatformDisplay>> {
-        None
+    fn display(&self, id: DisplayId) -> Option<Rc<dyn PlatformDisplay>> {
+        let state = self.0.borrow();
+        let display = state.displays.iter().find(|display| display.id() == id)?;
+        Some(display.clone())
     }
 
     #[cfg(feature = "screen-capture")]
@@ -84,19 +166,52 @@ impl LinuxClient for HeadlessClient {
     }
 
     fn active_window(&self) -> Option<AnyWindowHandle> {
-        None
+        self.0.borrow().windows.last().map(|window| window.handle)
     }
 
     fn window_stack(&self) -> Option<Vec<AnyWindowHandle>> {
-        None
+        Some(
+            self.0
+                .borrow()
+                .windows
+                .iter()
+                .rev()
+                .map(|window| window.handle)
+                .collect(),
+        )
     }
 
     fn open_window(
         &self,
-        _handle: AnyWindowHandle,
-        _params: WindowParams,
+        handle: AnyWindowHandle,
+        params: WindowParams,
     ) -> anyhow::Result<Box<dyn PlatformWindow>> {
-        anyhow::bail!("neither DISPLAY nor WAYLAND_DISPLAY is set. You can run in headless mode");
+        let mut state = self.0.borrow_mut();
+        let display = params
+            .display_id
+            .and_then(|id| state.displays.iter().find(|display| display.id() == id))
+            .or_else(|| {
+                state
+                    .displays
+                    .iter()
+                    .find(|display| display.bounds().contains(&params.bounds.center()))
+            })
+            .or_else(|| state.displays.first())
+            .with_context(|| {
+                format!("No headless displays are configured in {HEADLESS_DISPLAYS_ENV}")
+            })?
+            .clone();
+        let window = HeadlessWindow::new(
+            handle,
+            params,
+            Rc::downgrade(&self.0),
+            display,
+            state.common.background_executor.clone(),
+        );
+        state.windows.push(window.0.clone());
+        state.update_active_window();
+        state.arm_refresh_timer();
+        Ok(Box::new(window))
     }
 
     fn compositor_name(&self) -> &'static str {
@@ -0,0 +1,112 @@
+use anyhow::{Context as _, anyhow};
+use uuid::Uuid;
+
+use crate::{Bounds, DisplayId, Pixels, PlatformDisplay, Size, point, px, size};
+
+/// Lists the virtual displays of the headless client, as comma separated `WIDTHxHEIGHT[@SCALE]`
+/// entries in device pixels, e.g. `1920x1080@2,1280x720`. The first one is the primary display.
+pub(crate) const HEADLESS_DISPLAYS_ENV: &str = "GPUI_HEADLESS_DISPLAYS";
+
+/// A display that only exists in memory. Displays are laid out left to right, in the order they
+/// are configured.
+#[derive(Debug)]
+pub(crate) struct HeadlessDisplay {
+    id: DisplayId,
+    bounds: Bounds<Pixels>,
+    scale_factor: f32,
+}
+
+impl HeadlessDisplay {
+    /// The displays configured through [`HEADLESS_DISPLAYS_ENV`]. Without it there are none, and
+    /// windows can't be opened.
+    pub(crate) fn from_env() -> Vec<HeadlessDisplay> {
+        let Some(specs) = std::env::var(HEADLESS_DISPLAYS_ENV).ok() else {
+            return Vec::new();
+        };
+        let specs = parse_display_specs(&specs)
+            .with_context(|| format!("Invalid {HEADLESS_DISPLAYS_ENV}"))
+            .map_err(|error| log::error!("{error:#}"))
+            .unwrap_or_default();
+        let mut left = px(0.);
+        specs
+            .into_iter()
+            .enumerate()
+            .map(|(index, (device_size, scale_factor))| {
+                let bounds = Bounds::new(
+                    point(left, px(0.)),
+                    size(
+                        px(device_size.width as f32 / scale_factor),
+                        px(device_size.height as f32 / scale_factor),
+                    ),
+                );
+                left += bounds.size.width;
+                HeadlessDisplay {
+                    id: DisplayId(index as u32),
+                    bounds,
+                    scale_factor,
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+}
+
+impl PlatformDisplay for HeadlessDisplay {
+    fn id(&self) -> DisplayId {
+        self.id
+    }
+
+    fn uuid(&self) -> anyhow::Result<Uuid> {
+        // Stable across runs with the same configuration, so saved window states find it again.
+        Ok(Uuid::from_u128(self.id.0 as u128 + 1))
+    }
+
+    fn bounds(&self) -> Bounds<Pixels> {
+        self.bounds
+    }
+
+    fn refresh_rate(&self) -> Option<f32> {
+        Some(60.)
+    }
+}
+
+fn parse_display_specs(specs: &str) -> anyhow::Result<Vec<(Size<u32>, f32)>> {
+    specs
+        .split(',')
+        .map(|spec| {
+            let spec = spec.trim();
+            let (resolution, scale_factor) = match spec.split_once('@') {
+                Some((resolution, scale_factor)) => (resolution, scale_factor.parse::<f32>()?),
+                None => (spec, 1.),
+            };
+            let (width, height) = resolution
+                .split_once('x')
+                .ok_or_else(|| anyhow!("expected WIDTHxHEIGHT, got {spec:?}"))?;
+            let device_size = size(width.parse::<u32>()?, height.parse::<u32>()?);
+            anyhow::ensure!(
+                device_size.width > 0 && device_size.height > 0 && scale_factor > 0.,
+                "display {spec:?} is empty"
+            );
+            Ok((device_size, scale_factor))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display_specs() {
+        assert_eq!(
+            parse_display_specs("1920x1080@2, 1280x720").unwrap(),
+            vec![(size(1920, 1080), 2.), (size(1280, 720), 1.)]
+        );
+        assert!(parse_display_specs("1920").is_err());
+        assert!(parse_display_specs("0x1080").is_err());
+        assert!(parse_display_specs("1920x1080@").is_err());
+    }
+}
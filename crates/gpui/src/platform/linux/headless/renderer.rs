@@ -0,0 +1,680 @@
+//! A software rasterizer for headless windows. It follows the shaders of the GPU renderers closely
+//! enough for smoke tests and screenshots, but trades their fidelity for simplicity: dashed borders
+//! are drawn solid, quad blend modes are ignored and paths are antialiased with four samples.
+
+use std::{borrow::Cow, sync::Arc};
+
+use collections::HashMap;
+use image::RgbaImage;
+use parking_lot::Mutex;
+
+use crate::{
+    AtlasKey, AtlasTextureId, AtlasTile, Background, BackgroundTag, Bounds, ContentMask, Corners,
+    DevicePixels, Hsla, MonochromeSprite, Path, PlatformAtlas, PolychromeSprite, PrimitiveBatch,
+    Quad, ScaledPixels, Scene, Shadow, Size, TileId, TransformationMatrix, Underline, point,
+};
+
+/// An atlas that keeps each tile in its own texture, in memory.
+pub(crate) struct CpuAtlas(Mutex<CpuAtlasState>);
+
+struct CpuAtlasState {
+    next_id: u32,
+    tiles: HashMap<AtlasKey, AtlasTile>,
+    textures: HashMap<u32, CpuTexture>,
+}
+
+struct CpuTexture {
+    size: Size<DevicePixels>,
+    /// One byte of coverage per pixel for monochrome tiles, BGRA for polychrome ones.
+    bytes: Vec<u8>,
+}
+
+impl CpuAtlas {
+    pub(crate) fn new() -> Self {
+        CpuAtlas(Mutex::new(CpuAtlasState {
+            next_id: 0,
+            tiles: HashMap::default(),
+            textures: HashMap::default(),
+        }))
+    }
+}
+
+impl PlatformAtlas for CpuAtlas {
+    fn get_or_insert_with<'a>(
+        &self,
+        key: &AtlasKey,
+        build: &mut dyn FnMut() -> anyhow::Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> anyhow::Result<Option<AtlasTile>> {
+        if let Some(tile) = self.0.lock().tiles.get(key) {
+            return Ok(Some(tile.clone()));
+        }
+
+        let Some((size, bytes)) = build()? else {
+            return Ok(None);
+        };
+
+        let mut state = self.0.lock();
+        state.next_id += 1;
+        let id = state.next_id;
+        let tile = AtlasTile {
+            texture_id: AtlasTextureId {
+                index: id,
+                kind: key.texture_kind(),
+            },
+            tile_id: TileId(id),
+            padding: 0,
+            bounds: Bounds {
+                origin: Default::default(),
+                size,
+            },
+        };
+        state.textures.insert(
+            id,
+            CpuTexture {
+                size,
+                bytes: bytes.into_owned(),
+            },
+        );
+        state.tiles.insert(key.clone(), tile.clone());
+        Ok(Some(tile))
+    }
+
+    fn remove(&self, key: &AtlasKey) {
+        let mut state = self.0.lock();
+        if let Some(tile) = state.tiles.remove(key) {
+            state.textures.remove(&tile.texture_id.index);
+        }
+    }
+}
+
+/// Rasterizes scenes into an RGBA image with straight alpha.
+pub(crate) struct CpuRenderer {
+    atlas: Arc<CpuAtlas>,
+    frame: RgbaImage,
+}
+
+impl CpuRenderer {
+    pub(crate) fn new(atlas: Arc<CpuAtlas>, size: Size<DevicePixels>) -> Self {
+        Self {
+            atlas,
+            frame: RgbaImage::new(size.width.0.max(1) as u32, size.height.0.max(1) as u32),
+        }
+    }
+
+    pub(crate) fn sprite_atlas(&self) -> Arc<CpuAtlas> {
+        self.atlas.clone()
+    }
+
+    pub(crate) fn update_drawable_size(&mut self, size: Size<DevicePixels>) {
+        let (width, height) = (size.width.0.max(1) as u32, size.height.0.max(1) as u32);
+        if self.frame.dimensions() != (width, height) {
+            self.frame = RgbaImage::new(width, height);
+        }
+    }
+
+    /// The last frame drawn.
+    pub(crate) fn frame(&self) -> &RgbaImage {
+        &self.frame
+    }
+
+    pub(crate) fn draw(&mut self, scene: &Scene) {
+        self.frame.fill(0);
+        let atlas = self.atlas.clone();
+        for batch in scene.batches() {
+            match batch {
+                PrimitiveBatch::Shadows(shadows) => {
+                    for shadow in shadows {
+                        self.draw_shadow(shadow);
+                    }
+                }
+                PrimitiveBatch::Quads(quads) => {
+                    for quad in quads {
+                        self.draw_quad(quad);
+                    }
+                }
+                PrimitiveBatch::Paths(paths) => {
+                    for path in paths {
+                        self.draw_path(path);
+                    }
+                }
+                PrimitiveBatch::Underlines(underlines) => {
+                    for underline in underlines {
+                        self.draw_underline(underline);
+                    }
+                }
+                PrimitiveBatch::MonochromeSprites {
+                    texture_id,
+                    sprites,
+                } => {
+                    let atlas = atlas.0.lock();
+                    if let Some(texture) = atlas.textures.get(&texture_id.index) {
+                        for sprite in sprites {
+                            self.draw_monochrome_sprite(sprite, texture);
+                        }
+                    }
+                }
+                PrimitiveBatch::PolychromeSprites {
+                    texture_id,
+                    sprites,
+                } => {
+                    let atlas = atlas.0.lock();
+                    if let Some(texture) = atlas.textures.get(&texture_id.index) {
+                        for sprite in sprites {
+                            self.draw_polychrome_sprite(sprite, texture);
+                        }
+                    }
+                }
+                // Surfaces only exist on macOS.
+                PrimitiveBatch::Surfaces(_) => {}
+            }
+        }
+    }
+
+    fn draw_shadow(&mut self, shadow: &Shadow) {
+        let bounds = rect(&shadow.bounds);
+        let blur = shadow.blur_radius.0;
+        let radii = corners(&shadow.corner_radii);
+        let color = rgba(shadow.color);
+        let inset = shadow.inset != 0;
+        self.fill(dilate(bounds, blur * 3.), &shadow.content_mask, |x, y| {
+            let distance = quad_sdf(x, y, &bounds, &radii);
+            let mut alpha = if blur > 0. {
+                1. - smoothstep(-blur * 1.5, blur * 1.5, distance)
+            } else {
+                (0.5 - distance).clamp(0., 1.)
+            };
+            if inset {
+                alpha = 1. - alpha;
+            }
+            with_alpha(color, alpha)
+        });
+    }
+
+    fn draw_quad(&mut self, quad: &Quad) {
+        let bounds = rect(&quad.bounds);
+        let radii = corners(&quad.corner_radii);
+        let widths = [
+            quad.border_widths.top.0,
+            quad.border_widths.right.0,
+            quad.border_widths.bottom.0,
+            quad.border_widths.left.0,
+        ];
+        let [top, right, bottom, left] = widths;
+        let has_border = widths.iter().any(|width| *width > 0.);
+        let inner = Bounds {
+            origin: point(bounds.origin.x + left, bounds.origin.y + top),
+            size: Size {
+                width: (bounds.size.width - left - right).max(0.),
+                height: (bounds.size.height - top - bottom).max(0.),
+            },
+        };
+        let inner_radii = [
+            (radii[0] - top.max(left)).max(0.),
+            (radii[1] - top.max(right)).max(0.),
+            (radii[2] - bottom.max(right)).max(0.),
+            (radii[3] - bottom.max(left)).max(0.),
+        ];
+        let border_color = rgba(quad.border_color);
+        let transform = Transform::new(&quad.transform);
+        let area = transform.bounding_box(bounds);
+        self.fill(area, &quad.content_mask, |x, y| {
+            let (x, y) = transform.to_local(x, y);
+            let coverage = (0.5 - quad_sdf(x, y, &bounds, &radii)).clamp(0., 1.);
+            if coverage == 0. {
+                return None;
+            }
+            let fill = background_color(&quad.background, x, y, &bounds);
+            if !has_border {
+                return with_alpha(fill, coverage);
+            }
+            let inside = (0.5 - quad_sdf(x, y, &inner, &inner_radii)).clamp(0., 1.);
+            // The background shows under the border, like on the GPU.
+            let color = over(
+                with_alpha(fill, coverage),
+                border_color,
+                coverage * (1. - inside),
+            );
+            Some(color)
+        });
+    }
+
+    fn draw_underline(&mut self, underline: &Underline) {
+        const WAVE_FREQUENCY: f32 = 2.0;
+        const WAVE_HEIGHT_RATIO: f32 = 0.8;
+
+        let bounds = rect(&underline.bounds);
+        let color = rgba(underline.color);
+        let wavy = underline.wavy & 0xFF != 0;
+        let thickness = underline.thickness.0;
+        self.fill(bounds, &underline.content_mask, |x, y| {
+            if !wavy {
+                return with_alpha(color, 1.);
+            }
+            let height = bounds.size.height;
+            let s = (x - bounds.origin.x) / height;
+            let t = (y - bounds.origin.y) / height - 0.5;
+            let frequency = std::f32::consts::PI * WAVE_FREQUENCY * thickness / height;
+            let amplitude = thickness * WAVE_HEIGHT_RATIO / height;
+            let sine = (s * frequency).sin() * amplitude;
+            let slope = (s * frequency).cos() * amplitude * frequency;
+            let distance = (t - sine) / (1. + slope * slope).sqrt() * height;
+            let alpha = (0.5 - (distance.abs() - thickness * 0.5)).clamp(0., 1.);
+            with_alpha(color, alpha)
+        });
+    }
+
+    fn draw_path(&mut self, path: &Path<ScaledPixels>) {
+        let bounds = rect(&path.bounds);
+        let area = clip(bounds, &path.content_mask, &self.frame);
+        let Some((x0, y0, x1, y1)) = area else {
+            return;
+        };
+        let width = (x1 - x0) as usize;
+        // A bit per sample, four samples per pixel.
+        let mut samples = vec![0u8; width * (y1 - y0) as usize];
+        const OFFSETS: [(f32, f32); 4] = [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)];
+        for triangle in path.vertices.chunks_exact(3) {
+            let [a, b, c] = [&triangle[0], &triangle[1], &triangle[2]].map(|vertex| {
+                (
+                    vertex.xy_position.x.0,
+                    vertex.xy_position.y.0,
+                    vertex.st_position,
+                )
+            });
+            let area2 = (b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1);
+            if area2 == 0. {
+                continue;
+            }
+            let min_x = (a.0.min(b.0).min(c.0).floor() as i32).max(x0);
+            let max_x = (a.0.max(b.0).max(c.0).ceil() as i32).min(x1);
+            let min_y = (a.1.min(b.1).min(c.1).floor() as i32).max(y0);
+            let max_y = (a.1.max(b.1).max(c.1).ceil() as i32).min(y1);
+            for py in min_y..max_y {
+                for px in min_x..max_x {
+                    for (bit, (dx, dy)) in OFFSETS.iter().enumerate() {
+                        let (x, y) = (px as f32 + dx, py as f32 + dy);
+                        let wa = ((b.0 - x) * (c.1 - y) - (c.0 - x) * (b.1 - y)) / area2;
+                        let wb = ((c.0 - x) * (a.1 - y) - (a.0 - x) * (c.1 - y)) / area2;
+                        let wc = 1. - wa - wb;
+                        if wa < 0. || wb < 0. || wc < 0. {
+                            continue;
+                        }
+                        let s = wa * a.2.x + wb * b.2.x + wc * c.2.x;
+                        let t = wa * a.2.y + wb * b.2.y + wc * c.2.y;
+                        if s * s - t <= 0. {
+                            samples[(py - y0) as usize * width + (px - x0) as usize] |= 1 << bit;
+                        }
+                    }
+                }
+            }
+        }
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let covered = samples[(py - y0) as usize * width + (px - x0) as usize];
+                if covered == 0 {
+                    continue;
+                }
+                let (x, y) = (px as f32 + 0.5, py as f32 + 0.5);
+                let color = background_color(&path.color, x, y, &bounds);
+                if let Some(color) = with_alpha(color, covered.count_ones() as f32 / 4.) {
+                    blend(self.frame.get_pixel_mut(px as u32, py as u32), color);
+                }
+            }
+        }
+    }
+
+    fn draw_monochrome_sprite(&mut self, sprite: &MonochromeSprite, texture: &CpuTexture) {
+        let bounds = rect(&sprite.bounds);
+        let color = rgba(sprite.color);
+        let transform = Transform::new(&sprite.transformation);
+        let area = transform.bounding_box(bounds);
+        self.fill(area, &sprite.content_mask, |x, y| {
+            let (x, y) = transform.to_local(x, y);
+            let (column, row) = texel(x, y, &bounds, texture)?;
+            let coverage = texture.bytes[row * texture.size.width.0 as usize + column];
+            with_alpha(color, coverage as f32 / 255.)
+        });
+    }
+
+    fn draw_polychrome_sprite(&mut self, sprite: &PolychromeSprite, texture: &CpuTexture) {
+        let bounds = rect(&sprite.bounds);
+        let radii = corners(&sprite.corner_radii);
+        self.fill(bounds, &sprite.content_mask, |x, y| {
+            let (column, row) = texel(x, y, &bounds, texture)?;
+            let offset = (row * texture.size.width.0 as usize + column) * 4;
+            let [b, g, r, a] = texture.bytes.get(offset..offset + 4)?.try_into().ok()?;
+            let mut color = [r, g, b, a].map(|channel| channel as f32 / 255.);
+            if sprite.grayscale {
+                let gray = 0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2];
+                color = [gray, gray, gray, color[3]];
+            }
+            let coverage = (0.5 - quad_sdf(x, y, &bounds, &radii)).clamp(0., 1.);
+            with_alpha(color, sprite.opacity * coverage)
+        });
+    }
+
+    /// Blend the color `shade` gives each pixel center in `area` and the content mask.
+    fn fill(
+        &mut self,
+        area: Bounds<f32>,
+        content_mask: &ContentMask<ScaledPixels>,
+        mut shade: impl FnMut(f32, f32) -> Option<[f32; 4]>,
+    ) {
+        let Some((x0, y0, x1, y1)) = clip(area, content_mask, &self.frame) else {
+            return;
+        };
+        for py in y0..y1 {
+            for px in x0..x1 {
+                if let Some(color) = shade(px as f32 + 0.5, py as f32 + 0.5) {
+                    blend(self.frame.get_pixel_mut(px as u32, py as u32), color);
+                }
+            }
+        }
+    }
+}
+
+/// The pixels whose centers are in `area`, the content mask and the frame.
+fn clip(
+    area: Bounds<f32>,
+    content_mask: &ContentMask<ScaledPixels>,
+    frame: &RgbaImage,
+) -> Option<(i32, i32, i32, i32)> {
+    let mask = rect(&content_mask.bounds);
+    let left = area.origin.x.max(mask.origin.x).max(0.);
+    let top = area.origin.y.max(mask.origin.y).max(0.);
+    let right = (area.origin.x + area.size.width)
+        .min(mask.origin.x + mask.size.width)
+        .min(frame.width() as f32);
+    let bottom = (area.origin.y + area.size.height)
+        .min(mask.origin.y + mask.size.height)
+        .min(frame.height() as f32);
+    let (x0, y0) = ((left - 0.5).ceil() as i32, (top - 0.5).ceil() as i32);
+    let (x1, y1) = ((right - 0.5).ceil() as i32, (bottom - 0.5).ceil() as i32);
+    (x0 < x1 && y0 < y1).then_some((x0, y0, x1, y1))
+}
+
+/// The texel of `texture` under a point of a sprite drawn at `bounds`.
+fn texel(x: f32, y: f32, bounds: &Bounds<f32>, texture: &CpuTexture) -> Option<(usize, usize)> {
+    let u = (x - bounds.origin.x) / bounds.size.width;
+    let v = (y - bounds.origin.y) / bounds.size.height;
+    if !(0. ..1.).contains(&u) || !(0. ..1.).contains(&v) {
+        return None;
+    }
+    Some((
+        (u * texture.size.width.0 as f32) as usize,
+        (v * texture.size.height.0 as f32) as usize,
+    ))
+}
+
+/// Maps device pixels back into the space a primitive was laid out in.
+struct Transform {
+    matrix: TransformationMatrix,
+    inverse: Option<[[f32; 2]; 2]>,
+}
+
+impl Transform {
+    fn new(matrix: &TransformationMatrix) -> Self {
+        let [[a, b], [c, d]] = matrix.rotation_scale;
+        let determinant = a * d - b * c;
+        let inverse = (*matrix != TransformationMatrix::unit() && determinant != 0.).then(|| {
+            [
+                [d / determinant, -b / determinant],
+                [-c / determinant, a / determinant],
+            ]
+        });
+        Self {
+            matrix: *matrix,
+            inverse,
+        }
+    }
+
+    fn to_local(&self, x: f32, y: f32) -> (f32, f32) {
+        let Some([[a, b], [c, d]]) = self.inverse else {
+            return (x, y);
+        };
+        let (x, y) = (
+            x - self.matrix.translation[0],
+            y - self.matrix.translation[1],
+        );
+        (a * x + b * y, c * x + d * y)
+    }
+
+    fn bounding_box(&self, bounds: Bounds<f32>) -> Bounds<f32> {
+        if self.inverse.is_none() {
+            return bounds;
+        }
+        let [[a, b], [c, d]] = self.matrix.rotation_scale;
+        let [tx, ty] = self.matrix.translation;
+        let corners = [
+            bounds.origin,
+            point(bounds.origin.x + bounds.size.width, bounds.origin.y),
+            point(bounds.origin.x, bounds.origin.y + bounds.size.height),
+            point(
+                bounds.origin.x + bounds.size.width,
+                bounds.origin.y + bounds.size.height,
+            ),
+        ]
+        .map(|corner| {
+            (
+                a * corner.x + b * corner.y + tx,
+                c * corner.x + d * corner.y + ty,
+            )
+        });
+        let (mut left, mut top) = (f32::MAX, f32::MAX);
+        let (mut right, mut bottom) = (f32::MIN, f32::MIN);
+        for (x, y) in corners {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+        }
+        Bounds {
+            origin: point(left, top),
+            size: Size {
+                width: right - left,
+                height: bottom - top,
+            },
+        }
+    }
+}
+
+fn rect(bounds: &Bounds<ScaledPixels>) -> Bounds<f32> {
+    Bounds {
+        origin: point(bounds.origin.x.0, bounds.origin.y.0),
+        size: Size {
+            width: bounds.size.width.0,
+            height: bounds.size.height.0,
+        },
+    }
+}
+
+fn dilate(bounds: Bounds<f32>, amount: f32) -> Bounds<f32> {
+    Bounds {
+        origin: point(bounds.origin.x - amount, bounds.origin.y - amount),
+        size: Size {
+            width: bounds.size.width + amount * 2.,
+            height: bounds.size.height + amount * 2.,
+        },
+    }
+}
+
+/// Corner radii in clockwise order from the top left.
+fn corners(radii: &Corners<ScaledPixels>) -> [f32; 4] {
+    [
+        radii.top_left.0,
+        radii.top_right.0,
+        radii.bottom_right.0,
+        radii.bottom_left.0,
+    ]
+}
+
+/// The signed distance from a point to the edge of a rounded rectangle, negative inside.
+fn quad_sdf(x: f32, y: f32, bounds: &Bounds<f32>, radii: &[f32; 4]) -> f32 {
+    let half_width = bounds.size.width / 2.;
+    let half_height = bounds.size.height / 2.;
+    let dx = x - (bounds.origin.x + half_width);
+    let dy = y - (bounds.origin.y + half_height);
+    let radius = match (dx < 0., dy < 0.) {
+        (true, true) => radii[0],
+        (false, true) => radii[1],
+        (false, false) => radii[2],
+        (true, false) => radii[3],
+    };
+    let cx = dx.abs() - half_width + radius;
+    let cy = dy.abs() - half_height + radius;
+    if radius == 0. {
+        cx.max(cy)
+    } else {
+        cx.max(0.).hypot(cy.max(0.)) + cx.max(cy).min(0.) - radius
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
+fn rgba(color: Hsla) -> [f32; 4] {
+    let color = color.to_rgb();
+    [color.r, color.g, color.b, color.a]
+}
+
+fn with_alpha([r, g, b, a]: [f32; 4], coverage: f32) -> Option<[f32; 4]> {
+    let alpha = a * coverage;
+    (alpha > 0.).then_some([r, g, b, alpha])
+}
+
+/// Composite `color` with `coverage` over `base`, both with straight alpha.
+fn over(base: Option<[f32; 4]>, color: [f32; 4], coverage: f32) -> [f32; 4] {
+    let base = base.unwrap_or_default();
+    let alpha = color[3] * coverage;
+    let out_alpha = alpha + base[3] * (1. - alpha);
+    if out_alpha <= 0. {
+        return [0.; 4];
+    }
+    let channel =
+        |index: usize| (color[index] * alpha + base[index] * base[3] * (1. - alpha)) / out_alpha;
+    [channel(0), channel(1), channel(2), out_alpha]
+}
+
+fn blend(pixel: &mut image::Rgba<u8>, color: [f32; 4]) {
+    let base = pixel.0.map(|channel| channel as f32 / 255.);
+    let result = over(Some(base), color, 1.);
+    pixel.0 = result.map(|channel| (channel.clamp(0., 1.) * 255.).round() as u8);
+}
+
+/// The color of a background at a point, following the gradient math of the shaders.
+fn background_color(background: &Background, x: f32, y: f32, bounds: &Bounds<f32>) -> [f32; 4] {
+    let t = match background.tag {
+        BackgroundTag::Solid | BackgroundTag::PatternSlash => return rgba(background.solid),
+        BackgroundTag::LinearGradient => {
+            let angle = background.gradient_angle_or_pattern_height;
+            let radians = ((angle % 360.) - 90.).to_radians();
+            let (mut dx, mut dy) = (radians.cos(), radians.sin());
+            if bounds.size.width > bounds.size.height {
+                dy *= bounds.size.height / bounds.size.width;
+            } else {
+                dx *= bounds.size.width / bounds.size.height;
+            }
+            let half_width = bounds.size.width / 2.;
+            let half_height = bounds.size.height / 2.;
+            let px = x - (bounds.origin.x + half_width);
+            let py = y - (bounds.origin.y + half_height);
+            let t = (px * dx + py * dy) / dx.hypot(dy);
+            if dx.abs() > dy.abs() {
+                (t + half_width) / bounds.size.width
+            } else {
+                (t + half_height) / bounds.size.height
+            }
+        }
+        BackgroundTag::RadialGradient => {
+            let cx = bounds.origin.x + background.center[0] * bounds.size.width;
+            let cy = bounds.origin.y + background.center[1] * bounds.size.height;
+            ((x - cx) / (background.radius[0] * bounds.size.width))
+                .hypot((y - cy) / (background.radius[1] * bounds.size.height))
+        }
+        BackgroundTag::ConicGradient => {
+            let cx = bounds.origin.x + background.center[0] * bounds.size.width;
+            let cy = bounds.origin.y + background.center[1] * bounds.size.height;
+            let offset = background.gradient_angle_or_pattern_height.to_radians();
+            let angle = (y - cy).atan2(x - cx);
+            ((angle + std::f32::consts::PI + offset) / std::f32::consts::TAU).rem_euclid(1.)
+        }
+    };
+    let count = match background.stop_count {
+        0 => 2,
+        count => (count as usize).min(background.colors.len()),
+    };
+    let stops = &background.colors[..count];
+    let t = t.clamp(0., 1.);
+    let first = &stops[0];
+    if t <= first.percentage {
+        return rgba(first.color);
+    }
+    for pair in stops.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        if t <= to.percentage {
+            let span = to.percentage - from.percentage;
+            let amount = if span > 0. {
+                (t - from.percentage) / span
+            } else {
+                1.
+            };
+            let (from, to) = (rgba(from.color), rgba(to.color));
+            return std::array::from_fn(|index| from[index] + (to[index] - from[index]) * amount);
+        }
+    }
+    rgba(stops[count - 1].color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Edges, Pixels, hsla, px, size};
+
+    fn quad(bounds: Bounds<Pixels>, background: Hsla) -> Quad {
+        let bounds = bounds.scale(1.);
+        Quad {
+            bounds,
+            content_mask: ContentMask { bounds },
+            background: background.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_draw_quads() {
+        let red = hsla(0., 1., 0.5, 1.);
+        let mut outlined = quad(
+            Bounds::new(point(px(10.), px(10.)), size(px(20.), px(20.))),
+            hsla(0., 0., 1., 1.),
+        );
+        outlined.border_widths = Edges::all(px(2.)).scale(1.);
+        outlined.border_color = hsla(0., 0., 0., 1.);
+        let mut scene = Scene::default();
+        scene.insert_primitive(quad(
+            Bounds::new(point(px(0.), px(0.)), size(px(40.), px(40.))),
+            red,
+        ));
+        scene.insert_primitive(outlined);
+        scene.finish();
+
+        let mut renderer =
+            CpuRenderer::new(Arc::new(CpuAtlas::new()), size(40, 40).map(DevicePixels));
+        renderer.draw(&scene);
+        let frame = renderer.frame();
+        assert_eq!(frame.get_pixel(2, 2).0, [255, 0, 0, 255]);
+        assert_eq!(frame.get_pixel(11, 20).0, [0, 0, 0, 255]);
+        assert_eq!(frame.get_pixel(20, 20).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_quad_sdf() {
+        let bounds = Bounds::new(point(0., 0.), size(10., 10.));
+        assert_eq!(quad_sdf(5., 5., &bounds, &[0.; 4]), -5.);
+        assert_eq!(quad_sdf(12., 5., &bounds, &[0.; 4]), 2.);
+        // The corner of a rounded quad is outside it.
+        assert!(quad_sdf(0.5, 0.5, &bounds, &[4.; 4]) > 0.);
+        assert!(quad_sdf(9.5, 5., &bounds, &[4.; 4]) < 0.);
+    }
+}
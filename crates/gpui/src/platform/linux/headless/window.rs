@@ -0,0 +1,442 @@
+use std::{
+    cell::RefCell,
+    path::PathBuf,
+    rc::{Rc, Weak},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use futures::channel::oneshot::Receiver;
+use image::RgbaImage;
+use raw_window_handle as rwh;
+use util::ResultExt;
+
+use crate::{
+    AnyWindowHandle, BackgroundExecutor, Bounds, Capslock, DispatchEventResult, GpuSpecs,
+    Modifiers, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput, PlatformInputHandler,
+    PlatformWindow, Point, PromptButton, PromptLevel, RequestFrameOptions, Scene, Size,
+    WindowAppearance, WindowBackgroundAppearance, WindowBounds, WindowControlArea, WindowParams,
+};
+
+use super::{CpuAtlas, CpuRenderer, HeadlessClientState, HeadlessDisplay};
+
+/// Writes the frames headless windows draw into this directory, as
+/// `window-<window id>-<frame number>.png`.
+pub(crate) const HEADLESS_FRAMES_ENV: &str = "GPUI_HEADLESS_FRAMES";
+
+/// How many frames of a window can be waiting to be written. Frames drawn while the writer is
+/// this far behind are skipped, leaving a gap in the frame numbers.
+const MAX_PENDING_FRAMES: usize = 4;
+
+/// Encodes and writes the frames of a window into [`HEADLESS_FRAMES_ENV`] on the background
+/// executor.
+struct FrameWriter {
+    dir: PathBuf,
+    executor: BackgroundExecutor,
+    frame_count: u32,
+    pending: Arc<AtomicUsize>,
+}
+
+impl FrameWriter {
+    fn write(&mut self, window_id: u64, frame: &RgbaImage) {
+        self.frame_count += 1;
+        if self.pending.load(Ordering::Acquire) >= MAX_PENDING_FRAMES {
+            return;
+        }
+        self.pending.fetch_add(1, Ordering::AcqRel);
+        let path = self
+            .dir
+            .join(format!("window-{window_id}-{:05}.png", self.frame_count));
+        let frame = frame.clone();
+        let pending = self.pending.clone();
+        self.executor
+            .spawn(async move {
+                frame.save(path).log_err();
+                pending.fetch_sub(1, Ordering::AcqRel);
+            })
+            .detach();
+    }
+}
+
+/// Nothing outside the app moves, hovers or closes a headless window, so only the callbacks for
+/// changes the app makes itself are kept.
+#[derive(Default)]
+pub(crate) struct Callbacks {
+    request_frame: Option<Box<dyn FnMut(RequestFrameOptions)>>,
+    active_status_change: Option<Box<dyn FnMut(bool)>>,
+    resize: Option<Box<dyn FnMut(Size<Pixels>, f32)>>,
+    moved: Option<Box<dyn FnMut()>>,
+    close: Option<Box<dyn FnOnce()>>,
+}
+
+pub(crate) struct HeadlessWindowState {
+    client: Weak<RefCell<HeadlessClientState>>,
+    display: Rc<HeadlessDisplay>,
+    bounds: Bounds<Pixels>,
+    restore_bounds: Bounds<Pixels>,
+    maximized: bool,
+    fullscreen: bool,
+    hidden: bool,
+    active: bool,
+    title: String,
+    input_handler: Option<PlatformInputHandler>,
+    renderer: CpuRenderer,
+    frame_writer: Option<FrameWriter>,
+    /// Changes made while the app was busy, reported on the next frame.
+    resized: bool,
+    activation_changed: bool,
+}
+
+#[derive(Clone)]
+pub(crate) struct HeadlessWindowStatePtr {
+    pub(crate) handle: AnyWindowHandle,
+    state: Rc<RefCell<HeadlessWindowState>>,
+    callbacks: Rc<RefCell<Callbacks>>,
+}
+
+/// A window that is only ever drawn into memory.
+pub(crate) struct HeadlessWindow(pub(crate) HeadlessWindowStatePtr);
+
+impl HeadlessWindow {
+    pub(crate) fn new(
+        handle: AnyWindowHandle,
+        params: WindowParams,
+        client: Weak<RefCell<HeadlessClientState>>,
+        display: Rc<HeadlessDisplay>,
+        executor: BackgroundExecutor,
+    ) -> Self {
+        let scale_factor = display.scale_factor();
+        let renderer = CpuRenderer::new(
+            Arc::new(CpuAtlas::new()),
+            params.bounds.size.to_device_pixels(scale_factor),
+        );
+        let frame_writer = std::env::var_os(HEADLESS_FRAMES_ENV).map(|dir| {
+            let dir = PathBuf::from(dir);
+            std::fs::create_dir_all(&dir).log_err();
+            FrameWriter {
+                dir,
+                executor,
+                frame_count: 0,
+                pending: Arc::default(),
+            }
+        });
+        Self(HeadlessWindowStatePtr {
+            handle,
+            state: Rc::new(RefCell::new(HeadlessWindowState {
+                client,
+                display,
+                bounds: params.bounds,
+                restore_bounds: params.bounds,
+                maximized: false,
+                fullscreen: false,
+                hidden: !params.show,
+                active: false,
+                title: String::new(),
+                input_handler: None,
+                renderer,
+                frame_writer,
+                resized: false,
+                activation_changed: false,
+            })),
+            callbacks: Rc::new(RefCell::new(Callbacks::default())),
+        })
+    }
+
+    /// Move or resize the window. The app hears about it on the next frame.
+    fn set_bounds(&self, bounds: Bounds<Pixels>) {
+        let mut state = self.0.state.borrow_mut();
+        let scale_factor = state.display.scale_factor();
+        state.bounds = bounds;
+        state
+            .renderer
+            .update_drawable_size(bounds.size.to_device_pixels(scale_factor));
+        state.resized = true;
+    }
+}
+
+impl HeadlessWindowStatePtr {
+    pub(crate) fn set_active(&self, active: bool) {
+        let mut state = self.state.borrow_mut();
+        if state.active != active {
+            state.active = active;
+            state.activation_changed = !state.activation_changed;
+        }
+    }
+
+    /// Report pending changes and give the app a chance to draw a frame.
+    pub(crate) fn refresh(&self) {
+        let mut state = self.state.borrow_mut();
+        let resized = std::mem::take(&mut state.resized)
+            .then(|| (state.bounds.size, state.display.scale_factor()));
+        let activated = std::mem::take(&mut state.activation_changed).then_some(state.active);
+        let display_id = state.display.id();
+        drop(state);
+
+        let mut callbacks = self.callbacks.borrow_mut();
+        if let Some((size, scale_factor)) = resized {
+            if let Some(fun) = callbacks.resize.as_mut() {
+                fun(size, scale_factor);
+            }
+            if let Some(fun) = callbacks.moved.as_mut() {
+                fun();
+            }
+        }
+        if let Some(active) = activated
+            && let Some(fun) = callbacks.active_status_change.as_mut()
+        {
+            fun(active);
+        }
+        if let Some(fun) = callbacks.request_frame.as_mut() {
+            fun(RequestFrameOptions {
+                require_presentation: false,
+                force_render: false,
+                display_id: Some(display_id),
+            });
+        }
+    }
+}
+
+impl Drop for HeadlessWindow {
+    fn drop(&mut self) {
+        let client = self.0.state.borrow().client.upgrade();
+        if let Some(client) = client {
+            let mut client = client.borrow_mut();
+            client.drop_window(self.0.handle);
+            // The app is still removing the window, so tell it about the close afterwards.
+            let callbacks = self.0.callbacks.clone();
+            client
+                .common
+                .foreground_executor
+                .spawn(async move {
+                    let close = callbacks.borrow_mut().close.take();
+                    if let Some(fun) = close {
+                        fun();
+                    }
+                })
+                .detach();
+        }
+    }
+}
+
+impl rwh::HasWindowHandle for HeadlessWindow {
+    fn window_handle(&self) -> Result<rwh::WindowHandle<'_>, rwh::HandleError> {
+        Err(rwh::HandleError::NotSupported)
+    }
+}
+
+impl rwh::HasDisplayHandle for HeadlessWindow {
+    fn display_handle(&self) -> Result<rwh::DisplayHandle<'_>, rwh::HandleError> {
+        Err(rwh::HandleError::NotSupported)
+    }
+}
+
+impl PlatformWindow for HeadlessWindow {
+    fn bounds(&self) -> Bounds<Pixels> {
+        self.0.state.borrow().bounds
+    }
+
+    fn is_maximized(&self) -> bool {
+        self.0.state.borrow().maximized
+    }
+
+    fn window_bounds(&self) -> WindowBounds {
+        let state = self.0.state.borrow();
+        if state.fullscreen {
+            WindowBounds::Fullscreen(state.restore_bounds)
+        } else if state.maximized {
+            WindowBounds::Maximized(state.restore_bounds)
+        } else {
+            WindowBounds::Windowed(state.bounds)
+        }
+    }
+
+    fn content_size(&self) -> Size<Pixels> {
+        self.0.state.borrow().bounds.size
+    }
+
+    fn resize(&mut self, size: Size<Pixels>) {
+        let bounds = Bounds {
+            origin: self.bounds().origin,
+            size,
+        };
+        self.set_bounds(bounds);
+    }
+
+    fn scale_factor(&self) -> f32 {
+        self.0.state.borrow().display.scale_factor()
+    }
+
+    fn appearance(&self) -> WindowAppearance {
+        WindowAppearance::Light
+    }
+
+    fn display(&self) -> Option<Rc<dyn PlatformDisplay>> {
+        Some(self.0.state.borrow().display.clone())
+    }
+
+    fn mouse_position(&self) -> Point<Pixels> {
+        Point::default()
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        Modifiers::default()
+    }
+
+    fn capslock(&self) -> Capslock {
+        Capslock::default()
+    }
+
+    fn set_input_handler(&mut self, input_handler: PlatformInputHandler) {
+        self.0.state.borrow_mut().input_handler = Some(input_handler);
+    }
+
+    fn take_input_handler(&mut self) -> Option<PlatformInputHandler> {
+        self.0.state.borrow_mut().input_handler.take()
+    }
+
+    fn prompt(
+        &self,
+        _level: PromptLevel,
+        _msg: &str,
+        _detail: Option<&str>,
+        _answers: &[PromptButton],
+    ) -> Option<Receiver<usize>> {
+        None
+    }
+
+    fn activate(&self) {
+        let client = self.0.state.borrow().client.upgrade();
+        if let Some(client) = client {
+            client.borrow_mut().activate_window(self.0.handle);
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.0.state.borrow().active
+    }
+
+    fn is_hovered(&self) -> bool {
+        false
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.0.state.borrow_mut().title = title.to_owned();
+    }
+
+    fn get_title(&self) -> String {
+        self.0.state.borrow().title.clone()
+    }
+
+    fn set_background_appearance(&self, _background_appearance: WindowBackgroundAppearance) {}
+
+    fn minimize(&self) {}
+
+    fn zoom(&self) {
+        let mut state = self.0.state.borrow_mut();
+        state.maximized = !state.maximized;
+        let bounds = if state.maximized {
+            state.restore_bounds = state.bounds;
+            state.display.visible_bounds()
+        } else {
+            state.restore_bounds
+        };
+        drop(state);
+        self.set_bounds(bounds);
+    }
+
+    fn toggle_fullscreen(&self) {
+        let mut state = self.0.state.borrow_mut();
+        state.fullscreen = !state.fullscreen;
+        let bounds = if state.fullscreen {
+            if !state.maximized {
+                state.restore_bounds = state.bounds;
+            }
+            state.display.bounds()
+        } else if state.maximized {
+            state.display.visible_bounds()
+        } else {
+            state.restore_bounds
+        };
+        drop(state);
+        self.set_bounds(bounds);
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.0.state.borrow().fullscreen
+    }
+
+    fn on_request_frame(&self, callback: Box<dyn FnMut(RequestFrameOptions)>) {
+        self.0.callbacks.borrow_mut().request_frame = Some(callback);
+    }
+
+    /// Headless windows get no input from outside the app, so this is never called.
+    fn on_input(&self, _callback: Box<dyn FnMut(PlatformInput) -> DispatchEventResult>) {}
+
+    fn on_active_status_change(&self, callback: Box<dyn FnMut(bool)>) {
+        self.0.callbacks.borrow_mut().active_status_change = Some(callback);
+    }
+
+    fn on_hover_status_change(&self, _callback: Box<dyn FnMut(bool)>) {}
+
+    fn on_resize(&self, callback: Box<dyn FnMut(Size<Pixels>, f32)>) {
+        self.0.callbacks.borrow_mut().resize = Some(callback);
+    }
+
+    fn on_moved(&self, callback: Box<dyn FnMut()>) {
+        self.0.callbacks.borrow_mut().moved = Some(callback);
+    }
+
+    /// Nothing asks to close a headless window, so this is never called. Windows the app closes
+    /// itself still report it to the close callback.
+    fn on_should_close(&self, _callback: Box<dyn FnMut() -> bool>) {}
+
+    fn on_hit_test_window_control(&self, _callback: Box<dyn FnMut() -> Option<WindowControlArea>>) {
+    }
+
+    fn on_close(&self, callback: Box<dyn FnOnce()>) {
+        self.0.callbacks.borrow_mut().close = Some(callback);
+    }
+
+    fn on_appearance_changed(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn draw(&self, scene: &Scene) {
+        let state = &mut *self.0.state.borrow_mut();
+        state.renderer.draw(scene);
+        if let Some(frame_writer) = &mut state.frame_writer {
+            frame_writer.write(self.0.handle.window_id().as_u64(), state.renderer.frame());
+        }
+    }
+
+    fn capture_frame(&self) -> Option<RgbaImage> {
+        Some(self.0.state.borrow().renderer.frame().clone())
+    }
+
+    fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
+        self.0.state.borrow().renderer.sprite_atlas()
+    }
+
+    fn gpu_specs(&self) -> Option<GpuSpecs> {
+        Some(GpuSpecs {
+            is_software_emulated: true,
+            device_name: "CPU".into(),
+            driver_name: "gpui headless".into(),
+            driver_info: String::new(),
+        })
+    }
+
+    fn update_ime_position(&self, _bounds: Bounds<Pixels>) {}
+
+    fn show(&self) {
+        self.0.state.borrow_mut().hidden = false;
+    }
+
+    fn hide(&self) {
+        self.0.state.borrow_mut().hidden = true;
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.0.state.borrow().hidden
+    }
+}
@@ -0,0 +1,353 @@
+//! System power and session events from [logind].
+//!
+//! Listens for `PrepareForSleep` and `PrepareForShutdown` on the `org.freedesktop.login1`
+//! manager and for `Lock` and `Unlock` on the current session, and delivers them as
+//! [`SystemPowerEvent`]s on the main thread. While a sleep delay is requested, a logind delay
+//! inhibitor is held so that the system waits for the suspend or shutdown callback to return,
+//! up to logind's `InhibitDelayMaxSec`.
+//!
+//! [logind]: https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+
+use std::{cell::RefCell, future, rc::Rc};
+
+use anyhow::{Context as _, Result};
+use futures::{StreamExt as _, channel::mpsc, stream};
+use util::ResultExt as _;
+use zbus::{
+    Connection, MatchRule, MessageStream, Proxy, message,
+    zvariant::{OwnedFd, OwnedObjectPath},
+};
+
+use super::app_name;
+use crate::{ForegroundExecutor, SystemPowerEvent, Task};
+
+const DESTINATION: &str = "org.freedesktop.login1";
+const MANAGER_PATH: &str = "/org/freedesktop/login1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+type PowerCallback = Rc<RefCell<Option<Box<dyn FnMut(SystemPowerEvent)>>>>;
+
+pub(crate) struct Logind {
+    callback: PowerCallback,
+    sleep_delay: mpsc::UnboundedSender<Option<String>>,
+    _task: Task<()>,
+}
+
+impl Logind {
+    /// Connect to logind on the system bus.
+    pub fn new(executor: &ForegroundExecutor) -> Self {
+        Self::with_connection(executor, Connection::system())
+    }
+
+    pub fn with_connection(
+        executor: &ForegroundExecutor,
+        connection: impl Future<Output = zbus::Result<Connection>> + 'static,
+    ) -> Self {
+        let callback = PowerCallback::default();
+        let (sleep_delay, sleep_delay_requests) = mpsc::unbounded();
+        let task = executor.spawn({
+            let callback = callback.clone();
+            async move {
+                let result = async {
+                    let connection = connection
+                        .await
+                        .context("failed to connect to the system bus")?;
+                    run(connection, sleep_delay_requests, callback).await
+                };
+                result.await.log_err();
+            }
+        });
+        Self {
+            callback,
+            sleep_delay,
+            _task: task,
+        }
+    }
+
+    pub fn set_callback(&self, callback: Box<dyn FnMut(SystemPowerEvent)>) {
+        self.callback.replace(Some(callback));
+    }
+
+    /// Hold a delay inhibitor with the given reason, or release it if `reason` is `None`.
+    pub fn set_sleep_delay(&self, reason: Option<&str>) {
+        self.sleep_delay
+            .unbounded_send(reason.map(ToString::to_string))
+            .ok();
+    }
+}
+
+enum Message {
+    PrepareForSleep(bool),
+    PrepareForShutdown(bool),
+    Lock,
+    Unlock,
+    SleepDelay(Option<String>),
+}
+
+async fn run(
+    connection: Connection,
+    sleep_delay_requests: mpsc::UnboundedReceiver<Option<String>>,
+    callback: PowerCallback,
+) -> Result<()> {
+    let manager = Proxy::new(&connection, DESTINATION, MANAGER_PATH, MANAGER_INTERFACE).await?;
+
+    // All logind signals arrive on one stream, so they are handled in the order logind sent them.
+    let rule = MatchRule::builder()
+        .msg_type(message::Type::Signal)
+        .sender(DESTINATION)?
+        .path_namespace(MANAGER_PATH)?
+        .build();
+    let signals = MessageStream::for_match_rule(rule, &connection, None).await?;
+
+    // Lock and unlock are only available when the application runs inside a logind session.
+    let session_path = manager
+        .call::<_, _, OwnedObjectPath>("GetSession", &("auto",))
+        .await
+        .inspect_err(|error| {
+            log::info!("not in a logind session, lock events are unavailable: {error}")
+        })
+        .ok();
+
+    let signals = signals.filter_map(move |message| {
+        future::ready(
+            message
+                .log_err()
+                .and_then(|message| parse_signal(&message, session_path.as_ref())),
+        )
+    });
+    let mut messages = stream::select(sleep_delay_requests.map(Message::SleepDelay), signals);
+    let mut sleep_delay = None;
+    let mut inhibitor = None;
+    while let Some(message) = messages.next().await {
+        match message {
+            Message::SleepDelay(reason) => {
+                inhibitor = None;
+                if let Some(reason) = &reason {
+                    inhibitor = inhibit(&manager, reason).await.log_err();
+                }
+                sleep_delay = reason;
+            }
+            Message::PrepareForSleep(true) => {
+                dispatch(&callback, SystemPowerEvent::Suspend);
+                inhibitor.take();
+            }
+            Message::PrepareForSleep(false) => {
+                if let Some(reason) = &sleep_delay {
+                    inhibitor = inhibit(&manager, reason).await.log_err();
+                }
+                dispatch(&callback, SystemPowerEvent::Resume);
+            }
+            Message::PrepareForShutdown(true) => {
+                dispatch(&callback, SystemPowerEvent::Shutdown);
+                inhibitor.take();
+            }
+            // A scheduled shutdown was cancelled.
+            Message::PrepareForShutdown(false) => {
+                if let Some(reason) = sleep_delay.as_ref().filter(|_| inhibitor.is_none()) {
+                    inhibitor = inhibit(&manager, reason).await.log_err();
+                }
+            }
+            Message::Lock => dispatch(&callback, SystemPowerEvent::LockScreen),
+            Message::Unlock => dispatch(&callback, SystemPowerEvent::UnlockScreen),
+        }
+    }
+
+    Ok(())
+}
+
+/// The message for a manager signal, or a signal of our own session.
+fn parse_signal(
+    message: &zbus::Message,
+    session_path: Option<&OwnedObjectPath>,
+) -> Option<Message> {
+    let header = message.header();
+    let path = header.path()?.as_str();
+    let is_manager = path == MANAGER_PATH;
+    let is_session = session_path.is_some_and(|session_path| session_path.as_str() == path);
+    match (header.interface()?.as_str(), header.member()?.as_str()) {
+        (MANAGER_INTERFACE, "PrepareForSleep") if is_manager => message
+            .body()
+            .deserialize()
+            .log_err()
+            .map(Message::PrepareForSleep),
+        (MANAGER_INTERFACE, "PrepareForShutdown") if is_manager => message
+            .body()
+            .deserialize()
+            .log_err()
+            .map(Message::PrepareForShutdown),
+        (SESSION_INTERFACE, "Lock") if is_session => Some(Message::Lock),
+        (SESSION_INTERFACE, "Unlock") if is_session => Some(Message::Unlock),
+        _ => None,
+    }
+}
+
+async fn inhibit(manager: &Proxy<'_>, reason: &str) -> Result<OwnedFd> {
+    manager
+        .call("Inhibit", &("sleep:shutdown", app_name(), reason, "delay"))
+        .await
+        .context("failed to take a logind delay inhibitor")
+}
+
+fn dispatch(callback: &PowerCallback, event: SystemPowerEvent) {
+    let Some(mut handler) = callback.borrow_mut().take() else {
+        return;
+    };
+    handler(event);
+    callback.borrow_mut().get_or_insert(handler);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{ErrorKind, Read as _},
+        os::unix::net::UnixStream,
+        sync::{Arc, Mutex},
+    };
+
+    use futures::FutureExt as _;
+    use zbus::{fdo, names::BusName};
+
+    use super::*;
+    use crate::platform::linux::test_bus::{TestBus, wait_until};
+
+    const SESSION_PATH: &str = "/org/freedesktop/login1/session/_31";
+
+    struct FakeManager {
+        inhibitors: Arc<Mutex<Vec<UnixStream>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.login1.Manager")]
+    impl FakeManager {
+        fn get_session(&self, _session_id: &str) -> OwnedObjectPath {
+            OwnedObjectPath::try_from(SESSION_PATH).unwrap()
+        }
+
+        fn inhibit(&self, what: &str, _who: &str, _why: &str, mode: &str) -> fdo::Result<OwnedFd> {
+            assert_eq!((what, mode), ("sleep:shutdown", "delay"));
+            let (held, sent) = UnixStream::pair().map_err(|e| fdo::Error::Failed(e.to_string()))?;
+            self.inhibitors.lock().unwrap().push(held);
+            Ok(std::os::fd::OwnedFd::from(sent).into())
+        }
+    }
+
+    async fn emit(
+        service: &Connection,
+        path: &str,
+        interface: &str,
+        signal: &str,
+        body: &(impl serde::Serialize + zbus::zvariant::DynamicType),
+    ) {
+        service
+            .emit_signal(None::<BusName>, path, interface, signal, body)
+            .await
+            .unwrap();
+    }
+
+    /// Whether the client has closed its end of an inhibitor.
+    fn is_released(inhibitor: &mut UnixStream) -> bool {
+        inhibitor.set_nonblocking(true).unwrap();
+        match inhibitor.read(&mut [0]) {
+            Ok(0) => true,
+            Err(error) if error.kind() == ErrorKind::WouldBlock => false,
+            result => panic!("unexpected read from inhibitor: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_power_events_and_sleep_delay() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let inhibitors = Arc::new(Mutex::new(Vec::new()));
+            let service = bus
+                .builder()
+                .name(DESTINATION)
+                .unwrap()
+                .serve_at(
+                    MANAGER_PATH,
+                    FakeManager {
+                        inhibitors: inhibitors.clone(),
+                    },
+                )
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            let events = Rc::new(RefCell::new(Vec::new()));
+            let callback = PowerCallback::default();
+            callback.replace(Some(Box::new({
+                let events = events.clone();
+                move |event| events.borrow_mut().push(event)
+            })));
+            let (sleep_delay, sleep_delay_requests) = mpsc::unbounded();
+            let client = bus.connect().await;
+
+            let script = async {
+                // The inhibitor is taken once the client is listening for signals.
+                sleep_delay.unbounded_send(Some("Saving".into())).unwrap();
+                wait_until(|| inhibitors.lock().unwrap().len() == 1).await;
+
+                // Signals of the session and the manager are delivered in the order they are sent.
+                emit(&service, SESSION_PATH, SESSION_INTERFACE, "Lock", &()).await;
+                emit(
+                    &service,
+                    MANAGER_PATH,
+                    MANAGER_INTERFACE,
+                    "PrepareForSleep",
+                    &(true,),
+                )
+                .await;
+                wait_until(|| events.borrow().len() == 2).await;
+                wait_until(|| is_released(&mut inhibitors.lock().unwrap()[0])).await;
+
+                // The inhibitor is taken again after resuming.
+                emit(
+                    &service,
+                    MANAGER_PATH,
+                    MANAGER_INTERFACE,
+                    "PrepareForSleep",
+                    &(false,),
+                )
+                .await;
+                emit(&service, SESSION_PATH, SESSION_INTERFACE, "Unlock", &()).await;
+                wait_until(|| events.borrow().len() == 4).await;
+                assert_eq!(inhibitors.lock().unwrap().len(), 2);
+                assert!(!is_released(&mut inhibitors.lock().unwrap()[1]));
+
+                sleep_delay.unbounded_send(None).unwrap();
+                wait_until(|| is_released(&mut inhibitors.lock().unwrap()[1])).await;
+
+                emit(
+                    &service,
+                    MANAGER_PATH,
+                    MANAGER_INTERFACE,
+                    "PrepareForShutdown",
+                    &(true,),
+                )
+                .await;
+                wait_until(|| events.borrow().len() == 5).await;
+            };
+
+            futures::select! {
+                result = run(client, sleep_delay_requests, callback).fuse() => {
+                    panic!("logind monitor stopped: {result:?}")
+                }
+                _ = Box::pin(script).fuse() => {}
+            }
+
+            assert_eq!(
+                *events.borrow(),
+                [
+                    SystemPowerEvent::LockScreen,
+                    SystemPowerEvent::Suspend,
+                    SystemPowerEvent::Resume,
+                    SystemPowerEvent::UnlockScreen,
+                    SystemPowerEvent::Shutdown,
+                ]
+            );
+        });
+    }
+}
@@ -0,0 +1,521 @@
+//! An [MPRIS] media player, so that desktop shells route media keys and their media controls to
+//! the application even when none of its windows has keyboard focus, and show what is playing.
+//!
+//! [MPRIS]: https://specifications.freedesktop.org/mpris-spec/latest/
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Duration, time::Instant};
+
+use anyhow::Result;
+use futures::{StreamExt as _, channel::mpsc};
+use util::ResultExt as _;
+use zbus::{
+    connection, fdo,
+    object_server::{InterfaceRef, SignalEmitter},
+    zvariant::{ObjectPath, Value},
+};
+
+use super::app_name;
+use crate::{ForegroundExecutor, MediaKeyEvent, NowPlaying, PlaybackState, SeekDirection, Task};
+
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+/// A seek is announced when the new position differs from the expected one by more than this.
+const SEEK_TOLERANCE: Duration = Duration::from_millis(500);
+
+pub(crate) type MediaKeyCallback = Rc<RefCell<Option<Box<dyn FnMut(MediaKeyEvent)>>>>;
+
+pub(crate) struct Mpris {
+    now_playing: mpsc::UnboundedSender<Option<NowPlaying>>,
+    _task: Task<()>,
+}
+
+impl Mpris {
+    /// Publish a player on the session bus that reports controls to `callback`.
+    pub fn new(executor: &ForegroundExecutor, callback: MediaKeyCallback) -> Self {
+        Self::with_connection(executor, callback, connection::Builder::session())
+    }
+
+    pub fn with_connection(
+        executor: &ForegroundExecutor,
+        callback: MediaKeyCallback,
+        builder: zbus::Result<connection::Builder<'static>>,
+    ) -> Self {
+        let (now_playing, now_playing_updates) = mpsc::unbounded();
+        let task = executor.spawn(async move {
+            let dispatch = |event| {
+                let Some(mut handler) = callback.borrow_mut().take() else {
+                    return;
+                };
+                handler(event);
+                callback.borrow_mut().get_or_insert(handler);
+            };
+            let result = async { run(builder?, now_playing_updates, dispatch).await };
+            result.await.log_err();
+        });
+        Self {
+            now_playing,
+            _task: task,
+        }
+    }
+
+    pub fn set_now_playing(&self, now_playing: Option<NowPlaying>) {
+        self.now_playing.unbounded_send(now_playing).ok();
+    }
+}
+
+async fn run(
+    builder: connection::Builder<'static>,
+    mut now_playing_updates: mpsc::UnboundedReceiver<Option<NowPlaying>>,
+    mut dispatch: impl FnMut(MediaKeyEvent),
+) -> Result<()> {
+    let (events, mut event_receiver) = mpsc::unbounded();
+    let app_name = app_name();
+    let connection = builder
+        .name(format!(
+            "org.mpris.MediaPlayer2.{app_name}.instance{}",
+            std::process::id()
+        ))?
+        .serve_at(OBJECT_PATH, MediaPlayer { identity: app_name })?
+        .serve_at(OBJECT_PATH, Player::new(events))?
+        .build()
+        .await?;
+    let player = connection
+        .object_server()
+        .interface::<_, Player>(OBJECT_PATH)
+        .await?;
+
+    loop {
+        futures::select! {
+            event = event_receiver.next() => match event {
+                Some(event) => dispatch(event),
+                None => break,
+            },
+            now_playing = now_playing_updates.next() => match now_playing {
+                Some(now_playing) => update_player(&player, now_playing).await?,
+                None => break,
+            },
+        }
+    }
+    Ok(())
+}
+
+async fn update_player(
+    player: &InterfaceRef<Player>,
+    now_playing: Option<NowPlaying>,
+) -> Result<()> {
+    let emitter = player.signal_emitter();
+    let mut player = player.get_mut().await;
+
+    let expected_position = player.position();
+    let previous = player.now_playing.take();
+    let same_track = previous
+        .as_ref()
+        .zip(now_playing.as_ref())
+        .is_some_and(|(a, b)| {
+            (&a.title, &a.artist, &a.album, a.duration)
+                == (&b.title, &b.artist, &b.album, b.duration)
+        });
+    if !same_track {
+        player.track_number += 1;
+    }
+    player.position_updated_at = Instant::now();
+    player.now_playing = now_playing;
+
+    let current = &player.now_playing;
+    if !same_track
+        || changed(&previous, current, |now_playing| {
+            now_playing.art_url.clone()
+        })
+    {
+        player.metadata_changed(emitter).await?;
+    }
+    if previous.is_some() != current.is_some() {
+        player.can_play_changed(emitter).await?;
+        player.can_pause_changed(emitter).await?;
+        player.can_go_next_changed(emitter).await?;
+        player.can_go_previous_changed(emitter).await?;
+    }
+    if changed(&previous, current, |now_playing| {
+        now_playing.duration.is_some()
+    }) {
+        player.can_seek_changed(emitter).await?;
+    }
+    if changed(&previous, current, |now_playing| now_playing.state) {
+        player.playback_status_changed(emitter).await?;
+    }
+    // The position isn't announced as it advances, only when it jumps.
+    let position = player.position();
+    if same_track && position.abs_diff(expected_position) as u128 > SEEK_TOLERANCE.as_micros() {
+        Player::seeked(emitter, position).await?;
+    }
+    Ok(())
+}
+
+fn changed<T: PartialEq>(
+    previous: &Option<NowPlaying>,
+    current: &Option<NowPlaying>,
+    property: impl Fn(&NowPlaying) -> T,
+) -> bool {
+    previous.as_ref().map(&property) != current.as_ref().map(&property)
+}
+
+struct MediaPlayer {
+    identity: String,
+}
+
+#[zbus::interface(name = "org.mpris.MediaPlayer2")]
+impl MediaPlayer {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        &self.identity
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+struct Player {
+    events: mpsc::UnboundedSender<MediaKeyEvent>,
+    now_playing: Option<NowPlaying>,
+    position_updated_at: Instant,
+    /// Incremented for every track, to give each one a distinct `mpris:trackid`.
+    track_number: u64,
+}
+
+impl Player {
+    fn new(events: mpsc::UnboundedSender<MediaKeyEvent>) -> Self {
+        Self {
+            events,
+            now_playing: None,
+            position_updated_at: Instant::now(),
+            track_number: 0,
+        }
+    }
+
+    fn send(&self, event: MediaKeyEvent) {
+        self.events.unbounded_send(event).ok();
+    }
+
+    fn track_id(&self) -> ObjectPath<'static> {
+        match self.now_playing {
+            Some(_) => ObjectPath::try_from(format!("/org/gpui/Track/{}", self.track_number))
+                .unwrap_or_else(|_| ObjectPath::from_static_str_unchecked(NO_TRACK)),
+            None => ObjectPath::from_static_str_unchecked(NO_TRACK),
+        }
+    }
+}
+
+#[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) {
+        self.send(MediaKeyEvent::NextTrack);
+    }
+
+    fn previous(&self) {
+        self.send(MediaKeyEvent::PreviousTrack);
+    }
+
+    fn pause(&self) {
+        self.send(MediaKeyEvent::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.send(MediaKeyEvent::PlayPause);
+    }
+
+    fn stop(&self) {
+        self.send(MediaKeyEvent::Stop);
+    }
+
+    fn play(&self) {
+        self.send(MediaKeyEvent::Play);
+    }
+
+    fn seek(&self, offset: i64) {
+        let direction = if offset < 0 {
+            SeekDirection::Backward
+        } else {
+            SeekDirection::Forward
+        };
+        self.send(MediaKeyEvent::Seek {
+            direction,
+            offset: Duration::from_micros(offset.unsigned_abs()),
+        });
+    }
+
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        // Requests for a track that has since changed are ignored, as the spec requires.
+        if track_id == self.track_id() && position >= 0 {
+            self.send(MediaKeyEvent::SetPosition(Duration::from_micros(
+                position as u64,
+            )));
+        }
+    }
+
+    fn open_uri(&self, _uri: &str) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "opening URIs is not supported".into(),
+        ))
+    }
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        match self
+            .now_playing
+            .as_ref()
+            .map(|now_playing| now_playing.state)
+        {
+            Some(PlaybackState::Playing) => "Playing",
+            Some(PlaybackState::Paused) => "Paused",
+            Some(PlaybackState::Stopped) | None => "Stopped",
+        }
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<&'static str, Value<'static>> {
+        let mut metadata = HashMap::from([("mpris:trackid", Value::from(self.track_id()))]);
+        let Some(now_playing) = &self.now_playing else {
+            return metadata;
+        };
+        if let Some(title) = &now_playing.title {
+            metadata.insert("xesam:title", Value::from(title.to_string()));
+        }
+        if let Some(artist) = &now_playing.artist {
+            metadata.insert("xesam:artist", Value::from(vec![artist.to_string()]));
+        }
+        if let Some(album) = &now_playing.album {
+            metadata.insert("xesam:album", Value::from(album.to_string()));
+        }
+        if let Some(art_url) = &now_playing.art_url {
+            metadata.insert("mpris:artUrl", Value::from(art_url.to_string()));
+        }
+        if let Some(duration) = now_playing.duration {
+            metadata.insert("mpris:length", Value::from(duration.as_micros() as i64));
+        }
+        metadata
+    }
+
+    /// The position in microseconds, advanced by the time spent playing since it was set.
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        let Some(now_playing) = &self.now_playing else {
+            return 0;
+        };
+        let mut position = now_playing.position;
+        if now_playing.state == PlaybackState::Playing {
+            position += self.position_updated_at.elapsed();
+        }
+        if let Some(duration) = now_playing.duration {
+            position = position.min(duration);
+        }
+        position.as_micros() as i64
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.now_playing.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.now_playing.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.now_playing.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.now_playing.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.now_playing
+            .as_ref()
+            .is_some_and(|now_playing| now_playing.duration.is_some())
+    }
+
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt as _;
+    use zbus::{Proxy, zvariant::OwnedValue};
+
+    use super::*;
+    use crate::platform::linux::test_bus::{TestBus, wait_until, wait_until_async};
+
+    #[test]
+    fn test_mpris_player() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let events = RefCell::new(Vec::new());
+            let (now_playing, now_playing_updates) = mpsc::unbounded();
+            let client = bus.connect().await;
+
+            let script = async {
+                let name = format!(
+                    "org.mpris.MediaPlayer2.{}.instance{}",
+                    app_name(),
+                    std::process::id()
+                );
+                let dbus = fdo::DBusProxy::new(&client).await.unwrap();
+                wait_until_async(|| async {
+                    dbus.name_has_owner(name.as_str().try_into().unwrap())
+                        .await
+                        .unwrap()
+                })
+                .await;
+                let player = zbus::proxy::Builder::<Proxy>::new(&client)
+                    .destination(name)
+                    .unwrap()
+                    .path(OBJECT_PATH)
+                    .unwrap()
+                    .interface("org.mpris.MediaPlayer2.Player")
+                    .unwrap()
+                    .cache_properties(zbus::proxy::CacheProperties::No)
+                    .build()
+                    .await
+                    .unwrap();
+                let status = || async {
+                    player
+                        .get_property::<String>("PlaybackStatus")
+                        .await
+                        .unwrap()
+                };
+                assert_eq!(status().await, "Stopped");
+
+                let track = NowPlaying {
+                    title: Some("Song".into()),
+                    artist: Some("Artist".into()),
+                    duration: Some(Duration::from_secs(180)),
+                    position: Duration::from_secs(10),
+                    state: PlaybackState::Paused,
+                    ..Default::default()
+                };
+                now_playing.unbounded_send(Some(track.clone())).unwrap();
+                wait_until_async(|| async { status().await == "Paused" }).await;
+
+                let metadata: HashMap<String, OwnedValue> =
+                    player.get_property("Metadata").await.unwrap();
+                assert_eq!(
+                    String::try_from(metadata["xesam:title"].try_clone().unwrap()).unwrap(),
+                    "Song"
+                );
+                assert_eq!(
+                    i64::try_from(&metadata["mpris:length"]).unwrap(),
+                    180_000_000
+                );
+                assert_eq!(
+                    player.get_property::<i64>("Position").await.unwrap(),
+                    10_000_000
+                );
+                let track_id =
+                    ObjectPath::try_from(metadata["mpris:trackid"].try_clone().unwrap()).unwrap();
+
+                player.call::<_, _, ()>("PlayPause", &()).await.unwrap();
+                player.call::<_, _, ()>("Next", &()).await.unwrap();
+                player
+                    .call::<_, _, ()>("Seek", &(-5_000_000i64,))
+                    .await
+                    .unwrap();
+                player
+                    .call::<_, _, ()>("SetPosition", &(&track_id, 60_000_000i64))
+                    .await
+                    .unwrap();
+                // Positions for a different track are ignored.
+                let other_track = ObjectPath::from_static_str_unchecked("/org/gpui/Track/0");
+                player
+                    .call::<_, _, ()>("SetPosition", &(other_track, 1i64))
+                    .await
+                    .unwrap();
+                wait_until(|| events.borrow().len() == 4).await;
+
+                // Clearing what's playing stops the player.
+                now_playing.unbounded_send(None).unwrap();
+                wait_until_async(|| async { status().await == "Stopped" }).await;
+                assert!(!player.get_property::<bool>("CanPlay").await.unwrap());
+            };
+
+            futures::select! {
+                result = run(bus.builder(), now_playing_updates, |event| events.borrow_mut().push(event)).fuse() => {
+                    panic!("MPRIS player stopped: {result:?}")
+                }
+                _ = Box::pin(script).fuse() => {}
+            }
+
+            assert_eq!(
+                *events.borrow(),
+                [
+                    MediaKeyEvent::PlayPause,
+                    MediaKeyEvent::NextTrack,
+                    MediaKeyEvent::Seek {
+                        direction: SeekDirection::Backward,
+                        offset: Duration::from_secs(5),
+                    },
+                    MediaKeyEvent::SetPosition(Duration::from_secs(60)),
+                ]
+            );
+        });
+    }
+}
@@ -0,0 +1,582 @@
+//! Network connectivity monitoring.
+//!
+//! Connectivity, metering and the kind of the primary connection come from NetworkManager when
+//! it is running. Otherwise, rtnetlink link and address notifications trigger a rescan of the
+//! interfaces in `/sys/class/net`. The monitor switches between the two as NetworkManager comes
+//! and goes on the system bus, and retries after errors.
+
+use std::{
+    cell::{Cell, RefCell},
+    fs, io,
+    os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd},
+    path::Path,
+    rc::Rc,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use futures::{FutureExt as _, future::LocalBoxFuture, stream};
+use smol::Async;
+use util::ResultExt as _;
+use zbus::{
+    Connection, Proxy,
+    fdo::{DBusProxy, PropertiesProxy},
+    names::InterfaceName,
+    proxy::CacheProperties,
+    zvariant::OwnedObjectPath,
+};
+
+use crate::{ForegroundExecutor, NetworkConnectivity, NetworkInterfaceKind, NetworkStatus, Task};
+
+const NETWORK_MANAGER: &str = "org.freedesktop.NetworkManager";
+const NETWORK_MANAGER_PATH: &str = "/org/freedesktop/NetworkManager";
+const ACTIVE_CONNECTION_INTERFACE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+type NetworkCallback = Rc<RefCell<Option<Box<dyn FnMut(NetworkStatus)>>>>;
+
+pub(crate) struct NetworkMonitor {
+    callback: NetworkCallback,
+    status: Rc<Cell<Option<NetworkStatus>>>,
+    _task: Task<()>,
+}
+
+impl NetworkMonitor {
+    /// Watch NetworkManager on the system bus, or netlink if it isn't available.
+    pub fn new(executor: &ForegroundExecutor) -> Self {
+        Self::with_connection(executor, Connection::system())
+    }
+
+    pub fn with_connection(
+        executor: &ForegroundExecutor,
+        connection: impl Future<Output = zbus::Result<Connection>> + 'static,
+    ) -> Self {
+        let callback = NetworkCallback::default();
+        let status = Rc::new(Cell::new(None));
+        let task = executor.spawn({
+            let callback = callback.clone();
+            let status = status.clone();
+            async move {
+                let update = |new_status| update_status(&status, &callback, new_status);
+                run(connection.await, update).await.log_err();
+            }
+        });
+        Self {
+            callback,
+            status,
+            _task: task,
+        }
+    }
+
+    pub fn set_callback(&self, callback: Box<dyn FnMut(NetworkStatus)>) {
+        self.callback.replace(Some(callback));
+    }
+
+    /// The last status reported by NetworkManager or netlink.
+    pub fn status(&self) -> Option<NetworkStatus> {
+        self.status.get()
+    }
+}
+
+fn update_status(
+    status: &Cell<Option<NetworkStatus>>,
+    callback: &NetworkCallback,
+    new_status: NetworkStatus,
+) {
+    let previous = status.replace(Some(new_status));
+    // The first status is the one the application sees when it starts, not a change.
+    if previous.is_none_or(|previous| previous == new_status) {
+        return;
+    }
+    let Some(mut handler) = callback.borrow_mut().take() else {
+        return;
+    };
+    handler(new_status);
+    callback.borrow_mut().get_or_insert(handler);
+}
+
+async fn run(
+    connection: zbus::Result<Connection>,
+    mut update: impl FnMut(NetworkStatus),
+) -> Result<()> {
+    use futures::StreamExt as _;
+
+    let connection = match connection {
+        Ok(connection) => connection,
+        Err(error) => {
+            log::info!("can't connect to the system bus, using netlink: {error}");
+            return watch_netlink(&mut update).await;
+        }
+    };
+    let dbus = DBusProxy::new(&connection).await?;
+    let mut owner_changes = dbus
+        .receive_name_owner_changed_with_args(&[(0, NETWORK_MANAGER)])
+        .await?;
+
+    loop {
+        let has_network_manager = dbus
+            .name_has_owner(NETWORK_MANAGER.try_into()?)
+            .await
+            .unwrap_or(false);
+        let watch: LocalBoxFuture<Result<()>> = if has_network_manager {
+            watch_network_manager(&connection, &mut update).boxed_local()
+        } else {
+            watch_netlink(&mut update).boxed_local()
+        };
+
+        // Start over whenever NetworkManager starts, stops or is restarted.
+        let mut retry = false;
+        futures::select_biased! {
+            change = owner_changes.next() => {
+                if change.is_none() {
+                    break;
+                }
+            }
+            result = watch.fuse() => {
+                if let Err(error) = result {
+                    let source = if has_network_manager { "NetworkManager" } else { "netlink" };
+                    log::warn!("failed to monitor the network with {source}, retrying: {error}");
+                }
+                retry = true;
+            }
+        }
+        if retry && !wait_to_retry(owner_changes.next()).await {
+            break;
+        }
+    }
+
+    log::info!("lost the system bus, using netlink");
+    watch_netlink(&mut update).await
+}
+
+/// Wait out the retry delay, or less if NetworkManager starts or stops in the meantime. Returns
+/// false if the bus is gone.
+async fn wait_to_retry<T>(owner_change: impl Future<Output = Option<T>>) -> bool {
+    futures::select_biased! {
+        change = owner_change.fuse() => change.is_some(),
+        _ = smol::Timer::after(RETRY_DELAY).fuse() => true,
+    }
+}
+
+async fn watch_network_manager(
+    connection: &Connection,
+    update: &mut impl FnMut(NetworkStatus),
+) -> Result<()> {
+    use futures::StreamExt as _;
+
+    let manager = Proxy::new(
+        connection,
+        NETWORK_MANAGER,
+        NETWORK_MANAGER_PATH,
+        NETWORK_MANAGER,
+    )
+    .await?;
+    let properties = PropertiesProxy::builder(connection)
+        .destination(NETWORK_MANAGER)?
+        .path(NETWORK_MANAGER_PATH)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    // `Connectivity`, `Metered` and `PrimaryConnection` are announced with `PropertiesChanged`.
+    let mut changes = stream::select(
+        manager.receive_signal("StateChanged").await?.map(|_| ()),
+        properties.receive_properties_changed().await?.map(|_| ()),
+    );
+
+    update(network_manager_status(connection, &properties).await?);
+    while changes.next().await.is_some() {
+        update(network_manager_status(connection, &properties).await?);
+    }
+    Ok(())
+}
+
+async fn network_manager_status(
+    connection: &Connection,
+    properties: &PropertiesProxy<'_>,
+) -> Result<NetworkStatus> {
+    let manager = properties
+        .get_all(InterfaceName::from_static_str_unchecked(NETWORK_MANAGER))
+        .await?;
+    let property = |name: &str| -> Result<u32> {
+        manager
+            .get(name)
+            .context("missing NetworkManager property")?
+            .try_into()
+            .context("invalid NetworkManager property")
+    };
+
+    // NMConnectivityState, falling back to NMState when connectivity checking is disabled.
+    let connectivity = match property("Connectivity")? {
+        4 => NetworkConnectivity::Online,
+        2 => NetworkConnectivity::Portal,
+        3 => NetworkConnectivity::Limited,
+        1 => NetworkConnectivity::Offline,
+        _ => match property("State")? {
+            70.. => NetworkConnectivity::Online,
+            50..70 => NetworkConnectivity::Limited,
+            _ => NetworkConnectivity::Offline,
+        },
+    };
+
+    // NMMetered: `YES` or `GUESS_YES`.
+    let metered = matches!(property("Metered")?, 1 | 3);
+
+    let primary_connection = manager
+        .get("PrimaryConnection")
+        .map(|path| OwnedObjectPath::try_from(path.try_clone()?))
+        .transpose()?
+        .filter(|path| path.as_str() != "/");
+    let interface = match primary_connection {
+        Some(path) => {
+            let connection_type: String = Proxy::new(
+                connection,
+                NETWORK_MANAGER,
+                path,
+                ACTIVE_CONNECTION_INTERFACE,
+            )
+            .await?
+            .get_property("Type")
+            .await?;
+            Some(interface_kind_from_connection_type(&connection_type))
+        }
+        None => None,
+    };
+
+    Ok(NetworkStatus {
+        connectivity,
+        metered,
+        interface,
+    })
+}
+
+fn interface_kind_from_connection_type(connection_type: &str) -> NetworkInterfaceKind {
+    match connection_type {
+        "802-3-ethernet" => NetworkInterfaceKind::Ethernet,
+        "802-11-wireless" => NetworkInterfaceKind::Wifi,
+        "vpn" | "wireguard" => NetworkInterfaceKind::Vpn,
+        "gsm" | "cdma" => NetworkInterfaceKind::Cellular,
+        _ => NetworkInterfaceKind::Other,
+    }
+}
+
+async fn watch_netlink(update: &mut impl FnMut(NetworkStatus)) -> Result<()> {
+    let socket = netlink_route_socket().context("failed to open a netlink socket")?;
+    let socket = Async::new(socket)?;
+    let mut buffer = vec![0u8; 16 * 1024];
+
+    update(network_status());
+    loop {
+        // The messages themselves aren't needed: any change to links or addresses triggers a
+        // rescan of the interfaces.
+        let result = socket
+            .read_with(|socket| {
+                let read = unsafe {
+                    libc::recv(
+                        socket.as_raw_fd(),
+                        buffer.as_mut_ptr().cast(),
+                        buffer.len(),
+                        0,
+                    )
+                };
+                if read < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(())
+                }
+            })
+            .await;
+        match result {
+            Ok(()) => {}
+            // The kernel drops notifications when the socket's buffer overflows, which the rescan
+            // makes up for.
+            Err(error)
+                if error.raw_os_error() == Some(libc::ENOBUFS)
+                    || error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error).context("failed to receive netlink notifications"),
+        }
+        update(network_status());
+    }
+}
+
+fn netlink_route_socket() -> io::Result<OwnedFd> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            libc::NETLINK_ROUTE,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    address.nl_groups =
+        (libc::RTMGRP_LINK | libc::RTMGRP_IPV4_IFADDR | libc::RTMGRP_IPV6_IFADDR) as u32;
+    let result = unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            (&raw const address).cast(),
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(socket)
+}
+
+/// Scan `/sys/class/net` for an interface that is up, preferring physical interfaces.
+pub(crate) fn network_status() -> NetworkStatus {
+    let Ok(entries) = fs::read_dir("/sys/class/net") else {
+        return NetworkStatus::OFFLINE;
+    };
+    let interface = entries
+        .flatten()
+        .filter(|entry| entry.file_name() != "lo")
+        .filter(|entry| {
+            fs::read_to_string(entry.path().join("operstate"))
+                .is_ok_and(|state| state.trim() == "up")
+        })
+        .map(|entry| sysfs_interface_kind(&entry.path()))
+        .min_by_key(|kind| *kind == NetworkInterfaceKind::Vpn);
+    match interface {
+        Some(interface) => NetworkStatus {
+            interface: Some(interface),
+            ..NetworkStatus::ONLINE
+        },
+        None => NetworkStatus::OFFLINE,
+    }
+}
+
+fn sysfs_interface_kind(path: &Path) -> NetworkInterfaceKind {
+    let uevent = fs::read_to_string(path.join("uevent")).unwrap_or_default();
+    let device_type = uevent
+        .lines()
+        .find_map(|line| line.strip_prefix("DEVTYPE="))
+        .unwrap_or_default();
+    if path.join("wireless").exists() || device_type == "wlan" {
+        NetworkInterfaceKind::Wifi
+    } else if path.join("tun_flags").exists() || device_type == "wireguard" {
+        NetworkInterfaceKind::Vpn
+    } else if device_type == "wwan" {
+        NetworkInterfaceKind::Cellular
+    } else if path.join("device").exists() {
+        NetworkInterfaceKind::Ethernet
+    } else {
+        NetworkInterfaceKind::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt as _;
+
+    use super::*;
+    use crate::platform::linux::test_bus::{TestBus, wait_until};
+
+    const ACTIVE_CONNECTION_PATH: &str = "/org/freedesktop/NetworkManager/ActiveConnection/1";
+
+    struct FakeNetworkManager {
+        connectivity: u32,
+        metered: u32,
+        primary_connection: OwnedObjectPath,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager")]
+    impl FakeNetworkManager {
+        #[zbus(property)]
+        fn connectivity(&self) -> u32 {
+            self.connectivity
+        }
+
+        #[zbus(property)]
+        fn state(&self) -> u32 {
+            70
+        }
+
+        #[zbus(property)]
+        fn metered(&self) -> u32 {
+            self.metered
+        }
+
+        #[zbus(property)]
+        fn primary_connection(&self) -> OwnedObjectPath {
+            self.primary_connection.clone()
+        }
+    }
+
+    struct FakeActiveConnection;
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.Connection.Active")]
+    impl FakeActiveConnection {
+        #[zbus(property, name = "Type")]
+        fn connection_type(&self) -> String {
+            "802-11-wireless".into()
+        }
+    }
+
+    #[test]
+    fn test_network_manager_status() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let service = bus
+                .builder()
+                .name(NETWORK_MANAGER)
+                .unwrap()
+                .serve_at(
+                    NETWORK_MANAGER_PATH,
+                    FakeNetworkManager {
+                        connectivity: 4,
+                        metered: 4,
+                        primary_connection: OwnedObjectPath::try_from(ACTIVE_CONNECTION_PATH)
+                            .unwrap(),
+                    },
+                )
+                .unwrap()
+                .serve_at(ACTIVE_CONNECTION_PATH, FakeActiveConnection)
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            let manager = service
+                .object_server()
+                .interface::<_, FakeNetworkManager>(NETWORK_MANAGER_PATH)
+                .await
+                .unwrap();
+
+            let statuses = RefCell::new(Vec::new());
+            let last_status = || statuses.borrow().last().copied();
+            let script = async {
+                wait_until(|| {
+                    last_status()
+                        == Some(NetworkStatus {
+                            interface: Some(NetworkInterfaceKind::Wifi),
+                            ..NetworkStatus::ONLINE
+                        })
+                })
+                .await;
+
+                // Connected to a metered network behind a captive portal.
+                {
+                    let mut manager = manager.get_mut().await;
+                    manager.connectivity = 2;
+                    manager.metered = 1;
+                }
+                let emitter = manager.signal_emitter();
+                manager
+                    .get()
+                    .await
+                    .connectivity_changed(emitter)
+                    .await
+                    .unwrap();
+                wait_until(|| {
+                    last_status()
+                        == Some(NetworkStatus {
+                            connectivity: NetworkConnectivity::Portal,
+                            metered: true,
+                            interface: Some(NetworkInterfaceKind::Wifi),
+                        })
+                })
+                .await;
+
+                {
+                    let mut manager = manager.get_mut().await;
+                    manager.connectivity = 1;
+                    manager.metered = 0;
+                    manager.primary_connection = OwnedObjectPath::try_from("/").unwrap();
+                }
+                manager
+                    .get()
+                    .await
+                    .primary_connection_changed(emitter)
+                    .await
+                    .unwrap();
+                wait_until(|| last_status() == Some(NetworkStatus::OFFLINE)).await;
+            };
+
+            futures::select! {
+                result = run(Ok(bus.connect().await), |status| statuses.borrow_mut().push(status)).fuse() => {
+                    panic!("network monitor stopped: {result:?}")
+                }
+                _ = Box::pin(script).fuse() => {}
+            }
+        });
+    }
+
+    #[test]
+    fn test_network_manager_restarts() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let start_network_manager = |connectivity| {
+                bus.builder()
+                    .name(NETWORK_MANAGER)
+                    .unwrap()
+                    .serve_at(
+                        NETWORK_MANAGER_PATH,
+                        FakeNetworkManager {
+                            connectivity,
+                            metered: 4,
+                            primary_connection: OwnedObjectPath::try_from("/").unwrap(),
+                        },
+                    )
+                    .unwrap()
+                    .build()
+            };
+
+            let statuses = RefCell::new(Vec::new());
+            let last_status = || statuses.borrow().last().copied();
+            let script = async {
+                // NetworkManager isn't running yet, so netlink is watched until it starts.
+                let service = start_network_manager(4).await.unwrap();
+                wait_until(|| last_status() == Some(NetworkStatus::ONLINE)).await;
+
+                drop(service);
+                let service = start_network_manager(2).await.unwrap();
+                wait_until(|| {
+                    last_status()
+                        == Some(NetworkStatus {
+                            connectivity: NetworkConnectivity::Portal,
+                            ..NetworkStatus::ONLINE
+                        })
+                })
+                .await;
+                drop(service);
+            };
+
+            futures::select! {
+                result = run(Ok(bus.connect().await), |status| statuses.borrow_mut().push(status)).fuse() => {
+                    panic!("network monitor stopped: {result:?}")
+                }
+                _ = Box::pin(script).fuse() => {}
+            }
+        });
+    }
+
+    #[test]
+    fn test_status_changes_are_reported_once() {
+        let status = Cell::new(None);
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let callback = NetworkCallback::default();
+        callback.replace(Some(Box::new({
+            let reported = reported.clone();
+            move |status| reported.borrow_mut().push(status)
+        })));
+
+        update_status(&status, &callback, NetworkStatus::ONLINE);
+        update_status(&status, &callback, NetworkStatus::ONLINE);
+        update_status(&status, &callback, NetworkStatus::OFFLINE);
+        update_status(&status, &callback, NetworkStatus::OFFLINE);
+        assert_eq!(*reported.borrow(), [NetworkStatus::OFFLINE]);
+        assert_eq!(status.get(), Some(NetworkStatus::OFFLINE));
+    }
+}
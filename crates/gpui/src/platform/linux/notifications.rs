@@ -1,10 +1,799 @@
-use anyhow::Result;
-
-pub fn show_notification(title: &str, body: &str) -> Result<()> {
-    notify_rust::Notification::new()
-        .summary(title)
-        .body(body)
-        .show()
-        .map_err(|e| anyhow::anyhow!("Failed to show notification: {}", e))?;
+//! Desktop notifications over D-Bus.
+//!
+//! Notifications are sent to the [notification server] on the session bus, or through the
+//! xdg-desktop-portal [`Notification`] interface inside a Flatpak or Snap sandbox. Actions,
+//! inline replies and closing are delivered as [`NotificationEvent`]s on the main thread.
+//!
+//! [notification server]: https://specifications.freedesktop.org/notification-spec/latest/
+//! [`Notification`]: https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.Notification.html
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    future,
+    rc::Rc,
+};
+
+use anyhow::{Context as _, Result};
+use futures::{
+    StreamExt as _,
+    channel::{mpsc, oneshot},
+    stream::{self, LocalBoxStream},
+};
+use util::ResultExt as _;
+use zbus::{
+    Connection, Message, Proxy,
+    zvariant::{OwnedValue, Value},
+};
+
+use crate::{
+    BackgroundExecutor, ForegroundExecutor, NotificationCloseReason, NotificationEvent,
+    NotificationIcon, NotificationId, NotificationOptions, NotificationUrgency, Task,
+};
+
+use super::app_name;
+
+/// The action reported by both backends when the inline reply field is used.
+const REPLY_ACTION: &str = "inline-reply";
+const REPLY_LABEL: &str = "Reply";
+const PORTAL_ID_PREFIX: &str = "gpui-";
+
+type NotificationCallback = Rc<RefCell<Option<Box<dyn FnMut(NotificationEvent)>>>>;
+
+enum Command {
+    Show(NotificationOptions, oneshot::Sender<Result<NotificationId>>),
+    Close(NotificationId),
+}
+
+pub(crate) struct Notifications {
+    callback: NotificationCallback,
+    commands: mpsc::UnboundedSender<Command>,
+    _task: Task<()>,
+}
+
+impl Notifications {
+    /// Connect to the notification server, or the portal when sandboxed. Icons are read and
+    /// decoded on the `background` executor.
+    pub fn new(executor: &ForegroundExecutor, background: BackgroundExecutor) -> Self {
+        Self::with_connection(
+            executor,
+            background,
+            Connection::session(),
+            super::power::is_sandboxed(),
+        )
+    }
+
+    pub fn with_connection(
+        executor: &ForegroundExecutor,
+        background: BackgroundExecutor,
+        connection: impl Future<Output = zbus::Result<Connection>> + 'static,
+        sandboxed: bool,
+    ) -> Self {
+        let callback = NotificationCallback::default();
+        let (commands, command_receiver) = mpsc::unbounded();
+        let task = executor.spawn({
+            let callback = callback.clone();
+            async move {
+                let dispatch = |event| {
+                    let Some(mut handler) = callback.borrow_mut().take() else {
+                        return;
+                    };
+                    handler(event);
+                    callback.borrow_mut().get_or_insert(handler);
+                };
+                let result = async {
+                    let connection = connection
+                        .await
+                        .context("failed to connect to the session bus")?;
+                    run(
+                        connection,
+                        sandboxed,
+                        background,
+                        command_receiver,
+                        dispatch,
+                    )
+                    .await
+                };
+                result.await.log_err();
+            }
+        });
+        Self {
+            callback,
+            commands,
+            _task: task,
+        }
+    }
+
+    pub fn set_callback(&self, callback: Box<dyn FnMut(NotificationEvent)>) {
+        self.callback.replace(Some(callback));
+    }
+
+    pub fn show(&self, options: NotificationOptions) -> oneshot::Receiver<Result<NotificationId>> {
+        let (sender, receiver) = oneshot::channel();
+        self.commands
+            .unbounded_send(Command::Show(options, sender))
+            .ok();
+        receiver
+    }
+
+    pub fn close(&self, id: NotificationId) {
+        self.commands.unbounded_send(Command::Close(id)).ok();
+    }
+}
+
+async fn run(
+    connection: Connection,
+    sandboxed: bool,
+    background: BackgroundExecutor,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    mut dispatch: impl FnMut(NotificationEvent),
+) -> Result<()> {
+    let mut backend = if sandboxed {
+        Backend::Portal {
+            proxy: Proxy::new(
+                &connection,
+                "org.freedesktop.portal.Desktop",
+                "/org/freedesktop/portal/desktop",
+                "org.freedesktop.portal.Notification",
+            )
+            .await?,
+            next_id: 1,
+        }
+    } else {
+        Backend::Server {
+            proxy: Proxy::new(
+                &connection,
+                "org.freedesktop.Notifications",
+                "/org/freedesktop/Notifications",
+                "org.freedesktop.Notifications",
+            )
+            .await?,
+        }
+    };
+    let mut events = backend.events().await?.fuse();
+    // The notification server broadcasts signals for every application's notifications.
+    let mut shown = HashSet::new();
+
+    loop {
+        futures::select! {
+            command = commands.next() => match command {
+                Some(Command::Show(options, reply)) => {
+                    let result = backend.show(options, &background).await;
+                    if let Ok(id) = &result {
+                        shown.insert(*id);
+                    }
+                    reply.send(result).ok();
+                }
+                Some(Command::Close(id)) => {
+                    if let Some(event) = backend.close(id).await.log_err().flatten()
+                        && shown.remove(&id)
+                    {
+                        dispatch(event);
+                    }
+                }
+                None => break,
+            },
+            event = events.next() => match event {
+                Some(event) => {
+                    let id = match &event {
+                        NotificationEvent::Closed { id, .. } => shown.take(id),
+                        // The portal doesn't report closing, and activating a notification
+                        // removes it.
+                        NotificationEvent::ActionInvoked { id, .. }
+                        | NotificationEvent::Replied { id, .. }
+                            if matches!(backend, Backend::Portal { .. }) =>
+                        {
+                            shown.take(id)
+                        }
+                        NotificationEvent::ActionInvoked { id, .. }
+                        | NotificationEvent::Replied { id, .. } => shown.get(id).copied(),
+                    };
+                    if id.is_some() {
+                        dispatch(event);
+                    }
+                }
+                None => break,
+            },
+        }
+    }
     Ok(())
 }
+
+enum Backend {
+    Server { proxy: Proxy<'static> },
+    Portal { proxy: Proxy<'static>, next_id: u32 },
+}
+
+impl Backend {
+    async fn events(&self) -> Result<LocalBoxStream<'static, NotificationEvent>> {
+        fn signal<T: serde::de::DeserializeOwned + zbus::zvariant::Type>(
+            signals: zbus::proxy::SignalStream<'static>,
+            event: impl Fn(T) -> Option<NotificationEvent> + 'static,
+        ) -> LocalBoxStream<'static, NotificationEvent> {
+            signals
+                .filter_map(move |message: Message| {
+                    future::ready(message.body().deserialize().ok().and_then(&event))
+                })
+                .boxed_local()
+        }
+
+        Ok(match self {
+            Backend::Server { proxy } => stream::select_all([
+                signal(
+                    proxy.receive_signal("ActionInvoked").await?,
+                    |(id, action): (u32, String)| {
+                        (action != REPLY_ACTION).then(|| NotificationEvent::ActionInvoked {
+                            id: NotificationId(id),
+                            action: action.into(),
+                        })
+                    },
+                ),
+                signal(
+                    proxy.receive_signal("NotificationReplied").await?,
+                    |(id, text): (u32, String)| {
+                        Some(NotificationEvent::Replied {
+                            id: NotificationId(id),
+                            text,
+                        })
+                    },
+                ),
+                signal(
+                    proxy.receive_signal("NotificationClosed").await?,
+                    |(id, reason): (u32, u32)| {
+                        Some(NotificationEvent::Closed {
+                            id: NotificationId(id),
+                            reason: match reason {
+                                1 => NotificationCloseReason::Expired,
+                                2 => NotificationCloseReason::Dismissed,
+                                3 => NotificationCloseReason::Closed,
+                                _ => NotificationCloseReason::Unknown,
+                            },
+                        })
+                    },
+                ),
+            ])
+            .boxed_local(),
+            Backend::Portal { proxy, .. } => signal(
+                proxy.receive_signal("ActionInvoked").await?,
+                |(id, action, parameter): (String, String, Vec<OwnedValue>)| {
+                    let id = NotificationId(id.strip_prefix(PORTAL_ID_PREFIX)?.parse().ok()?);
+                    Some(if action == REPLY_ACTION {
+                        NotificationEvent::Replied {
+                            id,
+                            text: parameter.first()?.try_clone().ok()?.try_into().ok()?,
+                        }
+                    } else {
+                        NotificationEvent::ActionInvoked {
+                            id,
+                            action: action.into(),
+                        }
+                    })
+                },
+            ),
+        })
+    }
+
+    async fn show(
+        &mut self,
+        mut options: NotificationOptions,
+        background: &BackgroundExecutor,
+    ) -> Result<NotificationId> {
+        match self {
+            Backend::Server { proxy } => {
+                let mut actions = Vec::new();
+                for action in &options.actions {
+                    actions.push(action.id.as_ref());
+                    actions.push(action.label.as_ref());
+                }
+                let mut hints = HashMap::from([(
+                    "urgency",
+                    Value::from(match options.urgency {
+                        NotificationUrgency::Low => 0u8,
+                        NotificationUrgency::Normal => 1,
+                        NotificationUrgency::Critical => 2,
+                    }),
+                )]);
+                if let Some(category) = &options.category {
+                    hints.insert("category", Value::from(category.as_ref()));
+                }
+                if let Some(placeholder) = &options.reply_placeholder {
+                    actions.extend([REPLY_ACTION, REPLY_LABEL]);
+                    hints.insert(
+                        "x-kde-reply-placeholder-text",
+                        Value::from(placeholder.as_ref()),
+                    );
+                }
+                let mut app_icon = String::new();
+                match options.icon.take() {
+                    Some(NotificationIcon::Named(name)) => app_icon = name.to_string(),
+                    Some(NotificationIcon::Path(path)) => {
+                        hints.insert(
+                            "image-path",
+                            Value::from(path.to_string_lossy().into_owned()),
+                        );
+                    }
+                    Some(NotificationIcon::Image(image)) => {
+                        let image = background
+                            .spawn(async move {
+                                image::load_from_memory(&image.bytes)
+                                    .map(|image| image.into_rgba8())
+                            })
+                            .await;
+                        if let Some(image) = image
+                            .context("failed to decode notification image")
+                            .log_err()
+                        {
+                            let (width, height) = image.dimensions();
+                            hints.insert(
+                                "image-data",
+                                Value::from((
+                                    width as i32,
+                                    height as i32,
+                                    width as i32 * 4,
+                                    true,
+                                    8i32,
+                                    4i32,
+                                    image.into_raw(),
+                                )),
+                            );
+                        }
+                    }
+                    None => {}
+                }
+                let expire_timeout = match options.timeout {
+                    None => -1,
+                    Some(timeout) => timeout.as_millis().try_into().unwrap_or(i32::MAX),
+                };
+
+                let id = proxy
+                    .call(
+                        "Notify",
+                        &(
+                            app_name(),
+                            options.replaces.map_or(0, |id| id.0),
+                            app_icon,
+                            options.title.as_ref(),
+                            options.body.as_ref(),
+                            actions,
+                            hints,
+                            expire_timeout,
+                        ),
+                    )
+                    .await
+                    .context("failed to show notification")?;
+                Ok(NotificationId(id))
+            }
+            Backend::Portal { proxy, next_id } => {
+                let id = options.replaces.unwrap_or_else(|| {
+                    let id = NotificationId(*next_id);
+                    *next_id += 1;
+                    id
+                });
+                let mut notification = HashMap::from([
+                    ("title", Value::from(options.title.as_ref())),
+                    ("body", Value::from(options.body.as_ref())),
+                    (
+                        "priority",
+                        Value::from(match options.urgency {
+                            NotificationUrgency::Low => "low",
+                            NotificationUrgency::Normal => "normal",
+                            NotificationUrgency::Critical => "urgent",
+                        }),
+                    ),
+                ]);
+                if let Some(category) = &options.category {
+                    notification.insert("category", Value::from(category.as_ref()));
+                }
+                let icon = match options.icon.take() {
+                    Some(NotificationIcon::Named(name)) => {
+                        Some(("themed", Value::from(vec![name.to_string()])))
+                    }
+                    Some(NotificationIcon::Path(path)) => background
+                        .spawn(async move {
+                            std::fs::read(&path).with_context(|| format!("failed to read {path:?}"))
+                        })
+                        .await
+                        .log_err()
+                        .map(|bytes| ("bytes", Value::from(bytes))),
+                    Some(NotificationIcon::Image(image)) => {
+                        Some(("bytes", Value::from(image.bytes.clone())))
+                    }
+                    None => None,
+                };
+                if let Some(icon) = icon {
+                    notification.insert("icon", Value::from(icon));
+                }
+                let mut buttons = options
+                    .actions
+                    .iter()
+                    .map(|action| {
+                        HashMap::from([
+                            ("label", Value::from(action.label.as_ref())),
+                            ("action", Value::from(action.id.as_ref())),
+                        ])
+                    })
+                    .collect::<Vec<_>>();
+                if options.reply_placeholder.is_some() {
+                    buttons.push(HashMap::from([
+                        ("label", Value::from(REPLY_LABEL)),
+                        ("action", Value::from(REPLY_ACTION)),
+                        ("purpose", Value::from("im.reply-with-text")),
+                    ]));
+                }
+                if !buttons.is_empty() {
+                    notification.insert("buttons", Value::from(buttons));
+                }
+
+                proxy
+                    .call::<_, _, ()>(
+                        "AddNotification",
+                        &(format!("{PORTAL_ID_PREFIX}{}", id.0), notification),
+                    )
+                    .await
+                    .context("failed to show notification")?;
+                Ok(id)
+            }
+        }
+    }
+
+    /// Close a notification, returning the event to report if the backend doesn't signal it.
+    async fn close(&mut self, id: NotificationId) -> Result<Option<NotificationEvent>> {
+        match self {
+            Backend::Server { proxy } => {
+                proxy
+                    .call::<_, _, ()>("CloseNotification", &(id.0,))
+                    .await?;
+                Ok(None)
+            }
+            Backend::Portal { proxy, .. } => {
+                proxy
+                    .call::<_, _, ()>(
+                        "RemoveNotification",
+                        &(format!("{PORTAL_ID_PREFIX}{}", id.0),),
+                    )
+                    .await?;
+                Ok(Some(NotificationEvent::Closed {
+                    id,
+                    reason: NotificationCloseReason::Closed,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use futures::FutureExt as _;
+    use rand::{SeedableRng as _, rngs::StdRng};
+    use zbus::object_server::SignalEmitter;
+
+    use super::*;
+    use crate::{
+        TestDispatcher,
+        platform::linux::test_bus::{TestBus, wait_until},
+    };
+
+    const SERVER_PATH: &str = "/org/freedesktop/Notifications";
+    const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    struct FakeServer(Calls);
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl FakeServer {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            _app_name: &str,
+            replaces_id: u32,
+            app_icon: &str,
+            summary: &str,
+            _body: &str,
+            actions: Vec<String>,
+            hints: HashMap<String, OwnedValue>,
+            expire_timeout: i32,
+        ) -> u32 {
+            let urgency = u8::try_from(&hints["urgency"]).unwrap();
+            let placeholder = hints
+                .get("x-kde-reply-placeholder-text")
+                .map(|value| String::try_from(value.try_clone().unwrap()).unwrap());
+            self.0.lock().unwrap().push(format!(
+                "Notify {summary} {app_icon} {actions:?} {urgency} {placeholder:?} {expire_timeout}"
+            ));
+            if replaces_id == 0 { 7 } else { replaces_id }
+        }
+
+        async fn close_notification(
+            &self,
+            id: u32,
+            #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        ) -> zbus::fdo::Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("CloseNotification {id}"));
+            Self::notification_closed(&emitter, id, 3).await?;
+            Ok(())
+        }
+
+        #[zbus(signal)]
+        async fn action_invoked(
+            emitter: &SignalEmitter<'_>,
+            id: u32,
+            action: &str,
+        ) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        async fn notification_replied(
+            emitter: &SignalEmitter<'_>,
+            id: u32,
+            text: &str,
+        ) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        async fn notification_closed(
+            emitter: &SignalEmitter<'_>,
+            id: u32,
+            reason: u32,
+        ) -> zbus::Result<()>;
+    }
+
+    struct FakePortal(Calls);
+
+    #[zbus::interface(name = "org.freedesktop.portal.Notification")]
+    impl FakePortal {
+        fn add_notification(&self, id: &str, notification: HashMap<String, OwnedValue>) {
+            let title = String::try_from(notification["title"].try_clone().unwrap()).unwrap();
+            let priority = String::try_from(notification["priority"].try_clone().unwrap()).unwrap();
+            let buttons = notification
+                .get("buttons")
+                .map(|buttons| {
+                    Vec::<HashMap<String, OwnedValue>>::try_from(buttons.try_clone().unwrap())
+                        .unwrap()
+                        .len()
+                })
+                .unwrap_or_default();
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("AddNotification {id} {title} {priority} {buttons}"));
+        }
+
+        fn remove_notification(&self, id: &str) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("RemoveNotification {id}"));
+        }
+
+        #[zbus(signal)]
+        async fn action_invoked(
+            emitter: &SignalEmitter<'_>,
+            id: &str,
+            action: &str,
+            parameter: Vec<Value<'_>>,
+        ) -> zbus::Result<()>;
+    }
+
+    /// An executor that never runs its tasks, for tests whose notifications have no icons to
+    /// load.
+    fn background() -> BackgroundExecutor {
+        BackgroundExecutor::new(Arc::new(TestDispatcher::new(StdRng::seed_from_u64(0))))
+    }
+
+    fn show(
+        commands: &mpsc::UnboundedSender<Command>,
+        options: NotificationOptions,
+    ) -> oneshot::Receiver<Result<NotificationId>> {
+        let (sender, receiver) = oneshot::channel();
+        commands
+            .unbounded_send(Command::Show(options, sender))
+            .unwrap();
+        receiver
+    }
+
+    #[test]
+    fn test_notification_server() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let calls = Calls::default();
+            let service = bus
+                .builder()
+                .name("org.freedesktop.Notifications")
+                .unwrap()
+                .serve_at(SERVER_PATH, FakeServer(calls.clone()))
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            let server = service
+                .object_server()
+                .interface::<_, FakeServer>(SERVER_PATH)
+                .await
+                .unwrap();
+            let emitter = server.signal_emitter();
+            let events = RefCell::new(Vec::new());
+            let (commands, command_receiver) = mpsc::unbounded();
+
+            let script = async {
+                let options = NotificationOptions::new("Message")
+                    .body("Hello")
+                    .icon(NotificationIcon::Named("mail-unread".into()))
+                    .urgency(NotificationUrgency::Critical)
+                    .timeout(Duration::ZERO)
+                    .action("open", "Open")
+                    .reply("Reply to Alice");
+                let id = show(&commands, options).await.unwrap().unwrap();
+                assert_eq!(id, NotificationId(7));
+
+                FakeServer::action_invoked(emitter, 7, "open")
+                    .await
+                    .unwrap();
+                wait_until(|| events.borrow().len() == 1).await;
+                // Other applications' notifications are ignored.
+                FakeServer::action_invoked(emitter, 99, "open")
+                    .await
+                    .unwrap();
+                FakeServer::notification_replied(emitter, 7, "Hi")
+                    .await
+                    .unwrap();
+                wait_until(|| events.borrow().len() == 2).await;
+
+                let options = NotificationOptions::new("Progress")
+                    .timeout(Duration::from_secs(5))
+                    .replaces(id);
+                let replaced = show(&commands, options).await.unwrap().unwrap();
+                assert_eq!(replaced, id);
+                commands.unbounded_send(Command::Close(id)).unwrap();
+                wait_until(|| events.borrow().len() == 3).await;
+
+                // Events for closed notifications are ignored.
+                FakeServer::action_invoked(emitter, 7, "open")
+                    .await
+                    .unwrap();
+                FakeServer::notification_closed(emitter, 7, 2)
+                    .await
+                    .unwrap();
+                smol::Timer::after(Duration::from_millis(50)).await;
+            };
+
+            futures::select! {
+                result = run(bus.connect().await, false, background(), command_receiver, |event| events.borrow_mut().push(event)).fuse() => {
+                    panic!("notifications stopped: {result:?}")
+                }
+                _ = Box::pin(script).fuse() => {}
+            }
+
+            assert_eq!(
+                *events.borrow(),
+                [
+                    NotificationEvent::ActionInvoked {
+                        id: NotificationId(7),
+                        action: "open".into(),
+                    },
+                    NotificationEvent::Replied {
+                        id: NotificationId(7),
+                        text: "Hi".into(),
+                    },
+                    NotificationEvent::Closed {
+                        id: NotificationId(7),
+                        reason: NotificationCloseReason::Closed,
+                    },
+                ]
+            );
+            assert_eq!(
+                *calls.lock().unwrap(),
+                [
+                    r#"Notify Message mail-unread ["open", "Open", "inline-reply", "Reply"] 2 Some("Reply to Alice") 0"#,
+                    "Notify Progress  [] 1 None 5000",
+                    "CloseNotification 7",
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_notification_portal() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let calls = Calls::default();
+            let service = bus
+                .builder()
+                .name("org.freedesktop.portal.Desktop")
+                .unwrap()
+                .serve_at(PORTAL_PATH, FakePortal(calls.clone()))
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            let portal = service
+                .object_server()
+                .interface::<_, FakePortal>(PORTAL_PATH)
+                .await
+                .unwrap();
+            let emitter = portal.signal_emitter();
+            let events = RefCell::new(Vec::new());
+            let (commands, command_receiver) = mpsc::unbounded();
+
+            let script = async {
+                let options = NotificationOptions::new("Message")
+                    .urgency(NotificationUrgency::Low)
+                    .action("open", "Open")
+                    .reply("Reply");
+                let id = show(&commands, options.clone()).await.unwrap().unwrap();
+                assert_eq!(id, NotificationId(1));
+                let replied_id = show(&commands, options.clone()).await.unwrap().unwrap();
+                assert_eq!(replied_id, NotificationId(2));
+
+                // Activating a notification removes it, so later events for it are ignored.
+                FakePortal::action_invoked(emitter, "gpui-1", "open", Vec::new())
+                    .await
+                    .unwrap();
+                FakePortal::action_invoked(emitter, "gpui-1", "open", Vec::new())
+                    .await
+                    .unwrap();
+                FakePortal::action_invoked(
+                    emitter,
+                    "gpui-2",
+                    REPLY_ACTION,
+                    vec![Value::from("Hi")],
+                )
+                .await
+                .unwrap();
+                wait_until(|| events.borrow().len() == 2).await;
+
+                commands.unbounded_send(Command::Close(id)).unwrap();
+                let closed_id = show(&commands, options).await.unwrap().unwrap();
+                commands.unbounded_send(Command::Close(closed_id)).unwrap();
+                wait_until(|| events.borrow().len() == 3).await;
+            };
+
+            futures::select! {
+                result = run(bus.connect().await, true, background(), command_receiver, |event| events.borrow_mut().push(event)).fuse() => {
+                    panic!("notifications stopped: {result:?}")
+                }
+                _ = Box::pin(script).fuse() => {}
+            }
+
+            assert_eq!(
+                *events.borrow(),
+                [
+                    NotificationEvent::ActionInvoked {
+                        id: NotificationId(1),
+                        action: "open".into(),
+                    },
+                    NotificationEvent::Replied {
+                        id: NotificationId(2),
+                        text: "Hi".into(),
+                    },
+                    NotificationEvent::Closed {
+                        id: NotificationId(3),
+                        reason: NotificationCloseReason::Closed,
+                    },
+                ]
+            );
+            assert_eq!(
+                *calls.lock().unwrap(),
+                [
+                    "AddNotification gpui-1 Message low 2",
+                    "AddNotification gpui-2 Message low 2",
+                    "RemoveNotification gpui-1",
+                    "AddNotification gpui-3 Message low 2",
+                    "RemoveNotification gpui-3",
+                ]
+            );
+        });
+    }
+}
@@ -1,5 +1,4 @@
 use std::{
-    collections::HashMap,
     env,
     path::{Path, PathBuf},
     process::Command,
@@ -24,13 +23,13 @@ use util::ResultExt as _;
 use xkbcommon::xkb::{self, Keycode, Keysym, State};
 
 use crate::{
-    Action, AnyWindowHandle, AttentionType, BackgroundExecutor, BiometricStatus, ClipboardItem,
-    CursorStyle, DialogOptions, DisplayId, FocusedWindowInfo, ForegroundExecutor, Keymap, Keystroke,
-    LinuxDispatcher, MediaKeyEvent, Menu, MenuItem, NetworkStatus, OsInfo, OwnedMenu,
-    PathPromptOptions, Pixels, Platform, PlatformDisplay, PlatformKeyboardLayout,
-    PlatformKeyboardMapper, PlatformTextSystem, PlatformWindow, Point, PowerSaveBlockerKind,
-    Result, SharedString, SystemPowerEvent, Task, TrayIconEvent, TrayMenuItem, WindowAppearance,
-    WindowParams, px,
+    Action, AnyWindowHandle, AttentionType, BackgroundExecutor, BiometricStatus, Bounds,
+    ClipboardItem, CursorStyle, DisplayId, FocusedWindowInfo, ForegroundExecutor, Keymap,
+    Keystroke, LinuxDispatcher, MediaKeyEvent, Menu, MenuItem, NetworkStatus, NotificationEvent,
+    NotificationId, NotificationOptions, NowPlaying, OsInfo, OwnedMenu, PathPromptOptions, Pixels,
+    Platform, PlatformDisplay, PlatformKeyboardLayout, PlatformKeyboardMapper, PlatformTextSystem,
+    PlatformWindow, Point, PowerSaveBlockerKind, Result, SharedString, SystemPowerEvent, Task,
+    TrayIconEvent, TrayIconFrame, TrayMenuItem, WindowAppearance, WindowParams, px,
 };
 
 #[cfg(any(feature = "wayland", feature = "x11"))]
@@ -81,10 +80,16 @@ pub trait LinuxClient {
     fn focused_window_info(&self) -> Option<FocusedWindowInfo> {
         None
     }
+    fn watch_focused_window(&self, _watch: bool) {}
 
-    fn set_tray_icon(&self, _icon: Option<&[u8]>) {}
+    fn set_tray_icon(&self, _icon: Option<TrayIconFrame>) {}
+    fn set_tray_attention(&self, _icon: Option<TrayIconFrame>) {}
+    fn set_tray_overlay(&self, _icon: Option<TrayIconFrame>) {}
     fn set_tray_menu(&self, _menu: Vec<TrayMenuItem>) {}
     fn set_tray_tooltip(&self, _tooltip: &str) {}
+    fn tray_icon_bounds(&self) -> Option<Bounds<Pixels>> {
+        None
+    }
     fn register_global_hotkey(&self, _id: u32, _keystroke: &Keystroke) -> Result<()> {
         Err(anyhow::anyhow!(
             "Global hotkeys not supported on this platform"
@@ -117,18 +122,12 @@ pub(crate) struct PlatformHandlers {
     pub(crate) will_open_app_menu: Option<Box<dyn FnMut()>>,
     pub(crate) validate_app_menu_command: Option<Box<dyn FnMut(&dyn Action) -> bool>>,
     pub(crate) keyboard_layout_change: Option<Box<dyn FnMut()>>,
+    pub(crate) focused_window_change: Option<Box<dyn FnMut(Option<FocusedWindowInfo>)>>,
+    pub(crate) displays_changed: Option<Box<dyn FnMut()>>,
     pub(crate) tray_icon_event: Option<Box<dyn FnMut(TrayIconEvent)>>,
     pub(crate) tray_menu_action: Option<Box<dyn FnMut(SharedString)>>,
     pub(crate) global_hotkey: Option<Box<dyn FnMut(u32)>>,
-    pub(crate) system_power: Option<Box<dyn FnMut(SystemPowerEvent)>>,
-    pub(crate) network_status_change: Option<Box<dyn FnMut(NetworkStatus)>>,
-    pub(crate) media_key: Option<Box<dyn FnMut(MediaKeyEvent)>>,
-    pub(crate) context_menu: Option<Box<dyn FnMut(SharedString)>>,
-}
-
-pub(crate) enum PowerSaveHandle {
-    ScreenSaverCookie(u32),
-    ChildProcess(std::process::Child),
+    pub(crate) media_key: super::mpris::MediaKeyCallback,
 }
 
 pub(crate) struct LinuxCommon {
@@ -141,10 +140,12 @@ pub(crate) struct LinuxCommon {
     pub(crate) signal: LoopSignal,
     pub(crate) menus: Vec<OwnedMenu>,
     pub(crate) keep_alive_without_windows: bool,
-    pub(crate) power_save_blockers: HashMap<u32, PowerSaveHandle>,
-    pub(crate) next_blocker_id: u32,
-    pub(crate) last_network_status: NetworkStatus,
+    pub(crate) power_save_blockers: Option<super::power::PowerSaveBlockers>,
+    pub(crate) network_monitor: Option<super::network::NetworkMonitor>,
+    pub(crate) mpris: Option<super::mpris::Mpris>,
+    pub(crate) notifications: Option<super::notifications::Notifications>,
     pub(crate) attention_window: Option<AnyWindowHandle>,
+    pub(crate) logind: Option<super::logind::Logind>,
 }
 
 impl LinuxCommon {
@@ -154,7 +155,7 @@ impl LinuxCommon {
         #[cfg(any(feature = "wayland", feature = "x11"))]
         let text_system = Arc::new(crate::CosmicTextSystem::new());
         #[cfg(not(any(feature = "wayland", feature = "x11")))]
-        let text_system = Arc::new(crate::NoopTextSystem::new());
+        let text_system = Arc::new(crate::NoopTextSystem);
 
         let callbacks = PlatformHandlers::default();
 
@@ -172,21 +173,40 @@ impl LinuxCommon {
             signal,
             menus: Vec::new(),
             keep_alive_without_windows: false,
-            power_save_blockers: HashMap::new(),
-            next_blocker_id: 0,
-            last_network_status: NetworkStatus::Online,
+            power_save_blockers: None,
+            network_monitor: None,
+            mpris: None,
+            notifications: None,
             attention_window: None,
+            logind: None,
         };
 
         (common, main_receiver)
     }
-}
 
-impl Drop for LinuxCommon {
-    fn drop(&mut self) {
-        for (_, handle) in self.power_save_blockers.drain() {
-            crate::platform::linux::power::release_blocker(handle);
-        }
+    fn mpris(&mut self) -> &super::mpris::Mpris {
+        self.mpris.get_or_insert_with(|| {
+            super::mpris::Mpris::new(&self.foreground_executor, self.callbacks.media_key.clone())
+        })
+    }
+
+    fn notifications(&mut self) -> &super::notifications::Notifications {
+        self.notifications.get_or_insert_with(|| {
+            super::notifications::Notifications::new(
+                &self.foreground_executor,
+                self.background_executor.clone(),
+            )
+        })
+    }
+
+    fn power_save_blockers(&mut self) -> &super::power::PowerSaveBlockers {
+        self.power_save_blockers
+            .get_or_insert_with(|| super::power::PowerSaveBlockers::new(&self.foreground_executor))
+    }
+
+    fn logind(&mut self) -> &super::logind::Logind {
+        self.logind
+            .get_or_insert_with(|| super::logind::Logind::new(&self.foreground_executor))
     }
 }
 
@@ -651,10 +671,18 @@ impl<P: LinuxClient + 'static> Platform for P {
         self.with_common(|common| common.keep_alive_without_windows = keep_alive);
     }
 
-    fn set_tray_icon(&self, icon: Option<&[u8]>) {
+    fn set_tray_icon(&self, icon: Option<TrayIconFrame>) {
         LinuxClient::set_tray_icon(self, icon);
     }
 
+    fn set_tray_attention(&self, icon: Option<TrayIconFrame>) {
+        LinuxClient::set_tray_attention(self, icon);
+    }
+
+    fn set_tray_overlay(&self, icon: Option<TrayIconFrame>) {
+        LinuxClient::set_tray_overlay(self, icon);
+    }
+
     fn set_tray_menu(&self, menu: Vec<TrayMenuItem>) {
         LinuxClient::set_tray_menu(self, menu);
     }
@@ -663,6 +691,10 @@ impl<P: LinuxClient + 'static> Platform for P {
         LinuxClient::set_tray_tooltip(self, tooltip);
     }
 
+    fn get_tray_icon_bounds(&self) -> Option<Bounds<Pixels>> {
+        LinuxClient::tray_icon_bounds(self)
+    }
+
     fn on_tray_icon_event(&self, callback: Box<dyn FnMut(TrayIconEvent)>) {
         self.with_common(|common| common.callbacks.tray_icon_event = Some(callback));
     }
@@ -687,6 +719,18 @@ impl<P: LinuxClient + 'static> Platform for P {
         LinuxClient::focused_window_info(self)
     }
 
+    fn on_focused_window_change(&self, callback: Box<dyn FnMut(Option<FocusedWindowInfo>)>) {
+        self.with_common(|common| common.callbacks.focused_window_change = Some(callback));
+    }
+
+    fn watch_focused_window(&self, watch: bool) {
+        LinuxClient::watch_focused_window(self, watch);
+    }
+
+    fn on_displays_changed(&self, callback: Box<dyn FnMut()>) {
+        self.with_common(|common| common.callbacks.displays_changed = Some(callback));
+    }
+
     fn set_auto_launch(&self, app_id: &str, enabled: bool) -> Result<()> {
         crate::platform::linux::auto_launch::set_auto_launch(app_id, enabled)
     }
@@ -696,7 +740,38 @@ impl<P: LinuxClient + 'static> Platform for P {
     }
 
     fn show_notification(&self, title: &str, body: &str) -> Result<()> {
-        crate::platform::linux::notifications::show_notification(title, body)
+        // Waiting for the notification server would block the main thread, so failures are
+        // logged instead of returned.
+        let notification = self.show_notification_with_options(
+            NotificationOptions::new(title.to_string()).body(body.to_string()),
+        );
+        self.foreground_executor()
+            .spawn(async move {
+                notification.await.log_err();
+            })
+            .detach();
+        Ok(())
+    }
+
+    fn show_notification_with_options(
+        &self,
+        options: NotificationOptions,
+    ) -> Task<Result<NotificationId>> {
+        let (executor, notification) = self.with_common(|common| {
+            (
+                common.foreground_executor.clone(),
+                common.notifications().show(options),
+            )
+        });
+        executor.spawn(async move { notification.await.context("notification service stopped")? })
+    }
+
+    fn close_notification(&self, id: NotificationId) {
+        self.with_common(|common| common.notifications().close(id));
+    }
+
+    fn on_notification_event(&self, callback: Box<dyn FnMut(NotificationEvent)>) {
+        self.with_common(|common| common.notifications().set_callback(callback));
     }
 
     fn os_info(&self) -> OsInfo {
@@ -704,43 +779,42 @@ impl<P: LinuxClient + 'static> Platform for P {
     }
 
     fn network_status(&self) -> NetworkStatus {
-        network_status_from_sysfs()
+        self.with_common(|common| {
+            common
+                .network_monitor
+                .as_ref()
+                .and_then(|monitor| monitor.status())
+        })
+        .unwrap_or_else(super::network::network_status)
     }
 
     fn on_network_status_change(&self, callback: Box<dyn FnMut(NetworkStatus)>) {
-        self.with_common(|common| common.callbacks.network_status_change = Some(callback));
-        log::warn!("Network change monitoring requires D-Bus integration — not yet implemented on Linux");
+        self.with_common(|common| {
+            common
+                .network_monitor
+                .get_or_insert_with(|| {
+                    super::network::NetworkMonitor::new(&common.foreground_executor)
+                })
+                .set_callback(callback)
+        });
     }
 
-    fn start_power_save_blocker(&self, kind: PowerSaveBlockerKind) -> Option<u32> {
-        self.with_common(|common| {
-            let handle = match kind {
-                PowerSaveBlockerKind::PreventDisplaySleep => {
-                    crate::platform::linux::power::inhibit_screensaver(
-                        "gpui",
-                        "Power save blocker",
-                    )?
-                }
-                PowerSaveBlockerKind::PreventAppSuspension => {
-                    crate::platform::linux::power::inhibit_suspend(
-                        "gpui",
-                        "Power save blocker",
-                    )?
-                }
-            };
-            let id = common.next_blocker_id;
-            common.next_blocker_id += 1;
-            common.power_save_blockers.insert(id, handle);
-            Some(id)
+    fn start_power_save_blocker(&self, kind: PowerSaveBlockerKind) -> Task<Result<u32>> {
+        let (executor, blocker) = self.with_common(|common| {
+            (
+                common.foreground_executor.clone(),
+                common.power_save_blockers().start(kind),
+            )
+        });
+        executor.spawn(async move {
+            blocker
+                .await
+                .context("power save blocker service stopped")?
         })
     }
 
     fn stop_power_save_blocker(&self, id: u32) {
-        self.with_common(|common| {
-            if let Some(handle) = common.power_save_blockers.remove(&id) {
-                crate::platform::linux::power::release_blocker(handle);
-            }
-        });
+        self.with_common(|common| common.power_save_blockers().stop(id));
     }
 
     fn system_idle_time(&self) -> Option<Duration> {
@@ -748,12 +822,22 @@ impl<P: LinuxClient + 'static> Platform for P {
     }
 
     fn on_system_power_event(&self, callback: Box<dyn FnMut(SystemPowerEvent)>) {
-        self.with_common(|common| common.callbacks.system_power = Some(callback));
-        log::warn!("System power events require D-Bus logind integration — not yet implemented on Linux");
+        self.with_common(|common| common.logind().set_callback(callback));
+    }
+
+    fn delay_system_sleep(&self, reason: Option<&str>) {
+        self.with_common(|common| common.logind().set_sleep_delay(reason));
     }
 
     fn on_media_key_event(&self, callback: Box<dyn FnMut(MediaKeyEvent)>) {
-        self.with_common(|common| common.callbacks.media_key = Some(callback));
+        self.with_common(|common| {
+            common.callbacks.media_key.replace(Some(callback));
+            common.mpris();
+        });
+    }
+
+    fn set_now_playing(&self, now_playing: Option<NowPlaying>) {
+        self.with_common(|common| common.mpris().set_now_playing(now_playing));
     }
 
     fn request_user_attention(&self, level: AttentionType) {
@@ -767,50 +851,29 @@ impl<P: LinuxClient + 'static> Platform for P {
         LinuxClient::cancel_user_attention(self, handle);
     }
 
-    fn show_context_menu(
-        &self,
-        _position: Point<Pixels>,
-        _items: Vec<TrayMenuItem>,
-        _callback: Box<dyn FnMut(SharedString)>,
-    ) {
-        log::warn!("Context menus not yet implemented on Linux");
-    }
-
-    fn show_dialog(&self, options: DialogOptions) -> oneshot::Receiver<usize> {
-        let (tx, rx) = oneshot::channel();
-        self.background_executor()
-            .spawn(async move {
-                let result = crate::platform::linux::dialog::show_dialog(&options);
-                let _ = tx.send(result);
-            })
-            .detach();
-        rx
+    // Dialogs are rendered by GPUI, see `window/dialog.rs`.
+    fn has_native_dialogs(&self) -> bool {
+        false
     }
 
-    fn biometric_status(&self) -> BiometricStatus {
-        BiometricStatus::Unavailable
+    fn biometric_status(&self) -> Task<BiometricStatus> {
+        self.foreground_executor()
+            .spawn(super::fprintd::system_biometric_status())
     }
 
+    // fprintd has no UI, so the application shows the reason while the reader waits for a finger.
     fn authenticate_biometric(&self, _reason: &str, callback: Box<dyn FnOnce(bool) + Send>) {
-        callback(false);
+        super::fprintd::authenticate(&self.foreground_executor(), callback);
     }
-}
 
-fn network_status_from_sysfs() -> NetworkStatus {
-    if let Ok(entries) = std::fs::read_dir("/sys/class/net") {
-        for entry in entries.flatten() {
-            let name = entry.file_name();
-            if name == "lo" {
-                continue;
-            }
-            if let Ok(state) = std::fs::read_to_string(entry.path().join("operstate")) {
-                if state.trim() == "up" {
-                    return NetworkStatus::Online;
-                }
-            }
-        }
+    fn authenticate_admin(
+        &self,
+        action_id: &str,
+        _reason: &str,
+        callback: Box<dyn FnOnce(bool) + Send>,
+    ) {
+        super::polkit::authenticate(&self.foreground_executor(), action_id.to_string(), callback);
     }
-    NetworkStatus::Offline
 }
 
 #[cfg(any(feature = "wayland", feature = "x11"))]
@@ -891,6 +954,23 @@ pub(super) fn reveal_path_internal(
         .detach();
 }
 
+/// The name the app identifies itself with to desktop services: the name of the executable, as
+/// a valid D-Bus name element.
+pub(super) fn app_name() -> String {
+    let name: String = std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("gpui{name}")
+    } else {
+        name
+    }
+}
+
 #[allow(unused)]
 pub(super) fn is_within_click_distance(a: Point<Pixels>, b: Point<Pixels>) -> bool {
     let diff = a - b;
@@ -0,0 +1,168 @@
+//! Authorization of privileged actions through [polkit].
+//!
+//! `CheckAuthorization` is called for the application's bus name with user interaction allowed,
+//! so the session's authentication agent asks for a password when the action's policy requires
+//! one. Actions are declared by the application in a `.policy` file installed to
+//! `/usr/share/polkit-1/actions`, whose `<message>` is what the agent shows. polkit only accepts
+//! details, such as a message of the caller's own, from root or the action's
+//! `org.freedesktop.policykit.owner`, so none are passed.
+//!
+//! [polkit]: https://www.freedesktop.org/software/polkit/docs/latest/eggdbus-interface-org.freedesktop.PolicyKit1.Authority.html
+
+use std::collections::HashMap;
+
+use anyhow::{Context as _, Result};
+use util::ResultExt as _;
+use zbus::{Connection, Proxy, zvariant::Value};
+
+use crate::ForegroundExecutor;
+
+const DESTINATION: &str = "org.freedesktop.PolicyKit1";
+const AUTHORITY_PATH: &str = "/org/freedesktop/PolicyKit1/Authority";
+const AUTHORITY_INTERFACE: &str = "org.freedesktop.PolicyKit1.Authority";
+
+/// `AllowUserInteraction` in `CheckAuthorizationFlags`.
+const ALLOW_USER_INTERACTION: u32 = 0x1;
+
+/// Check whether the application is authorized for `action_id` on the system bus.
+pub(crate) fn authenticate(
+    executor: &ForegroundExecutor,
+    action_id: String,
+    callback: Box<dyn FnOnce(bool) + Send>,
+) {
+    executor
+        .spawn(async move {
+            let result = async {
+                let connection = Connection::system().await?;
+                check_authorization(&connection, &action_id).await
+            };
+            let authorized = result
+                .await
+                .with_context(|| format!("failed to check authorization for {action_id}"))
+                .log_err();
+            callback(authorized.unwrap_or(false));
+        })
+        .detach();
+}
+
+async fn check_authorization(connection: &Connection, action_id: &str) -> Result<bool> {
+    let authority =
+        Proxy::new(connection, DESTINATION, AUTHORITY_PATH, AUTHORITY_INTERFACE).await?;
+    let name = connection
+        .unique_name()
+        .context("the connection has no bus name")?;
+    let subject = (
+        "system-bus-name",
+        HashMap::from([("name", Value::from(name.as_str()))]),
+    );
+    let details = HashMap::<&str, &str>::new();
+    let (is_authorized, _is_challenge, _details) = authority
+        .call::<_, _, (bool, bool, HashMap<String, String>)>(
+            "CheckAuthorization",
+            &(subject, action_id, details, ALLOW_USER_INTERACTION, ""),
+        )
+        .await?;
+    Ok(is_authorized)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use zbus::{message::Header, zvariant::OwnedValue};
+
+    use super::*;
+    use crate::platform::linux::test_bus::TestBus;
+
+    struct FakeAuthority {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[derive(Debug, zbus::DBusError)]
+    #[zbus(prefix = "org.freedesktop.PolicyKit1.Error")]
+    enum PolkitError {
+        #[zbus(error)]
+        ZBus(zbus::Error),
+        NotAuthorized(String),
+    }
+
+    #[zbus::interface(name = "org.freedesktop.PolicyKit1.Authority")]
+    impl FakeAuthority {
+        fn check_authorization(
+            &self,
+            subject: (String, HashMap<String, OwnedValue>),
+            action_id: &str,
+            details: HashMap<String, String>,
+            flags: u32,
+            _cancellation_id: &str,
+            #[zbus(header)] header: Header<'_>,
+        ) -> Result<(bool, bool, HashMap<String, String>), PolkitError> {
+            let (kind, subject) = subject;
+            let name = String::try_from(subject["name"].try_clone().unwrap()).unwrap();
+            // The subject is the caller itself.
+            assert_eq!(kind, "system-bus-name");
+            assert_eq!(header.sender().unwrap().as_str(), name);
+            // Like polkit for callers that are neither root nor the owner of the action.
+            if !details.is_empty() {
+                return Err(PolkitError::NotAuthorized(
+                    "Only trusted callers (e.g. uid 0 or an action owner) can use \
+                     CheckAuthorization() and pass details"
+                        .into(),
+                ));
+            }
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{action_id} {flags}"));
+            Ok((
+                action_id == "com.example.settings.write",
+                false,
+                HashMap::new(),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_check_authorization() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        smol::block_on(async {
+            let calls = Arc::new(Mutex::new(Vec::new()));
+            let _service = bus
+                .builder()
+                .name(DESTINATION)
+                .unwrap()
+                .serve_at(
+                    AUTHORITY_PATH,
+                    FakeAuthority {
+                        calls: calls.clone(),
+                    },
+                )
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            let client = bus.connect().await;
+
+            assert!(
+                check_authorization(&client, "com.example.settings.write")
+                    .await
+                    .unwrap()
+            );
+            assert!(
+                !check_authorization(&client, "com.example.settings.reset")
+                    .await
+                    .unwrap()
+            );
+            assert_eq!(
+                *calls.lock().unwrap(),
+                [
+                    "com.example.settings.write 1",
+                    "com.example.settings.reset 1",
+                ]
+            );
+        });
+    }
+}
@@ -1,72 +1,396 @@
-use super::platform::PowerSaveHandle;
-
-pub fn inhibit_screensaver(app_name: &str, reason: &str) -> Option<PowerSaveHandle> {
-    let output = std::process::Command::new("dbus-send")
-        .args([
-            "--session",
-            "--dest=org.freedesktop.ScreenSaver",
-            "--type=method_call",
-            "--print-reply",
-            "/org/freedesktop/ScreenSaver",
-            "org.freedesktop.ScreenSaver.Inhibit",
-            &format!("string:{}", app_name),
-            &format!("string:{}", reason),
-        ])
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
+//! Power save blockers over D-Bus.
+//!
+//! Display sleep is blocked with `org.freedesktop.ScreenSaver`, and system sleep with a logind
+//! block inhibitor. Inside a Flatpak or Snap sandbox, where those services usually aren't
+//! reachable, both go through the xdg-desktop-portal `Inhibit` interface instead.
+//!
+//! The screensaver and portal release a blocker when the connection that took it closes, so
+//! each blocker keeps its connection alive until it is released.
+
+use std::{collections::HashMap, env, path::Path};
+
+use anyhow::{Context as _, Result};
+use futures::{
+    StreamExt as _,
+    channel::{mpsc, oneshot},
+};
+use util::ResultExt as _;
+use zbus::{
+    Connection,
+    zvariant::{OwnedFd, OwnedObjectPath, Value},
+};
+
+use super::app_name;
+use crate::{ForegroundExecutor, PowerSaveBlockerKind, Task};
+
+const REASON: &str = "Power save blocker";
+
+/// Flags for `org.freedesktop.portal.Inhibit.Inhibit`.
+const PORTAL_INHIBIT_SUSPEND: u32 = 4;
+const PORTAL_INHIBIT_IDLE: u32 = 8;
+
+enum Command {
+    Start(PowerSaveBlockerKind, oneshot::Sender<Result<u32>>),
+    Stop(u32),
+}
+
+/// Takes and releases power save blockers without blocking the main thread. Commands are handled
+/// in the order they are sent, and dropping this releases every blocker along with its connection.
+pub(crate) struct PowerSaveBlockers {
+    commands: mpsc::UnboundedSender<Command>,
+    _task: Task<()>,
+}
+
+impl PowerSaveBlockers {
+    pub fn new(executor: &ForegroundExecutor) -> Self {
+        let mut backend = Backend::default();
+        let (commands, mut command_receiver) = mpsc::unbounded();
+        let task = executor.spawn(async move {
+            while let Some(command) = command_receiver.next().await {
+                match command {
+                    Command::Start(kind, sender) => {
+                        sender.send(backend.start(kind).await).ok();
+                    }
+                    Command::Stop(id) => backend.stop(id).await,
+                }
+            }
+        });
+        Self {
+            commands,
+            _task: task,
+        }
+    }
+
+    pub fn start(&self, kind: PowerSaveBlockerKind) -> oneshot::Receiver<Result<u32>> {
+        let (sender, receiver) = oneshot::channel();
+        self.commands
+            .unbounded_send(Command::Start(kind, sender))
+            .ok();
+        receiver
+    }
+
+    pub fn stop(&self, id: u32) {
+        self.commands.unbounded_send(Command::Stop(id)).ok();
     }
+}
 
-    parse_dbus_uint32(&output.stdout).map(PowerSaveHandle::ScreenSaverCookie)
+enum PowerSaveBlocker {
+    ScreenSaver {
+        connection: Connection,
+        cookie: u32,
+    },
+    Logind {
+        _inhibitor: OwnedFd,
+    },
+    Portal {
+        connection: Connection,
+        request: OwnedObjectPath,
+    },
 }
 
-pub fn uninhibit_screensaver(cookie: u32) {
-    let _ = std::process::Command::new("dbus-send")
-        .args([
-            "--session",
-            "--dest=org.freedesktop.ScreenSaver",
-            "--type=method_call",
-            "/org/freedesktop/ScreenSaver",
-            "org.freedesktop.ScreenSaver.UnInhibit",
-            &format!("uint32:{}", cookie),
-        ])
-        .output();
+impl PowerSaveBlocker {
+    async fn release(self) -> Result<()> {
+        match self {
+            PowerSaveBlocker::ScreenSaver { connection, cookie } => {
+                connection
+                    .call_method(
+                        Some("org.freedesktop.ScreenSaver"),
+                        "/org/freedesktop/ScreenSaver",
+                        Some("org.freedesktop.ScreenSaver"),
+                        "UnInhibit",
+                        &(cookie,),
+                    )
+                    .await?;
+            }
+            // Logind releases the inhibitor when the last copy of its file descriptor is closed.
+            PowerSaveBlocker::Logind { .. } => {}
+            PowerSaveBlocker::Portal {
+                connection,
+                request,
+            } => {
+                connection
+                    .call_method(
+                        Some("org.freedesktop.portal.Desktop"),
+                        &request,
+                        Some("org.freedesktop.portal.Request"),
+                        "Close",
+                        &(),
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
 }
 
-pub fn inhibit_suspend(app_name: &str, reason: &str) -> Option<PowerSaveHandle> {
-    let who_arg = format!("--who={}", app_name);
-    let why_arg = format!("--why={}", reason);
-    #[allow(clippy::disallowed_methods)]
-    let child = std::process::Command::new("systemd-inhibit")
-        .args(["--what=sleep", &who_arg, &why_arg, "sleep", "infinity"])
-        .stdin(std::process::Stdio::null())
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .spawn()
-        .ok()?;
-
-    Some(PowerSaveHandle::ChildProcess(child))
+#[derive(Default)]
+struct Backend {
+    session_bus: Option<Connection>,
+    system_bus: Option<Connection>,
+    sandboxed: Option<bool>,
+    blockers: HashMap<u32, PowerSaveBlocker>,
+    next_id: u32,
 }
 
-pub fn release_blocker(handle: PowerSaveHandle) {
-    match handle {
-        PowerSaveHandle::ScreenSaverCookie(cookie) => uninhibit_screensaver(cookie),
-        PowerSaveHandle::ChildProcess(mut child) => {
-            let _ = child.kill();
-            let _ = child.wait();
+impl Backend {
+    #[cfg(test)]
+    fn with_connections(session_bus: Connection, system_bus: Connection, sandboxed: bool) -> Self {
+        Self {
+            session_bus: Some(session_bus),
+            system_bus: Some(system_bus),
+            sandboxed: Some(sandboxed),
+            blockers: HashMap::default(),
+            next_id: 0,
+        }
+    }
+
+    async fn start(&mut self, kind: PowerSaveBlockerKind) -> Result<u32> {
+        let blocker = if *self.sandboxed.get_or_insert_with(is_sandboxed) {
+            let flags = match kind {
+                PowerSaveBlockerKind::PreventDisplaySleep => PORTAL_INHIBIT_IDLE,
+                PowerSaveBlockerKind::PreventAppSuspension => PORTAL_INHIBIT_SUSPEND,
+            };
+            let connection = self.session_bus().await?;
+            let options = HashMap::from([("reason", Value::from(REASON))]);
+            let request: OwnedObjectPath = connection
+                .call_method(
+                    Some("org.freedesktop.portal.Desktop"),
+                    "/org/freedesktop/portal/desktop",
+                    Some("org.freedesktop.portal.Inhibit"),
+                    "Inhibit",
+                    &("", flags, options),
+                )
+                .await
+                .and_then(|reply| reply.body().deserialize())
+                .context("failed to inhibit through the desktop portal")?;
+            PowerSaveBlocker::Portal {
+                connection,
+                request,
+            }
+        } else {
+            match kind {
+                PowerSaveBlockerKind::PreventDisplaySleep => {
+                    let connection = self.session_bus().await?;
+                    let cookie = connection
+                        .call_method(
+                            Some("org.freedesktop.ScreenSaver"),
+                            "/org/freedesktop/ScreenSaver",
+                            Some("org.freedesktop.ScreenSaver"),
+                            "Inhibit",
+                            &(app_name(), REASON),
+                        )
+                        .await
+                        .and_then(|reply| reply.body().deserialize())
+                        .context("failed to inhibit the screensaver")?;
+                    PowerSaveBlocker::ScreenSaver { connection, cookie }
+                }
+                PowerSaveBlockerKind::PreventAppSuspension => {
+                    let inhibitor = self
+                        .system_bus()
+                        .await?
+                        .call_method(
+                            Some("org.freedesktop.login1"),
+                            "/org/freedesktop/login1",
+                            Some("org.freedesktop.login1.Manager"),
+                            "Inhibit",
+                            &("sleep", app_name(), REASON, "block"),
+                        )
+                        .await
+                        .and_then(|reply| reply.body().deserialize())
+                        .context("failed to take a logind inhibitor")?;
+                    PowerSaveBlocker::Logind {
+                        _inhibitor: inhibitor,
+                    }
+                }
+            }
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.blockers.insert(id, blocker);
+        Ok(id)
+    }
+
+    async fn stop(&mut self, id: u32) {
+        if let Some(blocker) = self.blockers.remove(&id) {
+            blocker.release().await.log_err();
+        }
+    }
+
+    async fn session_bus(&mut self) -> Result<Connection> {
+        if let Some(connection) = &self.session_bus {
+            return Ok(connection.clone());
+        }
+        let connection = Connection::session()
+            .await
+            .context("failed to connect to the session bus")?;
+        Ok(self.session_bus.insert(connection).clone())
+    }
+
+    async fn system_bus(&mut self) -> Result<Connection> {
+        if let Some(connection) = &self.system_bus {
+            return Ok(connection.clone());
         }
+        let connection = Connection::system()
+            .await
+            .context("failed to connect to the system bus")?;
+        Ok(self.system_bus.insert(connection).clone())
     }
 }
 
-fn parse_dbus_uint32(stdout: &[u8]) -> Option<u32> {
-    let text = std::str::from_utf8(stdout).ok()?;
-    for line in text.lines() {
-        let trimmed = line.trim();
-        if let Some(rest) = trimmed.strip_prefix("uint32") {
-            return rest.trim().parse::<u32>().ok();
+pub(super) fn is_sandboxed() -> bool {
+    Path::new("/.flatpak-info").exists() || env::var_os("SNAP").is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{ErrorKind, Read as _},
+        os::unix::net::UnixStream,
+        sync::{Arc, Mutex},
+    };
+
+    use zbus::{fdo, zvariant::ObjectPath};
+
+    use super::*;
+    use crate::platform::linux::test_bus::TestBus;
+
+    const PORTAL_REQUEST_PATH: &str = "/org/freedesktop/portal/desktop/request/1/t";
+
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    struct FakeScreenSaver(Calls);
+
+    #[zbus::interface(name = "org.freedesktop.ScreenSaver")]
+    impl FakeScreenSaver {
+        fn inhibit(&self, application: &str, _reason: &str) -> u32 {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("Inhibit {application}"));
+            42
+        }
+
+        fn un_inhibit(&self, cookie: u32) {
+            self.0.lock().unwrap().push(format!("UnInhibit {cookie}"));
+        }
+    }
+
+    struct FakeLogind(Calls, Arc<Mutex<Vec<UnixStream>>>);
+
+    #[zbus::interface(name = "org.freedesktop.login1.Manager")]
+    impl FakeLogind {
+        fn inhibit(&self, what: &str, _who: &str, _why: &str, mode: &str) -> fdo::Result<OwnedFd> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("Inhibit {what} {mode}"));
+            let (held, sent) = UnixStream::pair().map_err(|e| fdo::Error::Failed(e.to_string()))?;
+            self.1.lock().unwrap().push(held);
+            Ok(std::os::fd::OwnedFd::from(sent).into())
+        }
+    }
+
+    struct FakePortal(Calls);
+
+    #[zbus::interface(name = "org.freedesktop.portal.Inhibit")]
+    impl FakePortal {
+        fn inhibit(
+            &self,
+            _window: &str,
+            flags: u32,
+            options: HashMap<String, zbus::zvariant::OwnedValue>,
+        ) -> ObjectPath<'static> {
+            assert!(options.contains_key("reason"));
+            self.0.lock().unwrap().push(format!("Inhibit {flags}"));
+            ObjectPath::from_static_str_unchecked(PORTAL_REQUEST_PATH)
+        }
+    }
+
+    struct FakeRequest(Calls);
+
+    #[zbus::interface(name = "org.freedesktop.portal.Request")]
+    impl FakeRequest {
+        fn close(&self) {
+            self.0.lock().unwrap().push("Close".into());
         }
     }
-    None
+
+    #[test]
+    fn test_power_save_blockers() {
+        let Some(bus) = TestBus::start() else {
+            return;
+        };
+
+        let calls = Calls::default();
+        let inhibitors = Arc::new(Mutex::new(Vec::new()));
+        let _service = smol::block_on(
+            bus.builder()
+                .name("org.freedesktop.ScreenSaver")
+                .unwrap()
+                .name("org.freedesktop.login1")
+                .unwrap()
+                .name("org.freedesktop.portal.Desktop")
+                .unwrap()
+                .serve_at(
+                    "/org/freedesktop/ScreenSaver",
+                    FakeScreenSaver(calls.clone()),
+                )
+                .unwrap()
+                .serve_at(
+                    "/org/freedesktop/login1",
+                    FakeLogind(calls.clone(), inhibitors.clone()),
+                )
+                .unwrap()
+                .serve_at("/org/freedesktop/portal/desktop", FakePortal(calls.clone()))
+                .unwrap()
+                .serve_at(PORTAL_REQUEST_PATH, FakeRequest(calls.clone()))
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+        let connect = || smol::block_on(bus.connect());
+        let take_calls = || std::mem::take(&mut *calls.lock().unwrap());
+
+        let mut blockers = Backend::with_connections(connect(), connect(), false);
+        let display =
+            smol::block_on(blockers.start(PowerSaveBlockerKind::PreventDisplaySleep)).unwrap();
+        let suspend =
+            smol::block_on(blockers.start(PowerSaveBlockerKind::PreventAppSuspension)).unwrap();
+        assert_eq!(
+            take_calls(),
+            [
+                format!("Inhibit {}", app_name()),
+                "Inhibit sleep block".into()
+            ]
+        );
+
+        smol::block_on(blockers.stop(display));
+        assert_eq!(take_calls(), ["UnInhibit 42"]);
+
+        let mut inhibitor = inhibitors.lock().unwrap().pop().unwrap();
+        inhibitor.set_nonblocking(true).unwrap();
+        assert_eq!(
+            inhibitor.read(&mut [0]).unwrap_err().kind(),
+            ErrorKind::WouldBlock
+        );
+        smol::block_on(blockers.stop(suspend));
+        inhibitor.set_nonblocking(false).unwrap();
+        assert_eq!(inhibitor.read(&mut [0]).unwrap(), 0);
+
+        // Inside a sandbox, blockers go through the portal.
+        let mut blockers = Backend::with_connections(connect(), connect(), true);
+        let display =
+            smol::block_on(blockers.start(PowerSaveBlockerKind::PreventDisplaySleep)).unwrap();
+        smol::block_on(blockers.stop(display));
+        let suspend =
+            smol::block_on(blockers.start(PowerSaveBlockerKind::PreventAppSuspension)).unwrap();
+        smol::block_on(blockers.stop(suspend));
+        assert_eq!(take_calls(), ["Inhibit 8", "Close", "Inhibit 4", "Close"]);
+
+        // Errors from the services are reported.
+        drop(_service);
+        let mut blockers = Backend::with_connections(connect(), connect(), false);
+        assert!(smol::block_on(blockers.start(PowerSaveBlockerKind::PreventDisplaySleep)).is_err());
+    }
 }
@@ -0,0 +1,66 @@
+//! A private D-Bus daemon for testing integrations against fake services.
+
+use std::{
+    io::{BufRead as _, BufReader},
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+pub(crate) struct TestBus {
+    daemon: Child,
+    address: String,
+}
+
+impl TestBus {
+    /// Start a private session bus, or return `None` if `dbus-daemon` isn't installed.
+    pub fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .inspect_err(|error| eprintln!("skipping test, dbus-daemon is unavailable: {error}"))
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+        Some(Self {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+
+    pub async fn connect(&self) -> zbus::Connection {
+        self.builder().build().await.unwrap()
+    }
+
+    pub fn builder(&self) -> zbus::connection::Builder<'static> {
+        zbus::connection::Builder::address(self.address.as_str()).unwrap()
+    }
+}
+
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        self.daemon.kill().ok();
+        self.daemon.wait().ok();
+    }
+}
+
+/// Wait for a condition that is satisfied by messages passing through the bus.
+pub(crate) async fn wait_until(mut condition: impl FnMut() -> bool) {
+    wait_until_async(|| std::future::ready(condition())).await
+}
+
+/// Like [`wait_until`], for a condition that is checked by calling a service on the bus.
+pub(crate) async fn wait_until_async<F: Future<Output = bool>>(mut condition: impl FnMut() -> F) {
+    let start = Instant::now();
+    while !condition().await {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "timed out waiting for a condition"
+        );
+        smol::Timer::after(Duration::from_millis(5)).await;
+    }
+}
@@ -1,48 +1,126 @@
 #![allow(dead_code)]
 
+use std::io::Cursor;
 use std::sync::{Arc, Mutex};
 
 use crate::platform::TrayMenuItem;
-use crate::{SharedString, TrayIconEvent};
+use crate::{Keystroke, SharedString, TrayIcon, TrayIconEvent, TrayIconFrame};
 
 type TrayActionCallback = Arc<Mutex<Option<Box<dyn Fn(SharedString) + Send>>>>;
 type TrayClickCallback = Arc<Mutex<Option<Box<dyn Fn(TrayIconEvent) + Send>>>>;
+/// Where the tray icon was last clicked, in screen coordinates.
+type TrayActivation = Arc<Mutex<Option<(i32, i32)>>>;
+
+/// The size of the icons next to menu items.
+const MENU_ICON_SIZE: u32 = 16;
+
+/// A [`TrayIconFrame`] as StatusNotifierItem describes it: a theme icon name, or pixmaps of the
+/// icon at different sizes.
+#[derive(Default)]
+struct SniIcon {
+    name: String,
+    pixmaps: Vec<ksni::Icon>,
+}
+
+impl SniIcon {
+    fn new(icon: Option<&TrayIconFrame>) -> Self {
+        match icon {
+            Some(TrayIconFrame::Named(name)) => Self {
+                name: name.to_string(),
+                pixmaps: Vec::new(),
+            },
+            Some(TrayIconFrame::Images(images)) => Self {
+                name: String::new(),
+                pixmaps: images
+                    .iter()
+                    .map(|image| argb_pixmap(&image.rgba))
+                    .collect(),
+            },
+            None => Self::default(),
+        }
+    }
+}
+
+/// Convert `rgba` into the ARGB32 pixmap of `IconPixmap`.
+fn argb_pixmap(rgba: &image::RgbaImage) -> ksni::Icon {
+    let raw = rgba.as_raw();
+    let mut argb_data = Vec::with_capacity(raw.len());
+    for pixel in raw.chunks_exact(4) {
+        argb_data.push(pixel[3]);
+        argb_data.push(pixel[0]);
+        argb_data.push(pixel[1]);
+        argb_data.push(pixel[2]);
+    }
+    ksni::Icon {
+        width: rgba.width() as i32,
+        height: rgba.height() as i32,
+        data: argb_data,
+    }
+}
 
 struct GpuiTray {
-    icon_data: Vec<u8>,
+    icon: SniIcon,
+    /// The icon shown while the tray asks for attention.
+    attention: Option<SniIcon>,
+    /// The badge shown over the icon.
+    overlay: SniIcon,
     tooltip: String,
-    menu_items: Vec<TrayMenuItem>,
+    menu_items: Vec<MenuItem>,
     action_callback: TrayActionCallback,
     click_callback: TrayClickCallback,
+    activation: TrayActivation,
+}
+
+impl GpuiTray {
+    fn record_activation(&self, x: i32, y: i32) {
+        // Hosts that don't know where the icon is, which includes most on Wayland, send (0, 0).
+        if (x, y) != (0, 0)
+            && let Ok(mut activation) = self.activation.lock()
+        {
+            *activation = Some((x, y));
+        }
+    }
 }
 
 impl ksni::Tray for GpuiTray {
-    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
-        if self.icon_data.is_empty() {
-            return vec![];
-        }
-        if let Ok(img) = image::load_from_memory(&self.icon_data) {
-            let rgba = img.to_rgba8();
-            let width = rgba.width() as i32;
-            let height = rgba.height() as i32;
-            let raw = rgba.as_raw();
-            let mut argb_data = Vec::with_capacity(raw.len());
-            for pixel in raw.chunks_exact(4) {
-                argb_data.push(pixel[3]);
-                argb_data.push(pixel[0]);
-                argb_data.push(pixel[1]);
-                argb_data.push(pixel[2]);
-            }
-            vec![ksni::Icon {
-                width,
-                height,
-                data: argb_data,
-            }]
+    fn status(&self) -> ksni::Status {
+        if self.attention.is_some() {
+            ksni::Status::NeedsAttention
         } else {
-            vec![]
+            ksni::Status::Active
         }
     }
 
+    fn icon_name(&self) -> String {
+        self.icon.name.clone()
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        self.icon.pixmaps.clone()
+    }
+
+    fn overlay_icon_name(&self) -> String {
+        self.overlay.name.clone()
+    }
+
+    fn overlay_icon_pixmap(&self) -> Vec<ksni::Icon> {
+        self.overlay.pixmaps.clone()
+    }
+
+    fn attention_icon_name(&self) -> String {
+        self.attention
+            .as_ref()
+            .map(|icon| icon.name.clone())
+            .unwrap_or_default()
+    }
+
+    fn attention_icon_pixmap(&self) -> Vec<ksni::Icon> {
+        self.attention
+            .as_ref()
+            .map(|icon| icon.pixmaps.clone())
+            .unwrap_or_default()
+    }
+
     fn title(&self) -> String {
         self.tooltip.clone()
     }
@@ -54,7 +132,8 @@ impl ksni::Tray for GpuiTray {
         }
     }
 
-    fn activate(&mut self, _x: i32, _y: i32) {
+    fn activate(&mut self, x: i32, y: i32) {
+        self.record_activation(x, y);
         if let Ok(guard) = self.click_callback.lock() {
             if let Some(ref cb) = *guard {
                 cb(TrayIconEvent::LeftClick);
@@ -62,7 +141,8 @@ impl ksni::Tray for GpuiTray {
         }
     }
 
-    fn secondary_activate(&mut self, _x: i32, _y: i32) {
+    fn secondary_activate(&mut self, x: i32, y: i32) {
+        self.record_activation(x, y);
         if let Ok(guard) = self.click_callback.lock() {
             if let Some(ref cb) = *guard {
                 cb(TrayIconEvent::RightClick);
@@ -71,69 +151,265 @@ impl ksni::Tray for GpuiTray {
     }
 
     fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
-        self.menu_items
+        convert_menu_items(&self.menu_items, &self.action_callback)
+    }
+}
+
+fn action_activator(
+    id: &SharedString,
+    action_callback: &TrayActionCallback,
+) -> Box<dyn Fn(&mut GpuiTray)> {
+    let id = id.clone();
+    let cb = action_callback.clone();
+    Box::new(move |_tray: &mut GpuiTray| {
+        if let Ok(guard) = cb.lock() {
+            if let Some(ref callback) = *guard {
+                callback(id.clone());
+            }
+        }
+    })
+}
+
+/// A [`TrayMenuItem`] with its icon converted for dbusmenu, done once when the menu is set
+/// since hosts ask for the whole menu every time it opens.
+struct MenuItem {
+    item: TrayMenuItem,
+    icon: MenuIcon,
+    /// The converted items of a [`TrayMenuItem::Submenu`], which are taken out of `item`.
+    submenu: Vec<MenuItem>,
+}
+
+impl MenuItem {
+    fn new(mut item: TrayMenuItem) -> Self {
+        let (icon, submenu) = match &mut item {
+            TrayMenuItem::Action { icon, .. }
+            | TrayMenuItem::Toggle { icon, .. }
+            | TrayMenuItem::Radio { icon, .. } => (MenuIcon::new(icon.as_ref()), Vec::new()),
+            TrayMenuItem::Submenu { icon, items, .. } => (
+                MenuIcon::new(icon.as_ref()),
+                std::mem::take(items).into_iter().map(Self::new).collect(),
+            ),
+            TrayMenuItem::Separator => (MenuIcon::default(), Vec::new()),
+        };
+        Self {
+            item,
+            icon,
+            submenu,
+        }
+    }
+}
+
+/// Convert `items` to dbusmenu items, joining adjacent radio items of the same group into one
+/// radio group.
+fn convert_menu_items(
+    items: &[MenuItem],
+    action_callback: &TrayActionCallback,
+) -> Vec<ksni::MenuItem<GpuiTray>> {
+    let mut menu = Vec::new();
+    let mut ix = 0;
+    while ix < items.len() {
+        let TrayMenuItem::Radio { group, .. } = &items[ix].item else {
+            menu.push(convert_menu_item(&items[ix], action_callback));
+            ix += 1;
+            continue;
+        };
+        let len = items[ix..]
             .iter()
-            .map(|item| convert_menu_item(item, &self.action_callback))
-            .collect()
+            .take_while(|item| {
+                matches!(&item.item, TrayMenuItem::Radio { group: other, .. } if other == group)
+            })
+            .count();
+        menu.push(convert_radio_group(&items[ix..ix + len], action_callback));
+        ix += len;
     }
+    menu
+}
+
+fn convert_radio_group(
+    items: &[MenuItem],
+    action_callback: &TrayActionCallback,
+) -> ksni::MenuItem<GpuiTray> {
+    let mut ids = Vec::new();
+    let mut options = Vec::new();
+    // Nothing is checked if the index is past the options.
+    let mut selected = items.len();
+    for (ix, item) in items.iter().enumerate() {
+        let TrayMenuItem::Radio {
+            label,
+            checked,
+            id,
+            enabled,
+            accelerator,
+            ..
+        } = &item.item
+        else {
+            continue;
+        };
+        if *checked {
+            selected = ix;
+        }
+        ids.push(id.clone());
+        options.push(ksni::menu::RadioItem {
+            label: label.to_string(),
+            enabled: *enabled,
+            icon_name: item.icon.name.clone(),
+            icon_data: item.icon.data.clone(),
+            shortcut: shortcut(accelerator.as_ref()),
+            ..Default::default()
+        });
+    }
+    let cb = action_callback.clone();
+    ksni::MenuItem::RadioGroup(ksni::menu::RadioGroup {
+        selected,
+        select: Box::new(move |_tray: &mut GpuiTray, ix| {
+            if let (Ok(guard), Some(id)) = (cb.lock(), ids.get(ix)) {
+                if let Some(ref callback) = *guard {
+                    callback(id.clone());
+                }
+            }
+        }),
+        options,
+    })
 }
 
 fn convert_menu_item(
-    item: &TrayMenuItem,
+    item: &MenuItem,
     action_callback: &TrayActionCallback,
 ) -> ksni::MenuItem<GpuiTray> {
-    match item {
-        TrayMenuItem::Action { label, id } => {
-            let id_clone = id.clone();
-            let cb = action_callback.clone();
-            ksni::MenuItem::Standard(ksni::menu::StandardItem {
+    let icon_name = item.icon.name.clone();
+    let icon_data = item.icon.data.clone();
+    match &item.item {
+        TrayMenuItem::Action {
+            label,
+            id,
+            enabled,
+            accelerator,
+            ..
+        } => ksni::MenuItem::Standard(ksni::menu::StandardItem {
+            label: label.to_string(),
+            enabled: *enabled,
+            icon_name,
+            icon_data,
+            shortcut: shortcut(accelerator.as_ref()),
+            activate: action_activator(id, action_callback),
+            ..Default::default()
+        }),
+        TrayMenuItem::Separator => ksni::MenuItem::Separator,
+        TrayMenuItem::Submenu { label, enabled, .. } => {
+            ksni::MenuItem::SubMenu(ksni::menu::SubMenu {
                 label: label.to_string(),
-                activate: Box::new(move |_tray: &mut GpuiTray| {
-                    if let Ok(guard) = cb.lock() {
-                        if let Some(ref callback) = *guard {
-                            callback(id_clone.clone());
-                        }
-                    }
-                }),
+                enabled: *enabled,
+                icon_name,
+                icon_data,
+                submenu: convert_menu_items(&item.submenu, action_callback),
                 ..Default::default()
             })
         }
-        TrayMenuItem::Separator => ksni::MenuItem::Separator,
-        TrayMenuItem::Submenu { label, items } => ksni::MenuItem::SubMenu(ksni::menu::SubMenu {
+        TrayMenuItem::Toggle {
+            label,
+            checked,
+            id,
+            enabled,
+            accelerator,
+            ..
+        } => ksni::MenuItem::Checkmark(ksni::menu::CheckmarkItem {
             label: label.to_string(),
-            submenu: items
-                .iter()
-                .map(|i| convert_menu_item(i, action_callback))
-                .collect(),
+            enabled: *enabled,
+            checked: *checked,
+            icon_name,
+            icon_data,
+            shortcut: shortcut(accelerator.as_ref()),
+            activate: action_activator(id, action_callback),
             ..Default::default()
         }),
-        TrayMenuItem::Toggle { label, checked, id } => {
-            let id_clone = id.clone();
-            let cb = action_callback.clone();
-            ksni::MenuItem::Standard(ksni::menu::StandardItem {
-                label: label.to_string(),
-                icon_name: if *checked {
-                    "checkbox-checked-symbolic".to_string()
-                } else {
-                    String::new()
-                },
-                activate: Box::new(move |_tray: &mut GpuiTray| {
-                    if let Ok(guard) = cb.lock() {
-                        if let Some(ref callback) = *guard {
-                            callback(id_clone.clone());
-                        }
+        TrayMenuItem::Radio { .. } => {
+            convert_radio_group(std::slice::from_ref(item), action_callback)
+        }
+    }
+}
+
+/// The `icon-name` and `icon-data` of a menu item, where the data is a PNG image.
+#[derive(Default)]
+struct MenuIcon {
+    name: String,
+    data: Vec<u8>,
+}
+
+impl MenuIcon {
+    fn new(icon: Option<&TrayIcon>) -> Self {
+        match icon {
+            Some(TrayIcon::Named(name)) => Self {
+                name: name.to_string(),
+                data: Vec::new(),
+            },
+            Some(TrayIcon::Images(images)) => {
+                let frame = TrayIconFrame::images(images);
+                let Some(image) = frame.image_closest_to(MENU_ICON_SIZE) else {
+                    return Self::default();
+                };
+                if image::guess_format(&image.bytes)
+                    .is_ok_and(|format| format == image::ImageFormat::Png)
+                {
+                    return Self {
+                        name: String::new(),
+                        data: image.bytes.to_vec(),
+                    };
+                }
+                let mut png = Vec::new();
+                match image
+                    .rgba
+                    .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+                {
+                    Ok(()) => Self {
+                        name: String::new(),
+                        data: png,
+                    },
+                    Err(error) => {
+                        log::error!("failed to convert a menu icon: {error}");
+                        Self::default()
                     }
-                }),
-                ..Default::default()
-            })
+                }
+            }
+            // Menu items are resolved to images or names before they reach the tray.
+            Some(TrayIcon::Svg(_) | TrayIcon::Animated { .. }) | None => Self::default(),
         }
     }
 }
 
+/// The dbusmenu `shortcut` of `keystroke`: the modifiers followed by the key.
+fn shortcut(keystroke: Option<&Keystroke>) -> Vec<Vec<String>> {
+    let Some(keystroke) = keystroke else {
+        return Vec::new();
+    };
+    let modifiers = keystroke.modifiers;
+    let mut keys = [
+        (modifiers.control, "Control"),
+        (modifiers.alt, "Alt"),
+        (modifiers.shift, "Shift"),
+        (modifiers.platform, "Super"),
+    ]
+    .into_iter()
+    .filter(|(pressed, _)| *pressed)
+    .map(|(_, name)| name.to_string())
+    .collect::<Vec<_>>();
+    // dbusmenu uses the key names of GTK accelerators.
+    let key = match keystroke.key.as_str() {
+        "enter" => "Return".to_string(),
+        "escape" => "Escape".to_string(),
+        "backspace" => "BackSpace".to_string(),
+        "delete" => "Delete".to_string(),
+        "tab" => "Tab".to_string(),
+        key => key.to_uppercase(),
+    };
+    keys.push(key);
+    vec![keys]
+}
+
 pub struct LinuxTray {
     handle: Option<ksni::Handle<GpuiTray>>,
     action_callback: TrayActionCallback,
     click_callback: TrayClickCallback,
+    activation: TrayActivation,
 }
 
 impl LinuxTray {
@@ -142,6 +418,7 @@ impl LinuxTray {
             handle: None,
             action_callback: Arc::new(Mutex::new(None)),
             click_callback: Arc::new(Mutex::new(None)),
+            activation: TrayActivation::default(),
         }
     }
 
@@ -150,23 +427,47 @@ impl LinuxTray {
             return;
         }
         let tray = GpuiTray {
-            icon_data: Vec::new(),
+            icon: SniIcon::default(),
+            attention: None,
+            overlay: SniIcon::default(),
             tooltip: String::new(),
             menu_items: Vec::new(),
             action_callback: self.action_callback.clone(),
             click_callback: self.click_callback.clone(),
+            activation: self.activation.clone(),
         };
         let service = ksni::TrayService::new(tray);
         self.handle = Some(service.handle());
         service.spawn();
     }
 
-    pub fn set_icon(&mut self, icon_data: Option<&[u8]>) {
+    pub fn set_icon(&mut self, icon: Option<TrayIconFrame>) {
+        self.ensure_started();
+        if let Some(handle) = &self.handle {
+            let icon = SniIcon::new(icon.as_ref());
+            handle.update(move |tray: &mut GpuiTray| {
+                tray.icon = icon;
+            });
+        }
+    }
+
+    /// Set the status to `NeedsAttention` with `icon` as the attention icon, or back to `Active`.
+    pub fn set_attention(&mut self, icon: Option<TrayIconFrame>) {
         self.ensure_started();
         if let Some(handle) = &self.handle {
-            let data = icon_data.unwrap_or(&[]).to_vec();
+            let attention = icon.map(|icon| SniIcon::new(Some(&icon)));
             handle.update(move |tray: &mut GpuiTray| {
-                tray.icon_data = data.clone();
+                tray.attention = attention;
+            });
+        }
+    }
+
+    pub fn set_overlay(&mut self, icon: Option<TrayIconFrame>) {
+        self.ensure_started();
+        if let Some(handle) = &self.handle {
+            let overlay = SniIcon::new(icon.as_ref());
+            handle.update(move |tray: &mut GpuiTray| {
+                tray.overlay = overlay;
             });
         }
     }
@@ -176,7 +477,7 @@ impl LinuxTray {
         if let Some(handle) = &self.handle {
             let tooltip = tooltip.to_string();
             handle.update(move |tray: &mut GpuiTray| {
-                tray.tooltip = tooltip.clone();
+                tray.tooltip = tooltip;
             });
         }
     }
@@ -184,8 +485,9 @@ impl LinuxTray {
     pub fn set_menu(&mut self, items: Vec<TrayMenuItem>) {
         self.ensure_started();
         if let Some(handle) = &self.handle {
+            let items = items.into_iter().map(MenuItem::new).collect::<Vec<_>>();
             handle.update(move |tray: &mut GpuiTray| {
-                tray.menu_items = items.clone();
+                tray.menu_items = items;
             });
         }
     }
@@ -202,6 +504,11 @@ impl LinuxTray {
         }
     }
 
+    /// Where the host reported the tray icon was last clicked, in the host's screen coordinates.
+    pub fn activation_position(&self) -> Option<(i32, i32)> {
+        *self.activation.lock().ok()?
+    }
+
     pub fn shutdown(&mut self) {
         if let Some(handle) = self.handle.take() {
             handle.shutdown();
@@ -214,3 +521,93 @@ impl Drop for LinuxTray {
         self.shutdown();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(size: u32) -> Arc<[u8]> {
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(size, size, image::Rgba([255, 0, 0, 128]))
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        png.into()
+    }
+
+    #[test]
+    fn test_icon_pixmaps() {
+        let icon = SniIcon::new(Some(&TrayIconFrame::images(&[png(16), png(32)])));
+        assert_eq!(icon.name, "");
+        assert_eq!(
+            icon.pixmaps
+                .iter()
+                .map(|pixmap| (pixmap.width, pixmap.height))
+                .collect::<Vec<_>>(),
+            [(16, 16), (32, 32)]
+        );
+        // Pixels are ARGB in network byte order.
+        assert_eq!(icon.pixmaps[0].data[..4], [128, 255, 0, 0]);
+
+        let icon = SniIcon::new(Some(&TrayIconFrame::Named("mail-unread".into())));
+        assert_eq!(icon.name, "mail-unread");
+        assert!(icon.pixmaps.is_empty());
+
+        // The menu shows the image closest to its icon size.
+        let icon = MenuIcon::new(Some(&TrayIcon::Images(vec![png(32), png(16)])));
+        assert_eq!(icon.name, "");
+        assert_eq!(icon.data, png(16).as_ref());
+    }
+
+    #[test]
+    fn test_menu_items() {
+        let callback = TrayActionCallback::default();
+        let chosen = Arc::new(Mutex::new(Vec::new()));
+        *callback.lock().unwrap() = Some(Box::new({
+            let chosen = chosen.clone();
+            move |id| chosen.lock().unwrap().push(id)
+        }));
+        let items = vec![
+            TrayMenuItem::action("Quit", "quit")
+                .accelerator(Keystroke::parse("ctrl-q").unwrap())
+                .enabled(false),
+            TrayMenuItem::radio("Low", "quality", false, "low"),
+            TrayMenuItem::radio("High", "quality", true, "high"),
+            TrayMenuItem::radio("Left", "side", true, "left"),
+            TrayMenuItem::toggle("Mute", true, "mute"),
+        ];
+        let items = items.into_iter().map(MenuItem::new).collect::<Vec<_>>();
+        let menu = convert_menu_items(&items, &callback);
+        assert_eq!(menu.len(), 4);
+
+        let ksni::MenuItem::Standard(quit) = &menu[0] else {
+            panic!("expected a standard item");
+        };
+        assert!(!quit.enabled);
+        assert_eq!(quit.shortcut, [["Control", "Q"]]);
+
+        // Adjacent radio items of the same group form one group.
+        let ksni::MenuItem::RadioGroup(quality) = &menu[1] else {
+            panic!("expected a radio group");
+        };
+        assert_eq!(quality.options.len(), 2);
+        assert_eq!(quality.selected, 1);
+        let ksni::MenuItem::RadioGroup(side) = &menu[2] else {
+            panic!("expected a radio group");
+        };
+        assert_eq!(side.selected, 0);
+        assert!(matches!(&menu[3], ksni::MenuItem::Checkmark(mute) if mute.checked));
+
+        let mut tray = GpuiTray {
+            icon: SniIcon::default(),
+            attention: None,
+            overlay: SniIcon::default(),
+            tooltip: String::new(),
+            menu_items: Vec::new(),
+            action_callback: callback.clone(),
+            click_callback: TrayClickCallback::default(),
+            activation: TrayActivation::default(),
+        };
+        (quality.select)(&mut tray, 0);
+        assert_eq!(*chosen.lock().unwrap(), ["low"]);
+    }
+}
@@ -1,9 +1,12 @@
 mod client;
 mod clipboard;
+mod color_management;
 mod cursor;
 mod display;
+mod foreign_toplevel;
 mod serial;
 mod window;
+mod xdg_dialog;
 
 pub(crate) use client::*;
 
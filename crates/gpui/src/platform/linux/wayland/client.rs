@@ -4,6 +4,7 @@ use std::{
     os::fd::{AsRawFd, BorrowedFd},
     path::PathBuf,
     rc::{Rc, Weak},
+    sync::atomic::{AtomicBool, Ordering},
     time::{Duration, Instant},
 };
 
@@ -60,24 +61,41 @@ use wayland_protocols::xdg::activation::v1::client::{xdg_activation_token_v1, xd
 use wayland_protocols::xdg::decoration::zv1::client::{
     zxdg_decoration_manager_v1, zxdg_toplevel_decoration_v1,
 };
-use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base};
+use wayland_protocols::xdg::shell::client::{
+    xdg_popup, xdg_positioner, xdg_surface, xdg_toplevel, xdg_wm_base,
+};
 use wayland_protocols_plasma::blur::client::{org_kde_kwin_blur, org_kde_kwin_blur_manager};
+use wayland_protocols_plasma::plasma_window_management::client::{
+    org_kde_plasma_window, org_kde_plasma_window_management,
+};
 use xkbcommon::xkb::ffi::XKB_KEYMAP_FORMAT_TEXT_V1;
 use xkbcommon::xkb::{self, KEYMAP_COMPILE_NO_FLAGS, Keycode};
 
 use super::{
+    color_management::{
+        is_hdr_transfer_function,
+        v1::{
+            wp_color_management_output_v1, wp_color_manager_v1, wp_image_description_info_v1,
+            wp_image_description_v1,
+        },
+    },
     display::WaylandDisplay,
+    foreign_toplevel::{
+        ForeignToplevelHandle, ForeignToplevelManager, ForeignToplevels, ToplevelEvent,
+        wlr::{zwlr_foreign_toplevel_handle_v1, zwlr_foreign_toplevel_manager_v1},
+    },
     window::{ImeInput, WaylandWindowStatePtr},
+    xdg_dialog::v1::{xdg_dialog_v1, xdg_wm_dialog_v1},
 };
 
 use crate::platform::{PlatformWindow, blade::BladeContext};
 use crate::{
     AnyWindowHandle, Bounds, Capslock, CursorStyle, DOUBLE_CLICK_INTERVAL, DevicePixels, DisplayId,
-    FileDropEvent, ForegroundExecutor, KeyDownEvent, KeyUpEvent, Keystroke, LinuxCommon,
-    LinuxKeyboardLayout, Modifiers, ModifiersChangedEvent, MouseButton, MouseDownEvent,
-    MouseExitEvent, MouseMoveEvent, MouseUpEvent, NavigationDirection, Pixels, PlatformDisplay,
-    PlatformInput, PlatformKeyboardLayout, Point, SCROLL_LINES, ScrollDelta, ScrollWheelEvent,
-    Size, TouchPhase, WindowParams, point, px, size,
+    DisplayRotation, FileDropEvent, ForegroundExecutor, KeyDownEvent, KeyUpEvent, Keystroke,
+    LinuxCommon, LinuxKeyboardLayout, Modifiers, ModifiersChangedEvent, MouseButton,
+    MouseDownEvent, MouseExitEvent, MouseMoveEvent, MouseUpEvent, NavigationDirection, Pixels,
+    PlatformDisplay, PlatformInput, PlatformKeyboardLayout, Point, SCROLL_LINES, ScrollDelta,
+    ScrollWheelEvent, Size, TouchPhase, WindowParams, point, px, size,
 };
 use crate::{
     SharedString,
@@ -117,7 +135,11 @@ pub struct Globals {
     pub decoration_manager: Option<zxdg_decoration_manager_v1::ZxdgDecorationManagerV1>,
     pub blur_manager: Option<org_kde_kwin_blur_manager::OrgKdeKwinBlurManager>,
     pub text_input_manager: Option<zwp_text_input_manager_v3::ZwpTextInputManagerV3>,
+    pub dialog_manager: Option<xdg_wm_dialog_v1::XdgWmDialogV1>,
+    pub color_manager: Option<wp_color_manager_v1::WpColorManagerV1>,
     pub executor: ForegroundExecutor,
+    /// Globals that are only bound on demand.
+    registry: Rc<GlobalList>,
 }
 
 impl Globals {
@@ -154,10 +176,26 @@ impl Globals {
             decoration_manager: globals.bind(&qh, 1..=1, ()).ok(),
             blur_manager: globals.bind(&qh, 1..=1, ()).ok(),
             text_input_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            dialog_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            color_manager: globals.bind(&qh, 1..=3, ()).ok(),
             executor,
             qh,
+            registry: Rc::new(globals),
         }
     }
+
+    /// Bind the wlroots foreign toplevel manager, or KDE Plasma's own protocol without it.
+    fn bind_foreign_toplevel_manager(&self) -> Option<ForeignToplevelManager> {
+        self.registry
+            .bind(&self.qh, 1..=3, ())
+            .map(ForeignToplevelManager::Wlr)
+            .or_else(|_| {
+                self.registry
+                    .bind(&self.qh, 1..=16, ())
+                    .map(ForeignToplevelManager::Plasma)
+            })
+            .ok()
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
@@ -166,6 +204,9 @@ pub struct InProgressOutput {
     scale: Option<i32>,
     position: Option<Point<DevicePixels>>,
     size: Option<Size<DevicePixels>>,
+    physical_size: Option<Size<u32>>,
+    rotation: DisplayRotation,
+    refresh_millihertz: Option<u32>,
 }
 
 impl InProgressOutput {
@@ -176,6 +217,10 @@ impl InProgressOutput {
                 name: self.name.clone(),
                 scale,
                 bounds: Bounds::new(position, size),
+                physical_size: self.physical_size,
+                rotation: self.rotation,
+                refresh_millihertz: self.refresh_millihertz,
+                is_hdr: false,
             })
         } else {
             None
@@ -188,6 +233,12 @@ pub struct Output {
     pub name: Option<String>,
     pub scale: i32,
     pub bounds: Bounds<DevicePixels>,
+    pub physical_size: Option<Size<u32>>,
+    pub rotation: DisplayRotation,
+    pub refresh_millihertz: Option<u32>,
+    /// Whether the compositor expects content for this output in an HDR encoding, as reported by
+    /// `color-management-v1`.
+    pub is_hdr: bool,
 }
 
 pub(crate) struct WaylandClientState {
@@ -209,6 +260,9 @@ pub(crate) struct WaylandClientState {
     // Output to scale mapping
     outputs: HashMap<ObjectId, Output>,
     in_progress_outputs: HashMap<ObjectId, InProgressOutput>,
+    // Registry name to output mapping, to find the output a global removal refers to
+    output_globals: HashMap<u32, ObjectId>,
+    color_outputs: HashMap<ObjectId, wp_color_management_output_v1::WpColorManagementOutputV1>,
     keyboard_layout: LinuxKeyboardLayout,
     keymap_state: Option<xkb::State>,
     compose_state: Option<xkb::compose::State>,
@@ -238,6 +292,11 @@ pub(crate) struct WaylandClientState {
     event_loop: Option<EventLoop<'static, WaylandClientStatePtr>>,
     common: LinuxCommon,
     tray: crate::platform::linux::tray::LinuxTray,
+    foreign_toplevels: ForeignToplevels,
+    foreign_toplevel_manager: Option<ForeignToplevelManager>,
+    foreign_toplevel_handles: HashMap<ObjectId, ForeignToplevelHandle>,
+    watching_focused_window: bool,
+    focused_window_requested: bool,
 }
 
 pub struct DragState {
@@ -371,6 +430,46 @@ impl WaylandClientStatePtr {
         }
     }
 
+    fn handle_displays_changed(&self) {
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        if let Some(mut callback) = state.common.callbacks.displays_changed.take() {
+            drop(state);
+            callback();
+            client.borrow_mut().common.callbacks.displays_changed = Some(callback);
+        }
+    }
+
+    fn handle_foreign_toplevel_event(&self, toplevel: ForeignToplevelHandle, event: ToplevelEvent) {
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        let id = toplevel.id();
+        if state.foreign_toplevel_manager.is_none() {
+            // Announced before the compositor saw the manager stop.
+            toplevel.destroy();
+            return;
+        }
+        if matches!(event, ToplevelEvent::Closed) {
+            state.foreign_toplevel_handles.remove(&id);
+            toplevel.destroy();
+        } else {
+            state
+                .foreign_toplevel_handles
+                .entry(id.clone())
+                .or_insert(toplevel);
+        }
+        if !state.foreign_toplevels.handle_event(id, event) {
+            return;
+        }
+        let info = state.foreign_toplevels.focused_window_info();
+        if let Some(mut callback) = state.common.callbacks.focused_window_change.take() {
+            drop(state);
+            callback(info);
+            state = client.borrow_mut();
+            state.common.callbacks.focused_window_change = Some(callback);
+        }
+    }
+
     pub fn drop_window(&self, surface_id: &ObjectId) {
         let mut client = self.get_client();
         let mut state = client.borrow_mut();
@@ -411,6 +510,9 @@ impl Drop for WaylandClient {
         if let Some(text_input) = &state.text_input {
             text_input.destroy();
         }
+        if let Some(foreign_toplevel_manager) = &state.foreign_toplevel_manager {
+            foreign_toplevel_manager.stop();
+        }
     }
 }
 
@@ -456,6 +558,7 @@ impl WaylandClient {
         let mut seat: Option<wl_seat::WlSeat> = None;
         #[allow(clippy::mutable_key_type)]
         let mut in_progress_outputs = HashMap::default();
+        let mut output_globals = HashMap::default();
         globals.contents().with_list(|list| {
             for global in list {
                 match &global.interface[..] {
@@ -475,6 +578,7 @@ impl WaylandClient {
                             (),
                         );
                         in_progress_outputs.insert(output.id(), InProgressOutput::default());
+                        output_globals.insert(global.name, output.id());
                     }
                     _ => {}
                 }
@@ -567,6 +671,8 @@ impl WaylandClient {
             composing: false,
             outputs: HashMap::default(),
             in_progress_outputs,
+            output_globals,
+            color_outputs: HashMap::default(),
             windows: HashMap::default(),
             common,
             keyboard_layout: LinuxKeyboardLayout::new(UNKNOWN_KEYBOARD_LAYOUT_NAME),
@@ -617,6 +723,11 @@ impl WaylandClient {
             pending_activation: None,
             event_loop: Some(event_loop),
             tray: crate::platform::linux::tray::LinuxTray::new(),
+            foreign_toplevels: ForeignToplevels::default(),
+            foreign_toplevel_manager: None,
+            foreign_toplevel_handles: HashMap::default(),
+            watching_focused_window: false,
+            focused_window_requested: false,
         }));
 
         WaylandSource::new(conn, event_queue)
@@ -638,11 +749,8 @@ impl LinuxClient for WaylandClient {
             .outputs
             .iter()
             .map(|(id, output)| {
-                Rc::new(WaylandDisplay {
-                    id: id.clone(),
-                    name: output.name.clone(),
-                    bounds: output.bounds.to_pixels(output.scale as f32),
-                }) as Rc<dyn PlatformDisplay>
+                Rc::new(WaylandDisplay::new(id.clone(), output, output.scale as f32))
+                    as Rc<dyn PlatformDisplay>
             })
             .collect()
     }
@@ -654,11 +762,11 @@ impl LinuxClient for WaylandClient {
             .iter()
             .find_map(|(object_id, output)| {
                 (object_id.protocol_id() == id.0).then(|| {
-                    Rc::new(WaylandDisplay {
-                        id: object_id.clone(),
-                        name: output.name.clone(),
-                        bounds: output.bounds.to_pixels(output.scale as f32),
-                    }) as Rc<dyn PlatformDisplay>
+                    Rc::new(WaylandDisplay::new(
+                        object_id.clone(),
+                        output,
+                        output.scale as f32,
+                    )) as Rc<dyn PlatformDisplay>
                 })
             })
     }
@@ -697,7 +805,27 @@ impl LinuxClient for WaylandClient {
     ) -> anyhow::Result<Box<dyn PlatformWindow>> {
         let mut state = self.0.borrow_mut();
 
-        let parent = state.keyboard_focused_window.as_ref().map(|w| w.toplevel());
+        let parent = state
+            .keyboard_focused_window
+            .as_ref()
+            .and_then(|w| w.toplevel());
+        let popup_parent = match params.popup {
+            Some(popup) => {
+                let parent = state
+                    .windows
+                    .values()
+                    .find(|window| window.handle() == popup.parent)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("Wayland: popup parent window not found"))?;
+                // Popups that grab input must be opened in response to the latest input event.
+                let serial = state
+                    .serial_tracker
+                    .get(SerialKind::MousePress)
+                    .max(state.serial_tracker.get(SerialKind::KeyPress));
+                Some((parent, serial))
+            }
+            None => None,
+        };
 
         let (window, surface_id) = WaylandWindow::new(
             handle,
@@ -707,6 +835,7 @@ impl LinuxClient for WaylandClient {
             params,
             state.common.appearance,
             parent,
+            popup_parent,
         )?;
         state.windows.insert(surface_id, window.0.clone());
 
@@ -861,6 +990,19 @@ impl LinuxClient for WaylandClient {
         None
     }
 
+    fn focused_window_info(&self) -> Option<crate::FocusedWindowInfo> {
+        let mut state = self.0.borrow_mut();
+        state.focused_window_requested = true;
+        state.update_foreign_toplevel_manager();
+        state.foreign_toplevels.focused_window_info()
+    }
+
+    fn watch_focused_window(&self, watch: bool) {
+        let mut state = self.0.borrow_mut();
+        state.watching_focused_window = watch;
+        state.update_foreign_toplevel_manager();
+    }
+
     fn compositor_name(&self) -> &'static str {
         "Wayland"
     }
@@ -879,10 +1021,18 @@ impl LinuxClient for WaylandClient {
         inner(active_window.map(|aw| aw.surface()))
     }
 
-    fn set_tray_icon(&self, icon: Option<&[u8]>) {
+    fn set_tray_icon(&self, icon: Option<crate::TrayIconFrame>) {
         self.0.borrow_mut().tray.set_icon(icon);
     }
 
+    fn set_tray_attention(&self, icon: Option<crate::TrayIconFrame>) {
+        self.0.borrow_mut().tray.set_attention(icon);
+    }
+
+    fn set_tray_overlay(&self, icon: Option<crate::TrayIconFrame>) {
+        self.0.borrow_mut().tray.set_overlay(icon);
+    }
+
     fn set_tray_menu(&self, menu: Vec<crate::TrayMenuItem>) {
         self.0.borrow_mut().tray.set_menu(menu);
     }
@@ -942,11 +1092,22 @@ impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandClientStat
                     state
                         .in_progress_outputs
                         .insert(output.id(), InProgressOutput::default());
+                    state.output_globals.insert(name, output.id());
                 }
                 _ => {}
             },
-            wl_registry::Event::GlobalRemove { name: _ } => {
-                // TODO: handle global removal
+            wl_registry::Event::GlobalRemove { name } => {
+                let Some(output) = state.output_globals.remove(&name) else {
+                    return;
+                };
+                state.in_progress_outputs.remove(&output);
+                if let Some(color_output) = state.color_outputs.remove(&output) {
+                    color_output.destroy();
+                }
+                if state.outputs.remove(&output).is_some() {
+                    drop(state);
+                    this.handle_displays_changed();
+                }
             }
             _ => {}
         }
@@ -970,6 +1131,8 @@ delegate_noop!(WaylandClientStatePtr: ignore zwp_text_input_manager_v3::ZwpTextI
 delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_blur::OrgKdeKwinBlur);
 delegate_noop!(WaylandClientStatePtr: ignore wp_viewporter::WpViewporter);
 delegate_noop!(WaylandClientStatePtr: ignore wp_viewport::WpViewport);
+delegate_noop!(WaylandClientStatePtr: ignore xdg_wm_dialog_v1::XdgWmDialogV1);
+delegate_noop!(WaylandClientStatePtr: ignore xdg_dialog_v1::XdgDialogV1);
 
 impl Dispatch<WlCallback, ObjectId> for WaylandClientStatePtr {
     fn event(
@@ -1030,7 +1193,7 @@ impl Dispatch<wl_output::WlOutput, ()> for WaylandClientStatePtr {
         event: <wl_output::WlOutput as Proxy>::Event,
         _: &(),
         _: &Connection,
-        _: &QueueHandle<Self>,
+        qh: &QueueHandle<Self>,
     ) {
         let mut client = this.get_client();
         let mut state = client.borrow_mut();
@@ -1046,17 +1209,140 @@ impl Dispatch<wl_output::WlOutput, ()> for WaylandClientStatePtr {
             wl_output::Event::Scale { factor } => {
                 in_progress_output.scale = Some(factor);
             }
-            wl_output::Event::Geometry { x, y, .. } => {
-                in_progress_output.position = Some(point(DevicePixels(x), DevicePixels(y)))
+            wl_output::Event::Geometry {
+                x,
+                y,
+                physical_width,
+                physical_height,
+                transform,
+                ..
+            } => {
+                in_progress_output.position = Some(point(DevicePixels(x), DevicePixels(y)));
+                // Projectors and virtual outputs report a size of zero.
+                in_progress_output.physical_size = (physical_width > 0 && physical_height > 0)
+                    .then(|| size(physical_width as u32, physical_height as u32));
+                in_progress_output.rotation = match transform {
+                    WEnum::Value(wl_output::Transform::_90 | wl_output::Transform::Flipped90) => {
+                        DisplayRotation::Rotate90
+                    }
+                    WEnum::Value(wl_output::Transform::_180 | wl_output::Transform::Flipped180) => {
+                        DisplayRotation::Rotate180
+                    }
+                    WEnum::Value(wl_output::Transform::_270 | wl_output::Transform::Flipped270) => {
+                        DisplayRotation::Rotate270
+                    }
+                    _ => DisplayRotation::Normal,
+                };
             }
-            wl_output::Event::Mode { width, height, .. } => {
-                in_progress_output.size = Some(size(DevicePixels(width), DevicePixels(height)))
+            wl_output::Event::Mode {
+                width,
+                height,
+                refresh,
+                ..
+            } => {
+                in_progress_output.size = Some(size(DevicePixels(width), DevicePixels(height)));
+                in_progress_output.refresh_millihertz = (refresh > 0).then_some(refresh as u32);
             }
             wl_output::Event::Done => {
-                if let Some(complete) = in_progress_output.complete() {
+                let complete = in_progress_output.complete();
+                state.in_progress_outputs.remove(&output.id());
+                if let Some(complete) = complete {
                     state.outputs.insert(output.id(), complete);
+                    if let Some(color_manager) = &state.globals.color_manager {
+                        let color_output = color_manager.get_output(output, qh, output.id());
+                        color_output.get_image_description(qh, output.id());
+                        state.color_outputs.insert(output.id(), color_output);
+                    }
+                    drop(state);
+                    this.handle_displays_changed();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(WaylandClientStatePtr: ignore wp_color_manager_v1::WpColorManagerV1);
+
+impl Dispatch<wp_color_management_output_v1::WpColorManagementOutputV1, ObjectId>
+    for WaylandClientStatePtr
+{
+    fn event(
+        _: &mut Self,
+        color_output: &wp_color_management_output_v1::WpColorManagementOutputV1,
+        event: wp_color_management_output_v1::Event,
+        output: &ObjectId,
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wp_color_management_output_v1::Event::ImageDescriptionChanged => {
+                color_output.get_image_description(qh, output.clone());
+            }
+        }
+    }
+}
+
+impl Dispatch<wp_image_description_v1::WpImageDescriptionV1, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        _: &mut Self,
+        image_description: &wp_image_description_v1::WpImageDescriptionV1,
+        event: wp_image_description_v1::Event,
+        output: &ObjectId,
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wp_image_description_v1::Event::Ready { .. }
+            | wp_image_description_v1::Event::Ready2 { .. } => {
+                image_description.get_information(
+                    qh,
+                    ImageDescriptionInfo {
+                        output: output.clone(),
+                        is_hdr: AtomicBool::new(false),
+                    },
+                );
+                image_description.destroy();
+            }
+            wp_image_description_v1::Event::Failed { .. } => image_description.destroy(),
+        }
+    }
+}
+
+/// The output an image description describes, and whether its transfer function is HDR.
+struct ImageDescriptionInfo {
+    output: ObjectId,
+    is_hdr: AtomicBool,
+}
+
+impl Dispatch<wp_image_description_info_v1::WpImageDescriptionInfoV1, ImageDescriptionInfo>
+    for WaylandClientStatePtr
+{
+    fn event(
+        this: &mut Self,
+        _: &wp_image_description_info_v1::WpImageDescriptionInfoV1,
+        event: wp_image_description_info_v1::Event,
+        info: &ImageDescriptionInfo,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            wp_image_description_info_v1::Event::TfNamed { tf } => {
+                info.is_hdr
+                    .store(is_hdr_transfer_function(tf), Ordering::Relaxed);
+            }
+            wp_image_description_info_v1::Event::Done => {
+                let client = this.get_client();
+                let mut state = client.borrow_mut();
+                let Some(output) = state.outputs.get_mut(&info.output) else {
+                    return;
+                };
+                let is_hdr = info.is_hdr.load(Ordering::Relaxed);
+                if output.is_hdr != is_hdr {
+                    output.is_hdr = is_hdr;
+                    drop(state);
+                    this.handle_displays_changed();
                 }
-                state.in_progress_outputs.remove(&output.id());
             }
             _ => {}
         }
@@ -1107,6 +1393,41 @@ impl Dispatch<xdg_toplevel::XdgToplevel, ObjectId> for WaylandClientStatePtr {
     }
 }
 
+impl Dispatch<xdg_popup::XdgPopup, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        _: &xdg_popup::XdgPopup,
+        event: <xdg_popup::XdgPopup as Proxy>::Event,
+        surface_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = get_window(&mut state, surface_id) else {
+            return;
+        };
+
+        drop(state);
+        if window.handle_popup_event(event) {
+            // The close logic will be handled in drop_window()
+            window.close();
+        }
+    }
+}
+
+impl Dispatch<xdg_positioner::XdgPositioner, ()> for WaylandClientStatePtr {
+    fn event(
+        _: &mut Self,
+        _: &xdg_positioner::XdgPositioner,
+        _: <xdg_positioner::XdgPositioner as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
 impl Dispatch<xdg_wm_base::XdgWmBase, ()> for WaylandClientStatePtr {
     fn event(
         _: &mut Self,
@@ -1327,7 +1648,7 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
                     if let Some(media_event) =
                         crate::platform::linux::keysym_to_media_key(keysym)
                     {
-                        if let Some(cb) = state.common.callbacks.media_key.as_mut() {
+                        if let Some(cb) = state.common.callbacks.media_key.borrow_mut().as_mut() {
                             cb(media_event);
                         }
                         return;
@@ -2188,3 +2509,128 @@ impl Dispatch<zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1, ()>
         }
     }
 }
+
+impl Dispatch<zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1, ()>
+    for WaylandClientStatePtr
+{
+    fn event(
+        _: &mut Self,
+        _: &zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1,
+        _: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+
+    event_created_child!(WaylandClientStatePtr, zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl WaylandClientState {
+    /// Bind the foreign toplevel manager once the focused window is watched or asked for, and
+    /// destroy it along with its toplevels when it's neither.
+    fn update_foreign_toplevel_manager(&mut self) {
+        let needed = self.watching_focused_window || self.focused_window_requested;
+        if needed && self.foreign_toplevel_manager.is_none() {
+            self.foreign_toplevel_manager = self.globals.bind_foreign_toplevel_manager();
+        } else if !needed && let Some(manager) = self.foreign_toplevel_manager.take() {
+            manager.stop();
+            for (_, toplevel) in self.foreign_toplevel_handles.drain() {
+                toplevel.destroy();
+            }
+            self.foreign_toplevels = ForeignToplevels::default();
+        }
+    }
+}
+
+impl Dispatch<zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1, ()>
+    for WaylandClientStatePtr
+{
+    fn event(
+        this: &mut Self,
+        toplevel: &zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let event = match event {
+            zwlr_foreign_toplevel_handle_v1::Event::Title { title } => ToplevelEvent::Title(title),
+            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
+                ToplevelEvent::AppId(app_id)
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::State { state } => {
+                ToplevelEvent::wlr_activated(&state)
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Done => ToplevelEvent::Done,
+            zwlr_foreign_toplevel_handle_v1::Event::Closed => ToplevelEvent::Closed,
+            _ => return,
+        };
+        this.handle_foreign_toplevel_event(ForeignToplevelHandle::Wlr(toplevel.clone()), event);
+    }
+}
+
+impl Dispatch<org_kde_plasma_window_management::OrgKdePlasmaWindowManagement, ()>
+    for WaylandClientStatePtr
+{
+    fn event(
+        this: &mut Self,
+        window_management: &org_kde_plasma_window_management::OrgKdePlasmaWindowManagement,
+        event: org_kde_plasma_window_management::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        // Managers bound by earlier watches can't be destroyed and keep announcing windows.
+        let client = this.get_client();
+        if !matches!(
+            &client.borrow().foreign_toplevel_manager,
+            Some(ForeignToplevelManager::Plasma(current)) if current == window_management
+        ) {
+            return;
+        }
+        match event {
+            org_kde_plasma_window_management::Event::Window { id }
+                if window_management.version()
+                    < org_kde_plasma_window_management::EVT_WINDOW_WITH_UUID_SINCE =>
+            {
+                window_management.get_window(id, qh, ());
+            }
+            org_kde_plasma_window_management::Event::WindowWithUuid { uuid, .. } => {
+                window_management.get_window_by_uuid(uuid, qh, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<org_kde_plasma_window::OrgKdePlasmaWindow, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        window: &org_kde_plasma_window::OrgKdePlasmaWindow,
+        event: org_kde_plasma_window::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Plasma windows have no equivalent of `done`, so every change applies immediately.
+        let event = match event {
+            org_kde_plasma_window::Event::TitleChanged { title } => ToplevelEvent::Title(title),
+            org_kde_plasma_window::Event::AppIdChanged { app_id } => ToplevelEvent::AppId(app_id),
+            org_kde_plasma_window::Event::PidChanged { pid } => ToplevelEvent::Pid(pid),
+            org_kde_plasma_window::Event::StateChanged { flags } => {
+                ToplevelEvent::plasma_activated(flags)
+            }
+            org_kde_plasma_window::Event::Unmapped => ToplevelEvent::Closed,
+            _ => return,
+        };
+        let toplevel = ForeignToplevelHandle::Plasma(window.clone());
+        let closed = matches!(event, ToplevelEvent::Closed);
+        this.handle_foreign_toplevel_event(toplevel.clone(), event);
+        if !closed {
+            this.handle_foreign_toplevel_event(toplevel, ToplevelEvent::Done);
+        }
+    }
+}
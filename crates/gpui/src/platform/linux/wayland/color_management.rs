@@ -0,0 +1,39 @@
+//! `color-management-v1`, which describes the color encoding each output expects. It is newer
+//! than the `wayland-protocols` release we depend on, so the client code is generated here.
+
+use v1::wp_color_manager_v1::TransferFunction;
+use wayland_client::WEnum;
+
+#[allow(missing_docs)]
+pub(crate) mod v1 {
+    #![allow(
+        dead_code,
+        non_camel_case_types,
+        non_upper_case_globals,
+        unused_imports
+    )]
+    #![allow(clippy::all)]
+
+    use wayland_client;
+    use wayland_client::protocol::*;
+
+    pub mod __interfaces {
+        use wayland_client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!(
+            "src/platform/linux/wayland/protocols/color-management-v1.xml"
+        );
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!(
+        "src/platform/linux/wayland/protocols/color-management-v1.xml"
+    );
+}
+
+/// Whether an output expecting content encoded with `tf` shows high dynamic range.
+pub(crate) fn is_hdr_transfer_function(tf: WEnum<TransferFunction>) -> bool {
+    matches!(
+        tf,
+        WEnum::Value(TransferFunction::St2084Pq | TransferFunction::Hlg)
+    )
+}
@@ -7,7 +7,7 @@ use anyhow::Context as _;
 use uuid::Uuid;
 use wayland_backend::client::ObjectId;
 
-use crate::{Bounds, DisplayId, Pixels, PlatformDisplay};
+use crate::{Bounds, DisplayId, DisplayRotation, Output, Pixels, PlatformDisplay, Size};
 
 #[derive(Debug, Clone)]
 pub(crate) struct WaylandDisplay {
@@ -15,6 +15,24 @@ pub(crate) struct WaylandDisplay {
     pub id: ObjectId,
     pub name: Option<String>,
     pub bounds: Bounds<Pixels>,
+    pub physical_size: Option<Size<u32>>,
+    pub rotation: DisplayRotation,
+    pub refresh_millihertz: Option<u32>,
+    pub is_hdr: bool,
+}
+
+impl WaylandDisplay {
+    pub fn new(id: ObjectId, output: &Output, scale: f32) -> Self {
+        Self {
+            id,
+            name: output.name.clone(),
+            bounds: output.bounds.to_pixels(scale),
+            physical_size: output.physical_size,
+            rotation: output.rotation,
+            refresh_millihertz: output.refresh_millihertz,
+            is_hdr: output.is_hdr,
+        }
+    }
 }
 
 impl Hash for WaylandDisplay {
@@ -39,4 +57,21 @@ impl PlatformDisplay for WaylandDisplay {
     fn bounds(&self) -> Bounds<Pixels> {
         self.bounds
     }
+
+    fn refresh_rate(&self) -> Option<f32> {
+        self.refresh_millihertz
+            .map(|millihertz| millihertz as f32 / 1000.)
+    }
+
+    fn physical_size(&self) -> Option<Size<u32>> {
+        self.physical_size
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+
+    fn is_hdr(&self) -> bool {
+        self.is_hdr
+    }
 }
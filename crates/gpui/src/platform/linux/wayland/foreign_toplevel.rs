@@ -0,0 +1,233 @@
+//! The focused window of any application, as reported by `zwlr_foreign_toplevel_manager_v1`
+//! (sway, Hyprland and other wlroots-based compositors) or `org_kde_plasma_window_management`
+//! (KDE Plasma). `ext-foreign-toplevel-list-v1` lists toplevels without saying which one is
+//! activated, so it can't tell us which window is focused.
+//!
+//! Compositors send every title change of every window to whoever binds these, so they're only
+//! bound while the app watches or has asked for the focused window.
+
+use std::hash::Hash;
+
+use collections::HashMap;
+use wayland_backend::client::ObjectId;
+use wayland_client::Proxy;
+use wayland_protocols_plasma::plasma_window_management::client::{
+    org_kde_plasma_window, org_kde_plasma_window_management,
+};
+
+use crate::FocusedWindowInfo;
+
+#[allow(missing_docs)]
+pub(crate) mod wlr {
+    #![allow(
+        dead_code,
+        non_camel_case_types,
+        non_upper_case_globals,
+        unused_imports
+    )]
+    #![allow(clippy::all)]
+
+    use wayland_client;
+    use wayland_client::protocol::*;
+
+    pub mod __interfaces {
+        use wayland_client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!(
+            "src/platform/linux/wayland/protocols/wlr-foreign-toplevel-management-unstable-v1.xml"
+        );
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!(
+        "src/platform/linux/wayland/protocols/wlr-foreign-toplevel-management-unstable-v1.xml"
+    );
+}
+
+/// The global that announces the toplevels of other applications.
+pub(crate) enum ForeignToplevelManager {
+    Wlr(wlr::zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1),
+    Plasma(org_kde_plasma_window_management::OrgKdePlasmaWindowManagement),
+}
+
+impl ForeignToplevelManager {
+    /// Stop announcing toplevels. `org_kde_plasma_window_management` has no destructor, so it
+    /// stays bound, but its announcements are ignored once it's no longer the current manager.
+    pub fn stop(&self) {
+        match self {
+            Self::Wlr(manager) => manager.stop(),
+            Self::Plasma(_) => {}
+        }
+    }
+}
+
+/// A toplevel of another application.
+#[derive(Clone)]
+pub(crate) enum ForeignToplevelHandle {
+    Wlr(wlr::zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1),
+    Plasma(org_kde_plasma_window::OrgKdePlasmaWindow),
+}
+
+impl ForeignToplevelHandle {
+    pub fn id(&self) -> ObjectId {
+        match self {
+            Self::Wlr(handle) => handle.id(),
+            Self::Plasma(window) => window.id(),
+        }
+    }
+
+    pub fn destroy(&self) {
+        match self {
+            Self::Wlr(handle) => handle.destroy(),
+            Self::Plasma(window) => {
+                if window.version() >= org_kde_plasma_window::REQ_DESTROY_SINCE {
+                    window.destroy();
+                }
+            }
+        }
+    }
+}
+
+/// The `activated` entry of `zwlr_foreign_toplevel_handle_v1.state`.
+const WLR_STATE_ACTIVATED: u32 = 2;
+/// The `active` flag of `org_kde_plasma_window_management.state`.
+const PLASMA_STATE_ACTIVE: u32 = 0x1;
+
+/// A change to a toplevel of another application.
+pub(crate) enum ToplevelEvent {
+    Title(String),
+    AppId(String),
+    Pid(u32),
+    Activated(bool),
+    /// Apply the changes since the last `Done`.
+    Done,
+    Closed,
+}
+
+impl ToplevelEvent {
+    /// Whether `states`, a `zwlr_foreign_toplevel_handle_v1.state` array, contains `activated`.
+    pub fn wlr_activated(states: &[u8]) -> Self {
+        Self::Activated(
+            states
+                .chunks_exact(4)
+                .any(|state| u32::from_ne_bytes(state.try_into().unwrap()) == WLR_STATE_ACTIVATED),
+        )
+    }
+
+    pub fn plasma_activated(flags: u32) -> Self {
+        Self::Activated(flags & PLASMA_STATE_ACTIVE != 0)
+    }
+}
+
+#[derive(Clone, Default)]
+struct Toplevel {
+    title: String,
+    app_id: String,
+    pid: Option<u32>,
+    activated: bool,
+}
+
+/// The toplevels of all applications, and which of them is activated.
+pub(crate) struct ForeignToplevels<Id = ObjectId> {
+    toplevels: HashMap<Id, Toplevel>,
+    /// Changes that haven't been applied by a [`ToplevelEvent::Done`] yet.
+    pending: HashMap<Id, Toplevel>,
+    active: Option<Id>,
+}
+
+impl<Id> Default for ForeignToplevels<Id> {
+    fn default() -> Self {
+        Self {
+            toplevels: HashMap::default(),
+            pending: HashMap::default(),
+            active: None,
+        }
+    }
+}
+
+impl<Id: Clone + Eq + Hash> ForeignToplevels<Id> {
+    pub fn focused_window_info(&self) -> Option<FocusedWindowInfo> {
+        let toplevel = self.toplevels.get(self.active.as_ref()?)?;
+        Some(FocusedWindowInfo {
+            app_name: toplevel.app_id.clone(),
+            window_title: toplevel.title.clone(),
+            bundle_id: None,
+            pid: toplevel.pid,
+        })
+    }
+
+    /// Handle an event for the toplevel `id`, returning whether the focused window or its title
+    /// changed.
+    pub fn handle_event(&mut self, id: Id, event: ToplevelEvent) -> bool {
+        let pending = match self.pending.get_mut(&id) {
+            Some(pending) => pending,
+            None => {
+                let current = self.toplevels.get(&id).cloned().unwrap_or_default();
+                self.pending.entry(id.clone()).or_insert(current)
+            }
+        };
+        match event {
+            ToplevelEvent::Title(title) => pending.title = title,
+            ToplevelEvent::AppId(app_id) => pending.app_id = app_id,
+            ToplevelEvent::Pid(pid) => pending.pid = Some(pid),
+            ToplevelEvent::Activated(activated) => pending.activated = activated,
+            ToplevelEvent::Done => {
+                let before = self.focused_window_info();
+                let toplevel = self.pending.remove(&id).unwrap_or_default();
+                if toplevel.activated {
+                    self.active = Some(id.clone());
+                } else if self.active.as_ref() == Some(&id) {
+                    self.active = None;
+                }
+                self.toplevels.insert(id, toplevel);
+                return before != self.focused_window_info();
+            }
+            ToplevelEvent::Closed => {
+                self.pending.remove(&id);
+                self.toplevels.remove(&id);
+                if self.active.as_ref() == Some(&id) {
+                    self.active = None;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focused_toplevel() {
+        let mut toplevels = ForeignToplevels::<u32>::default();
+        toplevels.handle_event(1, ToplevelEvent::Title("Inbox".into()));
+        toplevels.handle_event(1, ToplevelEvent::AppId("org.gnome.Evolution".into()));
+        toplevels.handle_event(1, ToplevelEvent::wlr_activated(&2u32.to_ne_bytes()));
+        // Nothing changes until the compositor is done sending the toplevel's state.
+        assert!(toplevels.focused_window_info().is_none());
+        assert!(toplevels.handle_event(1, ToplevelEvent::Done));
+        let info = toplevels.focused_window_info().unwrap();
+        assert_eq!(
+            (info.app_name.as_str(), info.window_title.as_str()),
+            ("org.gnome.Evolution", "Inbox")
+        );
+
+        toplevels.handle_event(2, ToplevelEvent::AppId("foot".into()));
+        assert!(!toplevels.handle_event(2, ToplevelEvent::Done));
+
+        // Focus moves from one toplevel to the other.
+        toplevels.handle_event(1, ToplevelEvent::wlr_activated(&[]));
+        assert!(toplevels.handle_event(1, ToplevelEvent::Done));
+        assert!(toplevels.focused_window_info().is_none());
+        toplevels.handle_event(2, ToplevelEvent::plasma_activated(0x1));
+        toplevels.handle_event(2, ToplevelEvent::Pid(42));
+        assert!(toplevels.handle_event(2, ToplevelEvent::Done));
+        assert_eq!(toplevels.focused_window_info().unwrap().pid, Some(42));
+
+        toplevels.handle_event(2, ToplevelEvent::Title("~".into()));
+        assert!(toplevels.handle_event(2, ToplevelEvent::Done));
+        assert!(toplevels.handle_event(2, ToplevelEvent::Closed));
+        assert!(toplevels.focused_window_info().is_none());
+    }
+}
@@ -16,8 +16,8 @@ use wayland_client::WEnum;
 use wayland_client::{Proxy, protocol::wl_surface};
 use wayland_protocols::wp::viewporter::client::wp_viewport;
 use wayland_protocols::xdg::decoration::zv1::client::zxdg_toplevel_decoration_v1;
-use wayland_protocols::xdg::shell::client::xdg_surface;
 use wayland_protocols::xdg::shell::client::xdg_toplevel::{self};
+use wayland_protocols::xdg::shell::client::{xdg_popup, xdg_positioner, xdg_surface};
 use wayland_protocols::{
     wp::fractional_scale::v1::client::wp_fractional_scale_v1,
     xdg::shell::client::xdg_toplevel::XdgToplevel,
@@ -25,10 +25,11 @@ use wayland_protocols::{
 use wayland_protocols_plasma::blur::client::org_kde_kwin_blur;
 
 use crate::{
-    AnyWindowHandle, Bounds, Decorations, Globals, GpuSpecs, Modifiers, Output, Pixels,
-    PlatformDisplay, PlatformInput, Point, PromptButton, PromptLevel, RequestFrameOptions,
-    ResizeEdge, Size, Tiling, WaylandClientStatePtr, WindowAppearance, WindowBackgroundAppearance,
-    WindowBounds, WindowControlArea, WindowControls, WindowDecorations, WindowParams, px, size,
+    AnchoredFitMode, AnyWindowHandle, Bounds, Corner, Decorations, DisplayId, Globals, GpuSpecs,
+    Modifiers, Output, Pixels, PlatformDisplay, PlatformInput, Point, PopupParams, PromptButton,
+    PromptLevel, RequestFrameOptions, ResizeEdge, Size, Tiling, WaylandClientStatePtr,
+    WindowAppearance, WindowBackgroundAppearance, WindowBounds, WindowControlArea, WindowControls,
+    WindowDecorations, WindowParams, px, size,
 };
 use crate::{
     Capslock,
@@ -40,6 +41,8 @@ use crate::{
 };
 use crate::{WindowKind, scene::Scene};
 
+use super::xdg_dialog::v1::xdg_dialog_v1;
+
 #[derive(Default)]
 pub(crate) struct Callbacks {
     request_frame: Option<Box<dyn FnMut(RequestFrameOptions)>>,
@@ -90,7 +93,10 @@ pub struct WaylandWindowState {
     app_id: Option<String>,
     appearance: WindowAppearance,
     blur: Option<org_kde_kwin_blur::OrgKdeKwinBlur>,
-    toplevel: xdg_toplevel::XdgToplevel,
+    toplevel: Option<xdg_toplevel::XdgToplevel>,
+    /// Marks a [`WindowKind::Dialog`] toplevel as modal.
+    dialog: Option<xdg_dialog_v1::XdgDialogV1>,
+    popup: Option<xdg_popup::XdgPopup>,
     viewport: Option<wp_viewport::WpViewport>,
     outputs: HashMap<ObjectId, Output>,
     display: Option<(ObjectId, Output)>,
@@ -128,7 +134,9 @@ impl WaylandWindowState {
         handle: AnyWindowHandle,
         surface: wl_surface::WlSurface,
         xdg_surface: xdg_surface::XdgSurface,
-        toplevel: xdg_toplevel::XdgToplevel,
+        toplevel: Option<xdg_toplevel::XdgToplevel>,
+        dialog: Option<xdg_dialog_v1::XdgDialogV1>,
+        popup: Option<xdg_popup::XdgPopup>,
         decoration: Option<zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1>,
         appearance: WindowAppearance,
         viewport: Option<wp_viewport::WpViewport>,
@@ -166,6 +174,8 @@ impl WaylandWindowState {
             app_id: None,
             blur: None,
             toplevel,
+            dialog,
+            popup,
             viewport,
             globals,
             outputs: HashMap::default(),
@@ -245,7 +255,15 @@ impl Drop for WaylandWindow {
         if let Some(blur) = &state.blur {
             blur.release();
         }
-        state.toplevel.destroy();
+        if let Some(dialog) = &state.dialog {
+            dialog.destroy();
+        }
+        if let Some(toplevel) = &state.toplevel {
+            toplevel.destroy();
+        }
+        if let Some(popup) = &state.popup {
+            popup.destroy();
+        }
         if let Some(viewport) = &state.viewport {
             viewport.destroy();
         }
@@ -282,27 +300,44 @@ impl WaylandWindow {
         params: WindowParams,
         appearance: WindowAppearance,
         parent: Option<XdgToplevel>,
+        popup_parent: Option<(WaylandWindowStatePtr, u32)>,
     ) -> anyhow::Result<(Self, ObjectId)> {
         let surface = globals.compositor.create_surface(&globals.qh, ());
         let xdg_surface = globals
             .wm_base
             .get_xdg_surface(&surface, &globals.qh, surface.id());
-        let toplevel = xdg_surface.get_toplevel(&globals.qh, surface.id());
 
-        if params.kind == WindowKind::Floating || params.kind == WindowKind::Overlay {
-            toplevel.set_parent(parent.as_ref());
-        }
+        let (toplevel, popup) = match params.popup.zip(popup_parent) {
+            Some((popup, (parent, serial))) => {
+                let popup = create_popup(&globals, &xdg_surface, &parent, popup, &params, &surface);
+                if params.focus {
+                    popup.grab(&globals.seat, serial);
+                }
+                (None, Some(popup))
+            }
+            None => {
+                let toplevel = xdg_surface.get_toplevel(&globals.qh, surface.id());
+
+                if matches!(
+                    params.kind,
+                    WindowKind::Floating | WindowKind::Dialog | WindowKind::Overlay
+                ) {
+                    toplevel.set_parent(parent.as_ref());
+                }
 
-        if params.kind == WindowKind::Overlay {
-            log::warn!(
-                "Wayland: WindowKind::Overlay does not support true always-on-top. \
-                 Always-on-top requires compositor support (e.g. wlr-layer-shell protocol)."
-            );
-        }
+                if params.kind == WindowKind::Overlay {
+                    log::warn!(
+                        "Wayland: WindowKind::Overlay does not support true always-on-top. \
+                         Always-on-top requires compositor support (e.g. wlr-layer-shell protocol)."
+                    );
+                }
 
-        if let Some(size) = params.window_min_size {
-            toplevel.set_min_size(size.width.0 as i32, size.height.0 as i32);
-        }
+                if let Some(size) = params.window_min_size {
+                    toplevel.set_min_size(size.width.0 as i32, size.height.0 as i32);
+                }
+                (Some(toplevel), None)
+            }
+        };
 
         if let Some(fractional_scale_manager) = globals.fractional_scale_manager.as_ref() {
             fractional_scale_manager.get_fractional_scale(&surface, &globals.qh, surface.id());
@@ -312,8 +347,20 @@ impl WaylandWindow {
         let decoration = globals
             .decoration_manager
             .as_ref()
-            .map(|decoration_manager| {
-                decoration_manager.get_toplevel_decoration(&toplevel, &globals.qh, surface.id())
+            .zip(toplevel.as_ref())
+            .map(|(decoration_manager, toplevel)| {
+                decoration_manager.get_toplevel_decoration(toplevel, &globals.qh, surface.id())
+            });
+
+        let dialog = globals
+            .dialog_manager
+            .as_ref()
+            .zip(toplevel.as_ref())
+            .filter(|_| params.kind == WindowKind::Dialog && params.modal)
+            .map(|(dialog_manager, toplevel)| {
+                let dialog = dialog_manager.get_xdg_dialog(toplevel, &globals.qh, ());
+                dialog.set_modal();
+                dialog
             });
 
         let viewport = globals
@@ -329,6 +376,8 @@ impl WaylandWindow {
                 surface.clone(),
                 xdg_surface,
                 toplevel,
+                dialog,
+                popup,
                 decoration,
                 appearance,
                 viewport,
@@ -360,10 +409,21 @@ impl WaylandWindowStatePtr {
         self.state.borrow().surface.clone()
     }
 
-    pub fn toplevel(&self) -> xdg_toplevel::XdgToplevel {
+    pub fn toplevel(&self) -> Option<xdg_toplevel::XdgToplevel> {
         self.state.borrow().toplevel.clone()
     }
 
+    pub fn xdg_surface(&self) -> xdg_surface::XdgSurface {
+        self.state.borrow().xdg_surface.clone()
+    }
+
+    /// The origin of the window geometry, which popups are positioned relative to, in window
+    /// coordinates.
+    fn window_geometry_origin(&self) -> Point<Pixels> {
+        let state = self.state.borrow();
+        inset_by_tiling(state.bounds, state.inset(), state.tiling).origin - state.bounds.origin
+    }
+
     pub fn ptr_eq(&self, other: &Self) -> bool {
         Rc::ptr_eq(&self.state, &other.state)
     }
@@ -372,11 +432,18 @@ impl WaylandWindowStatePtr {
         let mut state = self.state.borrow_mut();
         state.surface.frame(&state.globals.qh, state.surface.id());
         state.resize_throttle = false;
+        let display_id = state
+            .display
+            .as_ref()
+            .map(|(id, _)| DisplayId(id.protocol_id()));
         drop(state);
 
         let mut cb = self.callbacks.borrow_mut();
         if let Some(fun) = cb.request_frame.as_mut() {
-            fun(Default::default());
+            fun(RequestFrameOptions {
+                display_id,
+                ..Default::default()
+            });
         }
     }
 
@@ -490,6 +557,26 @@ impl WaylandWindowStatePtr {
         }
     }
 
+    pub fn handle_popup_event(&self, event: xdg_popup::Event) -> bool {
+        match event {
+            xdg_popup::Event::Configure { width, height, .. } => {
+                let size =
+                    (width > 0 && height > 0).then(|| size(px(width as f32), px(height as f32)));
+                self.state.borrow_mut().in_progress_configure = Some(InProgressConfigure {
+                    size,
+                    fullscreen: false,
+                    maximized: false,
+                    resizing: false,
+                    tiling: Tiling::default(),
+                });
+                false
+            }
+            // The compositor dismissed the popup, usually because of a click outside of it.
+            xdg_popup::Event::PopupDone => true,
+            _ => false,
+        }
+    }
+
     pub fn handle_toplevel_event(&self, event: xdg_toplevel::Event) -> bool {
         match event {
             xdg_toplevel::Event::Configure {
@@ -883,11 +970,8 @@ impl PlatformWindow for WaylandWindow {
     fn display(&self) -> Option<Rc<dyn PlatformDisplay>> {
         let state = self.borrow();
         state.display.as_ref().map(|(id, display)| {
-            Rc::new(WaylandDisplay {
-                id: id.clone(),
-                name: display.name.clone(),
-                bounds: display.bounds.to_pixels(state.scale),
-            }) as Rc<dyn PlatformDisplay>
+            Rc::new(WaylandDisplay::new(id.clone(), display, state.scale))
+                as Rc<dyn PlatformDisplay>
         })
     }
 
@@ -952,12 +1036,16 @@ impl PlatformWindow for WaylandWindow {
     }
 
     fn set_title(&mut self, title: &str) {
-        self.borrow().toplevel.set_title(title.to_string());
+        if let Some(toplevel) = &self.borrow().toplevel {
+            toplevel.set_title(title.to_string());
+        }
     }
 
     fn set_app_id(&mut self, app_id: &str) {
         let mut state = self.borrow_mut();
-        state.toplevel.set_app_id(app_id.to_owned());
+        if let Some(toplevel) = &state.toplevel {
+            toplevel.set_app_id(app_id.to_owned());
+        }
         state.app_id = Some(app_id.to_owned());
     }
 
@@ -968,24 +1056,32 @@ impl PlatformWindow for WaylandWindow {
     }
 
     fn minimize(&self) {
-        self.borrow().toplevel.set_minimized();
+        if let Some(toplevel) = &self.borrow().toplevel {
+            toplevel.set_minimized();
+        }
     }
 
     fn zoom(&self) {
         let state = self.borrow();
+        let Some(toplevel) = &state.toplevel else {
+            return;
+        };
         if !state.maximized {
-            state.toplevel.set_maximized();
+            toplevel.set_maximized();
         } else {
-            state.toplevel.unset_maximized();
+            toplevel.unset_maximized();
         }
     }
 
     fn toggle_fullscreen(&self) {
-        let mut state = self.borrow_mut();
+        let state = self.borrow();
+        let Some(toplevel) = &state.toplevel else {
+            return;
+        };
         if !state.fullscreen {
-            state.toplevel.set_fullscreen(None);
+            toplevel.set_fullscreen(None);
         } else {
-            state.toplevel.unset_fullscreen();
+            toplevel.unset_fullscreen();
         }
     }
 
@@ -1049,8 +1145,11 @@ impl PlatformWindow for WaylandWindow {
 
     fn show_window_menu(&self, position: Point<Pixels>) {
         let state = self.borrow();
+        let Some(toplevel) = &state.toplevel else {
+            return;
+        };
         let serial = state.client.get_serial(SerialKind::MousePress);
-        state.toplevel.show_window_menu(
+        toplevel.show_window_menu(
             &state.globals.seat,
             serial,
             position.x.0 as i32,
@@ -1060,13 +1159,19 @@ impl PlatformWindow for WaylandWindow {
 
     fn start_window_move(&self) {
         let state = self.borrow();
+        let Some(toplevel) = &state.toplevel else {
+            return;
+        };
         let serial = state.client.get_serial(SerialKind::MousePress);
-        state.toplevel._move(&state.globals.seat, serial);
+        toplevel._move(&state.globals.seat, serial);
     }
 
     fn start_window_resize(&self, edge: crate::ResizeEdge) {
         let state = self.borrow();
-        state.toplevel.resize(
+        let Some(toplevel) = &state.toplevel else {
+            return;
+        };
+        toplevel.resize(
             &state.globals.seat,
             state.client.get_serial(SerialKind::MousePress),
             edge.to_xdg(),
@@ -1247,6 +1352,66 @@ fn compute_outer_size(
     })
 }
 
+fn create_popup(
+    globals: &Globals,
+    xdg_surface: &xdg_surface::XdgSurface,
+    parent: &WaylandWindowStatePtr,
+    popup: PopupParams,
+    params: &WindowParams,
+    surface: &wl_surface::WlSurface,
+) -> xdg_popup::XdgPopup {
+    let positioner = globals.wm_base.create_positioner(&globals.qh, ());
+    let size = params.bounds.size.map(|v| (v.0 as i32).max(1));
+    positioner.set_size(size.width, size.height);
+    let anchor = popup.anchor_rect.map(|v| v.0 as i32);
+    let origin = parent.window_geometry_origin().map(|v| v.0 as i32);
+    positioner.set_anchor_rect(
+        anchor.origin.x - origin.x,
+        anchor.origin.y - origin.y,
+        anchor.size.width.max(1),
+        anchor.size.height.max(1),
+    );
+    let (anchor, gravity) = match popup.gravity {
+        Corner::TopLeft => (
+            xdg_positioner::Anchor::TopLeft,
+            xdg_positioner::Gravity::TopLeft,
+        ),
+        Corner::TopRight => (
+            xdg_positioner::Anchor::TopRight,
+            xdg_positioner::Gravity::TopRight,
+        ),
+        Corner::BottomLeft => (
+            xdg_positioner::Anchor::BottomLeft,
+            xdg_positioner::Gravity::BottomLeft,
+        ),
+        Corner::BottomRight => (
+            xdg_positioner::Anchor::BottomRight,
+            xdg_positioner::Gravity::BottomRight,
+        ),
+    };
+    positioner.set_anchor(anchor);
+    positioner.set_gravity(gravity);
+    let slide =
+        xdg_positioner::ConstraintAdjustment::SlideX | xdg_positioner::ConstraintAdjustment::SlideY;
+    let constraint_adjustment = match popup.constraint_adjustment {
+        AnchoredFitMode::SwitchAnchor => {
+            slide
+                | xdg_positioner::ConstraintAdjustment::FlipX
+                | xdg_positioner::ConstraintAdjustment::FlipY
+        }
+        AnchoredFitMode::SnapToWindow | AnchoredFitMode::SnapToWindowWithMargin(_) => slide,
+    };
+    positioner.set_constraint_adjustment(constraint_adjustment.bits());
+    let popup = xdg_surface.get_popup(
+        Some(&parent.xdg_surface()),
+        &positioner,
+        &globals.qh,
+        surface.id(),
+    );
+    positioner.destroy();
+    popup
+}
+
 fn inset_by_tiling(mut bounds: Bounds<Pixels>, inset: Pixels, tiling: Tiling) -> Bounds<Pixels> {
     if !tiling.top {
         bounds.origin.y += inset;
@@ -0,0 +1,30 @@
+//! `xdg-dialog-v1`, which marks a toplevel as a modal dialog of its parent. It is newer than the
+//! `wayland-protocols` release we depend on, so the client code is generated here.
+
+#[allow(missing_docs)]
+pub(crate) mod v1 {
+    #![allow(
+        dead_code,
+        non_camel_case_types,
+        non_upper_case_globals,
+        unused_imports
+    )]
+    #![allow(clippy::all)]
+
+    use wayland_client;
+    use wayland_client::protocol::*;
+    use wayland_protocols::xdg::shell::client::*;
+
+    pub mod __interfaces {
+        use wayland_client::protocol::__interfaces::*;
+        use wayland_protocols::xdg::shell::client::__interfaces::*;
+        wayland_scanner::generate_interfaces!(
+            "src/platform/linux/wayland/protocols/xdg-dialog-v1.xml"
+        );
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!(
+        "src/platform/linux/wayland/protocols/xdg-dialog-v1.xml"
+    );
+}
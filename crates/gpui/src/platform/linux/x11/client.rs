@@ -58,6 +58,7 @@ use crate::platform::{
         reveal_path_internal,
         xdg_desktop_portal::{Event as XDPEvent, XDPEventSource},
     },
+    window_positioner::popup_bounds,
 };
 use crate::{
     AnyWindowHandle, Bounds, ClipboardItem, CursorStyle, DisplayId, FileDropEvent, Keystroke,
@@ -189,8 +190,15 @@ pub struct X11ClientState {
     pub(crate) _resource_database: Database,
     pub(crate) atoms: XcbAtoms,
     pub(crate) windows: HashMap<xproto::Window, WindowRef>,
+    /// The displays of the X11 screens, queried when first needed and again after they change.
+    displays: HashMap<usize, Rc<X11Display>>,
     pub(crate) mouse_focused_window: Option<xproto::Window>,
     pub(crate) keyboard_focused_window: Option<xproto::Window>,
+    watching_focused_window: bool,
+    /// The active window of another application, whose title changes are selected.
+    watched_active_window: Option<xproto::Window>,
+    /// The focused window last reported, so that only changes are reported.
+    reported_focused_window: Option<crate::FocusedWindowInfo>,
     pub(crate) xkb: xkbc::State,
     keyboard_layout: LinuxKeyboardLayout,
     pub(crate) ximc: Option<X11rbClient<Rc<XCBConnection>>>,
@@ -220,6 +228,67 @@ pub struct X11ClientState {
     pub(crate) global_hotkey: crate::platform::linux::global_hotkey::x11::X11GlobalHotkey,
 }
 
+impl X11ClientState {
+    fn display(&mut self, x_screen_index: usize) -> anyhow::Result<Rc<X11Display>> {
+        if let Some(display) = self.displays.get(&x_screen_index) {
+            return Ok(display.clone());
+        }
+        let display = Rc::new(X11Display::new(
+            &self.xcb_connection,
+            &self.atoms,
+            self.scale_factor,
+            x_screen_index,
+        )?);
+        self.displays.insert(x_screen_index, display.clone());
+        Ok(display)
+    }
+
+    fn active_window(&self) -> Option<xproto::Window> {
+        let root = self.xcb_connection.setup().roots[self.x_root_index].root;
+        let reply = self
+            .xcb_connection
+            .get_property(
+                false,
+                root,
+                self.atoms._NET_ACTIVE_WINDOW,
+                xproto::AtomEnum::WINDOW,
+                0,
+                1,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+        reply
+            .value
+            .chunks_exact(4)
+            .next()
+            .and_then(|chunk| chunk.try_into().ok().map(u32::from_ne_bytes))
+            .filter(|&xid| xid != 0)
+    }
+
+    /// Select property changes of `window` to see its title change, unless it's one of ours,
+    /// which already selects them.
+    fn watch_active_window(&mut self, window: Option<xproto::Window>) {
+        if self.watched_active_window == window {
+            return;
+        }
+        for (window, event_mask) in [
+            (self.watched_active_window, EventMask::NO_EVENT),
+            (window, EventMask::PROPERTY_CHANGE),
+        ] {
+            if let Some(window) = window.filter(|window| !self.windows.contains_key(window)) {
+                self.xcb_connection
+                    .change_window_attributes(
+                        window,
+                        &ChangeWindowAttributesAux::new().event_mask(event_mask),
+                    )
+                    .log_err();
+            }
+        }
+        self.watched_active_window = window;
+    }
+}
+
 #[derive(Clone)]
 pub struct X11ClientStatePtr(pub Weak<RefCell<X11ClientState>>);
 
@@ -354,6 +423,25 @@ impl X11Client {
             .context("Failed to get XCB atoms")?;
 
         let root = xcb_connection.setup().roots[0].root;
+        // Watch `_NET_ACTIVE_WINDOW` to report focused window changes, and `_NET_WORKAREA` to
+        // report panels changing the visible bounds of the display.
+        check_reply(
+            || "Failed to select root window property changes",
+            xcb_connection.change_window_attributes(
+                xcb_connection.setup().roots[x_root_index].root,
+                &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+            ),
+        )
+        .log_err();
+        // Watch for monitors being connected, disconnected or rearranged.
+        check_reply(
+            || "Failed to select RandR screen changes",
+            xcb_connection.randr_select_input(
+                xcb_connection.setup().roots[x_root_index].root,
+                randr::NotifyMask::SCREEN_CHANGE,
+            ),
+        )
+        .log_err();
         let compositor_present = check_compositor_present(&xcb_connection, root);
         let gtk_frame_extents_supported =
             check_gtk_frame_extents_supported(&xcb_connection, &atoms, root);
@@ -495,8 +583,12 @@ impl X11Client {
             _resource_database: resource_database,
             atoms,
             windows: HashMap::default(),
+            displays: HashMap::default(),
             mouse_focused_window: None,
             keyboard_focused_window: None,
+            watching_focused_window: false,
+            watched_active_window: None,
+            reported_focused_window: None,
             xkb: xkb_state,
             keyboard_layout,
             ximc,
@@ -750,10 +842,15 @@ impl X11Client {
             }
             Event::MapNotify(event) => {
                 let mut state = self.0.borrow_mut();
-                if let Some(window_ref) = state.windows.get_mut(&event.window) {
+                let window = state.windows.get_mut(&event.window).map(|window_ref| {
                     window_ref.is_mapped = true;
-                }
+                    window_ref.window.clone()
+                });
                 state.update_refresh_loop(event.window);
+                drop(state);
+                if let Some(window) = window {
+                    window.handle_map();
+                }
             }
             Event::VisibilityNotify(event) => {
                 let mut state = self.0.borrow_mut();
@@ -900,7 +997,34 @@ impl X11Client {
                     .context("X11: Failed to set window bounds")
                     .log_err();
             }
+            Event::RandrScreenChangeNotify(_) => {
+                self.0.borrow_mut().displays.clear();
+                self.handle_displays_changed();
+            }
             Event::PropertyNotify(event) => {
+                let state = self.0.borrow();
+                if event.window == state.xcb_connection.setup().roots[state.x_root_index].root {
+                    let active_window_changed = event.atom == state.atoms._NET_ACTIVE_WINDOW;
+                    let work_area_changed = event.atom == state.atoms._NET_WORKAREA;
+                    let icc_profile_changed = event.atom == state.atoms._ICC_PROFILE;
+                    drop(state);
+                    if active_window_changed {
+                        self.handle_focused_window_change();
+                    }
+                    if work_area_changed || icc_profile_changed {
+                        self.0.borrow_mut().displays.clear();
+                    }
+                    if work_area_changed {
+                        self.handle_displays_changed();
+                    }
+                    return Some(());
+                }
+                let title_changed = Some(event.window) == state.watched_active_window
+                    && event.atom == state.atoms._NET_WM_NAME;
+                drop(state);
+                if title_changed {
+                    self.handle_focused_window_change();
+                }
                 let window = self.get_window(event.window)?;
                 window
                     .property_notify(event)
@@ -1008,7 +1132,7 @@ impl X11Client {
                     if let Some(media_event) =
                         crate::platform::linux::keysym_to_media_key(keysym)
                     {
-                        if let Some(cb) = state.common.callbacks.media_key.as_mut() {
+                        if let Some(cb) = state.common.callbacks.media_key.borrow_mut().as_mut() {
                             cb(media_event);
                         }
                         return Some(());
@@ -1138,6 +1262,11 @@ impl X11Client {
                             click_count: current_count,
                             first_mouse: false,
                         }));
+                        // A window that grabs input keeps the keyboard focus, even when the user
+                        // clicks elsewhere.
+                        if window.is_outside_grab(position) {
+                            window.set_active(false);
+                        }
                     }
                     Some(ButtonOrScroll::Scroll(direction)) => {
                         drop(state);
@@ -1390,6 +1519,39 @@ impl X11Client {
             }
         }
     }
+
+    fn handle_displays_changed(&self) {
+        let mut state = self.0.borrow_mut();
+        if let Some(mut callback) = state.common.callbacks.displays_changed.take() {
+            drop(state);
+            callback();
+            state = self.0.borrow_mut();
+            state.common.callbacks.displays_changed = Some(callback);
+        }
+    }
+
+    /// Report the focused window if it or its title changed since it was last reported.
+    fn handle_focused_window_change(&self) {
+        let mut state = self.0.borrow_mut();
+        if !state.watching_focused_window {
+            return;
+        }
+        let active_window = state.active_window();
+        state.watch_active_window(active_window);
+        drop(state);
+        let info = LinuxClient::focused_window_info(self);
+        let mut state = self.0.borrow_mut();
+        if info == state.reported_focused_window {
+            return;
+        }
+        state.reported_focused_window = info.clone();
+        if let Some(mut callback) = state.common.callbacks.focused_window_change.take() {
+            drop(state);
+            callback(info);
+            state = self.0.borrow_mut();
+            state.common.callbacks.focused_window_change = Some(callback);
+        }
+    }
 }
 
 impl LinuxClient for X11Client {
@@ -1407,37 +1569,27 @@ impl LinuxClient for X11Client {
     }
 
     fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
-        let state = self.0.borrow();
-        let setup = state.xcb_connection.setup();
-        setup
-            .roots
-            .iter()
-            .enumerate()
-            .filter_map(|(root_id, _)| {
-                Some(Rc::new(
-                    X11Display::new(&state.xcb_connection, state.scale_factor, root_id).ok()?,
-                ) as Rc<dyn PlatformDisplay>)
-            })
+        let mut state = self.0.borrow_mut();
+        (0..state.xcb_connection.setup().roots.len())
+            .filter_map(|root_id| Some(state.display(root_id).ok()? as Rc<dyn PlatformDisplay>))
             .collect()
     }
 
     fn primary_display(&self) -> Option<Rc<dyn PlatformDisplay>> {
-        let state = self.0.borrow();
-        X11Display::new(
-            &state.xcb_connection,
-            state.scale_factor,
-            state.x_root_index,
-        )
-        .log_err()
-        .map(|display| Rc::new(display) as Rc<dyn PlatformDisplay>)
+        let mut state = self.0.borrow_mut();
+        let x_root_index = state.x_root_index;
+        state
+            .display(x_root_index)
+            .log_err()
+            .map(|display| display as Rc<dyn PlatformDisplay>)
     }
 
     fn display(&self, id: DisplayId) -> Option<Rc<dyn PlatformDisplay>> {
-        let state = self.0.borrow();
-
-        Some(Rc::new(
-            X11Display::new(&state.xcb_connection, state.scale_factor, id.0 as usize).ok()?,
-        ))
+        self.0
+            .borrow_mut()
+            .display(id.0 as usize)
+            .ok()
+            .map(|display| display as Rc<dyn PlatformDisplay>)
     }
 
     #[cfg(feature = "screen-capture")]
@@ -1458,18 +1610,56 @@ impl LinuxClient for X11Client {
     fn open_window(
         &self,
         handle: AnyWindowHandle,
-        params: WindowParams,
+        mut params: WindowParams,
     ) -> anyhow::Result<Box<dyn PlatformWindow>> {
         let mut state = self.0.borrow_mut();
-        let parent_window = state
+        let mut parent_window = state
             .keyboard_focused_window
             .and_then(|focused_window| state.windows.get(&focused_window))
             .map(|window| window.window.x_window);
+        if let Some(popup) = params.popup {
+            let parent = state
+                .windows
+                .values()
+                .find(|window| window.handle() == popup.parent)
+                .map(|window| window.window.x_window)
+                .ok_or_else(|| anyhow!("X11: popup parent window not found"))?;
+            let parent_origin = get_reply(
+                || "X11 TranslateCoordinates for popup parent failed.",
+                state.xcb_connection.translate_coordinates(
+                    parent,
+                    state.xcb_connection.setup().roots[state.x_root_index].root,
+                    0,
+                    0,
+                ),
+            )?;
+            let parent_origin = point(
+                px(parent_origin.dst_x as f32 / state.scale_factor),
+                px(parent_origin.dst_y as f32 / state.scale_factor),
+            );
+            let x_root_index = state.x_root_index;
+            let display = state.display(x_root_index)?;
+            params.bounds = popup_bounds(
+                Bounds::new(
+                    popup.anchor_rect.origin + parent_origin,
+                    popup.anchor_rect.size,
+                ),
+                popup.gravity,
+                params.bounds.size,
+                display.visible_bounds(),
+                popup.constraint_adjustment,
+            );
+            parent_window = Some(parent);
+        }
         let x_window = state
             .xcb_connection
             .generate_id()
             .context("X11: Failed to generate window ID")?;
 
+        let x_screen_index = params
+            .display_id
+            .map_or(state.x_root_index, |did| did.0 as usize);
+        let display = state.display(x_screen_index)?;
         let window = X11Window::new(
             handle,
             X11ClientStatePtr(Rc::downgrade(&self.0)),
@@ -1478,7 +1668,7 @@ impl LinuxClient for X11Client {
             params,
             &state.xcb_connection,
             state.client_side_decorations_supported,
-            state.x_root_index,
+            display,
             x_window,
             &state.atoms,
             state.scale_factor,
@@ -1684,31 +1874,7 @@ impl LinuxClient for X11Client {
 
     fn focused_window_info(&self) -> Option<crate::FocusedWindowInfo> {
         let state = self.0.borrow();
-        let root = state.xcb_connection.setup().roots[state.x_root_index].root;
-
-        let active_window_reply = state
-            .xcb_connection
-            .get_property(
-                false,
-                root,
-                state.atoms._NET_ACTIVE_WINDOW,
-                xproto::AtomEnum::WINDOW,
-                0,
-                1,
-            )
-            .ok()?
-            .reply()
-            .ok()?;
-
-        let active_xid = active_window_reply
-            .value
-            .chunks_exact(4)
-            .next()
-            .and_then(|chunk| chunk.try_into().ok().map(u32::from_ne_bytes))?;
-
-        if active_xid == 0 {
-            return None;
-        }
+        let active_xid = state.active_window()?;
 
         let title = state
             .xcb_connection
@@ -1761,10 +1927,33 @@ impl LinuxClient for X11Client {
         })
     }
 
-    fn set_tray_icon(&self, icon: Option<&[u8]>) {
+    fn watch_focused_window(&self, watch: bool) {
+        let mut state = self.0.borrow_mut();
+        state.watching_focused_window = watch;
+        if watch {
+            let active_window = state.active_window();
+            state.watch_active_window(active_window);
+            drop(state);
+            let info = LinuxClient::focused_window_info(self);
+            self.0.borrow_mut().reported_focused_window = info;
+        } else {
+            state.watch_active_window(None);
+            state.reported_focused_window = None;
+        }
+    }
+
+    fn set_tray_icon(&self, icon: Option<crate::TrayIconFrame>) {
         self.0.borrow_mut().tray.set_icon(icon);
     }
 
+    fn set_tray_attention(&self, icon: Option<crate::TrayIconFrame>) {
+        self.0.borrow_mut().tray.set_attention(icon);
+    }
+
+    fn set_tray_overlay(&self, icon: Option<crate::TrayIconFrame>) {
+        self.0.borrow_mut().tray.set_overlay(icon);
+    }
+
     fn set_tray_menu(&self, menu: Vec<crate::TrayMenuItem>) {
         self.0.borrow_mut().tray.set_menu(menu);
     }
@@ -1773,6 +1962,18 @@ impl LinuxClient for X11Client {
         self.0.borrow_mut().tray.set_tooltip(tooltip);
     }
 
+    /// The point where the tray icon was last clicked, as the host doesn't report the icon's
+    /// bounds.
+    fn tray_icon_bounds(&self) -> Option<Bounds<Pixels>> {
+        let state = self.0.borrow();
+        let (x, y) = state.tray.activation_position()?;
+        let position = point(
+            px(x as f32 / state.scale_factor),
+            px(y as f32 / state.scale_factor),
+        );
+        Some(Bounds::new(position, Size::default()))
+    }
+
     fn register_global_hotkey(&self, id: u32, keystroke: &Keystroke) -> crate::Result<()> {
         let mut state = self.0.borrow_mut();
         let xcb = state.xcb_connection.clone();
@@ -1938,6 +2139,7 @@ impl X11ClientState {
                     let xcb_connection = {
                         let mut state = client.0.borrow_mut();
                         let xcb_connection = state.xcb_connection.clone();
+                        let display_id = DisplayId(state.x_root_index as u32);
                         if let Some(window) = state.windows.get_mut(&x_window) {
                             let expose_event_received = window.expose_event_received;
                             window.expose_event_received = false;
@@ -1946,6 +2148,7 @@ impl X11ClientState {
                             window.refresh(RequestFrameOptions {
                                 require_presentation: expose_event_received,
                                 force_render: false,
+                                display_id: Some(display_id),
                             });
                         }
                         xcb_connection
@@ -1,19 +1,41 @@
 use anyhow::Context as _;
 use uuid::Uuid;
-use x11rb::{connection::Connection as _, xcb_ffi::XCBConnection};
+use x11rb::{
+    connection::Connection as _,
+    protocol::{
+        randr::{self, ConnectionExt as _},
+        xproto::{self, ConnectionExt as _},
+    },
+    xcb_ffi::XCBConnection,
+};
 
-use crate::{Bounds, DisplayId, Pixels, PlatformDisplay, Size, px};
+use crate::{Bounds, DisplayId, DisplayRotation, Pixels, PlatformDisplay, Size, point, px, size};
+
+use super::{XcbAtoms, get_reply};
 
 #[derive(Debug)]
 pub(crate) struct X11Display {
     x_screen_index: usize,
     bounds: Bounds<Pixels>,
+    visible_bounds: Bounds<Pixels>,
+    monitor: MonitorInfo,
+    icc_profile: Option<Vec<u8>>,
     uuid: Uuid,
 }
 
+/// What RandR reports about the monitor showing a screen.
+#[derive(Debug, Default)]
+struct MonitorInfo {
+    refresh_rate: Option<f32>,
+    physical_size: Option<Size<u32>>,
+    rotation: DisplayRotation,
+    is_hdr: bool,
+}
+
 impl X11Display {
     pub(crate) fn new(
         xcb: &XCBConnection,
+        atoms: &XcbAtoms,
         scale_factor: f32,
         x_screen_index: usize,
     ) -> anyhow::Result<Self> {
@@ -22,6 +44,12 @@ impl X11Display {
             .roots
             .get(x_screen_index)
             .with_context(|| format!("No screen found with index {x_screen_index}"))?;
+        let screen_size = size(
+            screen.width_in_pixels as i32,
+            screen.height_in_pixels as i32,
+        );
+        let work_area = work_area(xcb, atoms, screen.root)
+            .unwrap_or_else(|| Bounds::new(point(0, 0), screen_size));
         Ok(Self {
             x_screen_index,
             bounds: Bounds {
@@ -31,6 +59,18 @@ impl X11Display {
                     height: px(screen.height_in_pixels as f32 / scale_factor),
                 },
             },
+            visible_bounds: Bounds {
+                origin: point(
+                    px(work_area.origin.x as f32 / scale_factor),
+                    px(work_area.origin.y as f32 / scale_factor),
+                ),
+                size: Size {
+                    width: px(work_area.size.width as f32 / scale_factor),
+                    height: px(work_area.size.height as f32 / scale_factor),
+                },
+            },
+            monitor: primary_monitor_info(xcb, atoms, screen.root).unwrap_or_default(),
+            icc_profile: icc_profile(xcb, atoms, screen.root),
             uuid: Uuid::from_bytes([0; 16]),
         })
     }
@@ -48,4 +88,318 @@ impl PlatformDisplay for X11Display {
     fn bounds(&self) -> Bounds<Pixels> {
         self.bounds
     }
+
+    fn visible_bounds(&self) -> Bounds<Pixels> {
+        self.visible_bounds
+    }
+
+    fn refresh_rate(&self) -> Option<f32> {
+        self.monitor.refresh_rate
+    }
+
+    fn physical_size(&self) -> Option<Size<u32>> {
+        self.monitor.physical_size
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.monitor.rotation
+    }
+
+    fn is_hdr(&self) -> bool {
+        self.monitor.is_hdr
+    }
+
+    fn icc_profile(&self) -> Option<Vec<u8>> {
+        self.icc_profile.clone()
+    }
+}
+
+/// Describe the primary RandR output of a screen, or the first one that is showing something if
+/// none is primary. An X11 screen spans all monitors, so this is the one windows open on.
+fn primary_monitor_info(
+    xcb: &XCBConnection,
+    atoms: &XcbAtoms,
+    root: xproto::Window,
+) -> Option<MonitorInfo> {
+    let resources = get_reply(
+        || "Failed to get screen resources",
+        xcb.randr_get_screen_resources_current(root),
+    )
+    .ok()?;
+    let primary = get_reply(
+        || "Failed to get primary output",
+        xcb.randr_get_output_primary(root),
+    )
+    .ok()
+    .map(|reply| reply.output)
+    .filter(|&output| output != x11rb::NONE);
+    primary
+        .into_iter()
+        .chain(resources.outputs.iter().copied())
+        .find_map(|output_id| {
+            let output = get_reply(
+                || "Failed to get output info",
+                xcb.randr_get_output_info(output_id, resources.config_timestamp),
+            )
+            .ok()
+            .filter(|output| output.crtc != x11rb::NONE)?;
+            let crtc = get_reply(
+                || "Failed to get CRTC info",
+                xcb.randr_get_crtc_info(output.crtc, resources.config_timestamp),
+            )
+            .ok()?;
+            let rotation = if crtc.rotation.contains(randr::Rotation::ROTATE90) {
+                DisplayRotation::Rotate90
+            } else if crtc.rotation.contains(randr::Rotation::ROTATE180) {
+                DisplayRotation::Rotate180
+            } else if crtc.rotation.contains(randr::Rotation::ROTATE270) {
+                DisplayRotation::Rotate270
+            } else {
+                DisplayRotation::Normal
+            };
+            Some(MonitorInfo {
+                refresh_rate: resources
+                    .modes
+                    .iter()
+                    .find(|mode| mode.id == crtc.mode)
+                    .and_then(mode_refresh_rate_hz),
+                physical_size: (output.mm_width > 0 && output.mm_height > 0)
+                    .then(|| size(output.mm_width, output.mm_height)),
+                rotation,
+                is_hdr: edid(xcb, atoms, output_id).is_some_and(|edid| edid_supports_hdr(&edid)),
+            })
+        })
+}
+
+/// The EDID the monitor connected to a RandR output reports.
+fn edid(xcb: &XCBConnection, atoms: &XcbAtoms, output: randr::Output) -> Option<Vec<u8>> {
+    let reply = get_reply(
+        || "Failed to get EDID",
+        xcb.randr_get_output_property(
+            output,
+            atoms.EDID,
+            xproto::AtomEnum::ANY,
+            0,
+            // In 32-bit units, enough for the base block and all 255 extension blocks.
+            256 * 128 / 4,
+            false,
+            false,
+        ),
+    )
+    .ok()?;
+    (reply.format == 8 && !reply.data.is_empty()).then_some(reply.data)
+}
+
+/// Whether an EDID has a CTA-861 HDR Static Metadata Data Block that lists the SMPTE ST 2084 (PQ)
+/// or Hybrid Log-Gamma transfer function.
+fn edid_supports_hdr(edid: &[u8]) -> bool {
+    const EDID_HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+    const CTA_EXTENSION_TAG: u8 = 0x02;
+    const EXTENDED_TAG: u8 = 7;
+    const HDR_STATIC_METADATA_TAG: u8 = 6;
+    const EOTF_ST2084: u8 = 1 << 2;
+    const EOTF_HLG: u8 = 1 << 3;
+
+    if !edid.starts_with(&EDID_HEADER) {
+        return false;
+    }
+    edid.chunks_exact(128)
+        .skip(1)
+        .filter(|extension| extension[0] == CTA_EXTENSION_TAG)
+        .any(|extension| {
+            // Data blocks run from byte 4 to the detailed timing descriptors.
+            let end = (extension[2] as usize).clamp(4, 127);
+            let mut blocks = &extension[4..end];
+            while let Some((&header, rest)) = blocks.split_first() {
+                let length = (header & 0x1f) as usize;
+                let Some(payload) = rest.get(..length) else {
+                    break;
+                };
+                if header >> 5 == EXTENDED_TAG
+                    && let [HDR_STATIC_METADATA_TAG, eotfs, ..] = *payload
+                    && eotfs & (EOTF_ST2084 | EOTF_HLG) != 0
+                {
+                    return true;
+                }
+                blocks = &rest[length..];
+            }
+            false
+        })
+}
+
+fn mode_refresh_rate_hz(mode: &randr::ModeInfo) -> Option<f32> {
+    let mut lines = mode.vtotal as f32;
+    if mode.mode_flags.contains(randr::ModeFlag::DOUBLE_SCAN) {
+        lines *= 2.;
+    }
+    if mode.mode_flags.contains(randr::ModeFlag::INTERLACE) {
+        lines /= 2.;
+    }
+    (mode.dot_clock > 0 && mode.htotal > 0 && mode.vtotal > 0)
+        .then(|| mode.dot_clock as f32 / (mode.htotal as f32 * lines))
+}
+
+/// The ICC profile color managers attach to a screen's root window, following the ICC Profiles in
+/// X Specification.
+fn icc_profile(xcb: &XCBConnection, atoms: &XcbAtoms, root: xproto::Window) -> Option<Vec<u8>> {
+    let reply = get_reply(
+        || "Failed to get _ICC_PROFILE",
+        xcb.get_property(
+            false,
+            root,
+            atoms._ICC_PROFILE,
+            xproto::AtomEnum::ANY,
+            0,
+            u32::MAX,
+        ),
+    )
+    .ok()?;
+    (reply.format == 8 && !reply.value.is_empty()).then_some(reply.value)
+}
+
+/// The area of the screen not covered by panels and docks, in device pixels. The window manager
+/// publishes it as `_NET_WORKAREA`; without one, it's computed from the struts the panels reserve.
+fn work_area(xcb: &XCBConnection, atoms: &XcbAtoms, root: xproto::Window) -> Option<Bounds<i32>> {
+    let cardinals = |window, property, length| -> Option<Vec<u32>> {
+        let reply = get_reply(
+            || "Failed to get window property",
+            xcb.get_property(
+                false,
+                window,
+                property,
+                xproto::AtomEnum::CARDINAL,
+                0,
+                length,
+            ),
+        )
+        .ok()?;
+        Some(reply.value32()?.collect())
+    };
+
+    let desktop = cardinals(root, atoms._NET_CURRENT_DESKTOP, 1)
+        .and_then(|value| value.first().copied())
+        .unwrap_or(0) as usize;
+    // `_NET_WORKAREA` holds one rectangle per desktop.
+    if let Some(work_areas) = cardinals(root, atoms._NET_WORKAREA, 1024) {
+        let work_area = work_areas
+            .chunks_exact(4)
+            .nth(desktop)
+            .or_else(|| work_areas.chunks_exact(4).next());
+        if let Some(&[x, y, width, height]) = work_area
+            && width > 0
+            && height > 0
+        {
+            return Some(Bounds::new(
+                point(x as i32, y as i32),
+                size(width as i32, height as i32),
+            ));
+        }
+    }
+
+    let geometry = get_reply(
+        || "Failed to get root window geometry",
+        xcb.get_geometry(root),
+    )
+    .ok()?;
+    let clients = get_reply(
+        || "Failed to get _NET_CLIENT_LIST",
+        xcb.get_property(
+            false,
+            root,
+            atoms._NET_CLIENT_LIST,
+            xproto::AtomEnum::WINDOW,
+            0,
+            u32::MAX,
+        ),
+    )
+    .ok()?;
+    let struts = clients.value32()?.filter_map(|window| {
+        let strut = cardinals(window, atoms._NET_WM_STRUT_PARTIAL, 12)
+            .filter(|strut| strut.len() >= 4)
+            .or_else(|| cardinals(window, atoms._NET_WM_STRUT, 4))?;
+        strut.get(..4)?.try_into().ok()
+    });
+    Some(work_area_from_struts(
+        size(geometry.width as i32, geometry.height as i32),
+        struts,
+    ))
+}
+
+/// Shrink a screen by the `[left, right, top, bottom]` space panels reserve along its edges.
+fn work_area_from_struts(
+    screen: Size<i32>,
+    struts: impl IntoIterator<Item = [u32; 4]>,
+) -> Bounds<i32> {
+    let [left, right, top, bottom] = struts
+        .into_iter()
+        .fold([0; 4], |reserved: [u32; 4], strut| {
+            std::array::from_fn(|edge| reserved[edge].max(strut[edge]))
+        });
+    let [left, right, top, bottom] = [left, right, top, bottom].map(|edge| edge as i32);
+    Bounds::new(
+        point(left.min(screen.width), top.min(screen.height)),
+        size(
+            (screen.width - left - right).max(0),
+            (screen.height - top - bottom).max(0),
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_refresh_rate_hz() {
+        // 1920x1080 at 60Hz with CVT reduced blanking.
+        let mode = randr::ModeInfo {
+            dot_clock: 138_500_000,
+            htotal: 2080,
+            vtotal: 1111,
+            ..Default::default()
+        };
+        let refresh_rate = mode_refresh_rate_hz(&mode).unwrap();
+        assert!((refresh_rate - 59.93).abs() < 0.01, "{refresh_rate}");
+        let interlaced = randr::ModeInfo {
+            mode_flags: randr::ModeFlag::INTERLACE,
+            ..mode
+        };
+        assert!((mode_refresh_rate_hz(&interlaced).unwrap() - 119.87).abs() < 0.01);
+        assert_eq!(mode_refresh_rate_hz(&randr::ModeInfo::default()), None);
+    }
+
+    #[test]
+    fn test_edid_supports_hdr() {
+        let mut edid = vec![0; 256];
+        edid[..8].copy_from_slice(&[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00]);
+        edid[126] = 1;
+        let cta = &mut edid[128..];
+        cta[..4].copy_from_slice(&[0x02, 0x03, 12, 0]);
+        // A video data block, then an HDR static metadata block listing SDR and PQ.
+        cta[4..12].copy_from_slice(&[0x42, 0x10, 0x04, 0xe3, 0x06, 0b0101, 0x01, 0x00]);
+        assert!(edid_supports_hdr(&edid));
+
+        edid[128 + 9] = 0b0001;
+        assert!(!edid_supports_hdr(&edid));
+        assert!(!edid_supports_hdr(&edid[..128]));
+        assert!(!edid_supports_hdr(&[]));
+    }
+
+    #[test]
+    fn test_work_area_from_struts() {
+        let screen = size(1920, 1080);
+        assert_eq!(
+            work_area_from_struts(screen, []),
+            Bounds::new(point(0, 0), screen)
+        );
+        // A top panel and a dock on the left. Overlapping panels reserve the larger strut.
+        assert_eq!(
+            work_area_from_struts(screen, [[0, 0, 32, 0], [64, 0, 0, 0], [0, 0, 24, 0]]),
+            Bounds::new(point(64, 32), size(1856, 1048))
+        );
+        assert_eq!(
+            work_area_from_struts(screen, [[0, 0, 0, 2000]]),
+            Bounds::new(point(0, 0), size(1920, 0))
+        );
+    }
 }
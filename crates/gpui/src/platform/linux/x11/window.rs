@@ -67,6 +67,7 @@ x11rb::atom_manager! {
         _NET_WM_STATE_HIDDEN,
         _NET_WM_STATE_FOCUSED,
         _NET_WM_STATE_ABOVE,
+        _NET_WM_STATE_MODAL,
         _NET_ACTIVE_WINDOW,
         _NET_WM_SYNC_REQUEST,
         _NET_WM_SYNC_REQUEST_COUNTER,
@@ -76,6 +77,7 @@ x11rb::atom_manager! {
         _NET_WM_WINDOW_TYPE_NOTIFICATION,
         _NET_WM_WINDOW_TYPE_DIALOG,
         _NET_WM_WINDOW_TYPE_DOCK,
+        _NET_WM_WINDOW_TYPE_POPUP_MENU,
         _NET_WM_SYNC,
         _NET_WM_STATE_DEMANDS_ATTENTION,
         _NET_SUPPORTED,
@@ -84,6 +86,13 @@ x11rb::atom_manager! {
         _GTK_FRAME_EXTENTS,
         _GTK_EDGE_CONSTRAINTS,
         _NET_CLIENT_LIST_STACKING,
+        _NET_CLIENT_LIST,
+        _NET_CURRENT_DESKTOP,
+        _NET_WORKAREA,
+        _NET_WM_STRUT,
+        _NET_WM_STRUT_PARTIAL,
+        _ICC_PROFILE,
+        EDID,
     }
 }
 
@@ -276,6 +285,7 @@ pub struct X11WindowState {
     edge_constraints: Option<EdgeConstraints>,
     pub handle: AnyWindowHandle,
     last_insets: [u32; 4],
+    grabs_input: bool,
 }
 
 impl X11WindowState {
@@ -392,16 +402,14 @@ impl X11WindowState {
         params: WindowParams,
         xcb: &Rc<XCBConnection>,
         client_side_decorations_supported: bool,
-        x_main_screen_index: usize,
+        display: Rc<X11Display>,
         x_window: xproto::Window,
         atoms: &XcbAtoms,
         scale_factor: f32,
         appearance: WindowAppearance,
         parent_window: Option<xproto::Window>,
     ) -> anyhow::Result<Self> {
-        let x_screen_index = params
-            .display_id
-            .map_or(x_main_screen_index, |did| did.0 as usize);
+        let x_screen_index = display.id().0 as usize;
 
         let visual_set = find_visuals(xcb, x_screen_index);
 
@@ -426,7 +434,8 @@ impl X11WindowState {
             id
         };
 
-        let win_aux = xproto::CreateWindowAux::new()
+        let is_popup = params.popup.is_some();
+        let mut win_aux = xproto::CreateWindowAux::new()
             // https://stackoverflow.com/questions/43218127/x11-xlib-xcb-creating-a-window-requires-border-pixel-if-specifying-colormap-wh
             .border_pixel(visual_set.black_pixel)
             .colormap(colormap)
@@ -439,6 +448,10 @@ impl X11WindowState {
                     | xproto::EventMask::PROPERTY_CHANGE
                     | xproto::EventMask::VISIBILITY_CHANGE,
             );
+        if is_popup {
+            // Popups are placed by us rather than the window manager, and dismiss themselves.
+            win_aux = win_aux.override_redirect(1).save_under(1);
+        }
 
         let mut bounds = params.bounds.to_device_pixels(scale_factor);
         if bounds.size.width.0 == 0 || bounds.size.height.0 == 0 {
@@ -450,6 +463,7 @@ impl X11WindowState {
             bounds.size.width = 800.into();
             bounds.size.height = 600.into();
         }
+        let x_offset = if is_popup { 0 } else { 2 };
 
         check_reply(
             || {
@@ -458,7 +472,7 @@ impl X11WindowState {
                     visual.depth,
                     x_window,
                     visual_set.root,
-                    bounds.origin.x.0 + 2,
+                    bounds.origin.x.0 + x_offset,
                     bounds.origin.y.0,
                     bounds.size.width.0,
                     bounds.size.height.0
@@ -468,7 +482,7 @@ impl X11WindowState {
                 visual.depth,
                 x_window,
                 visual_set.root,
-                (bounds.origin.x.0 + 2) as i16,
+                (bounds.origin.x.0 + x_offset) as i16,
                 bounds.origin.y.0 as i16,
                 bounds.size.width.0 as u16,
                 bounds.size.height.0 as u16,
@@ -509,7 +523,7 @@ impl X11WindowState {
             }
 
             let reply = get_reply(|| "X11 GetGeometry failed.", xcb.get_geometry(x_window))?;
-            if reply.x == 0 && reply.y == 0 {
+            if reply.x == 0 && reply.y == 0 && !is_popup {
                 bounds.origin.x.0 += 2;
                 // Work around a bug where our rendered content appears
                 // outside the window bounds when opened at the default position
@@ -549,7 +563,33 @@ impl X11WindowState {
                 )?;
             }
 
-            if params.kind == WindowKind::Floating {
+            if is_popup {
+                // Compositors use WM_TRANSIENT_FOR to stack and shade the popup with its parent.
+                if let Some(parent_window) = parent_window {
+                    check_reply(
+                        || "X11 ChangeProperty32 setting WM_TRANSIENT_FOR for popup failed.",
+                        xcb.change_property32(
+                            xproto::PropMode::REPLACE,
+                            x_window,
+                            atoms.WM_TRANSIENT_FOR,
+                            xproto::AtomEnum::WINDOW,
+                            &[parent_window],
+                        ),
+                    )?;
+                }
+                check_reply(
+                    || "X11 ChangeProperty32 setting window type for popup failed.",
+                    xcb.change_property32(
+                        xproto::PropMode::REPLACE,
+                        x_window,
+                        atoms._NET_WM_WINDOW_TYPE,
+                        xproto::AtomEnum::ATOM,
+                        &[atoms._NET_WM_WINDOW_TYPE_POPUP_MENU],
+                    ),
+                )?;
+            }
+
+            if matches!(params.kind, WindowKind::Floating | WindowKind::Dialog) {
                 if let Some(parent_window) = parent_window {
                     // WM_TRANSIENT_FOR hint indicating the main application window. For floating windows, we set
                     // a parent window (WM_TRANSIENT_FOR) such that the window manager knows where to
@@ -581,6 +621,21 @@ impl X11WindowState {
                 )?;
             }
 
+            if params.kind == WindowKind::Dialog && params.modal {
+                // _NET_WM_STATE_MODAL makes the window manager keep the dialog above its
+                // WM_TRANSIENT_FOR parent and refuse to focus the parent while it's open.
+                check_reply(
+                    || "X11 ChangeProperty32 setting _NET_WM_STATE_MODAL for dialog failed.",
+                    xcb.change_property32(
+                        xproto::PropMode::REPLACE,
+                        x_window,
+                        atoms._NET_WM_STATE,
+                        xproto::AtomEnum::ATOM,
+                        &[atoms._NET_WM_STATE_MODAL],
+                    ),
+                )?;
+            }
+
             if params.kind == WindowKind::Overlay {
                 check_reply(
                     || "X11 ChangeProperty32 setting window type for overlay failed.",
@@ -605,7 +660,7 @@ impl X11WindowState {
             }
 
             if params.mouse_passthrough {
-                use x11rb::protocol::shape::{self, ConnectionExt as _};
+                use x11rb::protocol::shape;
                 check_reply(
                     || "X11 shape::rectangles for mouse passthrough failed.",
                     shape::rectangles(
@@ -707,8 +762,6 @@ impl X11WindowState {
                 BladeRenderer::new(gpu_context, &raw_window, config)?
             };
 
-            let display = Rc::new(X11Display::new(xcb, scale_factor, x_screen_index)?);
-
             Ok(Self {
                 client,
                 executor,
@@ -735,6 +788,7 @@ impl X11WindowState {
                 edge_constraints: None,
                 counter_id: sync_request_counter,
                 last_sync_counter: None,
+                grabs_input: is_popup && params.focus,
             })
         });
 
@@ -811,7 +865,7 @@ impl X11Window {
         params: WindowParams,
         xcb: &Rc<XCBConnection>,
         client_side_decorations_supported: bool,
-        x_main_screen_index: usize,
+        display: Rc<X11Display>,
         x_window: xproto::Window,
         atoms: &XcbAtoms,
         scale_factor: f32,
@@ -827,7 +881,7 @@ impl X11Window {
                 params,
                 xcb,
                 client_side_decorations_supported,
-                x_main_screen_index,
+                display,
                 x_window,
                 atoms,
                 scale_factor,
@@ -928,6 +982,69 @@ impl X11Window {
 }
 
 impl X11WindowStatePtr {
+    /// Take the input grab the window asked for, now that it's mapped. Windows that the window
+    /// manager maps can't be grabbed any earlier.
+    pub fn handle_map(&self) {
+        if self.state.borrow().grabs_input {
+            self.grab_input().log_err();
+        }
+    }
+
+    /// Whether a click at `position` moves focus away from the window, because the window grabs
+    /// input and the click is outside of it.
+    pub fn is_outside_grab(&self, position: Point<Pixels>) -> bool {
+        let state = self.state.borrow();
+        state.grabs_input && !Bounds::new(Point::default(), state.bounds.size).contains(&position)
+    }
+
+    /// Route all pointer and keyboard input to the window, so that it sees clicks outside of it.
+    /// The grabs end when the window is destroyed.
+    fn grab_input(&self) -> anyhow::Result<()> {
+        let pointer = get_reply(
+            || "X11 XIGetClientPointer failed.",
+            self.xcb.xinput_xi_get_client_pointer(self.x_window),
+        )?;
+        let reply = get_reply(
+            || "X11 XIGrabDevice for grabbing window failed.",
+            self.xcb.xinput_xi_grab_device(
+                self.x_window,
+                x11rb::CURRENT_TIME,
+                x11rb::NONE,
+                pointer.deviceid,
+                xproto::GrabMode::ASYNC,
+                xproto::GrabMode::ASYNC,
+                xinput::GrabOwner::NO_OWNER,
+                &[(xinput::XIEventMask::MOTION
+                    | xinput::XIEventMask::BUTTON_PRESS
+                    | xinput::XIEventMask::BUTTON_RELEASE
+                    | xinput::XIEventMask::ENTER
+                    | xinput::XIEventMask::LEAVE)
+                    .into()],
+            ),
+        )?;
+        anyhow::ensure!(
+            reply.status == xproto::GrabStatus::SUCCESS,
+            "X11 XIGrabDevice for grabbing window failed: {:?}",
+            reply.status
+        );
+        let reply = get_reply(
+            || "X11 GrabKeyboard for grabbing window failed.",
+            self.xcb.grab_keyboard(
+                false,
+                self.x_window,
+                x11rb::CURRENT_TIME,
+                xproto::GrabMode::ASYNC,
+                xproto::GrabMode::ASYNC,
+            ),
+        )?;
+        anyhow::ensure!(
+            reply.status == xproto::GrabStatus::SUCCESS,
+            "X11 GrabKeyboard for grabbing window failed: {:?}",
+            reply.status
+        );
+        Ok(())
+    }
+
     pub fn should_close(&self) -> bool {
         let mut cb = self.callbacks.borrow_mut();
         if let Some(mut should_close) = cb.should_close.take() {
@@ -1416,8 +1533,11 @@ impl PlatformWindow for X11Window {
         check_reply(
             || "X11 MapWindow failed.",
             self.0.xcb.map_window(self.0.x_window),
-        )?;
-        Ok(())
+        )
+    }
+
+    fn grab_input(&self) {
+        self.0.state.borrow_mut().grabs_input = true;
     }
 
     fn set_background_appearance(&self, background_appearance: WindowBackgroundAppearance) {
@@ -1728,7 +1848,7 @@ impl PlatformWindow for X11Window {
     }
 
     fn set_mouse_passthrough(&self, passthrough: bool) {
-        use x11rb::protocol::shape::{self, ConnectionExt as _};
+        use x11rb::protocol::shape;
         if passthrough {
             shape::rectangles(
                 self.0.xcb.as_ref(),
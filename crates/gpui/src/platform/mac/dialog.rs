@@ -1,13 +1,15 @@
 use crate::{DialogKind, DialogOptions};
-use cocoa::base::{id, nil};
+use cocoa::base::{YES, id, nil};
 use cocoa::foundation::{NSAutoreleasePool, NSString};
 use futures::channel::oneshot;
 use objc::{class, msg_send, sel, sel_impl};
+use std::sync::atomic::Ordering;
 
 const NS_ALERT_STYLE_WARNING: u64 = 0;
 const NS_ALERT_STYLE_INFORMATIONAL: u64 = 1;
 const NS_ALERT_STYLE_CRITICAL: u64 = 2;
 const NS_ALERT_FIRST_BUTTON_RETURN: i64 = 1000;
+const NS_CONTROL_STATE_VALUE_ON: i64 = 1;
 
 pub fn show_dialog(options: DialogOptions) -> oneshot::Receiver<usize> {
     let (tx, rx) = oneshot::channel();
@@ -46,8 +48,23 @@ pub fn show_dialog(options: DialogOptions) -> oneshot::Receiver<usize> {
             let _: () = msg_send![alert, addButtonWithTitle: ok_label];
         }
 
+        if let Some(suppression) = &options.suppression {
+            let _: () = msg_send![alert, setShowsSuppressionButton: YES];
+            let button: id = msg_send![alert, suppressionButton];
+            let label = NSString::alloc(nil).init_str(suppression.label.as_ref());
+            let _: id = msg_send![label, autorelease];
+            let _: () = msg_send![button, setTitle: label];
+        }
+
         let response: i64 = msg_send![alert, runModal];
         let index = (response - NS_ALERT_FIRST_BUTTON_RETURN) as usize;
+        if let Some(suppression) = &options.suppression {
+            let button: id = msg_send![alert, suppressionButton];
+            let state: i64 = msg_send![button, state];
+            suppression
+                .checked
+                .store(state == NS_CONTROL_STATE_VALUE_ON, Ordering::SeqCst);
+        }
         tx.send(index).ok();
 
         let _: () = msg_send![alert, release];
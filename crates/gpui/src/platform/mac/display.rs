@@ -1,12 +1,18 @@
-use crate::{Bounds, DisplayId, Pixels, PlatformDisplay, px, size};
+use crate::{Bounds, DisplayId, Pixels, PlatformDisplay, Size, point, px, size};
 use anyhow::Result;
 use cocoa::{
     appkit::NSScreen,
-    base::{id, nil},
-    foundation::{NSDictionary, NSString},
+    base::{CGFloat, id, nil},
+    foundation::{NSArray, NSDictionary, NSString},
 };
 use core_foundation::uuid::{CFUUIDGetUUIDBytes, CFUUIDRef};
-use core_graphics::display::{CGDirectDisplayID, CGDisplayBounds, CGGetActiveDisplayList};
+use core_graphics::{
+    display::{
+        CGDirectDisplayID, CGDisplayBounds, CGDisplayCopyDisplayMode, CGDisplayModeRef,
+        CGDisplayModeRelease, CGGetActiveDisplayList,
+    },
+    geometry::CGSize,
+};
 use objc::{msg_send, sel, sel_impl};
 use uuid::Uuid;
 
@@ -33,14 +39,29 @@ impl MacDisplay {
         // https://chromium.googlesource.com/chromium/src/+/66.0.3359.158/ui/display/mac/screen_mac.mm#56
         unsafe {
             let screens = NSScreen::screens(nil);
-            let screen = cocoa::foundation::NSArray::objectAtIndex(screens, 0);
-            let device_description = NSScreen::deviceDescription(screen);
-            let screen_number_key: id = NSString::alloc(nil).init_str("NSScreenNumber");
-            let screen_number = device_description.objectForKey_(screen_number_key);
-            let screen_number: CGDirectDisplayID = msg_send![screen_number, unsignedIntegerValue];
-            Self(screen_number)
+            let screen = NSArray::objectAtIndex(screens, 0);
+            Self(screen_number(screen))
+        }
+    }
+
+    /// Get the `NSScreen` showing this display, if it is still connected.
+    unsafe fn screen(&self) -> Option<id> {
+        unsafe {
+            let screens = NSScreen::screens(nil);
+            (0..NSArray::count(screens))
+                .map(|index| NSArray::objectAtIndex(screens, index))
+                .find(|&screen| screen_number(screen) == self.0)
         }
     }
+}
+
+unsafe fn screen_number(screen: id) -> CGDirectDisplayID {
+    unsafe {
+        let device_description = NSScreen::deviceDescription(screen);
+        let screen_number_key: id = NSString::alloc(nil).init_str("NSScreenNumber");
+        let screen_number = device_description.objectForKey_(screen_number_key);
+        msg_send![screen_number, unsignedIntegerValue]
+    }
 
     /// Obtains an iterator over all currently active system displays.
     pub fn all() -> impl Iterator<Item = Self> {
@@ -67,6 +88,8 @@ impl MacDisplay {
 #[link(name = "ApplicationServices", kind = "framework")]
 unsafe extern "C" {
     fn CGDisplayCreateUUIDFromDisplayID(display: CGDirectDisplayID) -> CFUUIDRef;
+    fn CGDisplayModeGetRefreshRate(mode: CGDisplayModeRef) -> f64;
+    fn CGDisplayScreenSize(display: CGDirectDisplayID) -> CGSize;
 }
 
 impl PlatformDisplay for MacDisplay {
@@ -114,4 +137,82 @@ impl PlatformDisplay for MacDisplay {
             }
         }
     }
+
+    fn refresh_rate(&self) -> Option<f32> {
+        unsafe {
+            let mode = CGDisplayCopyDisplayMode(self.0);
+            if mode.is_null() {
+                return None;
+            }
+            let refresh_rate = CGDisplayModeGetRefreshRate(mode);
+            CGDisplayModeRelease(mode);
+            // Built-in displays report zero, and ProMotion ones vary their rate anyway.
+            (refresh_rate > 0.).then_some(refresh_rate as f32)
+        }
+    }
+
+    fn physical_size(&self) -> Option<Size<u32>> {
+        let physical_size = unsafe { CGDisplayScreenSize(self.0) };
+        (physical_size.width > 0. && physical_size.height > 0.).then(|| {
+            size(
+                physical_size.width.round() as u32,
+                physical_size.height.round() as u32,
+            )
+        })
+    }
+
+    fn is_hdr(&self) -> bool {
+        unsafe {
+            let Some(screen) = self.screen() else {
+                return false;
+            };
+            let max_component: CGFloat = msg_send![
+                screen,
+                maximumPotentialExtendedDynamicRangeColorComponentValue
+            ];
+            max_component > 1.
+        }
+    }
+
+    fn icc_profile(&self) -> Option<Vec<u8>> {
+        unsafe {
+            let screen = self.screen()?;
+            let color_space: id = msg_send![screen, colorSpace];
+            if color_space == nil {
+                return None;
+            }
+            let data: id = msg_send![color_space, ICCProfileData];
+            if data == nil {
+                return None;
+            }
+            let bytes: *const u8 = msg_send![data, bytes];
+            let length: usize = msg_send![data, length];
+            (!bytes.is_null() && length > 0)
+                .then(|| std::slice::from_raw_parts(bytes, length).to_vec())
+        }
+    }
+
+    fn visible_bounds(&self) -> Bounds<Pixels> {
+        unsafe {
+            let Some(screen) = self.screen() else {
+                return self.bounds();
+            };
+            // AppKit frames start at the bottom left of the primary display, while the bounds of
+            // this display start at its own top left.
+            let frame = NSScreen::frame(screen);
+            let visible_frame = NSScreen::visibleFrame(screen);
+            Bounds {
+                origin: point(
+                    px((visible_frame.origin.x - frame.origin.x) as f32),
+                    px((frame.origin.y + frame.size.height
+                        - visible_frame.origin.y
+                        - visible_frame.size.height) as f32),
+                ),
+                size: size(
+                    px(visible_frame.size.width as f32),
+                    px(visible_frame.size.height as f32),
+                ),
+            }
+        }
+    }
 }
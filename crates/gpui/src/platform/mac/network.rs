@@ -28,7 +28,7 @@ pub(crate) fn network_status() -> NetworkStatus {
         let host = b"captive.apple.com\0";
         let reachability = SCNetworkReachabilityCreateWithName(std::ptr::null(), host.as_ptr());
         if reachability.is_null() {
-            return NetworkStatus::Offline;
+            return NetworkStatus::OFFLINE;
         }
 
         let mut flags: u32 = 0;
@@ -37,9 +37,9 @@ pub(crate) fn network_status() -> NetworkStatus {
         core_foundation::base::CFRelease(reachability as core_foundation::base::CFTypeRef);
 
         if ok != 0 && (flags & K_SC_NETWORK_REACHABILITY_FLAGS_REACHABLE) != 0 {
-            NetworkStatus::Online
+            NetworkStatus::ONLINE
         } else {
-            NetworkStatus::Offline
+            NetworkStatus::OFFLINE
         }
     }
 }
@@ -47,9 +47,9 @@ pub(crate) fn network_status() -> NetworkStatus {
 pub(crate) fn path_status_to_network_status(path: *const c_void) -> NetworkStatus {
     let status = unsafe { nw_path_get_status(path) };
     if status == NW_PATH_STATUS_SATISFIED {
-        NetworkStatus::Online
+        NetworkStatus::ONLINE
     } else {
-        NetworkStatus::Offline
+        NetworkStatus::OFFLINE
     }
 }
 
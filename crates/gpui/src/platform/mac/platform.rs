@@ -11,7 +11,7 @@ use crate::{
     MacDisplay, MacWindow, Menu, MenuItem, OsMenu, OwnedMenu, PathPromptOptions, Platform,
     PlatformDisplay, PlatformKeyboardLayout, PlatformKeyboardMapper, PlatformTextSystem,
     PlatformWindow, Result, SemanticVersion, SharedString, SystemMenuType, Task, TrayIconEvent,
-    TrayMenuItem, WindowAppearance, WindowParams, hash,
+    TrayIconFrame, TrayMenuItem, WindowAppearance, WindowParams, hash,
 };
 use anyhow::{Context as _, anyhow};
 use block::ConcreteBlock;
@@ -159,6 +159,11 @@ unsafe fn build_classes() {
                 on_keyboard_layout_change as extern "C" fn(&mut Object, Sel, id),
             );
 
+            decl.add_method(
+                sel!(applicationDidChangeScreenParameters:),
+                did_change_screen_parameters as extern "C" fn(&mut Object, Sel, id),
+            );
+
             decl.add_method(
                 sel!(applicationShouldTerminateAfterLastWindowClosed:),
                 should_terminate_after_last_window_closed
@@ -193,6 +198,7 @@ pub(crate) struct MacPlatformState {
     metadata_pasteboard_type: id,
     reopen: Option<Box<dyn FnMut()>>,
     on_keyboard_layout_change: Option<Box<dyn FnMut()>>,
+    on_displays_changed: Option<Box<dyn FnMut()>>,
     quit: Option<Box<dyn FnMut()>>,
     menu_command: Option<Box<dyn FnMut(&dyn Action)>>,
     validate_menu_command: Option<Box<dyn FnMut(&dyn Action) -> bool>>,
@@ -233,7 +239,7 @@ impl MacPlatform {
         let text_system = Arc::new(crate::MacTextSystem::new());
 
         #[cfg(not(feature = "font-kit"))]
-        let text_system = Arc::new(crate::NoopTextSystem::new());
+        let text_system = Arc::new(crate::NoopTextSystem);
 
         let keyboard_layout = MacKeyboardLayout::new();
         let keyboard_mapper = Rc::new(MacKeyboardMapper::new(keyboard_layout.id()));
@@ -257,6 +263,7 @@ impl MacPlatform {
             finish_launching: None,
             dock_menu: None,
             on_keyboard_layout_change: None,
+            on_displays_changed: None,
             menus: None,
             keyboard_mapper,
             keep_alive_without_windows: false,
@@ -971,6 +978,10 @@ impl Platform for MacPlatform {
         self.0.lock().on_keyboard_layout_change = Some(callback);
     }
 
+    fn on_displays_changed(&self, callback: Box<dyn FnMut()>) {
+        self.0.lock().on_displays_changed = Some(callback);
+    }
+
     fn on_app_menu_action(&self, callback: Box<dyn FnMut(&dyn Action)>) {
         self.0.lock().menu_command = Some(callback);
     }
@@ -1314,13 +1325,15 @@ impl Platform for MacPlatform {
         self.0.lock().keep_alive_without_windows = keep_alive;
     }
 
-    fn set_tray_icon(&self, icon: Option<&[u8]>) {
+    fn set_tray_icon(&self, icon: Option<TrayIconFrame>) {
         let mut state = self.0.lock();
         if state.tray.is_none() {
             state.tray = Some(MacTray::new());
         }
         if let Some(tray) = &state.tray {
-            tray.set_icon(icon);
+            // The status bar shows icons at 18 points, on screens that are usually Retina.
+            let image = icon.as_ref().and_then(|icon| icon.image_closest_to(36));
+            tray.set_icon(image.map(|image| image.bytes.as_ref()));
         }
     }
 
@@ -1518,8 +1531,8 @@ impl Platform for MacPlatform {
         self.0.lock().system_power_callback = Some(callback);
     }
 
-    fn start_power_save_blocker(&self, kind: crate::PowerSaveBlockerKind) -> Option<u32> {
-        super::power::start_power_save_blocker(kind)
+    fn start_power_save_blocker(&self, kind: crate::PowerSaveBlockerKind) -> Task<Result<u32>> {
+        Task::ready(super::power::start_power_save_blocker(kind))
     }
 
     fn stop_power_save_blocker(&self, id: u32) {
@@ -1670,7 +1683,7 @@ impl Platform for MacPlatform {
         position: crate::Point<crate::Pixels>,
         items: Vec<crate::TrayMenuItem>,
         callback: Box<dyn FnMut(crate::SharedString)>,
-    ) {
+    ) -> Result<(), Vec<crate::TrayMenuItem>> {
         self.0.lock().context_menu_callback = Some(callback);
 
         unsafe {
@@ -1697,6 +1710,7 @@ impl Platform for MacPlatform {
             let _: () = msg_send![menu, popUpMenuPositioningItem: nil atLocation: point inView: nil];
             let _: () = msg_send![menu, release];
         }
+        Ok(())
     }
 
     fn show_dialog(
@@ -1710,8 +1724,8 @@ impl Platform for MacPlatform {
         super::os_info::get_os_info()
     }
 
-    fn biometric_status(&self) -> crate::BiometricStatus {
-        super::biometric::biometric_status()
+    fn biometric_status(&self) -> Task<crate::BiometricStatus> {
+        Task::ready(super::biometric::biometric_status())
     }
 
     fn authenticate_biometric(&self, reason: &str, callback: Box<dyn FnOnce(bool) + Send>) {
@@ -1946,6 +1960,20 @@ extern "C" fn on_keyboard_layout_change(this: &mut Object, _: Sel, _: id) {
     }
 }
 
+extern "C" fn did_change_screen_parameters(this: &mut Object, _: Sel, _: id) {
+    let platform = unsafe { get_mac_platform(this) };
+    let mut lock = platform.0.lock();
+    if let Some(mut callback) = lock.on_displays_changed.take() {
+        drop(lock);
+        callback();
+        platform
+            .0
+            .lock()
+            .on_displays_changed
+            .get_or_insert(callback);
+    }
+}
+
 extern "C" fn should_terminate_after_last_window_closed(this: &mut Object, _: Sel, _: id) -> BOOL {
     let platform = unsafe { get_mac_platform(this) };
     let lock = platform.0.lock();
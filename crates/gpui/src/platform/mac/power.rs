@@ -15,7 +15,7 @@ unsafe extern "C" {
 
 const IOPM_ASSERTION_LEVEL_ON: u32 = 255;
 
-pub fn start_power_save_blocker(kind: crate::PowerSaveBlockerKind) -> Option<u32> {
+pub fn start_power_save_blocker(kind: crate::PowerSaveBlockerKind) -> anyhow::Result<u32> {
     unsafe {
         let assertion_type = match kind {
             crate::PowerSaveBlockerKind::PreventAppSuspension => {
@@ -35,11 +35,11 @@ pub fn start_power_save_blocker(kind: crate::PowerSaveBlockerKind) -> Option<u32
             &mut assertion_id,
         );
 
-        if result == 0 {
-            Some(assertion_id)
-        } else {
-            None
-        }
+        anyhow::ensure!(
+            result == 0,
+            "IOPMAssertionCreateWithName failed with error {result}"
+        );
+        Ok(assertion_id)
     }
 }
 
@@ -1,7 +1,8 @@
+use super::{BoolExt as _, events::key_to_native};
 use crate::platform::TrayMenuItem;
-use crate::{point, px, size, Bounds, Pixels};
+use crate::{point, px, size, Bounds, Keystroke, Pixels};
 use cocoa::{
-    appkit::NSScreen,
+    appkit::{NSEventModifierFlags, NSMenuItem as _, NSScreen},
     base::{id, nil, NO, YES},
     foundation::{NSData, NSSize, NSString},
 };
@@ -205,21 +206,68 @@ pub(crate) unsafe fn configure_actionable_item_with_selector(
     }
 }
 
+/// Create a menu item showing `accelerator` as its key equivalent.
+unsafe fn new_menu_item(label: &str, enabled: bool, accelerator: Option<&Keystroke>) -> id {
+    unsafe {
+        let title = NSString::alloc(nil).init_str(label);
+        let key = accelerator.map_or(Default::default(), |keystroke| {
+            key_to_native(&keystroke.key)
+        });
+        let key_equivalent = NSString::alloc(nil).init_str(&key);
+        let menu_item: id = msg_send![class!(NSMenuItem), alloc];
+        let menu_item: id =
+            msg_send![menu_item, initWithTitle:title action:nil keyEquivalent:key_equivalent];
+        if let Some(keystroke) = accelerator {
+            let mut mask = NSEventModifierFlags::empty();
+            for (modifier, flag) in [
+                (
+                    keystroke.modifiers.platform,
+                    NSEventModifierFlags::NSCommandKeyMask,
+                ),
+                (
+                    keystroke.modifiers.control,
+                    NSEventModifierFlags::NSControlKeyMask,
+                ),
+                (
+                    keystroke.modifiers.alt,
+                    NSEventModifierFlags::NSAlternateKeyMask,
+                ),
+                (
+                    keystroke.modifiers.shift,
+                    NSEventModifierFlags::NSShiftKeyMask,
+                ),
+            ] {
+                if modifier {
+                    mask |= flag;
+                }
+            }
+            menu_item.setKeyEquivalentModifierMask_(mask);
+        }
+        let _: () = msg_send![menu_item, setEnabled: enabled.to_objc()];
+        menu_item
+    }
+}
+
 pub(crate) unsafe fn build_menu_with_selector(
     menu: id,
     items: &[TrayMenuItem],
     selector: objc::runtime::Sel,
 ) {
     unsafe {
+        // Keep disabled items disabled, even though the delegate handles their action.
+        let _: () = msg_send![menu, setAutoenablesItems: NO];
         for item in items {
             match item {
-                TrayMenuItem::Action { label, id } => {
-                    let title = NSString::alloc(nil).init_str(label.as_ref());
-                    let menu_item: id = msg_send![class!(NSMenuItem), alloc];
-                    let empty = NSString::alloc(nil).init_str("");
-                    let menu_item: id =
-                        msg_send![menu_item, initWithTitle:title action:nil keyEquivalent:empty];
+                TrayMenuItem::Action {
+                    label,
+                    id,
+                    enabled,
+                    accelerator,
+                    ..
+                } => {
+                    let menu_item = new_menu_item(label, *enabled, accelerator.as_ref());
                     configure_actionable_item_with_selector(menu_item, id.as_ref(), selector);
+                    let _: () = msg_send![menu_item, setEnabled: enabled.to_objc()];
                     let _: () = msg_send![menu, addItem: menu_item];
                 }
                 TrayMenuItem::Separator => {
@@ -229,24 +277,34 @@ pub(crate) unsafe fn build_menu_with_selector(
                 TrayMenuItem::Submenu {
                     label,
                     items: sub_items,
+                    enabled,
+                    ..
                 } => {
-                    let title = NSString::alloc(nil).init_str(label.as_ref());
-                    let menu_item: id = msg_send![class!(NSMenuItem), alloc];
-                    let empty = NSString::alloc(nil).init_str("");
-                    let menu_item: id =
-                        msg_send![menu_item, initWithTitle:title action:nil keyEquivalent:empty];
+                    let menu_item = new_menu_item(label, *enabled, None);
                     let submenu: id = msg_send![class!(NSMenu), new];
                     build_menu_with_selector(submenu, sub_items, selector);
                     let _: () = msg_send![menu_item, setSubmenu: submenu];
                     let _: () = msg_send![menu, addItem: menu_item];
                 }
-                TrayMenuItem::Toggle { label, checked, id } => {
-                    let title = NSString::alloc(nil).init_str(label.as_ref());
-                    let menu_item: id = msg_send![class!(NSMenuItem), alloc];
-                    let empty = NSString::alloc(nil).init_str("");
-                    let menu_item: id =
-                        msg_send![menu_item, initWithTitle:title action:nil keyEquivalent:empty];
+                TrayMenuItem::Toggle {
+                    label,
+                    checked,
+                    id,
+                    enabled,
+                    accelerator,
+                    ..
+                }
+                | TrayMenuItem::Radio {
+                    label,
+                    checked,
+                    id,
+                    enabled,
+                    accelerator,
+                    ..
+                } => {
+                    let menu_item = new_menu_item(label, *enabled, accelerator.as_ref());
                     configure_actionable_item_with_selector(menu_item, id.as_ref(), selector);
+                    let _: () = msg_send![menu_item, setEnabled: enabled.to_objc()];
                     let state: isize = if *checked { 1 } else { 0 };
                     let _: () = msg_send![menu_item, setState: state];
                     let _: () = msg_send![menu, addItem: menu_item];
@@ -1,6 +1,6 @@
 use super::{BoolExt, MacDisplay, NSRange, NSStringExt, ns_string, renderer};
 use crate::{
-    AnyWindowHandle, Bounds, Capslock, DisplayLink, ExternalPaths, FileDropEvent,
+    AnyWindowHandle, Bounds, Capslock, DisplayId, DisplayLink, ExternalPaths, FileDropEvent,
     ForegroundExecutor, KeyDownEvent, Keystroke, Modifiers, ModifiersChangedEvent, MouseButton,
     MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, PlatformAtlas, PlatformDisplay,
     PlatformInput, PlatformWindow, Point, PromptButton, PromptLevel, RequestFrameOptions,
@@ -583,6 +583,8 @@ impl MacWindow {
             window_min_size,
             tabbing_identifier,
             mouse_passthrough,
+            popup,
+            modal: _,
         }: WindowParams,
         executor: ForegroundExecutor,
         renderer_context: renderer::Context,
@@ -619,8 +621,10 @@ impl MacWindow {
             }
 
             let native_window: id = match kind {
-                WindowKind::Normal | WindowKind::Floating => msg_send![WINDOW_CLASS, alloc],
-                WindowKind::PopUp | WindowKind::Overlay => {
+                WindowKind::Normal | WindowKind::Floating | WindowKind::Dialog => {
+                    msg_send![WINDOW_CLASS, alloc]
+                }
+                WindowKind::PopUp | WindowKind::AnchoredPopup { .. } | WindowKind::Overlay => {
                     style_mask |= NSWindowStyleMaskNonactivatingPanel;
                     msg_send![PANEL_CLASS, alloc]
                 }
@@ -777,7 +781,7 @@ impl MacWindow {
             native_window.makeFirstResponder_(native_view);
 
             match kind {
-                WindowKind::Normal | WindowKind::Floating => {
+                WindowKind::Normal | WindowKind::Floating | WindowKind::Dialog => {
                     native_window.setLevel_(NSNormalWindowLevel);
                     native_window.setAcceptsMouseMovedEvents_(YES);
 
@@ -788,7 +792,7 @@ impl MacWindow {
                         let _: () = msg_send![native_window, setTabbingIdentifier:nil];
                     }
                 }
-                WindowKind::PopUp => {
+                WindowKind::PopUp | WindowKind::AnchoredPopup { .. } => {
                     // Use a tracking area to allow receiving MouseMoved events even when
                     // the window or application aren't active, which is often the case
                     // e.g. for notification windows.
@@ -880,6 +884,11 @@ impl MacWindow {
                 native_window.orderFront_(nil);
             }
 
+            // Attach popups to their parent, so that they move with it and stay above it.
+            if let Some(parent) = popup.and_then(|popup| Self::native_window(popup.parent)) {
+                let _: () = msg_send![parent, addChildWindow: native_window ordered: NSWindowOrderingMode::NSWindowAbove];
+            }
+
             // Set the initial position of the window to the specified origin.
             // Although we already specified the position using `initWithContentRect_styleMask_backing_defer_screen_`,
             // the window position might be incorrect if the main screen (the screen that contains the window that has focus)
@@ -929,6 +938,22 @@ impl MacWindow {
         }
     }
 
+    fn native_window(handle: AnyWindowHandle) -> Option<id> {
+        unsafe {
+            let app = NSApplication::sharedApplication(nil);
+            let windows: id = msg_send![app, windows];
+            let count: NSUInteger = msg_send![windows, count];
+            (0..count)
+                .map(|i| -> id { msg_send![windows, objectAtIndex: i] })
+                .find(|&window| {
+                    let is_gpui_window: bool = msg_send![window, isKindOfClass: WINDOW_CLASS];
+                    let is_gpui_panel: bool = msg_send![window, isKindOfClass: PANEL_CLASS];
+                    (is_gpui_window || is_gpui_panel)
+                        && get_window_state(&*window).lock().handle == handle
+                })
+        }
+    }
+
     pub fn get_user_tabbing_preference() -> Option<UserTabbingPreference> {
         unsafe {
             let defaults: id = NSUserDefaults::standardUserDefaults();
@@ -2258,8 +2283,13 @@ unsafe extern "C" fn step(view: *mut c_void) {
     let mut lock = window_state.lock();
 
     if let Some(mut callback) = lock.request_frame_callback.take() {
+        // The display link runs on the display showing the window.
+        let display_id = unsafe { display_id_for_screen(lock.native_window.screen()) };
         drop(lock);
-        callback(Default::default());
+        callback(RequestFrameOptions {
+            display_id: Some(DisplayId(display_id)),
+            ..Default::default()
+        });
         window_state.lock().request_frame_callback = Some(callback);
     }
 }
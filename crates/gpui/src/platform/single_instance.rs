@@ -2,7 +2,12 @@
 ///
 /// Uses Unix domain sockets on macOS/Linux and named mutexes on Windows
 /// to ensure only one instance of an application runs at a time.
+///
+/// On Unix, a second launch forwards its [`SecondInstanceArgs`] to the running instance over the
+/// socket and waits for a [`SecondInstanceReply`]. Each message is a little-endian `u32` length
+/// followed by that many bytes of JSON.
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 /// Error returned when another instance of the application is already running.
@@ -17,6 +22,116 @@ impl std::fmt::Display for AlreadyRunning {
 
 impl std::error::Error for AlreadyRunning {}
 
+/// The arguments of a second launch of the application, forwarded to the running instance.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecondInstanceArgs {
+    /// The command line arguments, without the program name.
+    pub args: Vec<String>,
+    /// The working directory of the second launch.
+    pub cwd: PathBuf,
+    /// The environment variables the second launch chose to forward.
+    pub env: Vec<(String, String)>,
+    /// The arguments that are URLs, such as `myapp://open?x`.
+    pub urls: Vec<String>,
+}
+
+impl SecondInstanceArgs {
+    /// Capture the arguments and working directory of the current process, along with the
+    /// variables named in `env` that are set.
+    pub fn current(env: &[&str]) -> Self {
+        let args = std::env::args().skip(1).collect::<Vec<_>>();
+        Self {
+            urls: args.iter().filter(|arg| is_url(arg)).cloned().collect(),
+            args,
+            cwd: std::env::current_dir().unwrap_or_default(),
+            env: env
+                .iter()
+                .filter_map(|name| Some((name.to_string(), std::env::var(name).ok()?)))
+                .collect(),
+        }
+    }
+}
+
+/// Whether `arg` starts with a URL scheme followed by `://`.
+fn is_url(arg: &str) -> bool {
+    arg.split_once("://").is_some_and(|(scheme, _)| {
+        let mut chars = scheme.chars();
+        chars.next().is_some_and(|ch| ch.is_ascii_alphabetic())
+            && chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+    })
+}
+
+/// The running instance's answer to a second launch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecondInstanceReply {
+    /// The code the second launch should exit with.
+    pub exit_code: i32,
+    /// Output for the second launch to print, such as an error message.
+    pub message: Option<String>,
+}
+
+/// A second launch waiting for the application to handle it.
+pub(crate) struct SecondInstanceRequest {
+    pub args: SecondInstanceArgs,
+    pub reply: std::sync::mpsc::Sender<SecondInstanceReply>,
+}
+
+/// The second launches of an application that wait for it to handle them.
+struct RequestQueue {
+    sender: futures::channel::mpsc::UnboundedSender<SecondInstanceRequest>,
+    /// `None` once [`App::on_second_instance`](crate::App::on_second_instance) took the queue.
+    receiver: Option<futures::channel::mpsc::UnboundedReceiver<SecondInstanceRequest>>,
+}
+
+impl RequestQueue {
+    fn new() -> Self {
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        Self {
+            sender,
+            receiver: Some(receiver),
+        }
+    }
+}
+
+/// The second launches of each application id, which are queued until the application handles
+/// them, so that launches during startup aren't lost.
+static REQUESTS: std::sync::LazyLock<
+    std::sync::Mutex<std::collections::HashMap<String, RequestQueue>>,
+> = std::sync::LazyLock::new(Default::default);
+
+/// Take the queue of second launches of `app_id`, or `None` if it was taken already.
+pub(crate) fn take_second_instance_requests(
+    app_id: &str,
+) -> Option<futures::channel::mpsc::UnboundedReceiver<SecondInstanceRequest>> {
+    REQUESTS
+        .lock()
+        .ok()?
+        .entry(app_id.to_string())
+        .or_insert_with(RequestQueue::new)
+        .receiver
+        .take()
+}
+
+/// Queue a second launch of `app_id`, returning the receiver of its reply if a callback handles
+/// the launches. Otherwise the launch is still queued for a callback registered later, but nothing
+/// waits for its reply.
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn queue_second_instance_request(
+    app_id: &str,
+    args: SecondInstanceArgs,
+) -> Option<std::sync::mpsc::Receiver<SecondInstanceReply>> {
+    let mut requests = REQUESTS.lock().ok()?;
+    let queue = requests
+        .entry(app_id.to_string())
+        .or_insert_with(RequestQueue::new);
+    let (reply, reply_receiver) = std::sync::mpsc::channel();
+    queue
+        .sender
+        .unbounded_send(SecondInstanceRequest { args, reply })
+        .ok()?;
+    queue.receiver.is_none().then_some(reply_receiver)
+}
+
 /// A guard that enforces single-instance behavior for an application.
 ///
 /// When acquired successfully, this struct holds a platform-specific lock
@@ -27,6 +142,8 @@ pub struct SingleInstance {
     _listener: std::os::unix::net::UnixListener,
     #[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
     _socket_path: PathBuf,
+    #[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+    activate: ActivateCallback,
     #[cfg(target_os = "windows")]
     _mutex: WindowsMutexHandle,
 }
@@ -45,28 +162,108 @@ impl Drop for WindowsMutexHandle {
     }
 }
 
+/// The directory of the current user's sockets: `$XDG_RUNTIME_DIR/gpui`, or `gpui-<uid>` in
+/// `$TMPDIR` or `/tmp`, where other users could otherwise take the name of the socket.
 #[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
-fn socket_path(app_id: &str) -> PathBuf {
-    let dir = std::env::var("XDG_RUNTIME_DIR")
-        .or_else(|_| std::env::var("TMPDIR"))
-        .unwrap_or_else(|_| "/tmp".to_string());
-    PathBuf::from(dir).join(format!("{}.sock", app_id))
+fn socket_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("gpui"),
+        None => std::env::temp_dir().join(format!("gpui-{}", current_uid())),
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+fn current_uid() -> u32 {
+    unsafe { libc::geteuid() }
+}
+
+/// Create `dir` if needed and make sure that only the current user can access it.
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+fn ensure_private_dir(dir: &std::path::Path) -> Result<()> {
+    use anyhow::Context as _;
+    use std::os::unix::fs::{DirBuilderExt as _, MetadataExt as _, PermissionsExt as _};
+
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Err(error) if error.kind() != std::io::ErrorKind::AlreadyExists => {
+            return Err(error).with_context(|| format!("failed to create {dir:?}"));
+        }
+        _ => {}
+    }
+    // Don't follow a symlink that another user may have planted in a shared directory.
+    let metadata = std::fs::symlink_metadata(dir)?;
+    anyhow::ensure!(metadata.is_dir(), "{dir:?} is not a directory");
+    anyhow::ensure!(
+        metadata.uid() == current_uid(),
+        "{dir:?} is owned by user {}",
+        metadata.uid()
+    );
+    if metadata.mode() & 0o077 != 0 {
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// The user the process at the other end of `stream` runs as.
+#[cfg(target_os = "linux")]
+fn peer_uid(stream: &std::os::unix::net::UnixStream) -> std::io::Result<u32> {
+    use std::os::fd::AsRawFd as _;
+
+    let mut credentials = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&raw mut credentials).cast(),
+            &mut len,
+        )
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(credentials.uid)
+}
+
+/// The user the process at the other end of `stream` runs as.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+fn peer_uid(stream: &std::os::unix::net::UnixStream) -> std::io::Result<u32> {
+    use std::os::fd::AsRawFd as _;
+
+    let (mut uid, mut gid) = (0, 0);
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(uid)
+}
+
+/// Make sure that the other end of `stream` runs as the current user.
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+fn verify_peer(stream: &std::os::unix::net::UnixStream) -> Result<()> {
+    let uid = peer_uid(stream)?;
+    anyhow::ensure!(uid == current_uid(), "the socket's peer runs as user {uid}");
+    Ok(())
 }
 
 impl SingleInstance {
     /// Attempt to acquire the single-instance lock for the given application ID.
     ///
     /// Returns `Ok(SingleInstance)` if this is the first instance, or
-    /// `Err(AlreadyRunning)` if another instance already holds the lock.
+    /// `Err(AlreadyRunning)` if another instance already holds the lock. A socket left behind by
+    /// an instance that crashed is replaced. If the socket can't be created safely, for example
+    /// because its directory belongs to another user, the error is logged and `AlreadyRunning` is
+    /// returned as well.
     pub fn acquire(app_id: &str) -> std::result::Result<Self, AlreadyRunning> {
         Self::platform_acquire(app_id)
     }
 
-    /// Register a callback to be invoked when another instance attempts to start
-    /// and sends an activation message.
-    ///
-    /// On Unix platforms, this spawns a background thread that listens for
-    /// incoming connections on the Unix domain socket.
+    /// Register a callback to be invoked on a background thread when another instance attempts
+    /// to start. Use [`App::on_second_instance`](crate::App::on_second_instance) to receive its
+    /// arguments on the main thread instead.
     pub fn on_activate(&self, callback: Box<dyn Fn() + Send + 'static>) {
         self.platform_on_activate(callback);
     }
@@ -77,79 +274,200 @@ impl SingleInstance {
 /// This is typically called after `SingleInstance::acquire` returns `Err(AlreadyRunning)`
 /// to signal the existing instance to come to the foreground.
 pub fn send_activate_to_existing(app_id: &str) -> Result<()> {
-    platform_send_activate(app_id)
+    send_to_existing(app_id, &SecondInstanceArgs::default()).map(|_| ())
 }
 
+/// Forward the arguments of this launch to an already-running instance of the application, and
+/// wait for its reply.
+///
+/// This is typically called with [`SecondInstanceArgs::current`] after `SingleInstance::acquire`
+/// returns `Err(AlreadyRunning)`, exiting with the reply's exit code afterwards. Not supported on
+/// Windows, where an error is returned.
+pub fn send_to_existing(app_id: &str, args: &SecondInstanceArgs) -> Result<SecondInstanceReply> {
+    platform_send(app_id, args)
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+type ActivateCallback = std::sync::Arc<std::sync::Mutex<Option<Box<dyn Fn() + Send + 'static>>>>;
+
+/// How long a second launch waits for the running instance to reply, and how long the running
+/// instance waits for the second launch to send its arguments.
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+const MAX_MESSAGE_LEN: u32 = 1 << 20;
+
 #[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
 impl SingleInstance {
     fn platform_acquire(app_id: &str) -> std::result::Result<Self, AlreadyRunning> {
-        use std::os::unix::net::UnixListener;
+        match Self::acquire_in(&socket_dir(), app_id) {
+            Ok(Some(instance)) => Ok(instance),
+            Ok(None) => Err(AlreadyRunning),
+            Err(error) => {
+                log::error!("failed to create the single instance socket: {error:#}");
+                Err(AlreadyRunning)
+            }
+        }
+    }
+
+    /// Listen on the socket of `app_id` in `dir`, or return `None` if a live instance is listening
+    /// on it already.
+    fn acquire_in(dir: &std::path::Path, app_id: &str) -> Result<Option<Self>> {
+        use std::os::fd::AsRawFd as _;
+        use std::os::unix::net::{UnixListener, UnixStream};
 
-        let path = socket_path(app_id);
+        ensure_private_dir(dir)?;
+        // Launches that race each other take turns, so only one of them replaces a stale socket.
+        let lock = std::fs::File::create(dir.join(format!("{app_id}.lock")))?;
+        if unsafe { libc::flock(lock.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
 
-        if std::os::unix::net::UnixStream::connect(&path).is_ok() {
-            return Err(AlreadyRunning);
+        let path = dir.join(format!("{app_id}.sock"));
+        match UnixStream::connect(&path) {
+            Ok(stream) => {
+                verify_peer(&stream)?;
+                return Ok(None);
+            }
+            // Nothing listens on a socket left behind by an instance that crashed.
+            Err(error) if error.kind() == std::io::ErrorKind::ConnectionRefused => {
+                std::fs::remove_file(&path)?;
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
         }
 
-        let _ = std::fs::remove_file(&path);
-        let listener = UnixListener::bind(&path).map_err(|_| AlreadyRunning)?;
-        listener.set_nonblocking(true).ok();
+        let listener = UnixListener::bind(&path)?;
+        let activate = ActivateCallback::default();
+        let incoming = listener.try_clone()?;
+        std::thread::spawn({
+            let app_id = app_id.to_string();
+            let activate = activate.clone();
+            move || listen(incoming, app_id, activate)
+        });
 
-        Ok(Self {
+        Ok(Some(Self {
             _listener: listener,
             _socket_path: path,
-        })
+            activate,
+        }))
     }
 
     fn platform_on_activate(&self, callback: Box<dyn Fn() + Send + 'static>) {
-        use std::io::Read;
-        use std::os::unix::net::UnixListener;
-
-        let listener = unsafe {
-            use std::os::unix::io::{AsRawFd, FromRawFd};
-            let fd = self._listener.as_raw_fd();
-            let dup_fd = libc::dup(fd);
-            if dup_fd < 0 {
-                return;
-            }
-            UnixListener::from_raw_fd(dup_fd)
-        };
-        listener.set_nonblocking(false).ok();
+        if let Ok(mut activate) = self.activate.lock() {
+            *activate = Some(callback);
+        }
+    }
+}
 
+/// Accept second launches until the listener is shut down, handling each on its own thread.
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+fn listen(listener: std::os::unix::net::UnixListener, app_id: String, activate: ActivateCallback) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            break;
+        };
+        if let Err(error) = verify_peer(&stream) {
+            log::warn!("rejected a second instance: {error:#}");
+            continue;
+        }
+        let app_id = app_id.clone();
+        let activate = activate.clone();
         std::thread::spawn(move || {
-            for stream in listener.incoming() {
-                match stream {
-                    Ok(mut stream) => {
-                        let mut buf = [0u8; 64];
-                        if let Ok(n) = stream.read(&mut buf) {
-                            if n > 0 && &buf[..n.min(8)] == b"activate" {
-                                callback();
-                            }
-                        }
-                    }
-                    Err(_) => break,
+            let result = handle_connection(stream, |args| {
+                if let Some(activate) = activate.lock().ok().as_deref().and_then(Option::as_ref) {
+                    activate();
+                }
+                // Without a callback for the launch, it doesn't wait for one.
+                match queue_second_instance_request(&app_id, args) {
+                    Some(reply_receiver) => Ok(reply_receiver.recv_timeout(TIMEOUT)?),
+                    None => Ok(SecondInstanceReply::default()),
                 }
+            });
+            // Launches that find this instance running connect without sending anything.
+            let is_probe = |error: &anyhow::Error| {
+                error
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|error| error.kind() == std::io::ErrorKind::UnexpectedEof)
+            };
+            if let Err(error) = result
+                && !is_probe(&error)
+            {
+                log::warn!("failed to handle a second instance: {error:#}");
             }
         });
     }
 }
 
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+fn handle_connection(
+    mut stream: std::os::unix::net::UnixStream,
+    deliver: impl FnOnce(SecondInstanceArgs) -> Result<SecondInstanceReply>,
+) -> Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let args = read_message(&mut stream)?;
+    let reply = deliver(args)?;
+    write_message(&mut stream, &reply)
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+fn request(
+    stream: &mut std::os::unix::net::UnixStream,
+    args: &SecondInstanceArgs,
+) -> Result<SecondInstanceReply> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    write_message(stream, args)?;
+    read_message(stream)
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+fn write_message(stream: &mut impl std::io::Write, message: &impl Serialize) -> Result<()> {
+    let body = serde_json::to_vec(message)?;
+    stream.write_all(&u32::try_from(body.len())?.to_le_bytes())?;
+    stream.write_all(&body)?;
+    Ok(())
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+fn read_message<T: serde::de::DeserializeOwned>(stream: &mut impl std::io::Read) -> Result<T> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len);
+    anyhow::ensure!(len <= MAX_MESSAGE_LEN, "message of {len} bytes is too long");
+    let mut body = vec![0; len as usize];
+    stream.read_exact(&mut body)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
 #[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
 impl Drop for SingleInstance {
     fn drop(&mut self) {
+        use std::os::fd::AsRawFd;
+
+        // Wake up the listening thread, which stops once accepting fails.
+        unsafe {
+            libc::shutdown(self._listener.as_raw_fd(), libc::SHUT_RDWR);
+        }
         let _ = std::fs::remove_file(&self._socket_path);
     }
 }
 
 #[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
-fn platform_send_activate(app_id: &str) -> Result<()> {
-    use std::io::Write;
-    use std::os::unix::net::UnixStream;
+fn platform_send(app_id: &str, args: &SecondInstanceArgs) -> Result<SecondInstanceReply> {
+    send_in(&socket_dir(), app_id, args)
+}
 
-    let path = socket_path(app_id);
-    let mut stream = UnixStream::connect(&path)?;
-    stream.write_all(b"activate")?;
-    Ok(())
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "freebsd"))]
+fn send_in(
+    dir: &std::path::Path,
+    app_id: &str,
+    args: &SecondInstanceArgs,
+) -> Result<SecondInstanceReply> {
+    let path = dir.join(format!("{app_id}.sock"));
+    let mut stream = std::os::unix::net::UnixStream::connect(&path)?;
+    verify_peer(&stream)?;
+    request(&mut stream, args)
 }
 
 #[cfg(target_os = "windows")]
@@ -177,6 +495,159 @@ impl SingleInstance {
 }
 
 #[cfg(target_os = "windows")]
-fn platform_send_activate(_app_id: &str) -> Result<()> {
-    Ok(())
+fn platform_send(_app_id: &str, _args: &SecondInstanceArgs) -> Result<SecondInstanceReply> {
+    anyhow::bail!("forwarding to the running instance is not supported on Windows")
+}
+
+#[cfg(all(
+    test,
+    any(target_os = "macos", target_os = "linux", target_os = "freebsd")
+))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn test_forward_second_instance() {
+        let (mut launcher, instance) = UnixStream::pair().unwrap();
+        let server = std::thread::spawn(move || {
+            handle_connection(instance, |args| {
+                Ok(SecondInstanceReply {
+                    exit_code: args.args.len() as i32,
+                    message: Some(format!("opened {:?}", args.urls)),
+                })
+            })
+        });
+
+        let args = SecondInstanceArgs {
+            args: vec!["file.txt".into(), "myapp://open?x".into()],
+            cwd: "/home/user".into(),
+            env: vec![("DISPLAY".into(), ":0".into())],
+            urls: vec!["myapp://open?x".into()],
+        };
+        let reply = request(&mut launcher, &args).unwrap();
+        server.join().unwrap().unwrap();
+        assert_eq!(
+            reply,
+            SecondInstanceReply {
+                exit_code: 2,
+                message: Some("opened [\"myapp://open?x\"]".into()),
+            }
+        );
+
+        // A message claiming to be larger than the limit is rejected before it is read.
+        let (mut launcher, mut instance) = UnixStream::pair().unwrap();
+        std::io::Write::write_all(&mut launcher, &u32::MAX.to_le_bytes()).unwrap();
+        assert!(read_message::<SecondInstanceArgs>(&mut instance).is_err());
+    }
+
+    #[test]
+    fn test_second_instance_queues() {
+        let args = |arg: &str| SecondInstanceArgs {
+            args: vec![arg.into()],
+            ..Default::default()
+        };
+
+        // Without a callback, launches don't wait for a reply but are still delivered later.
+        assert!(queue_second_instance_request("test-queue-a", args("early")).is_none());
+        let mut requests = take_second_instance_requests("test-queue-a").unwrap();
+        assert!(take_second_instance_requests("test-queue-a").is_none());
+        let reply = queue_second_instance_request("test-queue-a", args("late")).unwrap();
+
+        // Other applications have queues of their own.
+        assert!(queue_second_instance_request("test-queue-b", args("other")).is_none());
+
+        let early = requests.try_next().unwrap().unwrap();
+        assert_eq!(early.args, args("early"));
+        let late = requests.try_next().unwrap().unwrap();
+        assert_eq!(late.args, args("late"));
+        assert!(requests.try_next().is_err());
+        late.reply.send(SecondInstanceReply::default()).unwrap();
+        assert_eq!(reply.recv().unwrap(), SecondInstanceReply::default());
+    }
+
+    /// A directory under the temporary directory that is removed again when dropped.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "gpui-single-instance-{}-{name}",
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_stale_socket() {
+        let dir = TestDir::new("stale");
+        ensure_private_dir(&dir.0).unwrap();
+        // An instance that crashed leaves its socket behind, but nothing listens on it anymore.
+        let path = dir.0.join("app.sock");
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let instance = SingleInstance::acquire_in(&dir.0, "app").unwrap().unwrap();
+        assert!(UnixStream::connect(&path).is_ok());
+        drop(instance);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_running_instance() {
+        let dir = TestDir::new("running");
+        let _instance = SingleInstance::acquire_in(&dir.0, "app").unwrap().unwrap();
+        assert!(SingleInstance::acquire_in(&dir.0, "app").unwrap().is_none());
+        // Other applications have sockets of their own.
+        assert!(
+            SingleInstance::acquire_in(&dir.0, "other")
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_private_dir() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let dir = TestDir::new("private");
+        std::fs::create_dir(&dir.0).unwrap();
+        std::fs::set_permissions(&dir.0, std::fs::Permissions::from_mode(0o755)).unwrap();
+        ensure_private_dir(&dir.0).unwrap();
+        let mode = std::fs::metadata(&dir.0).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        // A symlink could point to a directory of another user.
+        let link = TestDir::new("link");
+        std::os::unix::fs::symlink(&dir.0, &link.0).unwrap();
+        assert!(ensure_private_dir(&link.0).is_err());
+        assert!(SingleInstance::acquire_in(&link.0, "app").is_err());
+        let file = dir.0.join("file");
+        std::fs::write(&file, "").unwrap();
+        assert!(ensure_private_dir(&file).is_err());
+    }
+
+    #[test]
+    fn test_verify_peer() {
+        let (launcher, instance) = UnixStream::pair().unwrap();
+        assert_eq!(peer_uid(&launcher).unwrap(), current_uid());
+        verify_peer(&instance).unwrap();
+    }
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("myapp://open?x"));
+        assert!(is_url("git+ssh://host/repo"));
+        assert!(!is_url("file.txt"));
+        assert!(!is_url("./dir://name"));
+        assert!(!is_url("://missing-scheme"));
+    }
 }
@@ -1,7 +1,11 @@
-use crate::{point, Bounds, Pixels, PlatformDisplay, Point, Size, WindowPosition};
+use crate::{
+    AnchoredFitMode, Bounds, Corner, Pixels, PlatformDisplay, Point, Size, WindowPosition,
+    fit_anchored_bounds, point, px,
+};
 use std::rc::Rc;
 
-/// Compute window bounds from a desired size and a semantic position.
+/// Compute window bounds from a desired size and a semantic position, inside the visible bounds
+/// of the display so that panels and docks don't cover the window.
 pub fn compute_window_bounds(
     size: Size<Pixels>,
     position: &WindowPosition,
@@ -11,25 +15,45 @@ pub fn compute_window_bounds(
     match position {
         WindowPosition::Center => {
             if let Some(display) = primary_display {
-                center_in(size, display.bounds())
+                center_in(size, display.visible_bounds())
             } else {
                 Bounds::new(Point::default(), size)
             }
         }
         WindowPosition::CenterOnDisplay(id) => {
-            let display_bounds = displays.iter().find(|d| d.id() == *id).map(|d| d.bounds());
+            let display_bounds = displays
+                .iter()
+                .find(|d| d.id() == *id)
+                .map(|d| d.visible_bounds());
             if let Some(bounds) = display_bounds {
                 center_in(size, bounds)
             } else if let Some(display) = primary_display {
-                center_in(size, display.bounds())
+                center_in(size, display.visible_bounds())
             } else {
                 Bounds::new(Point::default(), size)
             }
         }
         WindowPosition::TrayCenter(tray_bounds) => {
-            let x = tray_bounds.origin.x + (tray_bounds.size.width - size.width) * 0.5;
-            let y = tray_bounds.origin.y + tray_bounds.size.height;
-            Bounds::new(point(x, y), size)
+            let display = displays
+                .iter()
+                .find(|display| display.bounds().contains(&tray_bounds.center()))
+                .or(primary_display);
+            match display {
+                Some(display) => tray_popover_bounds(
+                    size,
+                    *tray_bounds,
+                    PanelEdge::nearest(*tray_bounds, display.bounds()),
+                    display.visible_bounds(),
+                    px(0.),
+                ),
+                None => tray_popover_bounds(
+                    size,
+                    *tray_bounds,
+                    PanelEdge::Top,
+                    Bounds::new(Point::default(), size),
+                    px(0.),
+                ),
+            }
         }
         WindowPosition::TopRight { margin } => {
             corner_position(size, primary_display, *margin, true, false)
@@ -46,6 +70,127 @@ pub fn compute_window_bounds(
     }
 }
 
+/// Compute the bounds of a popup of the given size, attached to the `gravity` corner of `anchor`
+/// and kept inside `limits` as described by `fit_mode`.
+pub(crate) fn popup_bounds(
+    anchor: Bounds<Pixels>,
+    gravity: Corner,
+    size: Size<Pixels>,
+    limits: Bounds<Pixels>,
+    fit_mode: AnchoredFitMode,
+) -> Bounds<Pixels> {
+    let corner = gravity.opposite_corner();
+    let desired = Bounds::from_corner_and_size(corner, anchor.corner(gravity), size);
+    fit_anchored_bounds(desired, anchor, corner, limits, fit_mode, px(0.))
+}
+
+/// The edge of a display that a panel, such as the one holding the system tray, is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelEdge {
+    /// A panel along the top of the display, like the macOS menu bar.
+    Top,
+    /// A panel along the bottom of the display, like the Windows taskbar.
+    Bottom,
+    /// A panel along the left of the display.
+    Left,
+    /// A panel along the right of the display.
+    Right,
+}
+
+impl PanelEdge {
+    /// The edge of `display` closest to the tray icon at `tray`, which is where the panel holding
+    /// the icon is attached.
+    pub fn nearest(tray: Bounds<Pixels>, display: Bounds<Pixels>) -> Self {
+        let center = tray.center();
+        [
+            (center.y - display.top(), PanelEdge::Top),
+            (display.bottom() - center.y, PanelEdge::Bottom),
+            (center.x - display.left(), PanelEdge::Left),
+            (display.right() - center.x, PanelEdge::Right),
+        ]
+        .into_iter()
+        .min_by_key(|(distance, _)| *distance)
+        .map_or(PanelEdge::Top, |(_, edge)| edge)
+    }
+}
+
+/// Compute the bounds of a popover of the given size next to the tray icon at `tray`, on the side
+/// of the panel at `edge` that faces the rest of the display. The popover is centered on the icon,
+/// flipped to the other side of it if it only fits there, and kept `margin` inside `work_area`.
+pub fn tray_popover_bounds(
+    size: Size<Pixels>,
+    tray: Bounds<Pixels>,
+    edge: PanelEdge,
+    work_area: Bounds<Pixels>,
+    margin: Pixels,
+) -> Bounds<Pixels> {
+    let vertical = (tray.top(), tray.bottom());
+    let horizontal = (tray.left(), tray.right());
+    let work_vertical = (work_area.top(), work_area.bottom());
+    let work_horizontal = (work_area.left(), work_area.right());
+    let origin = match edge {
+        PanelEdge::Top | PanelEdge::Bottom => point(
+            center_on(horizontal, size.width, work_horizontal, margin),
+            place_beside(
+                vertical,
+                size.height,
+                edge == PanelEdge::Top,
+                work_vertical,
+                margin,
+            ),
+        ),
+        PanelEdge::Left | PanelEdge::Right => point(
+            place_beside(
+                horizontal,
+                size.width,
+                edge == PanelEdge::Left,
+                work_horizontal,
+                margin,
+            ),
+            center_on(vertical, size.height, work_vertical, margin),
+        ),
+    };
+    Bounds::new(origin, size)
+}
+
+/// The start of a span of `len` that is `margin` after `anchor`, or before it if `after` is
+/// false, flipped to the other side of `anchor` if it only fits within `limits` there.
+fn place_beside(
+    anchor: (Pixels, Pixels),
+    len: Pixels,
+    after: bool,
+    limits: (Pixels, Pixels),
+    margin: Pixels,
+) -> Pixels {
+    let after_start = anchor.1 + margin;
+    let before_start = anchor.0 - margin - len;
+    let fits_after = after_start + len <= limits.1 - margin;
+    let fits_before = before_start >= limits.0 + margin;
+    let start = if after && (fits_after || !fits_before) || !after && !fits_before && fits_after {
+        after_start
+    } else {
+        before_start
+    };
+    clamp_span(start, len, limits, margin)
+}
+
+/// The start of a span of `len` centered on `anchor` and kept within `limits`.
+fn center_on(
+    anchor: (Pixels, Pixels),
+    len: Pixels,
+    limits: (Pixels, Pixels),
+    margin: Pixels,
+) -> Pixels {
+    let start = anchor.0 + (anchor.1 - anchor.0 - len) * 0.5;
+    clamp_span(start, len, limits, margin)
+}
+
+/// Move a span of `len` at `start` to be `margin` inside `limits`, aligning it with the start of
+/// `limits` if it is too long to fit.
+fn clamp_span(start: Pixels, len: Pixels, limits: (Pixels, Pixels), margin: Pixels) -> Pixels {
+    start.min(limits.1 - margin - len).max(limits.0 + margin)
+}
+
 fn center_in(size: Size<Pixels>, display: Bounds<Pixels>) -> Bounds<Pixels> {
     let x = display.origin.x + (display.size.width - size.width) * 0.5;
     let y = display.origin.y + (display.size.height - size.height) * 0.5;
@@ -60,7 +205,7 @@ fn corner_position(
     bottom: bool,
 ) -> Bounds<Pixels> {
     if let Some(display) = primary_display {
-        let db = display.bounds();
+        let db = display.visible_bounds();
         let x = if right {
             db.origin.x + db.size.width - size.width - margin
         } else {
@@ -76,3 +221,167 @@ fn corner_position(
         Bounds::new(Point::default(), size)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DisplayId, size};
+
+    #[derive(Debug)]
+    struct PanelDisplay {
+        bounds: Bounds<Pixels>,
+        visible_bounds: Bounds<Pixels>,
+    }
+
+    impl PlatformDisplay for PanelDisplay {
+        fn id(&self) -> DisplayId {
+            DisplayId(1)
+        }
+
+        fn uuid(&self) -> anyhow::Result<uuid::Uuid> {
+            Ok(uuid::Uuid::nil())
+        }
+
+        fn bounds(&self) -> Bounds<Pixels> {
+            self.bounds
+        }
+
+        fn visible_bounds(&self) -> Bounds<Pixels> {
+            self.visible_bounds
+        }
+    }
+
+    #[test]
+    fn test_window_bounds_avoid_panels() {
+        // A top panel and a dock at the bottom.
+        let display: Rc<dyn PlatformDisplay> = Rc::new(PanelDisplay {
+            bounds: Bounds::new(point(px(0.), px(0.)), size(px(1000.), px(800.))),
+            visible_bounds: Bounds::new(point(px(0.), px(30.)), size(px(1000.), px(710.))),
+        });
+        let displays = [display.clone()];
+        let window = size(px(200.), px(100.));
+        let origin =
+            |position| compute_window_bounds(window, &position, &displays, Some(&display)).origin;
+        let margin = px(10.);
+
+        assert_eq!(
+            origin(WindowPosition::TopLeft { margin }),
+            point(px(10.), px(40.))
+        );
+        assert_eq!(
+            origin(WindowPosition::TopRight { margin }),
+            point(px(790.), px(40.))
+        );
+        assert_eq!(
+            origin(WindowPosition::BottomLeft { margin }),
+            point(px(10.), px(630.))
+        );
+        assert_eq!(
+            origin(WindowPosition::BottomRight { margin }),
+            point(px(790.), px(630.))
+        );
+        assert_eq!(origin(WindowPosition::Center), point(px(400.), px(335.)));
+        assert_eq!(
+            origin(WindowPosition::CenterOnDisplay(DisplayId(1))),
+            point(px(400.), px(335.))
+        );
+        // Below a tray icon in the top panel.
+        let tray = Bounds::new(point(px(500.), px(3.)), size(px(24.), px(24.)));
+        assert_eq!(
+            origin(WindowPosition::TrayCenter(tray)),
+            point(px(412.), px(30.))
+        );
+    }
+
+    #[test]
+    fn test_tray_popover_bounds() {
+        let display = Bounds::new(point(px(0.), px(0.)), size(px(1000.), px(800.)));
+        let popover = size(px(300.), px(400.));
+        let margin = px(8.);
+        let popover_bounds = |tray: Bounds<Pixels>, work_area| {
+            let edge = PanelEdge::nearest(tray, display);
+            (
+                edge,
+                tray_popover_bounds(popover, tray, edge, work_area, margin).origin,
+            )
+        };
+
+        // Below an icon in a top panel, centered on it.
+        let tray = Bounds::new(point(px(500.), px(0.)), size(px(24.), px(24.)));
+        let work_area = Bounds::new(point(px(0.), px(24.)), size(px(1000.), px(776.)));
+        assert_eq!(
+            popover_bounds(tray, work_area),
+            (PanelEdge::Top, point(px(362.), px(32.)))
+        );
+        // Kept inside the work area near the right edge of the display.
+        let tray = Bounds::new(point(px(970.), px(0.)), size(px(24.), px(24.)));
+        assert_eq!(
+            popover_bounds(tray, work_area),
+            (PanelEdge::Top, point(px(692.), px(32.)))
+        );
+
+        // Above an icon in a bottom panel.
+        let tray = Bounds::new(point(px(900.), px(770.)), size(px(24.), px(24.)));
+        let work_area = Bounds::new(point(px(0.), px(0.)), size(px(1000.), px(764.)));
+        assert_eq!(
+            popover_bounds(tray, work_area),
+            (PanelEdge::Bottom, point(px(692.), px(356.)))
+        );
+
+        // Beside icons in left and right panels.
+        let tray = Bounds::new(point(px(4.), px(700.)), size(px(24.), px(24.)));
+        let work_area = Bounds::new(point(px(32.), px(0.)), size(px(968.), px(800.)));
+        assert_eq!(
+            popover_bounds(tray, work_area),
+            (PanelEdge::Left, point(px(40.), px(392.)))
+        );
+        let tray = Bounds::new(point(px(972.), px(100.)), size(px(24.), px(24.)));
+        let work_area = Bounds::new(point(px(0.), px(0.)), size(px(968.), px(800.)));
+        assert_eq!(
+            popover_bounds(tray, work_area),
+            (PanelEdge::Right, point(px(660.), px(8.)))
+        );
+
+        // A click in the middle of a top panel that doesn't leave room below flips above it.
+        let tray = Bounds::new(point(px(500.), px(500.)), Size::default());
+        let bounds = tray_popover_bounds(popover, tray, PanelEdge::Top, display, margin);
+        assert_eq!(bounds.origin, point(px(350.), px(92.)));
+    }
+
+    #[test]
+    fn test_popup_bounds() {
+        let limits = Bounds::new(point(px(0.), px(0.)), size(px(800.), px(600.)));
+        let menu = size(px(200.), px(300.));
+        let button = Bounds::new(point(px(100.), px(100.)), size(px(80.), px(20.)));
+
+        let bounds = popup_bounds(
+            button,
+            Corner::BottomRight,
+            menu,
+            limits,
+            AnchoredFitMode::SwitchAnchor,
+        );
+        assert_eq!(bounds.origin, point(px(180.), px(120.)));
+
+        // Near the bottom right corner, the popup flips to the other side of the anchor.
+        let button = Bounds::new(point(px(700.), px(500.)), size(px(80.), px(20.)));
+        let bounds = popup_bounds(
+            button,
+            Corner::BottomRight,
+            menu,
+            limits,
+            AnchoredFitMode::SwitchAnchor,
+        );
+        assert_eq!(bounds.origin, point(px(500.), px(200.)));
+
+        // Without flipping, it slides back onto the screen instead.
+        let bounds = popup_bounds(
+            button,
+            Corner::BottomRight,
+            menu,
+            limits,
+            AnchoredFitMode::SnapToWindow,
+        );
+        assert_eq!(bounds.origin, point(px(600.), px(300.)));
+    }
+}
@@ -1,49 +1,97 @@
-use windows::Win32::{Foundation::HWND, UI::WindowsAndMessaging::*};
+use std::sync::atomic::Ordering;
 
-use crate::DialogOptions;
+use ::util::ResultExt;
+use anyhow::Context as _;
+use windows::{
+    Win32::{
+        Foundation::*,
+        UI::{Controls::*, WindowsAndMessaging::*},
+    },
+    core::*,
+};
+
+use crate::{DialogKind, DialogOptions};
+
+/// The id of the first button; the others follow in order.
+const FIRST_BUTTON_ID: i32 = 100;
 
 pub(crate) fn show_dialog_sync(hwnd: HWND, options: DialogOptions) -> usize {
-    let kind_flag = match options.kind {
-        crate::DialogKind::Info => MB_ICONINFORMATION,
-        crate::DialogKind::Warning => MB_ICONWARNING,
-        crate::DialogKind::Error => MB_ICONERROR,
+    let title = HSTRING::from(options.title.as_ref());
+    let message = HSTRING::from(options.message.as_ref());
+    let detail = options
+        .detail
+        .as_ref()
+        .map(|detail| HSTRING::from(detail.as_ref()));
+    let labels = if options.buttons.is_empty() {
+        vec![HSTRING::from("OK")]
+    } else {
+        options
+            .buttons
+            .iter()
+            .map(|label| HSTRING::from(label.as_ref()))
+            .collect::<Vec<_>>()
     };
+    let buttons = labels
+        .iter()
+        .enumerate()
+        .map(|(ix, label)| TASKDIALOG_BUTTON {
+            nButtonID: FIRST_BUTTON_ID + ix as i32,
+            pszButtonText: PCWSTR::from_raw(label.as_ptr()),
+        })
+        .collect::<Vec<_>>();
+    let verification = options
+        .suppression
+        .as_ref()
+        .map(|suppression| HSTRING::from(suppression.label.as_ref()));
 
-    let button_flag = match options.buttons.len() {
-        0 | 1 => MB_OK,
-        2 => MB_OKCANCEL,
-        _ => MB_YESNOCANCEL,
+    let mut config = TASKDIALOGCONFIG::default();
+    config.cbSize = std::mem::size_of::<TASKDIALOGCONFIG>() as _;
+    config.hwndParent = hwnd;
+    // Escape and the close button cancel the dialog.
+    config.dwFlags = TDF_ALLOW_DIALOG_CANCELLATION;
+    config.pszWindowTitle = PCWSTR::from_raw(title.as_ptr());
+    config.Anonymous1.pszMainIcon = match options.kind {
+        DialogKind::Info => TD_INFORMATION_ICON,
+        DialogKind::Warning => TD_WARNING_ICON,
+        DialogKind::Error => TD_ERROR_ICON,
     };
-
-    let mut message = options.message.to_string();
-    if let Some(ref detail) = options.detail {
-        message.push_str("\n\n");
-        message.push_str(detail);
+    config.pszMainInstruction = PCWSTR::from_raw(message.as_ptr());
+    if let Some(detail) = &detail {
+        config.pszContent = PCWSTR::from_raw(detail.as_ptr());
+    }
+    config.cButtons = buttons.len() as _;
+    config.pButtons = buttons.as_ptr();
+    config.nDefaultButton = FIRST_BUTTON_ID;
+    // The "don't ask again" checkbox is the task dialog's verification checkbox.
+    if let Some(verification) = &verification {
+        config.pszVerificationText = PCWSTR::from_raw(verification.as_ptr());
     }
 
-    let title_wide: Vec<u16> = options.title.encode_utf16().chain(Some(0)).collect();
-    let message_wide: Vec<u16> = message.encode_utf16().chain(Some(0)).collect();
-
-    let result = unsafe {
-        MessageBoxW(
-            Some(hwnd),
-            windows::core::PCWSTR(message_wide.as_ptr()),
-            windows::core::PCWSTR(title_wide.as_ptr()),
-            MESSAGEBOX_STYLE(kind_flag.0 | button_flag.0),
+    let mut button_id = 0;
+    let mut verification_checked = BOOL(0);
+    unsafe {
+        TaskDialogIndirect(
+            &config,
+            Some(&mut button_id),
+            None,
+            Some(&mut verification_checked),
         )
-    };
+    }
+    .context("unable to create task dialog")
+    .log_err();
 
-    match result.0 {
-        1 => 0,
-        2 => {
-            if options.buttons.len() >= 2 {
-                options.buttons.len() - 1
-            } else {
-                0
-            }
-        }
-        6 => 0,
-        7 => 1,
-        _ => 0,
+    if let Some(suppression) = &options.suppression {
+        suppression
+            .checked
+            .store(verification_checked.as_bool(), Ordering::SeqCst);
+    }
+    // The last button is "Cancel" or "No" by convention, like in the dialogs GPUI renders.
+    let cancel_button = labels.len() - 1;
+    if button_id == IDCANCEL.0 {
+        return cancel_button;
     }
+    usize::try_from(button_id - FIRST_BUTTON_ID)
+        .ok()
+        .filter(|ix| *ix < labels.len())
+        .unwrap_or(cancel_button)
 }
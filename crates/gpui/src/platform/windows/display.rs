@@ -15,7 +15,10 @@ use windows::{
     core::*,
 };
 
-use crate::{Bounds, DevicePixels, DisplayId, Pixels, PlatformDisplay, logical_point, point, size};
+use crate::{
+    Bounds, DevicePixels, DisplayId, DisplayRotation, Pixels, PlatformDisplay, Size, logical_point,
+    point, size,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct WindowsDisplay {
@@ -23,6 +26,9 @@ pub(crate) struct WindowsDisplay {
     pub display_id: DisplayId,
     scale_factor: f32,
     bounds: Bounds<Pixels>,
+    visible_bounds: Bounds<Pixels>,
+    refresh_rate: Option<f32>,
+    rotation: DisplayRotation,
     physical_bounds: Bounds<DevicePixels>,
     uuid: Uuid,
 }
@@ -37,6 +43,7 @@ impl WindowsDisplay {
         let info = get_monitor_info(screen).log_err()?;
         let monitor_size = info.monitorInfo.rcMonitor;
         let uuid = generate_uuid(&info.szDevice);
+        let (refresh_rate, rotation) = display_mode(&info.szDevice);
         let scale_factor = get_scale_factor_for_monitor(screen).log_err()?;
         let physical_size = size(
             (monitor_size.right - monitor_size.left).into(),
@@ -55,6 +62,9 @@ impl WindowsDisplay {
                 ),
                 size: physical_size.to_pixels(scale_factor),
             },
+            visible_bounds: logical_bounds(info.monitorInfo.rcWork, scale_factor),
+            refresh_rate,
+            rotation,
             physical_bounds: Bounds {
                 origin: point(monitor_size.left.into(), monitor_size.top.into()),
                 size: physical_size,
@@ -67,6 +77,7 @@ impl WindowsDisplay {
         let info = get_monitor_info(monitor).expect("unable to get monitor info");
         let monitor_size = info.monitorInfo.rcMonitor;
         let uuid = generate_uuid(&info.szDevice);
+        let (refresh_rate, rotation) = display_mode(&info.szDevice);
         let display_id = available_monitors()
             .iter()
             .position(|handle| handle.0 == monitor.0)
@@ -90,6 +101,9 @@ impl WindowsDisplay {
                 ),
                 size: physical_size.to_pixels(scale_factor),
             },
+            visible_bounds: logical_bounds(info.monitorInfo.rcWork, scale_factor),
+            refresh_rate,
+            rotation,
             physical_bounds: Bounds {
                 origin: point(monitor_size.left.into(), monitor_size.top.into()),
                 size: physical_size,
@@ -102,6 +116,7 @@ impl WindowsDisplay {
         let info = get_monitor_info(handle).expect("unable to get monitor info");
         let monitor_size = info.monitorInfo.rcMonitor;
         let uuid = generate_uuid(&info.szDevice);
+        let (refresh_rate, rotation) = display_mode(&info.szDevice);
         let scale_factor =
             get_scale_factor_for_monitor(handle).expect("unable to get scale factor for monitor");
         let physical_size = size(
@@ -121,6 +136,9 @@ impl WindowsDisplay {
                 ),
                 size: physical_size.to_pixels(scale_factor),
             },
+            visible_bounds: logical_bounds(info.monitorInfo.rcWork, scale_factor),
+            refresh_rate,
+            rotation,
             physical_bounds: Bounds {
                 origin: point(monitor_size.left.into(), monitor_size.top.into()),
                 size: physical_size,
@@ -194,6 +212,18 @@ impl PlatformDisplay for WindowsDisplay {
     fn bounds(&self) -> Bounds<Pixels> {
         self.bounds
     }
+
+    fn visible_bounds(&self) -> Bounds<Pixels> {
+        self.visible_bounds
+    }
+
+    fn refresh_rate(&self) -> Option<f32> {
+        self.refresh_rate
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
 }
 
 fn available_monitors() -> SmallVec<[HMONITOR; 4]> {
@@ -222,6 +252,38 @@ unsafe extern "system" fn monitor_enum_proc(
     BOOL(1)
 }
 
+fn logical_bounds(rect: RECT, scale_factor: f32) -> Bounds<Pixels> {
+    let physical_size: Size<DevicePixels> = size(
+        (rect.right - rect.left).into(),
+        (rect.bottom - rect.top).into(),
+    );
+    Bounds {
+        origin: logical_point(rect.left as f32, rect.top as f32, scale_factor),
+        size: physical_size.to_pixels(scale_factor),
+    }
+}
+
+/// Read the refresh rate and rotation of a monitor's current display mode.
+fn display_mode(device: &[u16; 32]) -> (Option<f32>, DisplayRotation) {
+    let mut mode: DEVMODEW = unsafe { std::mem::zeroed() };
+    mode.dmSize = std::mem::size_of::<DEVMODEW>() as u16;
+    let found =
+        unsafe { EnumDisplaySettingsW(PCWSTR(device.as_ptr()), ENUM_CURRENT_SETTINGS, &mut mode) };
+    if !found.as_bool() {
+        return (None, DisplayRotation::Normal);
+    }
+    // Zero and one stand for the hardware's default rate.
+    let refresh_rate = (mode.dmDisplayFrequency > 1).then_some(mode.dmDisplayFrequency as f32);
+    // Windows measures rotation clockwise.
+    let rotation = match unsafe { mode.Anonymous1.Anonymous2.dmDisplayOrientation } {
+        DMDO_90 => DisplayRotation::Rotate270,
+        DMDO_180 => DisplayRotation::Rotate180,
+        DMDO_270 => DisplayRotation::Rotate90,
+        _ => DisplayRotation::Normal,
+    };
+    (refresh_rate, rotation)
+}
+
 fn get_monitor_info(hmonitor: HMONITOR) -> anyhow::Result<MONITORINFOEXW> {
     let mut monitor_info: MONITORINFOEXW = unsafe { std::mem::zeroed() };
     monitor_info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
@@ -1222,9 +1222,11 @@ impl WindowsWindowInner {
     #[inline]
     fn draw_window(&self, handle: HWND, force_render: bool) -> Option<isize> {
         let mut request_frame = self.state.borrow_mut().callbacks.request_frame.take()?;
+        let display_id = self.state.borrow().display.display_id;
         request_frame(RequestFrameOptions {
             require_presentation: false,
             force_render,
+            display_id: Some(display_id),
         });
         self.state.borrow_mut().callbacks.request_frame = Some(request_frame);
         unsafe { ValidateRect(Some(handle), None).ok().log_err() };
@@ -17,14 +17,14 @@ pub(crate) fn query_network_status() -> NetworkStatus {
                     let has_ipv4 = (connectivity.0 & NLM_CONNECTIVITY_IPV4_INTERNET.0) != 0;
                     let has_ipv6 = (connectivity.0 & NLM_CONNECTIVITY_IPV6_INTERNET.0) != 0;
                     if has_ipv4 || has_ipv6 {
-                        NetworkStatus::Online
+                        NetworkStatus::ONLINE
                     } else {
-                        NetworkStatus::Offline
+                        NetworkStatus::OFFLINE
                     }
                 }
-                Err(_) => NetworkStatus::Online,
+                Err(_) => NetworkStatus::ONLINE,
             },
-            Err(_) => NetworkStatus::Online,
+            Err(_) => NetworkStatus::ONLINE,
         }
     }
 }
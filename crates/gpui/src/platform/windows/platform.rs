@@ -79,6 +79,7 @@ struct PlatformCallbacks {
     will_open_app_menu: Option<Box<dyn FnMut()>>,
     validate_app_menu_command: Option<Box<dyn FnMut(&dyn Action) -> bool>>,
     keyboard_layout_change: Option<Box<dyn FnMut()>>,
+    displays_changed: Option<Box<dyn FnMut()>>,
     tray_icon_event: Option<Box<dyn FnMut(TrayIconEvent)>>,
     tray_menu_action: Option<Box<dyn FnMut(SharedString)>>,
     global_hotkey: Option<Box<dyn FnMut(u32)>>,
@@ -135,9 +136,11 @@ impl WindowsPlatform {
             main_receiver: Some(main_receiver),
             directx_devices: Some(directx_devices),
         };
+        // A hidden top-level window rather than a message-only one, which wouldn't receive
+        // broadcasts such as WM_DISPLAYCHANGE and WM_SETTINGCHANGE.
         let result = unsafe {
             CreateWindowExW(
-                WINDOW_EX_STYLE(0),
+                WS_EX_TOOLWINDOW,
                 PLATFORM_WINDOW_CLASS_NAME,
                 None,
                 WINDOW_STYLE(0),
@@ -145,7 +148,7 @@ impl WindowsPlatform {
                 0,
                 0,
                 0,
-                Some(HWND_MESSAGE),
+                None,
                 None,
                 None,
                 Some(&context as *const _ as *const _),
@@ -336,6 +339,10 @@ impl Platform for WindowsPlatform {
             .keyboard_layout_change = Some(callback);
     }
 
+    fn on_displays_changed(&self, callback: Box<dyn FnMut()>) {
+        self.inner.state.borrow_mut().callbacks.displays_changed = Some(callback);
+    }
+
     fn run(&self, on_finish_launching: Box<dyn 'static + FnOnce()>) {
         on_finish_launching();
         self.begin_vsync_thread();
@@ -441,8 +448,21 @@ impl Platform for WindowsPlatform {
         handle: AnyWindowHandle,
         options: WindowParams,
     ) -> Result<Box<dyn PlatformWindow>> {
+        let owner = options.popup.and_then(|popup| {
+            self.raw_window_handles
+                .read()
+                .iter()
+                .map(|hwnd| hwnd.as_raw())
+                .find(|&hwnd| {
+                    window_from_hwnd(hwnd).is_some_and(|window| window.handle == popup.parent)
+                })
+        });
         let window = WindowsWindow::new(handle, options, self.generate_creation_info())?;
         let handle = window.get_raw_handle();
+        // Popups are owned by their parent, which keeps them above it and hides them with it.
+        if let Some(owner) = owner {
+            unsafe { set_window_long(handle, GWLP_HWNDPARENT, owner.0 as isize) };
+        }
         self.raw_window_handles.write().push(handle.into());
 
         Ok(Box::new(window))
@@ -710,7 +730,12 @@ impl Platform for WindowsPlatform {
             .store(keep_alive, Ordering::Release);
     }
 
-    fn set_tray_icon(&self, icon: Option<&[u8]>) {
+    fn set_tray_icon(&self, icon: Option<TrayIconFrame>) {
+        let size = unsafe { GetSystemMetrics(SM_CXSMICON) };
+        let icon = icon
+            .as_ref()
+            .and_then(|icon| icon.image_closest_to(size as u32))
+            .map(|image| image.bytes.as_ref());
         let mut state = self.inner.state.borrow_mut();
         if let Some(ref mut tray) = state.tray {
             tray.set_icon(icon, self.handle);
@@ -786,14 +811,14 @@ impl Platform for WindowsPlatform {
         self.inner.state.borrow_mut().callbacks.system_power = Some(callback);
     }
 
-    fn start_power_save_blocker(&self, kind: PowerSaveBlockerKind) -> Option<u32> {
+    fn start_power_save_blocker(&self, kind: PowerSaveBlockerKind) -> Task<Result<u32>> {
         let mut state = self.inner.state.borrow_mut();
         let id = state.next_blocker_id;
         state.next_blocker_id += 1;
         let flags = super::power::power_save_flags(kind);
         state.power_save_blockers.insert(id, flags);
         super::power::apply_combined_power_state(&state.power_save_blockers);
-        Some(id)
+        Task::ready(Ok(id))
     }
 
     fn stop_power_save_blocker(&self, id: u32) {
@@ -878,21 +903,17 @@ impl Platform for WindowsPlatform {
         position: Point<Pixels>,
         items: Vec<TrayMenuItem>,
         callback: Box<dyn FnMut(SharedString)>,
-    ) {
-        {
-            let mut state = self.inner.state.borrow_mut();
-            state.callbacks.context_menu = Some(callback);
-            state.context_menu_command_map.clear();
-        }
-
+    ) -> Result<(), Vec<TrayMenuItem>> {
         unsafe {
             let hmenu = match CreatePopupMenu() {
                 Ok(m) => m,
-                Err(_) => return,
+                Err(_) => return Err(items),
             };
 
             {
                 let mut state = self.inner.state.borrow_mut();
+                state.callbacks.context_menu = Some(callback);
+                state.context_menu_command_map.clear();
                 let mut counter: u32 = 10000;
                 WindowsTray::build_menu(
                     hmenu,
@@ -929,6 +950,7 @@ impl Platform for WindowsPlatform {
                 }
             }
         }
+        Ok(())
     }
 
     fn show_dialog(&self, options: DialogOptions) -> oneshot::Receiver<usize> {
@@ -946,8 +968,8 @@ impl Platform for WindowsPlatform {
         super::os_info::get_os_info()
     }
 
-    fn biometric_status(&self) -> BiometricStatus {
-        BiometricStatus::Unavailable
+    fn biometric_status(&self) -> Task<BiometricStatus> {
+        Task::ready(BiometricStatus::Unavailable)
     }
 
     fn authenticate_biometric(
@@ -990,6 +1012,10 @@ impl WindowsPlatformInner {
             WM_COMMAND => self.handle_tray_menu_command(wparam),
             WM_HOTKEY => self.handle_global_hotkey(wparam),
             WM_POWERBROADCAST => self.handle_power_broadcast(wparam),
+            WM_DISPLAYCHANGE => self.handle_displays_changed(),
+            WM_SETTINGCHANGE if wparam.0 == SPI_SETWORKAREA.0 as usize => {
+                self.handle_displays_changed()
+            }
             super::events::WM_WTSSESSION_CHANGE => self.handle_session_change(wparam),
             WM_GPUI_NETWORK_CHANGE => self.handle_network_change(),
             WM_GPUI_MEDIA_KEY => self.handle_media_key(wparam, lparam),
@@ -1077,6 +1103,13 @@ impl WindowsPlatformInner {
         Some(0)
     }
 
+    fn handle_displays_changed(&self) -> Option<isize> {
+        let mut callback = self.state.borrow_mut().callbacks.displays_changed.take()?;
+        callback();
+        self.state.borrow_mut().callbacks.displays_changed = Some(callback);
+        Some(0)
+    }
+
     fn handle_tray_icon_event(&self, handle: HWND, lparam: LPARAM) -> Option<isize> {
         let event = match (lparam.0 & 0xFFFF) as u32 {
             WM_LBUTTONUP => Some(TrayIconEvent::LeftClick),
@@ -15,7 +15,7 @@ use windows::{
     },
 };
 
-use crate::{SharedString, TrayMenuItem, WM_GPUI_TRAY_ICON};
+use crate::{Keystroke, SharedString, TrayMenuItem, WM_GPUI_TRAY_ICON};
 
 const TRAY_ICON_ID: u32 = 1;
 
@@ -163,15 +163,29 @@ impl WindowsTray {
         id_map: &mut HashMap<u32, SharedString>,
     ) {
         for item in items.iter() {
+            let enabled_flag = if item.is_enabled() {
+                MF_ENABLED
+            } else {
+                MF_GRAYED
+            };
             match item {
-                TrayMenuItem::Action { label, id } => {
+                TrayMenuItem::Action {
+                    label,
+                    id,
+                    accelerator,
+                    ..
+                } => {
                     let cmd_id = *counter;
                     *counter += 1;
                     id_map.insert(cmd_id, id.clone());
-                    let wide: Vec<u16> = label.encode_utf16().chain(Some(0)).collect();
+                    let wide = menu_label(label, accelerator.as_ref());
                     unsafe {
-                        let _ =
-                            AppendMenuW(hmenu, MF_STRING, cmd_id as usize, PCWSTR(wide.as_ptr()));
+                        let _ = AppendMenuW(
+                            hmenu,
+                            MF_STRING | enabled_flag,
+                            cmd_id as usize,
+                            PCWSTR(wide.as_ptr()),
+                        );
                     }
                 }
                 TrayMenuItem::Separator => unsafe {
@@ -180,14 +194,15 @@ impl WindowsTray {
                 TrayMenuItem::Submenu {
                     label,
                     items: sub_items,
+                    ..
                 } => {
                     if let Ok(submenu) = unsafe { CreatePopupMenu() } {
                         unsafe { Self::build_menu(submenu, sub_items, counter, id_map) };
-                        let wide: Vec<u16> = label.encode_utf16().chain(Some(0)).collect();
+                        let wide = menu_label(label, None);
                         unsafe {
                             let _ = AppendMenuW(
                                 hmenu,
-                                MF_POPUP,
+                                MF_POPUP | enabled_flag,
                                 submenu.0 as usize,
                                 PCWSTR(wide.as_ptr()),
                             );
@@ -195,7 +210,18 @@ impl WindowsTray {
                     }
                 }
                 TrayMenuItem::Toggle {
-                    label, checked, id, ..
+                    label,
+                    checked,
+                    id,
+                    accelerator,
+                    ..
+                }
+                | TrayMenuItem::Radio {
+                    label,
+                    checked,
+                    id,
+                    accelerator,
+                    ..
                 } => {
                     let cmd_id = *counter;
                     *counter += 1;
@@ -205,9 +231,14 @@ impl WindowsTray {
                     } else {
                         MF_STRING
                     };
-                    let wide: Vec<u16> = label.encode_utf16().chain(Some(0)).collect();
+                    let wide = menu_label(label, accelerator.as_ref());
                     unsafe {
-                        let _ = AppendMenuW(hmenu, flags, cmd_id as usize, PCWSTR(wide.as_ptr()));
+                        let _ = AppendMenuW(
+                            hmenu,
+                            flags | enabled_flag,
+                            cmd_id as usize,
+                            PCWSTR(wide.as_ptr()),
+                        );
                     }
                 }
             }
@@ -215,6 +246,15 @@ impl WindowsTray {
     }
 }
 
+/// The null-terminated text of a menu item, with the accelerator right-aligned after a tab.
+fn menu_label(label: &str, accelerator: Option<&Keystroke>) -> Vec<u16> {
+    let text = match accelerator {
+        Some(keystroke) => format!("{label}\t{keystroke}"),
+        None => label.to_string(),
+    };
+    text.encode_utf16().chain(Some(0)).collect()
+}
+
 impl Drop for WindowsTray {
     fn drop(&mut self) {
         if self.icon_added {
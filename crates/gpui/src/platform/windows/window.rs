@@ -383,7 +383,11 @@ impl WindowsWindow {
                 .unwrap_or(""),
         );
 
-        let (mut dwexstyle, dwstyle) = if params.kind == WindowKind::PopUp {
+        let is_popup = matches!(
+            params.kind,
+            WindowKind::PopUp | WindowKind::AnchoredPopup { .. }
+        );
+        let (mut dwexstyle, dwstyle) = if is_popup {
             (WS_EX_TOOLWINDOW, WINDOW_STYLE(0x0))
         } else if params.kind == WindowKind::Overlay {
             (WS_EX_TOOLWINDOW | WS_EX_TOPMOST, WS_POPUP)
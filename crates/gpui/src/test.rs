@@ -25,6 +25,12 @@
 //!   assert!(true)
 //! }
 //! ```
+//!
+//! Views can also be fuzzed with random input using the `gpui::fuzz` macro; see [`FuzzAction`].
+mod fuzz;
+
+pub use fuzz::*;
+
 use crate::{Entity, Subscription, TestAppContext, TestDispatcher};
 use futures::StreamExt as _;
 use rand::prelude::*;
@@ -0,0 +1,622 @@
+//! Property-based fuzzing of views, as run by the `gpui::fuzz` macro.
+//!
+//! Each seed opens the view in a fresh test app and throws a random sequence of [`FuzzAction`]s
+//! at it. After every action the harness checks that the app is still healthy, and once the
+//! sequence is done it closes the window and checks that the view was released. A sequence that
+//! breaks any of these is shrunk to the shortest one that still fails before it is reported.
+
+use super::calculate_seeds;
+use crate::{
+    AnyWindowHandle, App, AppContext as _, Bounds, Context, Keystroke, Modifiers, MouseButton,
+    MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, Point, Render, ScrollDelta,
+    ScrollWheelEvent, Size, TestAppContext, TestDispatcher, TouchPhase, VisualTestContext,
+    WeakEntity, Window, WindowBounds, WindowOptions, point, px, size,
+};
+use rand::prelude::*;
+use std::{
+    any::Any,
+    fmt::Write as _,
+    panic::{self, AssertUnwindSafe},
+};
+
+/// Keys that are tried even when the keymap doesn't bind them, since views handle them directly.
+const COMMON_KEYS: &[&str] = &[
+    "a",
+    "z",
+    "1",
+    "space",
+    "enter",
+    "escape",
+    "tab",
+    "backspace",
+    "delete",
+    "left",
+    "right",
+    "up",
+    "down",
+    "home",
+    "end",
+    "pageup",
+    "pagedown",
+];
+
+/// One thing the user does to a window while it is fuzzed. Positions are fractions of the
+/// window's size at the time, so sequences keep hitting the same areas when they are shrunk.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FuzzAction {
+    /// Move the mouse to a position and click there.
+    Click {
+        /// Where to click.
+        position: Point<f32>,
+        /// The button to click with.
+        button: MouseButton,
+        /// 1 for a single click, 2 for a double click, and so on.
+        click_count: usize,
+        /// The modifiers held during the click.
+        modifiers: Modifiers,
+    },
+    /// Press the left button at one position, move to another and release it there.
+    Drag {
+        /// Where the drag starts.
+        from: Point<f32>,
+        /// Where the drag ends.
+        to: Point<f32>,
+    },
+    /// Scroll the wheel over a position.
+    Scroll {
+        /// Where the mouse is.
+        position: Point<f32>,
+        /// How far to scroll.
+        delta: Point<Pixels>,
+    },
+    /// Press a key.
+    Keystroke(Keystroke),
+    /// Resize the window.
+    Resize(Size<Pixels>),
+    /// Move focus to the next tab stop.
+    FocusNext,
+    /// Move focus to the previous tab stop.
+    FocusPrev,
+    /// Activate or deactivate the window.
+    SetWindowActive(bool),
+}
+
+impl FuzzAction {
+    /// Generate a random action, picking keystrokes from `keystrokes`.
+    pub fn random(rng: &mut impl Rng, keystrokes: &[Keystroke]) -> Self {
+        match rng.random_range(0..100) {
+            0..25 => FuzzAction::Click {
+                position: random_position(rng),
+                button: if rng.random_bool(0.9) {
+                    MouseButton::Left
+                } else {
+                    MouseButton::Right
+                },
+                click_count: if rng.random_bool(0.8) { 1 } else { 2 },
+                modifiers: match rng.random_range(0..6) {
+                    0 => Modifiers::shift(),
+                    1 => Modifiers::secondary_key(),
+                    _ => Modifiers::none(),
+                },
+            },
+            25..35 => FuzzAction::Drag {
+                from: random_position(rng),
+                to: random_position(rng),
+            },
+            35..45 => FuzzAction::Scroll {
+                position: random_position(rng),
+                delta: point(
+                    px(rng.random_range(-200..=200) as f32),
+                    px(rng.random_range(-600..=600) as f32),
+                ),
+            },
+            45..85 if !keystrokes.is_empty() => {
+                FuzzAction::Keystroke(keystrokes.choose(rng).unwrap().clone())
+            }
+            45..90 => FuzzAction::Resize(size(
+                px(rng.random_range(200..=1600) as f32),
+                px(rng.random_range(150..=1200) as f32),
+            )),
+            90..94 => FuzzAction::FocusNext,
+            94..97 => FuzzAction::FocusPrev,
+            _ => FuzzAction::SetWindowActive(rng.random_bool(0.5)),
+        }
+    }
+}
+
+fn random_position(rng: &mut impl Rng) -> Point<f32> {
+    point(rng.random(), rng.random())
+}
+
+/// A sequence of actions that broke a view.
+#[derive(Clone, Debug)]
+pub struct FuzzFailure {
+    /// The seed the failure was found with.
+    pub seed: u64,
+    /// The shortest sequence of actions found that still fails.
+    pub actions: Vec<FuzzAction>,
+    /// How many actions the sequence had before it was shrunk.
+    pub original_len: usize,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl std::fmt::Display for FuzzFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "fuzzing failed with seed {} after {} actions, shrunk from {}:",
+            self.seed,
+            self.actions.len(),
+            self.original_len
+        )?;
+        for (ix, action) in self.actions.iter().enumerate() {
+            writeln!(f, "  {}. {action:?}", ix + 1)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Run the fuzz test with the configured parameters, panicking with a shrunk reproduction if
+/// it finds a failure. This is intended for use with the `gpui::fuzz` macro and generally should
+/// not be used directly.
+pub fn run_fuzz_test<V: 'static + Render>(
+    num_iterations: usize,
+    explicit_seeds: &[u64],
+    steps: usize,
+    build_root_view: &dyn Fn(&mut Window, &mut Context<V>) -> V,
+) {
+    let (seeds, is_multiple_runs) = calculate_seeds(num_iterations as u64, explicit_seeds);
+    for seed in seeds {
+        if is_multiple_runs {
+            eprintln!("seed = {seed}");
+        }
+        if let Err(failure) = fuzz_view(seed, steps, build_root_view) {
+            panic!("{failure}");
+        }
+    }
+}
+
+/// Fuzz a view with one seed, shrinking the sequence of actions if it fails.
+pub fn fuzz_view<V: 'static + Render>(
+    seed: u64,
+    steps: usize,
+    build_root_view: &dyn Fn(&mut Window, &mut Context<V>) -> V,
+) -> Result<(), FuzzFailure> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut actions = Vec::with_capacity(steps);
+    let mut run = match FuzzRun::new(seed, build_root_view) {
+        Ok(run) => run,
+        Err(message) => {
+            return Err(FuzzFailure {
+                seed,
+                actions,
+                original_len: 0,
+                message,
+            });
+        }
+    };
+    let keystrokes = run.keystrokes();
+    let mut result = Ok(());
+    for _ in 0..steps {
+        let action = FuzzAction::random(&mut rng, &keystrokes);
+        actions.push(action.clone());
+        result = run.perform(&action);
+        if result.is_err() {
+            break;
+        }
+    }
+    let result = result.and_then(|()| run.finish());
+    let Err(mut message) = result else {
+        return Ok(());
+    };
+
+    let original_len = actions.len();
+    actions = shrink(actions, |actions| {
+        match replay_fuzz_actions(seed, build_root_view, actions) {
+            Ok(()) => false,
+            Err(error) => {
+                message = error;
+                true
+            }
+        }
+    });
+    Err(FuzzFailure {
+        seed,
+        actions,
+        original_len,
+        message,
+    })
+}
+
+/// Run a sequence of actions against a fresh copy of the view, e.g. to reproduce a
+/// [`FuzzFailure`] while debugging it.
+pub fn replay_fuzz_actions<V: 'static + Render>(
+    seed: u64,
+    build_root_view: &dyn Fn(&mut Window, &mut Context<V>) -> V,
+    actions: &[FuzzAction],
+) -> Result<(), String> {
+    let mut run = FuzzRun::new(seed, build_root_view)?;
+    for action in actions {
+        run.perform(action)?;
+    }
+    run.finish()
+}
+
+/// Remove as many actions as possible while `fails` keeps returning true, first in large chunks
+/// and then one at a time.
+fn shrink<T: Clone>(mut items: Vec<T>, mut fails: impl FnMut(&[T]) -> bool) -> Vec<T> {
+    let mut chunk_len = items.len().div_ceil(2);
+    while chunk_len > 0 {
+        let mut removed_any = false;
+        let mut start = 0;
+        while start < items.len() {
+            let end = (start + chunk_len).min(items.len());
+            let candidate = [&items[..start], &items[end..]].concat();
+            if fails(&candidate) {
+                items = candidate;
+                removed_any = true;
+            } else {
+                start = end;
+            }
+        }
+        if !removed_any {
+            chunk_len /= 2;
+        }
+    }
+    items
+}
+
+/// A view open in a window of its own test app.
+struct FuzzRun<V> {
+    cx: VisualTestContext,
+    dispatcher: TestDispatcher,
+    window: AnyWindowHandle,
+    view: WeakEntity<V>,
+}
+
+impl<V: 'static + Render> FuzzRun<V> {
+    fn new(
+        seed: u64,
+        build_root_view: &dyn Fn(&mut Window, &mut Context<V>) -> V,
+    ) -> Result<Self, String> {
+        let dispatcher = TestDispatcher::new(StdRng::seed_from_u64(seed));
+        let mut cx = TestAppContext::build(dispatcher.clone(), None);
+        let window = catch_panic(|| {
+            let mut app = cx.app.borrow_mut();
+            let bounds = Bounds::maximized(None, &app);
+            app.open_window(
+                WindowOptions {
+                    window_bounds: Some(WindowBounds::Windowed(bounds)),
+                    ..Default::default()
+                },
+                |window, cx| cx.new(|cx| build_root_view(window, cx)),
+            )
+            .unwrap()
+        })?;
+        let view = window.root(&mut cx).unwrap().downgrade();
+        let window = window.into();
+        let mut run = Self {
+            cx: VisualTestContext::from_window(window, &cx),
+            dispatcher,
+            window,
+            view,
+        };
+        run.settle()?;
+        Ok(run)
+    }
+
+    /// The keystrokes of every binding in the keymap, followed by some common keys.
+    fn keystrokes(&mut self) -> Vec<Keystroke> {
+        let keymap = self.cx.update(|_, cx| cx.key_bindings());
+        let keymap = keymap.borrow();
+        let mut keystrokes = Vec::new();
+        for keystroke in keymap
+            .bindings()
+            .flat_map(|binding| binding.keystrokes())
+            .map(|keystroke| keystroke.inner().clone())
+            .chain(COMMON_KEYS.iter().map(|key| Keystroke::parse(key).unwrap()))
+        {
+            if !keystrokes.contains(&keystroke) {
+                keystrokes.push(keystroke);
+            }
+        }
+        keystrokes
+    }
+
+    fn perform(&mut self, action: &FuzzAction) -> Result<(), String> {
+        let window = self.window;
+        catch_panic(|| {
+            let viewport_size = self.cx.update(|window, _| window.viewport_size());
+            let to_window = |position: Point<f32>| {
+                point(
+                    viewport_size.width * position.x,
+                    viewport_size.height * position.y,
+                )
+            };
+            match action {
+                FuzzAction::Click {
+                    position,
+                    button,
+                    click_count,
+                    modifiers,
+                } => {
+                    let position = to_window(*position);
+                    self.simulate(MouseMoveEvent {
+                        position,
+                        pressed_button: None,
+                        modifiers: *modifiers,
+                    });
+                    self.simulate(MouseDownEvent {
+                        button: *button,
+                        position,
+                        modifiers: *modifiers,
+                        click_count: *click_count,
+                        first_mouse: false,
+                    });
+                    self.simulate(MouseUpEvent {
+                        button: *button,
+                        position,
+                        modifiers: *modifiers,
+                        click_count: *click_count,
+                    });
+                }
+                FuzzAction::Drag { from, to } => {
+                    let (from, to) = (to_window(*from), to_window(*to));
+                    self.simulate(MouseMoveEvent {
+                        position: from,
+                        pressed_button: None,
+                        modifiers: Modifiers::none(),
+                    });
+                    self.simulate(MouseDownEvent {
+                        button: MouseButton::Left,
+                        position: from,
+                        modifiers: Modifiers::none(),
+                        click_count: 1,
+                        first_mouse: false,
+                    });
+                    for step in 1..=4 {
+                        let progress = step as f32 / 4.;
+                        self.simulate(MouseMoveEvent {
+                            position: point(
+                                from.x + (to.x - from.x) * progress,
+                                from.y + (to.y - from.y) * progress,
+                            ),
+                            pressed_button: Some(MouseButton::Left),
+                            modifiers: Modifiers::none(),
+                        });
+                    }
+                    self.simulate(MouseUpEvent {
+                        button: MouseButton::Left,
+                        position: to,
+                        modifiers: Modifiers::none(),
+                        click_count: 1,
+                    });
+                }
+                FuzzAction::Scroll { position, delta } => {
+                    self.simulate(ScrollWheelEvent {
+                        position: to_window(*position),
+                        delta: ScrollDelta::Pixels(*delta),
+                        modifiers: Modifiers::none(),
+                        touch_phase: TouchPhase::Moved,
+                    });
+                }
+                FuzzAction::Keystroke(keystroke) => {
+                    self.cx.dispatch_keystroke(window, keystroke.clone());
+                }
+                FuzzAction::Resize(size) => self.cx.simulate_resize(*size),
+                FuzzAction::FocusNext => self.cx.update(|window, _| window.focus_next()),
+                FuzzAction::FocusPrev => self.cx.update(|window, _| window.focus_prev()),
+                FuzzAction::SetWindowActive(true) => {
+                    self.cx.update(|window, _| window.activate_window())
+                }
+                FuzzAction::SetWindowActive(false) => self.cx.deactivate_window(),
+            }
+        })?;
+        self.settle()
+    }
+
+    fn simulate(&mut self, event: impl crate::InputEvent) {
+        self.cx.simulate_event(event);
+    }
+
+    /// Let the app finish what the last action started, then check it is still healthy.
+    fn settle(&mut self) -> Result<(), String> {
+        catch_panic(|| self.dispatcher.run_until_parked())?;
+        self.cx
+            .update_window(self.window, |_, window, cx| check_window(window, cx))
+            .map_err(|_| "the window was closed".to_string())?
+    }
+
+    /// Close the window and check that the view went with it.
+    fn finish(mut self) -> Result<(), String> {
+        catch_panic(|| {
+            self.cx
+                .update_window(self.window, |_, window, _| window.remove_window())
+                .ok();
+            self.dispatcher.run_until_parked();
+        })?;
+        catch_panic(|| self.view.assert_released())
+            .map_err(|_| "the view leaked after its window was closed".to_string())?;
+        catch_panic(|| {
+            self.cx.executor().forbid_parking();
+            self.cx.quit();
+            self.dispatcher.run_until_parked();
+        })
+    }
+}
+
+/// Check the invariants every frame should keep.
+fn check_window(window: &Window, cx: &App) -> Result<(), String> {
+    if let Some(focus) = window.focus {
+        if window.focused(cx).is_none() {
+            return Err("the window is focused on a released focus handle".into());
+        }
+        if window
+            .rendered_frame
+            .dispatch_tree
+            .focusable_node_id(focus)
+            .is_none()
+        {
+            return Err("the window is focused on an element that wasn't rendered".into());
+        }
+    }
+
+    let is_nan = |bounds: Bounds<f32>| {
+        bounds.origin.x.is_nan()
+            || bounds.origin.y.is_nan()
+            || bounds.size.width.is_nan()
+            || bounds.size.height.is_nan()
+    };
+    let frame = &window.rendered_frame;
+    if let Some(hitbox) = frame
+        .hitboxes
+        .iter()
+        .find(|hitbox| is_nan(hitbox.bounds.map(|pixels| pixels.0)))
+    {
+        return Err(format!(
+            "an element was laid out with NaN bounds: {:?}",
+            hitbox.bounds
+        ));
+    }
+    if let Some(quad) = frame
+        .scene
+        .quads
+        .iter()
+        .find(|quad| is_nan(quad.bounds.map(|pixels| pixels.0)))
+    {
+        return Err(format!(
+            "a quad was painted with NaN bounds: {:?}",
+            quad.bounds
+        ));
+    }
+    Ok(())
+}
+
+fn catch_panic<R>(f: impl FnOnce() -> R) -> Result<R, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let mut message = String::from("panicked");
+        if let Some(text) = panic_message(&*payload) {
+            write!(message, ": {text}").unwrap();
+        }
+        // The payload might itself panic when dropped:
+        // https://doc.rust-lang.org/std/panic/fn.catch_unwind.html#notes
+        std::mem::forget(payload);
+        message
+    })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FocusHandle, InteractiveElement as _, IntoElement, ParentElement as _,
+        StatefulInteractiveElement as _, Styled as _, div,
+    };
+
+    #[test]
+    fn test_shrink() {
+        let items = (0..100).collect::<Vec<_>>();
+        let shrunk = shrink(items, |items| {
+            items.contains(&17) && items.iter().filter(|item| *item % 10 == 3).count() >= 2
+        });
+        assert_eq!(shrunk.len(), 3);
+        assert!(shrunk.contains(&17));
+    }
+
+    struct Buttons {
+        focus_handles: Vec<FocusHandle>,
+        mouse_downs: usize,
+        panic_after_mouse_downs: Option<usize>,
+    }
+
+    impl Buttons {
+        fn new(panic_after_mouse_downs: Option<usize>, cx: &mut App) -> Self {
+            Self {
+                focus_handles: (0..3).map(|_| cx.focus_handle().tab_stop(true)).collect(),
+                mouse_downs: 0,
+                panic_after_mouse_downs,
+            }
+        }
+    }
+
+    impl Render for Buttons {
+        fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+            if let Some(limit) = self.panic_after_mouse_downs {
+                assert!(
+                    self.mouse_downs < limit,
+                    "pressed the mouse {} times",
+                    self.mouse_downs
+                );
+            }
+            div()
+                .id("buttons")
+                .size_full()
+                .overflow_y_scroll()
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(|this, _, _, cx| {
+                        this.mouse_downs += 1;
+                        cx.notify();
+                    }),
+                )
+                .children(self.focus_handles.iter().enumerate().map(|(ix, handle)| {
+                    div()
+                        .id(ix)
+                        .track_focus(handle)
+                        .h(px(300.))
+                        .child(format!("{ix}"))
+                }))
+        }
+    }
+
+    #[gpui::fuzz(iterations = 4, steps = 50)]
+    fn fuzz_buttons(_: &mut Window, cx: &mut Context<Buttons>) -> Buttons {
+        Buttons::new(None, cx)
+    }
+
+    #[test]
+    fn test_fuzz_shrinks_failures() {
+        let failure = fuzz_view(0, 200, &|_, cx| Buttons::new(Some(3), cx)).unwrap_err();
+        // Only left clicks and drags press the left button, and each presses it once.
+        assert_eq!(failure.actions.len(), 3, "{failure}");
+        assert!(failure.original_len > 3);
+        assert!(
+            failure.message.contains("pressed the mouse 3 times"),
+            "{failure}"
+        );
+    }
+
+    struct LeakOnKeystroke(FocusHandle);
+
+    impl Render for LeakOnKeystroke {
+        fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+            div()
+                .track_focus(&self.0)
+                .size_full()
+                .on_key_down(cx.listener(|_, _, _, cx| std::mem::forget(cx.entity())))
+        }
+    }
+
+    #[test]
+    fn test_fuzz_detects_leaks() {
+        let failure = fuzz_view(0, 50, &|window, cx| {
+            let focus_handle = cx.focus_handle();
+            window.focus(&focus_handle);
+            LeakOnKeystroke(focus_handle)
+        })
+        .unwrap_err();
+        assert!(
+            matches!(failure.actions.as_slice(), [FuzzAction::Keystroke(_)]),
+            "{failure}"
+        );
+        assert!(failure.message.contains("leaked"), "{failure}");
+    }
+}
@@ -9,16 +9,16 @@ use crate::{
     KeyBinding, KeyContext, KeyDownEvent, KeyEvent, Keystroke, KeystrokeEvent, LayoutId,
     LineLayoutIndex, Modifiers, ModifiersChangedEvent, MonochromeSprite, MouseButton, MouseEvent,
     MouseMoveEvent, MouseUpEvent, Path, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
-    PlatformInputHandler, PlatformWindow, Point, PolychromeSprite, ProgressBarState, PromptButton,
-    PromptLevel, Quad,
-    Render, RenderGlyphParams, RenderImage, RenderImageParams, RenderSvgParams, Replay, ResizeEdge,
-    SMOOTH_SVG_SCALE_FACTOR, SUBPIXEL_VARIANTS_X, SUBPIXEL_VARIANTS_Y, ScaledPixels, Scene, Shadow,
-    SharedString, Size, StrikethroughStyle, Style, SubscriberSet, Subscription, SystemWindowTab,
+    PlatformInputHandler, PlatformWindow, Point, PolychromeSprite, PopupParams, ProgressBarState,
+    PromptButton, PromptLevel, Quad, RecordedEvent, Render, RenderGlyphParams, RenderImage,
+    RenderImageParams, RenderSvgParams, Replay, ResizeEdge, SMOOTH_SVG_SCALE_FACTOR,
+    SUBPIXEL_VARIANTS_X, SUBPIXEL_VARIANTS_Y, ScaledPixels, Scene, Shadow, SharedString, Size,
+    StrikethroughStyle, Style, SubscriberSet, Subscription, SystemWindowTab,
     SystemWindowTabController, TabStopMap, TaffyLayoutEngine, Task, TextStyle, TextStyleRefinement,
-    TransformationMatrix, Underline, UnderlineStyle, WindowAppearance, WindowBackgroundAppearance,
-    WindowBounds, WindowControls, WindowDecorations, WindowOptions, WindowParams, WindowState,
-    WindowTextSystem,
-    point, prelude::*, px, rems, size, transparent_black,
+    Tiling, TransformationMatrix, Underline, UnderlineStyle, WindowAppearance,
+    WindowBackgroundAppearance, WindowBounds, WindowControls, WindowDecorations, WindowKind,
+    WindowOptions, WindowParams, WindowState, WindowTextSystem, point, prelude::*, px, rems, size,
+    transparent_black, window_positioner::popup_bounds,
 };
 use anyhow::{Context as _, Result, anyhow};
 use collections::{FxHashMap, FxHashSet};
@@ -55,10 +55,18 @@ use util::post_inc;
 use util::{ResultExt, measure};
 use uuid::Uuid;
 
+mod context_menu;
+mod dialog;
 mod prompts;
+mod state_store;
+mod tray_popover;
 
 use crate::util::atomic_incr_if_not_zero;
+pub(crate) use context_menu::open_context_menu;
+pub(crate) use dialog::open_dialog;
 pub use prompts::*;
+pub use state_store::*;
+pub use tray_popover::*;
 
 pub(crate) const DEFAULT_WINDOW_SIZE: Size<Pixels> = size(px(1536.), px(864.));
 
@@ -232,6 +240,7 @@ pub(crate) struct FocusRef {
     pub(crate) ref_count: AtomicUsize,
     pub(crate) tab_index: isize,
     pub(crate) tab_stop: bool,
+    pub(crate) secure_input: bool,
 }
 
 impl FocusId {
@@ -286,6 +295,7 @@ impl FocusHandle {
             ref_count: AtomicUsize::new(1),
             tab_index: 0,
             tab_stop: false,
+            secure_input: false,
         });
 
         Self {
@@ -330,6 +340,17 @@ impl FocusHandle {
         self
     }
 
+    /// Sets whether the element associated with this handle takes secure input, like a password.
+    ///
+    /// Input the window receives while it's focused isn't recorded by
+    /// [`App::start_input_recording`].
+    pub fn secure_input(self, secure_input: bool) -> Self {
+        if let Some(focus) = self.handles.write().get_mut(self.id) {
+            focus.secure_input = secure_input;
+        }
+        self
+    }
+
     /// Converts this focus handle into a weak variant, which does not prevent it from being released.
     pub fn downgrade(&self) -> WeakFocusHandle {
         WeakFocusHandle {
@@ -822,6 +843,12 @@ pub struct Window {
     pub(crate) handle: AnyWindowHandle,
     pub(crate) invalidator: WindowInvalidator,
     pub(crate) removed: bool,
+    /// The window a [`WindowKind::Dialog`] is modal for, which gets no input until the dialog
+    /// closes.
+    pub(crate) modal_parent: Option<AnyWindowHandle>,
+    /// The modal [`WindowKind::Dialog`] open over this window, set by [`App::open_window`] and
+    /// cleared when the dialog closes.
+    pub(crate) modal_dialog: Option<AnyWindowHandle>,
     pub(crate) platform_window: Box<dyn PlatformWindow>,
     display_id: Option<DisplayId>,
     sprite_atlas: Arc<dyn PlatformAtlas>,
@@ -864,6 +891,7 @@ pub struct Window {
     active: Rc<Cell<bool>>,
     hovered: Rc<Cell<bool>>,
     pub(crate) needs_present: Rc<Cell<bool>>,
+    frame_display_id: Rc<Cell<Option<DisplayId>>>,
     pub(crate) last_input_timestamp: Rc<Cell<Instant>>,
     pub(crate) refreshing: bool,
     pub(crate) activation_observers: SubscriberSet<(), AnyObserver>,
@@ -928,6 +956,32 @@ fn default_bounds(display_id: Option<DisplayId>, cx: &mut App) -> Bounds<Pixels>
         })
 }
 
+/// Place a [`WindowKind::AnchoredPopup`] on screen next to its parent, on platforms where window bounds
+/// are in screen coordinates. Wayland and X11 position popups themselves.
+fn popup_screen_bounds(
+    popup: PopupParams,
+    size: Size<Pixels>,
+    cx: &mut App,
+) -> Option<Bounds<Pixels>> {
+    // Like `default_bounds`, this can't see the parent while it's being updated.
+    let (parent_origin, display) = popup
+        .parent
+        .update(cx, |_, window, cx| {
+            (window.bounds().origin, window.display(cx))
+        })
+        .ok()?;
+    Some(popup_bounds(
+        Bounds::new(
+            popup.anchor_rect.origin + parent_origin,
+            popup.anchor_rect.size,
+        ),
+        popup.gravity,
+        size,
+        display?.visible_bounds(),
+        popup.constraint_adjustment,
+    ))
+}
+
 impl Window {
     pub(crate) fn new(
         handle: AnyWindowHandle,
@@ -951,11 +1005,33 @@ impl Window {
             #[cfg_attr(not(target_os = "macos"), allow(unused_variables))]
             tabbing_identifier,
             mouse_passthrough,
+            modal_parent,
         } = options;
 
         let bounds = window_bounds
             .map(|bounds| bounds.get_bounds())
             .unwrap_or_else(|| default_bounds(display_id, cx));
+        let popup = match kind {
+            WindowKind::AnchoredPopup {
+                parent,
+                anchor_rect,
+                gravity,
+                constraint_adjustment,
+            } => Some(PopupParams {
+                parent,
+                anchor_rect,
+                gravity,
+                constraint_adjustment,
+            }),
+            _ => None,
+        };
+        let bounds = match popup {
+            Some(popup) if cfg!(not(any(target_os = "linux", target_os = "freebsd"))) => {
+                popup_screen_bounds(popup, bounds.size, cx).unwrap_or(bounds)
+            }
+            _ => bounds,
+        };
+        let modal_parent = modal_parent.filter(|_| kind == WindowKind::Dialog);
         let mut platform_window = cx.platform.open_window(
             handle,
             WindowParams {
@@ -972,6 +1048,8 @@ impl Window {
                 #[cfg(target_os = "macos")]
                 tabbing_identifier,
                 mouse_passthrough,
+                popup,
+                modal: modal_parent.is_some(),
             },
         )?;
 
@@ -994,6 +1072,7 @@ impl Window {
         let active = Rc::new(Cell::new(platform_window.is_active()));
         let hovered = Rc::new(Cell::new(platform_window.is_hovered()));
         let needs_present = Rc::new(Cell::new(false));
+        let frame_display_id = Rc::new(Cell::new(None));
         let next_frame_callbacks: Rc<RefCell<Vec<FrameCallback>>> = Default::default();
         let last_input_timestamp = Rc::new(Cell::new(Instant::now()));
 
@@ -1024,9 +1103,13 @@ impl Window {
             let invalidator = invalidator.clone();
             let active = active.clone();
             let needs_present = needs_present.clone();
+            let frame_display_id = frame_display_id.clone();
             let next_frame_callbacks = next_frame_callbacks.clone();
             let last_input_timestamp = last_input_timestamp.clone();
             move |request_frame_options| {
+                if request_frame_options.display_id.is_some() {
+                    frame_display_id.set(request_frame_options.display_id);
+                }
                 let next_frame_callbacks = next_frame_callbacks.take();
                 if !next_frame_callbacks.is_empty() {
                     handle
@@ -1049,6 +1132,9 @@ impl Window {
                     measure("frame duration", || {
                         handle
                             .update(&mut cx, |_, window, cx| {
+                                cx.record_input(|| RecordedEvent::Frame {
+                                    window: handle.window_id().as_u64(),
+                                });
                                 let arena_clear_needed = window.draw(cx);
                                 window.present();
                                 // drop the arena elements after present to reduce latency
@@ -1071,9 +1157,16 @@ impl Window {
         }));
         platform_window.on_resize(Box::new({
             let mut cx = cx.to_async();
-            move |_, _| {
+            move |size, scale_factor| {
                 handle
-                    .update(&mut cx, |_, window, cx| window.bounds_changed(cx))
+                    .update(&mut cx, |_, window, cx| {
+                        cx.record_input(|| RecordedEvent::Resize {
+                            window: handle.window_id().as_u64(),
+                            size,
+                            scale_factor,
+                        });
+                        window.bounds_changed(cx)
+                    })
                     .log_err();
             }
         }));
@@ -1105,6 +1198,11 @@ impl Window {
                             .activation_observers
                             .clone()
                             .retain(&(), |callback| callback(window, cx));
+                        if active && let Some(dialog) = window.modal_dialog(cx) {
+                            dialog
+                                .update(cx, |_, dialog, _| dialog.activate_window())
+                                .log_err();
+                        }
 
                         window.bounds_changed(cx);
                         window.refresh();
@@ -1212,6 +1310,8 @@ impl Window {
             handle,
             invalidator,
             removed: false,
+            modal_parent,
+            modal_dialog: None,
             platform_window,
             display_id,
             sprite_atlas,
@@ -1249,6 +1349,7 @@ impl Window {
             active,
             hovered,
             needs_present,
+            frame_display_id,
             last_input_timestamp,
             refreshing: false,
             activation_observers: SubscriberSet::new(),
@@ -1321,7 +1422,6 @@ impl Window {
         }
     }
 
-
     /// Registers a callback to be invoked when the window appearance changes.
     pub fn observe_window_appearance(
         &self,
@@ -1809,6 +1909,12 @@ impl Window {
             .find(|display| Some(display.id()) == self.display_id)
     }
 
+    /// The display whose vertical blank drove the most recent frame, if the platform reports it.
+    /// Its [`PlatformDisplay::refresh_rate`] is the rate the window is being drawn at.
+    pub fn frame_display_id(&self) -> Option<DisplayId> {
+        self.frame_display_id.get()
+    }
+
     /// Show the platform character palette.
     pub fn show_character_palette(&self) {
         self.platform_window.show_character_palette();
@@ -3598,6 +3704,22 @@ impl Window {
     /// Dispatch a mouse or keyboard event on the window.
     #[profiling::function]
     pub fn dispatch_event(&mut self, event: PlatformInput, cx: &mut App) -> DispatchEventResult {
+        // A modal dialog takes all of the input until it closes.
+        if self.modal_dialog(cx).is_some() {
+            return DispatchEventResult {
+                propagate: false,
+                default_prevented: true,
+            };
+        }
+        if let Some(recorder) = cx.input_recorder.lock().as_mut() {
+            let secure = self.focus.is_some_and(|id| {
+                cx.focus_handles
+                    .read()
+                    .get(id)
+                    .is_some_and(|focus| focus.secure_input)
+            });
+            recorder.record_input(self.handle.window_id().as_u64(), &event, secure);
+        }
         self.last_input_timestamp.set(Instant::now());
         // Handlers may set this to false by calling `stop_propagation`.
         cx.propagate_event = true;
@@ -4132,6 +4254,18 @@ impl Window {
         self.platform_window.activate();
     }
 
+    /// Route all input to the window until it's closed, on platforms where a window that isn't
+    /// managed like a normal one wouldn't get focus or notice clicks outside of it otherwise.
+    pub(crate) fn grab_input(&self) {
+        self.platform_window.grab_input();
+    }
+
+    /// The modal [`WindowKind::Dialog`] open over this window, if any.
+    pub fn modal_dialog(&self, cx: &App) -> Option<AnyWindowHandle> {
+        self.modal_dialog
+            .filter(|dialog| cx.windows.contains_key(dialog.window_id()))
+    }
+
     /// Minimize the current window at the platform level.
     pub fn minimize_window(&self) {
         self.platform_window.minimize();
@@ -4172,10 +4306,15 @@ impl Window {
         let bounds = self.platform_window.window_bounds();
         let display_id = self.platform_window.display().map(|d| d.id());
         let fullscreen = self.platform_window.is_fullscreen();
+        let tiling = match self.platform_window.window_decorations() {
+            Decorations::Client { tiling } => tiling,
+            Decorations::Server => Tiling::default(),
+        };
         WindowState {
             bounds,
             display_id,
             fullscreen,
+            tiling,
         }
     }
 
@@ -4446,6 +4585,22 @@ impl Window {
         self.platform_window.gpu_specs()
     }
 
+    /// The last frame drawn to this window, in device pixels.
+    /// Currently only available on the Linux headless platform, where windows are drawn in memory.
+    pub fn capture_frame(&self) -> Option<image::RgbaImage> {
+        self.platform_window.capture_frame()
+    }
+
+    /// Save the last frame drawn to this window as a PNG file.
+    /// Currently only available on the Linux headless platform, where windows are drawn in memory.
+    pub fn save_frame(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        let frame = self
+            .capture_frame()
+            .context("this platform does not keep the frames it draws")?;
+        frame.save_with_format(path, image::ImageFormat::Png)?;
+        Ok(())
+    }
+
     /// Perform titlebar double-click action.
     /// This is macOS specific.
     pub fn titlebar_double_click(&self) {
@@ -4858,7 +5013,7 @@ unsafe impl<V> Send for WindowHandle<V> {}
 unsafe impl<V> Sync for WindowHandle<V> {}
 
 /// A handle to a window with any root view type, which can be downcast to a window with a specific root view type.
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct AnyWindowHandle {
     pub(crate) id: WindowId,
     state_type: TypeId,
@@ -0,0 +1,569 @@
+//! The context menu GPUI renders in a popup window on platforms without a native one.
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    AnchoredFitMode, App, AppContext as _, Bounds, Context, Corner, FocusHandle,
+    InteractiveElement as _, IntoElement, KeyDownEvent, Keystroke, ParentElement as _, Pixels,
+    Point, Render, SharedString, StatefulInteractiveElement as _, Styled as _, TextStyle,
+    TrayMenuItem, Window, WindowBounds, WindowKind, WindowOptions, colors::Colors, div, point,
+    prelude::FluentBuilder as _, px, size,
+};
+
+const ROW_HEIGHT: Pixels = px(28.);
+const SEPARATOR_HEIGHT: Pixels = px(9.);
+const PADDING: Pixels = px(4.);
+const FONT_SIZE: Pixels = px(14.);
+/// Width reserved for the checkmark before the label and the submenu arrow after it.
+const GUTTER_WIDTH: Pixels = px(24.);
+/// Space between a label and its accelerator.
+const ACCELERATOR_GAP: Pixels = px(24.);
+/// How long after the last key press type-ahead starts a new search.
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_secs(1);
+
+type SelectCallback = Rc<RefCell<dyn FnMut(SharedString, &mut App)>>;
+
+/// Open a [`ContextMenu`] at `position`, in screen coordinates, attached to the active window.
+pub(crate) fn open_context_menu(
+    position: Point<Pixels>,
+    items: Vec<TrayMenuItem>,
+    on_select: SelectCallback,
+    cx: &mut App,
+) {
+    let Some(parent) = cx.active_window() else {
+        log::warn!("cannot show a context menu without an active window");
+        return;
+    };
+    if items.is_empty() {
+        return;
+    }
+    // Context menus are usually shown from an event handler of the parent window, which can only
+    // be read once that handler returns.
+    cx.defer(move |cx| {
+        let Ok(parent_origin) = parent.update(cx, |_, window, _| window.bounds().origin) else {
+            return;
+        };
+        let options = WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(Bounds::new(
+                point(px(0.), px(0.)),
+                menu_size(&items, cx),
+            ))),
+            titlebar: None,
+            kind: WindowKind::AnchoredPopup {
+                parent,
+                anchor_rect: Bounds::new(position - parent_origin, Default::default()),
+                gravity: Corner::BottomRight,
+                constraint_adjustment: AnchoredFitMode::SwitchAnchor,
+            },
+            is_movable: false,
+            is_resizable: false,
+            is_minimizable: false,
+            ..Default::default()
+        };
+        cx.open_window(options, |window, cx| {
+            cx.new(|cx| ContextMenu::new(items, on_select, window, cx))
+        })
+        .map(|_| ())
+        .unwrap_or_else(|error| log::error!("failed to open context menu: {error:#}"));
+    });
+}
+
+/// The size that fits every level of the menu, so that entering a submenu doesn't resize the
+/// popup.
+fn menu_size(items: &[TrayMenuItem], cx: &App) -> crate::Size<Pixels> {
+    let text_system = cx.text_system();
+    let font_id = text_system.resolve_font(&TextStyle::default().font());
+    let text_width = |text: &str| {
+        text.chars()
+            .filter_map(|ch| text_system.advance(font_id, FONT_SIZE, ch).ok())
+            .fold(px(0.), |width, advance| width + advance.width)
+    };
+
+    fn measure(
+        items: &[TrayMenuItem],
+        is_submenu: bool,
+        text_width: &dyn Fn(&str) -> Pixels,
+    ) -> crate::Size<Pixels> {
+        let mut width = px(0.);
+        let mut height = if is_submenu { ROW_HEIGHT } else { px(0.) };
+        let mut nested = crate::Size::default();
+        for item in items {
+            match item {
+                TrayMenuItem::Separator => height += SEPARATOR_HEIGHT,
+                TrayMenuItem::Submenu { label, items, .. } => {
+                    width = width.max(text_width(label));
+                    height += ROW_HEIGHT;
+                    nested = nested.max(&measure(items, true, text_width));
+                }
+                TrayMenuItem::Action {
+                    label, accelerator, ..
+                }
+                | TrayMenuItem::Toggle {
+                    label, accelerator, ..
+                }
+                | TrayMenuItem::Radio {
+                    label, accelerator, ..
+                } => {
+                    let accelerator_width = accelerator.as_ref().map_or(px(0.), |keystroke| {
+                        ACCELERATOR_GAP + text_width(&keystroke.to_string())
+                    });
+                    width = width.max(text_width(label) + accelerator_width);
+                    height += ROW_HEIGHT;
+                }
+            }
+        }
+        size(width + GUTTER_WIDTH * 2., height).max(&nested)
+    }
+
+    let content = measure(items, false, &text_width);
+    // Leave room for the row padding and the window border.
+    size(
+        content.width + PADDING * 4. + px(2.),
+        content.height + PADDING * 2. + px(2.),
+    )
+}
+
+/// The result of handling a key press in a [`MenuState`].
+#[derive(Debug, PartialEq)]
+enum MenuOutcome {
+    Handled,
+    Ignored,
+    Activate(SharedString),
+    Close,
+}
+
+/// The navigation state of a [`ContextMenu`], kept apart from its rendering.
+struct MenuState {
+    items: Vec<TrayMenuItem>,
+    /// The index of each submenu entered from the root, innermost last.
+    path: Vec<usize>,
+    selected: Option<usize>,
+    type_ahead: String,
+    last_typed: Option<Instant>,
+}
+
+impl MenuState {
+    fn new(items: Vec<TrayMenuItem>) -> Self {
+        Self {
+            items,
+            path: Vec::new(),
+            selected: None,
+            type_ahead: String::new(),
+            last_typed: None,
+        }
+    }
+
+    /// The items of the innermost open submenu.
+    fn items(&self) -> &[TrayMenuItem] {
+        self.items_at(&self.path)
+    }
+
+    fn items_at(&self, path: &[usize]) -> &[TrayMenuItem] {
+        let mut items = self.items.as_slice();
+        for &ix in path {
+            if let Some(TrayMenuItem::Submenu { items: nested, .. }) = items.get(ix) {
+                items = nested;
+            }
+        }
+        items
+    }
+
+    /// The label of the innermost open submenu, if any.
+    fn submenu_label(&self) -> Option<SharedString> {
+        let (&last, parents) = self.path.split_last()?;
+        self.items_at(parents).get(last)?.label().cloned()
+    }
+
+    fn select(&mut self, ix: usize) {
+        if self.items().get(ix).is_some_and(TrayMenuItem::is_enabled) {
+            self.selected = Some(ix);
+        }
+    }
+
+    /// Move the selection by one item, skipping separators and disabled items and wrapping around.
+    fn select_next(&mut self, forward: bool) {
+        let len = self.items().len();
+        let start = match (self.selected, forward) {
+            (Some(ix), true) => ix + 1,
+            (Some(ix), false) => ix + len - 1,
+            (None, true) => 0,
+            (None, false) => len.saturating_sub(1),
+        };
+        self.selected = (0..len)
+            .map(|offset| {
+                if forward {
+                    (start + offset) % len
+                } else {
+                    (start + len - offset) % len
+                }
+            })
+            .find(|&ix| self.items()[ix].is_enabled());
+    }
+
+    fn select_edge(&mut self, first: bool) {
+        self.selected = None;
+        self.select_next(first);
+    }
+
+    fn activate(&mut self, ix: usize) -> MenuOutcome {
+        match self.items().get(ix) {
+            Some(item) if !item.is_enabled() => MenuOutcome::Ignored,
+            Some(
+                TrayMenuItem::Action { id, .. }
+                | TrayMenuItem::Toggle { id, .. }
+                | TrayMenuItem::Radio { id, .. },
+            ) => MenuOutcome::Activate(id.clone()),
+            Some(TrayMenuItem::Submenu { .. }) => {
+                self.path.push(ix);
+                self.select_edge(true);
+                MenuOutcome::Handled
+            }
+            Some(TrayMenuItem::Separator) | None => MenuOutcome::Ignored,
+        }
+    }
+
+    fn back(&mut self) -> MenuOutcome {
+        match self.path.pop() {
+            Some(ix) => {
+                self.selected = Some(ix);
+                MenuOutcome::Handled
+            }
+            None => MenuOutcome::Close,
+        }
+    }
+
+    /// Select the next item whose label starts with the text typed so far.
+    fn type_ahead(&mut self, text: &str, now: Instant) -> MenuOutcome {
+        if self
+            .last_typed
+            .is_none_or(|last| now.duration_since(last) > TYPE_AHEAD_TIMEOUT)
+        {
+            self.type_ahead.clear();
+        }
+        self.last_typed = Some(now);
+        self.type_ahead.push_str(&text.to_lowercase());
+
+        // A single character cycles through the matching items, while a longer prefix keeps the
+        // current item if it still matches.
+        let len = self.items().len();
+        let start = match self.selected {
+            Some(ix) if self.type_ahead.chars().count() == 1 => ix + 1,
+            Some(ix) => ix,
+            None => 0,
+        };
+        let found = (0..len).map(|offset| (start + offset) % len).find(|&ix| {
+            let item = &self.items()[ix];
+            item.is_enabled()
+                && item
+                    .label()
+                    .is_some_and(|label| label.to_lowercase().starts_with(&self.type_ahead))
+        });
+        if let Some(ix) = found {
+            self.selected = Some(ix);
+        }
+        MenuOutcome::Handled
+    }
+
+    fn handle_key(&mut self, keystroke: &Keystroke, now: Instant) -> MenuOutcome {
+        match keystroke.key.as_str() {
+            "up" => self.select_next(false),
+            "down" => self.select_next(true),
+            "home" => self.select_edge(true),
+            "end" => self.select_edge(false),
+            "enter" | "space" if self.selected.is_some() => {
+                return self.activate(self.selected.unwrap_or_default());
+            }
+            "right" => match self.selected {
+                Some(ix) if matches!(self.items()[ix], TrayMenuItem::Submenu { .. }) => {
+                    return self.activate(ix);
+                }
+                _ => return MenuOutcome::Ignored,
+            },
+            "left" if !self.path.is_empty() => return self.back(),
+            "escape" => return self.back(),
+            _ => {
+                let modifiers = keystroke.modifiers;
+                return match &keystroke.key_char {
+                    Some(text) if !modifiers.control && !modifiers.alt && !modifiers.platform => {
+                        self.type_ahead(text, now)
+                    }
+                    _ => MenuOutcome::Ignored,
+                };
+            }
+        }
+        MenuOutcome::Handled
+    }
+}
+
+/// A menu of [`TrayMenuItem`]s, shown in its own popup window. Submenus replace the items in
+/// place, with a header row to go back. The menu closes when an item is chosen, on Escape, or when
+/// the user clicks outside of it.
+pub(crate) struct ContextMenu {
+    state: MenuState,
+    focus_handle: FocusHandle,
+    on_select: SelectCallback,
+}
+
+impl ContextMenu {
+    fn new(
+        items: Vec<TrayMenuItem>,
+        on_select: SelectCallback,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let focus_handle = cx.focus_handle();
+        window.focus(&focus_handle);
+        cx.observe_window_activation(window, |_, window, _| {
+            if !window.is_window_active() {
+                window.remove_window();
+            }
+        })
+        .detach();
+        Self {
+            state: MenuState::new(items),
+            focus_handle,
+            on_select,
+        }
+    }
+
+    fn handle_outcome(
+        &mut self,
+        outcome: MenuOutcome,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match outcome {
+            MenuOutcome::Handled => cx.notify(),
+            MenuOutcome::Ignored => {}
+            MenuOutcome::Activate(id) => {
+                window.remove_window();
+                let on_select = self.on_select.clone();
+                cx.defer(move |cx| (on_select.borrow_mut())(id, cx));
+            }
+            MenuOutcome::Close => window.remove_window(),
+        }
+    }
+
+    fn key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let outcome = self.state.handle_key(&event.keystroke, Instant::now());
+        if outcome != MenuOutcome::Ignored {
+            cx.stop_propagation();
+        }
+        self.handle_outcome(outcome, window, cx);
+    }
+}
+
+impl Render for ContextMenu {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let colors = Colors::for_appearance(window);
+        let header = self.state.submenu_label().map(|label| {
+            div()
+                .id("context-menu-back")
+                .flex_none()
+                .h(ROW_HEIGHT)
+                .px(PADDING)
+                .flex()
+                .items_center()
+                .rounded_sm()
+                .text_color(colors.disabled)
+                .cursor_pointer()
+                .child(div().w(GUTTER_WIDTH).child("‹"))
+                .child(label)
+                .on_click(cx.listener(|this, _, window, cx| {
+                    let outcome = this.state.back();
+                    this.handle_outcome(outcome, window, cx);
+                }))
+        });
+        let selected = self.state.selected;
+        let rows = self
+            .state
+            .items()
+            .iter()
+            .enumerate()
+            .map(|(ix, item)| {
+                let Some(label) = item.label().cloned() else {
+                    return div()
+                        .id(ix)
+                        .flex_none()
+                        .h(SEPARATOR_HEIGHT)
+                        .flex()
+                        .items_center()
+                        .child(div().h(px(1.)).w_full().bg(colors.separator));
+                };
+                let mark = match item {
+                    TrayMenuItem::Toggle { checked: true, .. } => Some("✓"),
+                    TrayMenuItem::Radio { checked: true, .. } => Some("•"),
+                    _ => None,
+                };
+                let accelerator = match item {
+                    TrayMenuItem::Action { accelerator, .. }
+                    | TrayMenuItem::Toggle { accelerator, .. }
+                    | TrayMenuItem::Radio { accelerator, .. } => {
+                        accelerator.as_ref().map(|keystroke| keystroke.to_string())
+                    }
+                    _ => None,
+                };
+                let enabled = item.is_enabled();
+                let is_submenu = matches!(item, TrayMenuItem::Submenu { .. });
+                div()
+                    .id(ix)
+                    .flex_none()
+                    .h(ROW_HEIGHT)
+                    .px(PADDING)
+                    .flex()
+                    .items_center()
+                    .rounded_sm()
+                    .when(enabled, |row| row.cursor_pointer())
+                    .when(!enabled, |row| row.text_color(colors.disabled))
+                    .when(selected == Some(ix), |row| {
+                        row.bg(colors.selected).text_color(colors.selected_text)
+                    })
+                    .child(
+                        div()
+                            .flex_none()
+                            .w(GUTTER_WIDTH)
+                            .when_some(mark, |gutter, mark| gutter.child(mark)),
+                    )
+                    .child(div().flex_1().child(label))
+                    .when_some(accelerator, |row, accelerator| {
+                        row.child(
+                            div()
+                                .flex_none()
+                                .pl(ACCELERATOR_GAP)
+                                .text_color(colors.disabled)
+                                .child(accelerator),
+                        )
+                    })
+                    .when(is_submenu, |row| {
+                        row.child(div().flex_none().w(GUTTER_WIDTH).child("›"))
+                    })
+                    .on_mouse_move(cx.listener(move |this, _, _, cx| {
+                        if this.state.selected != Some(ix) {
+                            this.state.select(ix);
+                            cx.notify();
+                        }
+                    }))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        let outcome = this.state.activate(ix);
+                        this.handle_outcome(outcome, window, cx);
+                    }))
+            })
+            .collect::<Vec<_>>();
+
+        div()
+            .id("context-menu")
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(Self::key_down))
+            .on_mouse_down_out(cx.listener(|_, _, window, _| window.remove_window()))
+            .size_full()
+            .p(PADDING)
+            .flex()
+            .flex_col()
+            .overflow_hidden()
+            .bg(colors.background)
+            .border_1()
+            .border_color(colors.border)
+            .rounded_md()
+            .text_size(FONT_SIZE)
+            .text_color(colors.text)
+            .cursor_default()
+            .children(header)
+            .children(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(label: &str) -> TrayMenuItem {
+        TrayMenuItem::action(label.to_string(), label.to_lowercase())
+    }
+
+    fn keystroke(source: &str) -> Keystroke {
+        Keystroke::parse(source).unwrap()
+    }
+
+    fn menu() -> MenuState {
+        MenuState::new(vec![
+            action("Open"),
+            TrayMenuItem::Separator,
+            TrayMenuItem::toggle("Mute", true, "mute"),
+            TrayMenuItem::submenu("Move to", vec![action("Desktop"), action("Documents")]),
+            action("Quit"),
+            action("Mark as read").enabled(false),
+        ])
+    }
+
+    #[test]
+    fn test_keyboard_navigation() {
+        let mut menu = menu();
+        let now = Instant::now();
+        assert_eq!(
+            menu.handle_key(&keystroke("down"), now),
+            MenuOutcome::Handled
+        );
+        assert_eq!(menu.selected, Some(0));
+        // The separator is skipped.
+        menu.handle_key(&keystroke("down"), now);
+        assert_eq!(menu.selected, Some(2));
+        // Moving up from the first item wraps to the last one that is enabled.
+        menu.handle_key(&keystroke("home"), now);
+        menu.handle_key(&keystroke("up"), now);
+        assert_eq!(menu.selected, Some(4));
+        assert_eq!(menu.activate(5), MenuOutcome::Ignored);
+
+        menu.handle_key(&keystroke("up"), now);
+        assert_eq!(
+            menu.handle_key(&keystroke("right"), now),
+            MenuOutcome::Handled
+        );
+        assert_eq!(menu.submenu_label(), Some("Move to".into()));
+        assert_eq!(menu.selected, Some(0));
+        menu.handle_key(&keystroke("end"), now);
+        assert_eq!(
+            menu.handle_key(&keystroke("enter"), now),
+            MenuOutcome::Activate("documents".into())
+        );
+
+        assert_eq!(
+            menu.handle_key(&keystroke("left"), now),
+            MenuOutcome::Handled
+        );
+        assert_eq!(menu.submenu_label(), None);
+        assert_eq!(menu.selected, Some(3));
+        assert_eq!(
+            menu.handle_key(&keystroke("left"), now),
+            MenuOutcome::Ignored
+        );
+        assert_eq!(
+            menu.handle_key(&keystroke("escape"), now),
+            MenuOutcome::Close
+        );
+    }
+
+    #[test]
+    fn test_type_ahead() {
+        let mut menu = menu();
+        let now = Instant::now();
+        menu.handle_key(&keystroke("m->m"), now);
+        assert_eq!(menu.selected, Some(2));
+        // Repeating a single character cycles through the matches.
+        menu.handle_key(&keystroke("m->m"), now + Duration::from_secs(2));
+        assert_eq!(menu.selected, Some(3));
+        // A longer prefix narrows the search.
+        let now = now + Duration::from_secs(4);
+        menu.handle_key(&keystroke("m->m"), now);
+        assert_eq!(menu.selected, Some(2));
+        menu.handle_key(&keystroke("o->o"), now);
+        assert_eq!(menu.selected, Some(3));
+        assert_eq!(
+            menu.handle_key(&keystroke("cmd-q->q"), now),
+            MenuOutcome::Ignored
+        );
+    }
+}
@@ -0,0 +1,617 @@
+//! The dialog GPUI renders in a window of its own on platforms without a native one.
+
+use std::sync::atomic::Ordering;
+
+use futures::channel::oneshot;
+
+use crate::{
+    App, AppContext as _, Bounds, Context, DialogKind, DialogOptions, FocusHandle, Font,
+    FontWeight, InteractiveElement as _, IntoElement, KeyDownEvent, Keystroke, LineFragment,
+    ParentElement as _, Pixels, Render, Rgba, Size, StatefulInteractiveElement as _, Styled as _,
+    TextStyle, TitlebarOptions, Window, WindowBounds, WindowKind, WindowOptions, colors::Colors,
+    div, prelude::FluentBuilder as _, px, rgb, size,
+};
+
+const MIN_WIDTH: Pixels = px(420.);
+const PADDING: Pixels = px(20.);
+const GAP: Pixels = px(12.);
+const ICON_SIZE: Pixels = px(32.);
+const MESSAGE_FONT_SIZE: Pixels = px(15.);
+const MESSAGE_LINE_HEIGHT: Pixels = px(20.);
+const DETAIL_FONT_SIZE: Pixels = px(13.);
+const DETAIL_LINE_HEIGHT: Pixels = px(18.);
+const DETAIL_GAP: Pixels = px(6.);
+const BUTTON_HEIGHT: Pixels = px(28.);
+const BUTTON_PADDING: Pixels = px(14.);
+const BUTTON_GAP: Pixels = px(8.);
+const CHECKBOX_SIZE: Pixels = px(16.);
+
+/// Open a modal [`Dialog`] centered over the active window, which sends the index of the chosen
+/// button to `sender`.
+pub(crate) fn open_dialog(
+    mut options: DialogOptions,
+    sender: oneshot::Sender<usize>,
+    cx: &mut App,
+) {
+    if options.buttons.is_empty() {
+        options.buttons.push("OK".into());
+    }
+    let size = dialog_size(&options, cx);
+    let bounds = cx
+        .active_window()
+        .and_then(|parent| parent.update(cx, |_, window, _| window.bounds()).ok())
+        .map(|parent| Bounds::centered_at(parent.center(), size))
+        .unwrap_or_else(|| Bounds::centered(None, size, cx));
+    let window_options = WindowOptions {
+        window_bounds: Some(WindowBounds::Windowed(bounds)),
+        titlebar: Some(TitlebarOptions {
+            title: Some(options.title.clone()),
+            ..Default::default()
+        }),
+        // Dialogs are modal and transient for the active window.
+        kind: WindowKind::Dialog,
+        modal_parent: cx.active_window(),
+        is_resizable: false,
+        is_minimizable: false,
+        ..Default::default()
+    };
+    cx.open_window(window_options, |window, cx| {
+        cx.new(|cx| Dialog::new(options, sender, window, cx))
+    })
+    .map(|_| ())
+    .unwrap_or_else(|error| log::error!("failed to open dialog: {error:#}"));
+}
+
+/// The size that fits the wrapped message and detail, and all of the buttons in one row.
+fn dialog_size(options: &DialogOptions, cx: &App) -> Size<Pixels> {
+    let text_system = cx.text_system();
+    let font = TextStyle::default().font();
+    let font_id = text_system.resolve_font(&font);
+    let buttons_width = options
+        .buttons
+        .iter()
+        .map(|label| {
+            label
+                .chars()
+                .filter_map(|ch| text_system.advance(font_id, DETAIL_FONT_SIZE, ch).ok())
+                .fold(BUTTON_PADDING * 2. + px(2.), |width, advance| {
+                    width + advance.width
+                })
+        })
+        .fold(px(0.), |width, button| width + button + BUTTON_GAP);
+    let width = MIN_WIDTH.max(buttons_width + PADDING * 2.);
+
+    let text_width = width - PADDING * 2. - ICON_SIZE - GAP;
+    let line_count = |text: &str, font: Font, font_size: Pixels| {
+        let mut wrapper = text_system.line_wrapper(font, font_size);
+        text.lines()
+            .map(|line| {
+                1 + wrapper
+                    .wrap_line(&[LineFragment::text(line)], text_width)
+                    .count()
+            })
+            .sum::<usize>() as f32
+    };
+    let message_font = Font {
+        weight: FontWeight::SEMIBOLD,
+        ..font.clone()
+    };
+    let mut text_height =
+        MESSAGE_LINE_HEIGHT * line_count(&options.message, message_font, MESSAGE_FONT_SIZE);
+    if let Some(detail) = &options.detail {
+        text_height += DETAIL_GAP + DETAIL_LINE_HEIGHT * line_count(detail, font, DETAIL_FONT_SIZE);
+    }
+    let mut height = PADDING * 2. + text_height.max(ICON_SIZE) + GAP + BUTTON_HEIGHT;
+    if options.suppression.is_some() {
+        height += GAP + DETAIL_LINE_HEIGHT;
+    }
+    size(width, height)
+}
+
+/// The result of handling a key press in a [`DialogState`].
+#[derive(Debug, PartialEq)]
+enum DialogOutcome {
+    Handled,
+    Ignored,
+    Respond(usize),
+}
+
+/// The part of a [`Dialog`] that Enter and Space act on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DialogFocus {
+    Button(usize),
+    /// The "don't ask again" checkbox, which Space toggles.
+    Suppression,
+}
+
+/// The keyboard state of a [`Dialog`], kept apart from its rendering.
+struct DialogState {
+    button_count: usize,
+    has_suppression: bool,
+    /// The first button until the user moves the focus.
+    focused: DialogFocus,
+    suppressed: bool,
+}
+
+impl DialogState {
+    fn new(button_count: usize, has_suppression: bool) -> Self {
+        Self {
+            button_count,
+            has_suppression,
+            focused: DialogFocus::Button(0),
+            suppressed: false,
+        }
+    }
+
+    /// The button chosen by Escape or by closing the window: the last one, which is "Cancel" or
+    /// "No" by convention.
+    fn cancel_button(&self) -> usize {
+        self.button_count.saturating_sub(1)
+    }
+
+    /// Move the focus through the buttons and then the checkbox, wrapping around.
+    fn focus_next(&mut self, forward: bool) {
+        let count = self.button_count + usize::from(self.has_suppression);
+        let position = match self.focused {
+            DialogFocus::Button(ix) => ix,
+            DialogFocus::Suppression => self.button_count,
+        };
+        let position = if forward {
+            (position + 1) % count
+        } else {
+            (position + count - 1) % count
+        };
+        self.focused = if position == self.button_count {
+            DialogFocus::Suppression
+        } else {
+            DialogFocus::Button(position)
+        };
+    }
+
+    /// Move the focus by one button, wrapping around. Buttons are laid out from right to left.
+    fn focus_next_button(&mut self, forward: bool) -> DialogOutcome {
+        let DialogFocus::Button(ix) = self.focused else {
+            return DialogOutcome::Ignored;
+        };
+        self.focused = DialogFocus::Button(if forward {
+            (ix + 1) % self.button_count
+        } else {
+            (ix + self.button_count - 1) % self.button_count
+        });
+        DialogOutcome::Handled
+    }
+
+    fn handle_key(&mut self, keystroke: &Keystroke) -> DialogOutcome {
+        match (keystroke.key.as_str(), self.focused) {
+            ("enter" | "space", DialogFocus::Button(ix)) => DialogOutcome::Respond(ix),
+            // Enter chooses the default button while the checkbox is focused.
+            ("enter", DialogFocus::Suppression) => DialogOutcome::Respond(0),
+            ("space", DialogFocus::Suppression) => {
+                self.suppressed = !self.suppressed;
+                DialogOutcome::Handled
+            }
+            ("escape", _) => DialogOutcome::Respond(self.cancel_button()),
+            ("tab", _) => {
+                self.focus_next(!keystroke.modifiers.shift);
+                DialogOutcome::Handled
+            }
+            ("left", _) => self.focus_next_button(true),
+            ("right", _) => self.focus_next_button(false),
+            _ => DialogOutcome::Ignored,
+        }
+    }
+}
+
+/// A message with a row of buttons, and optionally a "don't ask again" checkbox, shown in a
+/// window transient for the window it was opened from.
+pub(crate) struct Dialog {
+    options: DialogOptions,
+    state: DialogState,
+    focus_handle: FocusHandle,
+    sender: Option<oneshot::Sender<usize>>,
+}
+
+impl Dialog {
+    fn new(
+        options: DialogOptions,
+        sender: oneshot::Sender<usize>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let focus_handle = cx.focus_handle();
+        window.focus(&focus_handle);
+        let this = cx.entity().downgrade();
+        window.on_window_should_close(cx, move |_, cx| {
+            this.update(cx, |this, _| this.respond(this.state.cancel_button()))
+                .ok();
+            true
+        });
+        Self {
+            state: DialogState::new(options.buttons.len(), options.suppression.is_some()),
+            options,
+            focus_handle,
+            sender: Some(sender),
+        }
+    }
+
+    fn respond(&mut self, ix: usize) {
+        let Some(sender) = self.sender.take() else {
+            return;
+        };
+        if let Some(suppression) = &self.options.suppression {
+            suppression
+                .checked
+                .store(self.state.suppressed, Ordering::SeqCst);
+        }
+        sender.send(ix).ok();
+    }
+
+    fn handle_outcome(
+        &mut self,
+        outcome: DialogOutcome,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match outcome {
+            DialogOutcome::Handled => cx.notify(),
+            DialogOutcome::Ignored => {}
+            DialogOutcome::Respond(ix) => {
+                self.respond(ix);
+                window.remove_window();
+            }
+        }
+    }
+
+    fn key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let outcome = self.state.handle_key(&event.keystroke);
+        if outcome != DialogOutcome::Ignored {
+            cx.stop_propagation();
+        }
+        self.handle_outcome(outcome, window, cx);
+    }
+}
+
+fn icon(kind: DialogKind) -> (Rgba, &'static str) {
+    match kind {
+        DialogKind::Info => (rgb(0x2a63d9), "i"),
+        DialogKind::Warning => (rgb(0xe5a50a), "!"),
+        DialogKind::Error => (rgb(0xe01b24), "×"),
+    }
+}
+
+impl Render for Dialog {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let colors = Colors::for_appearance(window);
+        let (icon_color, icon_glyph) = icon(self.options.kind);
+        let focused = self.state.focused;
+        let buttons = self
+            .options
+            .buttons
+            .iter()
+            .enumerate()
+            .map(|(ix, label)| {
+                div()
+                    .id(ix)
+                    .flex_none()
+                    .h(BUTTON_HEIGHT)
+                    .px(BUTTON_PADDING)
+                    .flex()
+                    .items_center()
+                    .rounded_md()
+                    .border_1()
+                    .border_color(colors.border)
+                    .bg(colors.container)
+                    .cursor_pointer()
+                    .when(focused == DialogFocus::Button(ix), |button| {
+                        button
+                            .bg(colors.selected)
+                            .border_color(colors.selected)
+                            .text_color(colors.selected_text)
+                    })
+                    .child(label.clone())
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.handle_outcome(DialogOutcome::Respond(ix), window, cx);
+                    }))
+            })
+            .collect::<Vec<_>>();
+        let suppression = self.options.suppression.as_ref().map(|suppression| {
+            let checked = self.state.suppressed;
+            let focused = focused == DialogFocus::Suppression;
+            div()
+                .id("dialog-suppression")
+                .h(DETAIL_LINE_HEIGHT)
+                .flex()
+                .items_center()
+                .gap(BUTTON_GAP)
+                .cursor_pointer()
+                .child(
+                    div()
+                        .flex_none()
+                        .size(CHECKBOX_SIZE)
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .rounded_sm()
+                        .border_1()
+                        .border_color(colors.border)
+                        .when(checked, |checkbox| {
+                            checkbox
+                                .bg(colors.selected)
+                                .border_color(colors.selected)
+                                .text_color(colors.selected_text)
+                                .child("✓")
+                        })
+                        .when(focused, |checkbox| {
+                            checkbox.border_2().border_color(colors.selected)
+                        }),
+                )
+                .child(suppression.label.clone())
+                .on_click(cx.listener(|this, _, _, cx| {
+                    this.state.suppressed = !this.state.suppressed;
+                    this.state.focused = DialogFocus::Suppression;
+                    cx.notify();
+                }))
+        });
+
+        div()
+            .id("dialog")
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(Self::key_down))
+            .size_full()
+            .p(PADDING)
+            .flex()
+            .flex_col()
+            .gap(GAP)
+            .bg(colors.background)
+            .text_size(DETAIL_FONT_SIZE)
+            .line_height(DETAIL_LINE_HEIGHT)
+            .text_color(colors.text)
+            .cursor_default()
+            .child(
+                div()
+                    .flex()
+                    .gap(GAP)
+                    .child(
+                        div()
+                            .flex_none()
+                            .size(ICON_SIZE)
+                            .flex()
+                            .items_center()
+                            .justify_center()
+                            .rounded_full()
+                            .bg(icon_color)
+                            .text_color(rgb(0xffffff))
+                            .text_size(MESSAGE_FONT_SIZE)
+                            .font_weight(FontWeight::BOLD)
+                            .child(icon_glyph),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .flex()
+                            .flex_col()
+                            .gap(DETAIL_GAP)
+                            .child(
+                                div()
+                                    .text_size(MESSAGE_FONT_SIZE)
+                                    .line_height(MESSAGE_LINE_HEIGHT)
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .child(self.options.message.clone()),
+                            )
+                            .children(self.options.detail.clone()),
+                    ),
+            )
+            .children(suppression)
+            .child(
+                div()
+                    .mt_auto()
+                    .flex()
+                    .flex_row_reverse()
+                    .gap(BUTTON_GAP)
+                    .children(buttons),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc, sync::Arc};
+
+    use super::*;
+    use crate::{DialogSuppression, Empty, TestAppContext, VisualTestContext};
+
+    fn keystroke(source: &str) -> Keystroke {
+        Keystroke::parse(source).unwrap()
+    }
+
+    #[test]
+    fn test_dialog_keys() {
+        let mut dialog = DialogState::new(3, false);
+        assert_eq!(
+            dialog.handle_key(&keystroke("enter")),
+            DialogOutcome::Respond(0)
+        );
+        assert_eq!(
+            dialog.handle_key(&keystroke("escape")),
+            DialogOutcome::Respond(2)
+        );
+
+        // The first button is on the right, so moving left focuses the next one.
+        assert_eq!(
+            dialog.handle_key(&keystroke("left")),
+            DialogOutcome::Handled
+        );
+        assert_eq!(
+            dialog.handle_key(&keystroke("space")),
+            DialogOutcome::Respond(1)
+        );
+        dialog.handle_key(&keystroke("tab"));
+        dialog.handle_key(&keystroke("tab"));
+        assert_eq!(dialog.focused, DialogFocus::Button(0));
+        dialog.handle_key(&keystroke("shift-tab"));
+        assert_eq!(dialog.focused, DialogFocus::Button(2));
+        dialog.handle_key(&keystroke("right"));
+        assert_eq!(dialog.focused, DialogFocus::Button(1));
+
+        assert_eq!(dialog.handle_key(&keystroke("a")), DialogOutcome::Ignored);
+        // Escape on a dialog with a single button chooses that button.
+        assert_eq!(
+            DialogState::new(1, false).handle_key(&keystroke("escape")),
+            DialogOutcome::Respond(0)
+        );
+    }
+
+    #[test]
+    fn test_suppression_checkbox_keys() {
+        let mut dialog = DialogState::new(2, true);
+        // Tab reaches the checkbox after the buttons.
+        dialog.handle_key(&keystroke("tab"));
+        dialog.handle_key(&keystroke("tab"));
+        assert_eq!(dialog.focused, DialogFocus::Suppression);
+        assert_eq!(
+            dialog.handle_key(&keystroke("space")),
+            DialogOutcome::Handled
+        );
+        assert!(dialog.suppressed);
+        // The arrow keys only move between buttons.
+        assert_eq!(
+            dialog.handle_key(&keystroke("left")),
+            DialogOutcome::Ignored
+        );
+        assert_eq!(
+            dialog.handle_key(&keystroke("enter")),
+            DialogOutcome::Respond(0)
+        );
+
+        dialog.handle_key(&keystroke("tab"));
+        assert_eq!(dialog.focused, DialogFocus::Button(0));
+        dialog.handle_key(&keystroke("shift-tab"));
+        dialog.handle_key(&keystroke("shift-tab"));
+        assert_eq!(dialog.focused, DialogFocus::Button(1));
+    }
+
+    struct Parent {
+        key_presses: Rc<Cell<usize>>,
+        focus_handle: FocusHandle,
+    }
+
+    impl Render for Parent {
+        fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+            let key_presses = self.key_presses.clone();
+            div()
+                .track_focus(&self.focus_handle)
+                .size_full()
+                .on_key_down(cx.listener(move |_, _: &KeyDownEvent, _, _| {
+                    key_presses.set(key_presses.get() + 1);
+                }))
+        }
+    }
+
+    #[gpui::test]
+    fn test_dialog_is_modal(cx: &mut TestAppContext) {
+        let key_presses = Rc::new(Cell::new(0));
+        let parent = cx.add_window(|window, cx| {
+            let focus_handle = cx.focus_handle();
+            window.focus(&focus_handle);
+            Parent {
+                key_presses: key_presses.clone(),
+                focus_handle,
+            }
+        });
+        let parent = parent.into();
+        cx.update_window(parent, |_, window, _| window.activate_window())
+            .unwrap();
+        cx.simulate_keystrokes(parent, "a");
+        assert_eq!(key_presses.get(), 1);
+
+        let (sender, mut receiver) = oneshot::channel();
+        cx.update(|cx| open_dialog(DialogOptions::default(), sender, cx));
+        let dialog = cx
+            .update_window(parent, |_, window, cx| window.modal_dialog(cx))
+            .unwrap()
+            .expect("the dialog is modal for the active window");
+
+        // The parent gets no input while the dialog is open.
+        cx.simulate_keystrokes(parent, "a");
+        assert_eq!(key_presses.get(), 1);
+
+        cx.simulate_keystrokes(dialog, "enter");
+        assert_eq!(receiver.try_recv().unwrap(), Some(0));
+        assert!(
+            cx.windows()
+                .iter()
+                .all(|window| window.window_id() != dialog.window_id())
+        );
+        let cached = cx
+            .update_window(parent, |_, window, _| window.modal_dialog)
+            .unwrap();
+        assert_eq!(cached, None);
+        cx.simulate_keystrokes(parent, "a");
+        assert_eq!(key_presses.get(), 2);
+    }
+
+    #[gpui::test]
+    fn test_dialog_without_modal_parent(cx: &mut TestAppContext) {
+        let key_presses = Rc::new(Cell::new(0));
+        let parent = cx.add_window(|window, cx| {
+            let focus_handle = cx.focus_handle();
+            window.focus(&focus_handle);
+            Parent {
+                key_presses: key_presses.clone(),
+                focus_handle,
+            }
+        });
+        let parent = parent.into();
+        cx.update_window(parent, |_, window, _| window.activate_window())
+            .unwrap();
+
+        cx.update(|cx| {
+            let options = WindowOptions {
+                kind: WindowKind::Dialog,
+                ..Default::default()
+            };
+            cx.open_window(options, |_, cx| cx.new(|_| Empty)).unwrap();
+        });
+        cx.run_until_parked();
+        let modal_dialog = cx
+            .update_window(parent, |_, window, cx| window.modal_dialog(cx))
+            .unwrap();
+        assert_eq!(modal_dialog, None);
+        cx.simulate_keystrokes(parent, "a");
+        assert_eq!(key_presses.get(), 1);
+    }
+
+    #[gpui::test]
+    fn test_dialog_suppression(cx: &mut TestAppContext) {
+        let suppression = DialogSuppression {
+            label: "Don't ask again".into(),
+            checked: Arc::default(),
+        };
+        let options = DialogOptions {
+            buttons: vec!["Delete".into(), "Cancel".into()],
+            suppression: Some(suppression.clone()),
+            ..Default::default()
+        };
+        let (sender, mut receiver) = oneshot::channel();
+        cx.update(|cx| open_dialog(options, sender, cx));
+        let dialog = *cx.windows().last().unwrap();
+
+        cx.simulate_keystrokes(dialog, "tab tab space shift-tab enter");
+        assert_eq!(receiver.try_recv().unwrap(), Some(1));
+        assert!(suppression.checked.load(Ordering::SeqCst));
+    }
+
+    #[gpui::test]
+    fn test_closing_dialog_cancels(cx: &mut TestAppContext) {
+        let options = DialogOptions {
+            buttons: vec!["Save".into(), "Discard".into(), "Cancel".into()],
+            ..Default::default()
+        };
+        let (sender, mut receiver) = oneshot::channel();
+        cx.update(|cx| open_dialog(options, sender, cx));
+        let dialog = *cx.windows().last().unwrap();
+
+        assert!(VisualTestContext::from_window(dialog, cx).simulate_close());
+        assert_eq!(receiver.try_recv().unwrap(), Some(2));
+    }
+}
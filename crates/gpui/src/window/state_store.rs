@@ -0,0 +1,450 @@
+//! Saving the layout of windows and restoring it on the next launch.
+
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use serde::{Deserialize, Serialize};
+use util::ResultExt as _;
+use uuid::Uuid;
+
+use crate::{
+    AnyWindowHandle, App, Bounds, Global, Pixels, PlatformDisplay, SharedString, Subscription,
+    Tiling, Window, WindowBounds, WindowOptions, WindowState, point, size,
+};
+
+/// Remembers where the windows of an application were, so that they reopen there.
+///
+/// Each window is saved under a role: a stable name for the part it plays in the application, like
+/// `"main"` or `"preferences"`. The store keeps the state of every tracked window as it moves and
+/// resizes, and writes it to its file when a window closes and when the application quits.
+///
+/// Restored states are checked against the displays connected now. A window whose display is gone
+/// opens on the primary display, and one that would be off screen is moved back into view.
+///
+/// Tiling is only known for windows that draw their own decorations. A window with server-side
+/// decorations that was snapped to an edge is saved as a plain window over the space it covered.
+pub struct WindowStateStore {
+    path: PathBuf,
+    states: HashMap<SharedString, SavedWindowState>,
+    tracked: HashMap<AnyWindowHandle, (SharedString, Subscription)>,
+}
+
+impl Global for WindowStateStore {}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedWindowStates {
+    windows: HashMap<SharedString, SavedWindowState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SavedWindowState {
+    /// The bounds of the window, or the bounds it returns to for maximized and fullscreen windows.
+    bounds: Bounds<Pixels>,
+    #[serde(default)]
+    mode: SavedWindowMode,
+    /// The [`PlatformDisplay::uuid`] of the display the window was on.
+    #[serde(default)]
+    display: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SavedWindowMode {
+    #[default]
+    Windowed,
+    Maximized,
+    Fullscreen,
+    Tiled {
+        top: bool,
+        left: bool,
+        right: bool,
+        bottom: bool,
+    },
+}
+
+impl WindowStateStore {
+    /// Load the window states saved at `path`, and save them there when windows close and when
+    /// the application quits.
+    pub fn init(path: impl Into<PathBuf>, cx: &mut App) {
+        cx.set_global(Self::load(path.into()));
+        cx.on_window_closed(|cx| {
+            let open_windows = cx.windows();
+            let store = cx.global_mut::<Self>();
+            store
+                .tracked
+                .retain(|handle, _| open_windows.contains(handle));
+            store.save().log_err();
+        })
+        .detach();
+        cx.on_app_quit(|cx| {
+            cx.global::<Self>().save().log_err();
+            async {}
+        })
+        .detach();
+    }
+
+    fn load(path: PathBuf) -> Self {
+        let states = match std::fs::read(&path) {
+            Ok(contents) => {
+                serde_json::from_slice::<SavedWindowStates>(&contents)
+                    .with_context(|| format!("Failed to parse window states in {path:?}"))
+                    .log_err()
+                    .unwrap_or_default()
+                    .windows
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => HashMap::default(),
+            Err(error) => {
+                log::error!("Failed to read window states from {path:?}: {error}");
+                HashMap::default()
+            }
+        };
+        Self {
+            path,
+            states,
+            tracked: HashMap::default(),
+        }
+    }
+
+    /// Write the state of every window that was tracked to the store's file.
+    pub fn save(&self) -> Result<()> {
+        let states = SavedWindowStates {
+            windows: self.states.clone(),
+        };
+        write_atomically(&self.path, &serde_json::to_vec_pretty(&states)?)
+            .with_context(|| format!("Failed to save window states to {:?}", self.path))
+    }
+
+    /// The state the window with this role was saved in, moved onto the displays connected now.
+    pub fn restore(&self, role: &str, cx: &App) -> Option<WindowState> {
+        let saved = self.states.get(role)?;
+        Some(saved.restore(&cx.displays(), cx.primary_display()))
+    }
+
+    /// Open the window with this role where it was saved, by setting the bounds and display of
+    /// `options`. Options are returned as they are if there is no saved state for the role.
+    ///
+    /// Windows can't be opened tiled, so one that was tiled on every edge opens maximized, and
+    /// others open over the space they were tiled in.
+    pub fn window_options(
+        &self,
+        role: &str,
+        mut options: WindowOptions,
+        cx: &App,
+    ) -> WindowOptions {
+        if let Some(state) = self.restore(role, cx) {
+            options.window_bounds = Some(open_bounds(&state));
+            options.display_id = state.display_id;
+        }
+        options
+    }
+
+    /// Save the state of `window` under `role` from now on. [`WindowStateStore::init`] must have
+    /// been called first.
+    pub fn track(role: impl Into<SharedString>, window: &mut Window, cx: &mut App) {
+        let role = role.into();
+        let (subscription, activate) = window.bounds_observers.insert((), {
+            let role = role.clone();
+            Box::new(move |window, cx| {
+                Self::record(role.clone(), window, cx);
+                true
+            })
+        });
+        activate();
+        Self::record(role.clone(), window, cx);
+        cx.global_mut::<Self>()
+            .tracked
+            .insert(window.window_handle(), (role, subscription));
+    }
+
+    fn record(role: SharedString, window: &Window, cx: &mut App) {
+        let display = window
+            .display(cx)
+            .and_then(|display| display.uuid().log_err());
+        let saved = SavedWindowState::new(window.window_state(), window.bounds(), display);
+        cx.global_mut::<Self>().states.insert(role, saved);
+    }
+}
+
+impl SavedWindowState {
+    /// Describe a window in `state`, which is tiled at `tiled_bounds` if any of its edges are.
+    fn new(state: WindowState, tiled_bounds: Bounds<Pixels>, display: Option<Uuid>) -> Self {
+        let tiling = state.tiling;
+        let (bounds, mode) = match state.bounds {
+            WindowBounds::Fullscreen(bounds) => (bounds, SavedWindowMode::Fullscreen),
+            bounds if state.fullscreen => (bounds.get_bounds(), SavedWindowMode::Fullscreen),
+            WindowBounds::Maximized(bounds) => (bounds, SavedWindowMode::Maximized),
+            WindowBounds::Windowed(_) if tiling.is_tiled() => (
+                tiled_bounds,
+                SavedWindowMode::Tiled {
+                    top: tiling.top,
+                    left: tiling.left,
+                    right: tiling.right,
+                    bottom: tiling.bottom,
+                },
+            ),
+            WindowBounds::Windowed(bounds) => (bounds, SavedWindowMode::Windowed),
+        };
+        Self {
+            bounds,
+            mode,
+            display,
+        }
+    }
+
+    fn restore(
+        &self,
+        displays: &[Rc<dyn PlatformDisplay>],
+        primary_display: Option<Rc<dyn PlatformDisplay>>,
+    ) -> WindowState {
+        // The saved display if it's still connected, otherwise one the window overlaps.
+        let display = self
+            .display
+            .and_then(|uuid| {
+                displays
+                    .iter()
+                    .find(|display| display.uuid().ok() == Some(uuid))
+            })
+            .or_else(|| {
+                displays
+                    .iter()
+                    .find(|display| display.bounds().intersects(&self.bounds))
+            })
+            .cloned()
+            .or(primary_display);
+        let bounds = match &display {
+            Some(display) => move_into(self.bounds, display.visible_bounds()),
+            None => self.bounds,
+        };
+        let (bounds, tiling) = match self.mode {
+            SavedWindowMode::Windowed => (WindowBounds::Windowed(bounds), Tiling::default()),
+            SavedWindowMode::Maximized => (WindowBounds::Maximized(bounds), Tiling::default()),
+            SavedWindowMode::Fullscreen => (WindowBounds::Fullscreen(bounds), Tiling::default()),
+            SavedWindowMode::Tiled {
+                top,
+                left,
+                right,
+                bottom,
+            } => (
+                WindowBounds::Windowed(bounds),
+                Tiling {
+                    top,
+                    left,
+                    right,
+                    bottom,
+                },
+            ),
+        };
+        WindowState {
+            bounds,
+            display_id: display.map(|display| display.id()),
+            fullscreen: self.mode == SavedWindowMode::Fullscreen,
+            tiling,
+        }
+    }
+}
+
+/// The bounds to open a window in `state` with. A window tiled on every edge fills the display
+/// just like a maximized one.
+fn open_bounds(state: &WindowState) -> WindowBounds {
+    match state.bounds {
+        WindowBounds::Windowed(bounds) if state.tiling == Tiling::tiled() => {
+            WindowBounds::Maximized(bounds)
+        }
+        bounds => bounds,
+    }
+}
+
+/// Move `bounds` to be inside `area`, shrinking them if they don't fit.
+fn move_into(bounds: Bounds<Pixels>, area: Bounds<Pixels>) -> Bounds<Pixels> {
+    let size = size(
+        bounds.size.width.min(area.size.width),
+        bounds.size.height.min(area.size.height),
+    );
+    let origin = point(
+        bounds
+            .origin
+            .x
+            .min(area.right() - size.width)
+            .max(area.left()),
+        bounds
+            .origin
+            .y
+            .min(area.bottom() - size.height)
+            .max(area.top()),
+    );
+    Bounds::new(origin, size)
+}
+
+/// Replace the file at `path`, so that it is never left half written.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temporary = path.with_extension("tmp");
+    std::fs::write(&temporary, contents)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DisplayId, px};
+
+    #[derive(Debug)]
+    struct TestDisplay {
+        id: u32,
+        uuid: Uuid,
+        bounds: Bounds<Pixels>,
+    }
+
+    impl PlatformDisplay for TestDisplay {
+        fn id(&self) -> DisplayId {
+            DisplayId(self.id)
+        }
+
+        fn uuid(&self) -> Result<Uuid> {
+            Ok(self.uuid)
+        }
+
+        fn bounds(&self) -> Bounds<Pixels> {
+            self.bounds
+        }
+
+        // A panel along the top of the display.
+        fn visible_bounds(&self) -> Bounds<Pixels> {
+            Bounds::new(
+                point(self.bounds.left(), self.bounds.top() + px(30.)),
+                size(self.bounds.size.width, self.bounds.size.height - px(30.)),
+            )
+        }
+    }
+
+    fn bounds(x: f32, y: f32, width: f32, height: f32) -> Bounds<Pixels> {
+        Bounds::new(point(px(x), px(y)), size(px(width), px(height)))
+    }
+
+    #[test]
+    fn test_restore_window_state() {
+        let primary: Rc<dyn PlatformDisplay> = Rc::new(TestDisplay {
+            id: 1,
+            uuid: Uuid::from_u128(1),
+            bounds: bounds(0., 0., 1920., 1080.),
+        });
+        let secondary: Rc<dyn PlatformDisplay> = Rc::new(TestDisplay {
+            id: 2,
+            uuid: Uuid::from_u128(2),
+            bounds: bounds(1920., 0., 1280., 1024.),
+        });
+        let displays = [primary.clone(), secondary.clone()];
+        let saved = |bounds, mode, display| SavedWindowState {
+            bounds,
+            mode,
+            display: Some(Uuid::from_u128(display)),
+        };
+
+        // A window on a display that is still connected opens where it was.
+        let state = saved(
+            bounds(2000., 100., 800., 600.),
+            SavedWindowMode::Windowed,
+            2,
+        )
+        .restore(&displays, Some(primary.clone()));
+        assert_eq!(
+            state.bounds,
+            WindowBounds::Windowed(bounds(2000., 100., 800., 600.))
+        );
+        assert_eq!(state.display_id, Some(DisplayId(2)));
+
+        // One whose display is gone moves onto the primary display.
+        let state = saved(
+            bounds(2000., 100., 800., 600.),
+            SavedWindowMode::Maximized,
+            2,
+        )
+        .restore(&displays[..1], Some(primary.clone()));
+        assert_eq!(
+            state.bounds,
+            WindowBounds::Maximized(bounds(1120., 100., 800., 600.))
+        );
+        assert_eq!(state.display_id, Some(DisplayId(1)));
+
+        // Bounds under the panel or past the edge of the display move back into view, and
+        // bounds too big for it shrink.
+        let state = saved(
+            bounds(-50., 0., 800., 2000.),
+            SavedWindowMode::Fullscreen,
+            1,
+        )
+        .restore(&displays, Some(primary.clone()));
+        assert_eq!(
+            state.bounds,
+            WindowBounds::Fullscreen(bounds(0., 30., 800., 1050.))
+        );
+        assert!(state.fullscreen);
+
+        // Windows tiled to half of the display open over the same half, and ones tiled on every
+        // edge open maximized, keeping their tiling.
+        let left_half = SavedWindowMode::Tiled {
+            top: true,
+            left: true,
+            right: false,
+            bottom: true,
+        };
+        let state = saved(bounds(0., 30., 960., 1050.), left_half, 1)
+            .restore(&displays, Some(primary.clone()));
+        assert_eq!(
+            state.bounds,
+            WindowBounds::Windowed(bounds(0., 30., 960., 1050.))
+        );
+        assert!(state.tiling.is_tiled());
+        assert_eq!(open_bounds(&state), state.bounds);
+        let tiled = SavedWindowMode::Tiled {
+            top: true,
+            left: true,
+            right: true,
+            bottom: true,
+        };
+        let state = saved(bounds(0., 30., 1920., 1050.), tiled, 1).restore(&displays, None);
+        assert_eq!(state.tiling, Tiling::tiled());
+        assert_eq!(
+            open_bounds(&state),
+            WindowBounds::Maximized(bounds(0., 30., 1920., 1050.))
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("gpui-window-states-{}", std::process::id()));
+        let path = dir.join("windows.json");
+        let state = SavedWindowState::new(
+            WindowState {
+                bounds: WindowBounds::Windowed(bounds(0., 30., 960., 1050.)),
+                display_id: None,
+                fullscreen: false,
+                tiling: Tiling {
+                    left: true,
+                    ..Tiling::default()
+                },
+            },
+            bounds(0., 30., 960., 1050.),
+            Some(Uuid::from_u128(1)),
+        );
+
+        let mut store = WindowStateStore::load(path.clone());
+        assert!(store.states.is_empty());
+        store.states.insert("main".into(), state.clone());
+        store.save().unwrap();
+        let store = WindowStateStore::load(path.clone());
+        assert_eq!(store.states.get("main"), Some(&state));
+
+        // A corrupt file is ignored, rather than keeping the application from starting.
+        std::fs::write(&path, "{").unwrap();
+        assert!(WindowStateStore::load(path).states.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+//! Windows that drop down from the system tray icon.
+
+use anyhow::Result;
+
+use crate::{
+    App, Bounds, DisplayId, Entity, Pixels, Render, Size, Window, WindowBounds, WindowHandle,
+    WindowKind, WindowOptions, point, px,
+    window_positioner::{PanelEdge, tray_popover_bounds},
+};
+
+/// A window attached to the system tray icon, like a macOS menu bar extra. It opens on the side of
+/// the tray's panel that faces the rest of the display, whichever edge the panel is on, and closes
+/// when it loses focus.
+///
+/// The tray icon is found with [`App::tray_icon_bounds`]. On Linux that is where the icon was last
+/// clicked, if the tray host says; without it, the popover opens in the top right corner of the
+/// primary display. Wayland compositors place the window themselves. On X11, a popover that
+/// hides on blur grabs the pointer and keyboard while it's open.
+#[derive(Debug, Clone)]
+pub struct TrayPopover {
+    size: Size<Pixels>,
+    margin: Pixels,
+    edge: Option<PanelEdge>,
+    hide_on_blur: bool,
+}
+
+impl TrayPopover {
+    /// A popover of the given size.
+    pub fn new(size: Size<Pixels>) -> Self {
+        Self {
+            size,
+            margin: px(8.),
+            edge: None,
+            hide_on_blur: true,
+        }
+    }
+
+    /// Set the space between the popover, the tray icon and the edges of the display.
+    pub fn margin(mut self, margin: Pixels) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Open next to a panel on `edge`, instead of the edge closest to the tray icon.
+    pub fn edge(mut self, edge: PanelEdge) -> Self {
+        self.edge = Some(edge);
+        self
+    }
+
+    /// Set whether the popover closes when it loses focus. Defaults to `true`.
+    pub fn hide_on_blur(mut self, hide_on_blur: bool) -> Self {
+        self.hide_on_blur = hide_on_blur;
+        self
+    }
+
+    /// The display and bounds the popover opens at.
+    pub fn bounds(&self, cx: &App) -> (Option<DisplayId>, Bounds<Pixels>) {
+        let tray = cx.tray_icon_bounds();
+        let display = tray
+            .and_then(|tray| {
+                cx.displays()
+                    .into_iter()
+                    .find(|display| display.bounds().contains(&tray.center()))
+            })
+            .or_else(|| cx.primary_display());
+        let Some(display) = display else {
+            return (None, Bounds::new(point(px(0.), px(0.)), self.size));
+        };
+        let work_area = display.visible_bounds();
+        // Without a tray icon, assume the common spot at the right end of a top panel.
+        let tray = tray.unwrap_or_else(|| {
+            Bounds::new(point(work_area.right(), work_area.top()), Size::default())
+        });
+        let edge = self
+            .edge
+            .unwrap_or_else(|| PanelEdge::nearest(tray, display.bounds()));
+        let bounds = tray_popover_bounds(self.size, tray, edge, work_area, self.margin);
+        (Some(display.id()), bounds)
+    }
+
+    /// Open the popover with the view built by `build_root_view`.
+    pub fn open<V: 'static + Render>(
+        self,
+        cx: &mut App,
+        build_root_view: impl FnOnce(&mut Window, &mut App) -> Entity<V>,
+    ) -> Result<WindowHandle<V>> {
+        let (display_id, bounds) = self.bounds(cx);
+        let options = WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(bounds)),
+            titlebar: None,
+            kind: WindowKind::PopUp,
+            is_movable: false,
+            is_resizable: false,
+            is_minimizable: false,
+            display_id,
+            ..Default::default()
+        };
+        let handle = cx.open_window(options, build_root_view)?;
+        if self.hide_on_blur {
+            handle.update(cx, |_, window, cx| {
+                // Window managers on X11 don't focus pop-up windows, which would then never lose
+                // focus either. Grabbing input focuses the popover, and clicks outside of it blur it.
+                window.grab_input();
+                cx.observe_window_activation(window, |_, window, _| {
+                    if !window.is_window_active() {
+                        window.remove_window();
+                    }
+                })
+                .detach();
+            })?;
+        }
+        Ok(handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AppContext as _, Empty, TestAppContext, VisualTestContext, size};
+
+    struct Popover;
+
+    impl Render for Popover {
+        fn render(
+            &mut self,
+            _: &mut Window,
+            _: &mut crate::Context<Self>,
+        ) -> impl crate::IntoElement {
+            Empty
+        }
+    }
+
+    fn open_popover(popover: TrayPopover, cx: &mut TestAppContext) -> VisualTestContext {
+        let handle = cx
+            .update(|cx| popover.open(cx, |_, cx| cx.new(|_| Popover)))
+            .unwrap();
+        cx.update_window(handle.into(), |_, window, _| window.activate_window())
+            .unwrap();
+        cx.run_until_parked();
+        VisualTestContext::from_window(handle.into(), cx)
+    }
+
+    #[gpui::test]
+    fn test_hide_on_blur(cx: &mut TestAppContext) {
+        let mut popover = open_popover(TrayPopover::new(size(px(300.), px(200.))), cx);
+        assert_eq!(cx.windows().len(), 1);
+        popover.deactivate_window();
+        assert!(cx.windows().is_empty());
+
+        let mut popover = open_popover(
+            TrayPopover::new(size(px(300.), px(200.))).hide_on_blur(false),
+            cx,
+        );
+        popover.deactivate_window();
+        assert_eq!(cx.windows().len(), 1);
+    }
+}